@@ -2,6 +2,10 @@
 //!
 //! Intelligent asset import, optimization, and processing pipeline.
 
+use crate::import_cache::{settings_hash, AssetMeta, ImportCache};
+use crate::model::{encode_meshes, ImportedModel, ModelLoader};
+use crate::texture_cooker::{open_image, TextureCooker};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
@@ -80,7 +84,8 @@ impl ImportFormat {
 }
 
 /// Import settings for 3D models
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModelImportSettings {
     /// Generate LODs automatically
     pub generate_lods: bool,
@@ -131,14 +136,15 @@ impl Default for ModelImportSettings {
 }
 
 /// Up axis
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UpAxis {
     Y,
     Z,
 }
 
 /// Import settings for textures
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TextureImportSettings {
     /// Generate mipmaps
     pub generate_mipmaps: bool,
@@ -152,6 +158,8 @@ pub struct TextureImportSettings {
     pub power_of_two: bool,
     /// Normal map detection
     pub detect_normal_map: bool,
+    /// Treat as a tangent-space normal map without detection
+    pub normal_map: bool,
     /// Alpha handling
     pub alpha_mode: AlphaMode,
 }
@@ -165,13 +173,14 @@ impl Default for TextureImportSettings {
             max_resolution: 4096,
             power_of_two: false,
             detect_normal_map: true,
+            normal_map: false,
             alpha_mode: AlphaMode::Auto,
         }
     }
 }
 
 /// Texture compression format
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TextureCompression {
     None,
     BC1,
@@ -186,7 +195,7 @@ pub enum TextureCompression {
 }
 
 /// Alpha handling mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AlphaMode {
     Auto,
     Opaque,
@@ -196,7 +205,8 @@ pub enum AlphaMode {
 }
 
 /// Import settings for audio
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AudioImportSettings {
     /// Sample rate conversion
     pub target_sample_rate: u32,
@@ -226,7 +236,7 @@ impl Default for AudioImportSettings {
 }
 
 /// Import result
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportResult {
     /// Source path
     pub source: PathBuf,
@@ -246,6 +256,12 @@ pub struct ImportResult {
     pub total_triangles: u32,
     /// Memory size estimate
     pub memory_estimate: u64,
+    /// Other source files the import read, such as external textures
+    #[serde(default)]
+    pub dependencies: Vec<PathBuf>,
+    /// Whether the result came from the import cache
+    #[serde(default)]
+    pub cached: bool,
 }
 
 /// LOD generation result
@@ -273,6 +289,8 @@ pub struct AssetImporter {
     pub output_dir: PathBuf,
     /// Cache directory
     pub cache_dir: PathBuf,
+    /// Cache of previous imports
+    cache: ImportCache,
     /// Import history
     history: Vec<ImportResult>,
     /// File watch enabled
@@ -294,6 +312,7 @@ impl AssetImporter {
             texture_settings: TextureImportSettings::default(),
             audio_settings: AudioImportSettings::default(),
             output_dir,
+            cache: ImportCache::open(&cache_dir),
             cache_dir,
             history: Vec::new(),
             watch_enabled: true,
@@ -301,132 +320,244 @@ impl AssetImporter {
     }
 
     /// Import a file
+    ///
+    /// Settings from the file's `.meta` sidecar override the importer's
+    /// settings. If neither the file, its settings nor any of its dependencies
+    /// changed since the last import, the cached result is returned.
     pub fn import(&mut self, path: &Path) -> Result<ImportResult, ImportError> {
-        let ext = path.extension()
-            .and_then(|e| e.to_str())
-            .ok_or(ImportError::UnknownFormat)?;
-
-        let format = ImportFormat::from_extension(ext)
-            .ok_or(ImportError::UnknownFormat)?;
+        let format = Self::format_of(path)?;
+        let settings = self.resolve_settings(path, format)?;
+        let settings_hash = settings.hash();
 
         let start = std::time::Instant::now();
 
-        let result = if format.is_model() {
-            self.import_model(path, format)
-        } else if format.is_texture() {
-            self.import_texture(path, format)
-        } else if format.is_audio() {
-            self.import_audio(path, format)
-        } else {
-            self.import_generic(path)
+        if let Some(mut result) = self.cache.lookup(path, &self.output_dir, settings_hash) {
+            tracing::debug!("Import of {} is up to date", path.display());
+            result.cached = true;
+            result.duration_ms = start.elapsed().as_millis() as u64;
+            self.history.push(result.clone());
+            return Ok(result);
+        }
+
+        let result = match &settings {
+            ResolvedSettings::Model(settings) => self.import_model(path, settings),
+            ResolvedSettings::Texture(settings) => self.import_texture(path, settings),
+            ResolvedSettings::Audio(settings) => self.import_audio(path, settings),
+            ResolvedSettings::None => self.import_generic(path),
         }?;
 
         let mut result = result;
         result.duration_ms = start.elapsed().as_millis() as u64;
 
+        if let Err(e) = self.cache.store(path, &self.output_dir, settings_hash, &result) {
+            tracing::warn!("Failed to cache import of {}: {}", path.display(), e);
+        }
+
         self.history.push(result.clone());
         Ok(result)
     }
 
-    fn import_model(&self, path: &Path, _format: ImportFormat) -> Result<ImportResult, ImportError> {
-        let mut outputs = Vec::new();
-        let mut warnings = Vec::new();
+    /// Whether importing a file would reprocess it rather than use the cache
+    pub fn is_stale(&mut self, path: &Path) -> bool {
+        let Ok(format) = Self::format_of(path) else {
+            return true;
+        };
+        let Ok(settings) = self.resolve_settings(path, format) else {
+            return true;
+        };
+        self.cache.lookup(path, &self.output_dir, settings.hash()).is_none()
+    }
+
+    /// Write the import cache manifest to disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest cannot be written.
+    pub fn save_cache(&mut self) -> Result<(), ImportError> {
+        self.cache.save()
+    }
+
+    fn format_of(path: &Path) -> Result<ImportFormat, ImportError> {
+        let ext = path.extension()
+            .and_then(|e| e.to_str())
+            .ok_or(ImportError::UnknownFormat)?;
+
+        ImportFormat::from_extension(ext).ok_or(ImportError::UnknownFormat)
+    }
+
+    /// Importer settings with the file's `.meta` overrides applied
+    fn resolve_settings(
+        &self,
+        path: &Path,
+        format: ImportFormat,
+    ) -> Result<ResolvedSettings, ImportError> {
+        let meta = AssetMeta::load(path)?.unwrap_or_default();
+        Ok(if format.is_model() {
+            ResolvedSettings::Model(meta.model.unwrap_or_else(|| self.model_settings.clone()))
+        } else if format.is_texture() {
+            let settings = meta.texture.unwrap_or_else(|| self.texture_settings.clone());
+            ResolvedSettings::Texture(settings)
+        } else if format.is_audio() {
+            ResolvedSettings::Audio(meta.audio.unwrap_or_else(|| self.audio_settings.clone()))
+        } else {
+            ResolvedSettings::None
+        })
+    }
+
+    /// Load a glTF model with the current model settings
+    ///
+    /// FBX, OBJ and Blender files must be exported to glTF first.
+    pub fn load_model(&self, path: &Path) -> Result<ImportedModel, ImportError> {
+        Self::load_model_with(path, &self.model_settings)
+    }
+
+    fn load_model_with(
+        path: &Path,
+        settings: &ModelImportSettings,
+    ) -> Result<ImportedModel, ImportError> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+        match ImportFormat::from_extension(ext) {
+            Some(ImportFormat::Gltf | ImportFormat::Glb) => {},
+            Some(format) if format.is_model() => {
+                return Err(ImportError::ParseError(format!(
+                    "{:?} models are not supported, export as glTF",
+                    format
+                )));
+            },
+            _ => return Err(ImportError::UnknownFormat),
+        }
+        if !path.exists() {
+            return Err(ImportError::FileNotFound);
+        }
+
+        ModelLoader::new(settings.clone())
+            .import(path)
+            .map_err(|e| match e {
+                lunaris_core::Error::Io(e) => ImportError::IoError(e.to_string()),
+                e => ImportError::ParseError(e.to_string()),
+            })
+    }
+
+    fn import_model(
+        &self,
+        path: &Path,
+        settings: &ModelImportSettings,
+    ) -> Result<ImportResult, ImportError> {
+        let model = Self::load_model_with(path, settings)?;
         let base_name = path.file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("model");
 
-        // Generate main mesh
-        let main_output = self.output_dir.join(format!("{}.mesh", base_name));
-        outputs.push(main_output);
-
-        // Generate LODs
-        let mut lods = Vec::new();
-        let original_triangles = 100000; // Would be read from file
-        let mut total_triangles = original_triangles;
-
-        if self.model_settings.generate_lods {
-            let mut triangles = original_triangles;
-            for level in 0..self.model_settings.lod_count {
-                let screen_threshold = match level {
-                    0 => 1.0,
-                    1 => 0.5,
-                    2 => 0.25,
-                    3 => 0.125,
-                    _ => 0.0625,
-                };
-
-                lods.push(LodResult {
-                    level,
-                    triangles,
-                    vertices: triangles * 3 / 2, // Estimate
-                    screen_threshold,
-                });
-
-                if level < self.model_settings.lod_count - 1 {
-                    triangles = (triangles as f32 * self.model_settings.lod_reduction) as u32;
-                    total_triangles += triangles;
-                }
+        let mut files: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+        let mesh_file = |name: String, meshes: &[&lunaris_renderer::Mesh]| {
+            (self.output_dir.join(name), encode_meshes(meshes))
+        };
+
+        // Full detail meshes, then one file per LOD level. Meshes with fewer
+        // LODs reuse their coarsest one so every level file is complete.
+        let base: Vec<_> = model.meshes.iter().map(|m| &m.mesh).collect();
+        files.push(mesh_file(format!("{}.mesh", base_name), &base));
+        let lod_count = model.meshes.iter().map(|m| m.lods.len()).max().unwrap_or(0);
+        for level in 1..=lod_count {
+            let lods: Vec<_> = model.meshes
+                .iter()
+                .map(|m| m.lods.get(level - 1).or(m.lods.last()).map_or(&m.mesh, |l| &l.mesh))
+                .collect();
+            files.push(mesh_file(format!("{}_lod{}.mesh", base_name, level), &lods));
+        }
 
-                let lod_output = self.output_dir.join(format!("{}_lod{}.mesh", base_name, level));
-                outputs.push(lod_output);
+        let to_json = |value: serde_json::Result<Vec<u8>>| {
+            value.map_err(|e| ImportError::IoError(e.to_string()))
+        };
+        if let Some(skeleton) = &model.skeleton {
+            let bytes = to_json(serde_json::to_vec(skeleton))?;
+            files.push((self.output_dir.join(format!("{}.skeleton", base_name)), bytes));
+        }
+        let mut clip_names = std::collections::HashSet::new();
+        for (index, clip) in model.animations.iter().enumerate() {
+            let mut name = file_safe_name(&clip.name);
+            if name.is_empty() || !clip_names.insert(name.clone()) {
+                name = format!("{}{}", name, index);
+                clip_names.insert(name.clone());
             }
+            let bytes = to_json(serde_json::to_vec(clip))?;
+            files.push((self.output_dir.join(format!("{}_{}.anim", base_name, name)), bytes));
         }
 
-        // Generate collision
-        if self.model_settings.generate_collision {
-            let collision_output = self.output_dir.join(format!("{}.collision", base_name));
-            outputs.push(collision_output);
+        std::fs::create_dir_all(&self.output_dir)
+            .map_err(|e| ImportError::IoError(e.to_string()))?;
+        for (output, bytes) in &files {
+            std::fs::write(output, bytes).map_err(|e| ImportError::IoError(e.to_string()))?;
         }
+        let outputs = files.into_iter().map(|(output, _)| output).collect();
 
-        // Nanite optimization
-        if self.model_settings.nanite_optimize {
-            let cluster_count = original_triangles / self.model_settings.max_cluster_triangles;
-            warnings.push(format!("Generated {} clusters for Nanite", cluster_count));
-        }
+        let original_triangles = model.triangle_count() as u32;
+        let total_triangles = model.total_triangle_count() as u32;
+
+        let vertex_size = std::mem::size_of::<lunaris_renderer::Vertex3D>() as u64;
+        let memory_estimate = model.meshes.iter()
+            .flat_map(|m| std::iter::once(&m.mesh).chain(m.lods.iter().map(|l| &l.mesh)))
+            .map(|mesh| mesh.vertices.len() as u64 * vertex_size + mesh.indices.len() as u64 * 4)
+            .sum();
 
         Ok(ImportResult {
             source: path.to_path_buf(),
             outputs,
-            warnings,
+            warnings: model.warnings,
             errors: Vec::new(),
             duration_ms: 0,
-            lod_count: self.model_settings.lod_count,
+            lod_count: lod_count as u8 + 1,
             original_triangles,
             total_triangles,
-            memory_estimate: total_triangles as u64 * 64, // Estimate bytes
+            memory_estimate,
+            dependencies: model.files,
+            cached: false,
         })
     }
 
-    fn import_texture(&self, path: &Path, _format: ImportFormat) -> Result<ImportResult, ImportError> {
+    fn import_texture(
+        &self,
+        path: &Path,
+        settings: &TextureImportSettings,
+    ) -> Result<ImportResult, ImportError> {
+        if !path.exists() {
+            return Err(ImportError::FileNotFound);
+        }
         let base_name = path.file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("texture");
 
-        let output = self.output_dir.join(format!("{}.tex", base_name));
-        let outputs = vec![output];
+        let image = open_image(path).map_err(|e| match e {
+            lunaris_core::Error::Io(e) => ImportError::IoError(e.to_string()),
+            e => ImportError::ParseError(e.to_string()),
+        })?;
+        let cooked = TextureCooker::new(settings.clone()).cook(base_name, &image);
 
-        let mut warnings = Vec::new();
-
-        if self.texture_settings.generate_mipmaps {
-            warnings.push("Generated 10 mipmap levels".to_string());
-        }
-
-        warnings.push(format!("Compressed with {:?}", self.texture_settings.compression));
+        let output = self.output_dir.join(format!("{}.tex", base_name));
+        std::fs::create_dir_all(&self.output_dir)
+            .and_then(|()| std::fs::write(&output, cooked.texture.to_bytes()))
+            .map_err(|e| ImportError::IoError(e.to_string()))?;
 
         Ok(ImportResult {
             source: path.to_path_buf(),
-            outputs,
-            warnings,
+            outputs: vec![output],
+            warnings: cooked.warnings,
             errors: Vec::new(),
             duration_ms: 0,
             lod_count: 0,
             original_triangles: 0,
             total_triangles: 0,
-            memory_estimate: 4 * 1024 * 1024, // 4MB estimate
+            memory_estimate: cooked.texture.data_size() as u64,
+            dependencies: Vec::new(),
+            cached: false,
         })
     }
 
-    fn import_audio(&self, path: &Path, _format: ImportFormat) -> Result<ImportResult, ImportError> {
+    fn import_audio(
+        &self,
+        path: &Path,
+        settings: &AudioImportSettings,
+    ) -> Result<ImportResult, ImportError> {
         let base_name = path.file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("audio");
@@ -435,9 +566,9 @@ impl AssetImporter {
         let outputs = vec![output];
 
         let mut warnings = Vec::new();
-        warnings.push(format!("Resampled to {}Hz", self.audio_settings.target_sample_rate));
+        warnings.push(format!("Resampled to {}Hz", settings.target_sample_rate));
 
-        if self.audio_settings.force_mono {
+        if settings.force_mono {
             warnings.push("Converted to mono".to_string());
         }
 
@@ -451,6 +582,8 @@ impl AssetImporter {
             original_triangles: 0,
             total_triangles: 0,
             memory_estimate: 1024 * 1024, // 1MB estimate
+            dependencies: Vec::new(),
+            cached: false,
         })
     }
 
@@ -471,22 +604,30 @@ impl AssetImporter {
             original_triangles: 0,
             total_triangles: 0,
             memory_estimate: 0,
+            dependencies: Vec::new(),
+            cached: false,
         })
     }
 
     /// Import all files in a directory
+    ///
+    /// Unchanged files are served from the cache.
     pub fn import_directory(&mut self, dir: &Path) -> Vec<Result<ImportResult, ImportError>> {
         let mut results = Vec::new();
 
         if let Ok(entries) = std::fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_file() {
+                if path.is_file() && !AssetMeta::is_meta(&path) {
                     results.push(self.import(&path));
                 }
             }
         }
 
+        if let Err(e) = self.cache.save() {
+            tracing::warn!("Failed to save import cache: {}", e);
+        }
+
         results
     }
 
@@ -502,6 +643,43 @@ impl AssetImporter {
     }
 }
 
+impl Drop for AssetImporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.cache.save() {
+            tracing::warn!("Failed to save import cache: {}", e);
+        }
+    }
+}
+
+/// Settings that apply to one import
+enum ResolvedSettings {
+    Model(ModelImportSettings),
+    Texture(TextureImportSettings),
+    Audio(AudioImportSettings),
+    None,
+}
+
+impl ResolvedSettings {
+    fn hash(&self) -> u64 {
+        match self {
+            Self::Model(settings) => settings_hash(settings),
+            Self::Texture(settings) => settings_hash(settings),
+            Self::Audio(settings) => settings_hash(settings),
+            Self::None => 0,
+        }
+    }
+}
+
+/// Reduce a name to characters that are safe in a file name
+///
+/// Anything but ASCII letters, digits, `-` and `_` becomes `_`, so names
+/// from model files cannot add path separators or `..` components.
+fn file_safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
 /// Import error
 #[derive(Debug, Clone)]
 pub enum ImportError {
@@ -0,0 +1,470 @@
+//! Audio decoding
+//!
+//! WAV, OGG Vorbis, MP3 and FLAC files are decoded through symphonia into
+//! interleaved `f32` samples. [`AudioStream`] decodes incrementally so long
+//! music tracks never have to sit in memory, and can convert the sample rate
+//! on the fly.
+
+use crate::loader::AudioAsset;
+use lunaris_core::{Error, Result};
+use std::fs::File;
+use std::io::{Cursor, ErrorKind};
+use std::path::Path;
+use std::time::Duration;
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as DecodeError,
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+    io::{MediaSource, MediaSourceStream},
+    meta::MetadataOptions,
+    probe::Hint,
+    units::Time,
+};
+
+/// Incremental decoder for an audio file
+///
+/// Samples are interleaved. When an output sample rate is set the stream
+/// resamples with linear interpolation.
+pub struct AudioStream {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    channels: u16,
+    source_rate: u32,
+    frames: Option<u64>,
+    resampler: Resampler,
+    /// Decoded samples at the output rate not yet read
+    pending: Vec<f32>,
+    /// Output frames handed out since the start or the last seek
+    position: u64,
+    /// Decoded frames to drop to land exactly on a seek target
+    skip_frames: u64,
+    finished: bool,
+}
+
+impl AudioStream {
+    /// Open an audio file for streaming
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened or decoded
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let extension = path.extension().and_then(|e| e.to_str());
+        Self::from_source(Box::new(file), extension)
+    }
+
+    /// Stream audio from an in-memory file
+    ///
+    /// `extension` is a hint for formats without a reliable signature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is not a supported audio file
+    pub fn from_bytes(bytes: Vec<u8>, extension: Option<&str>) -> Result<Self> {
+        Self::from_source(Box::new(Cursor::new(bytes)), extension)
+    }
+
+    fn from_source(source: Box<dyn MediaSource>, extension: Option<&str>) -> Result<Self> {
+        let mut hint = Hint::new();
+        if let Some(extension) = extension {
+            hint.with_extension(extension);
+        }
+        let stream = MediaSourceStream::new(source, Default::default());
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| Error::Asset(format!("Unsupported audio file: {e}")))?;
+        let format = probed.format;
+
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| Error::Asset("Audio file has no audio track".to_string()))?;
+        let (track_id, params) = (track.id, track.codec_params.clone());
+        let decoder = symphonia::default::get_codecs()
+            .make(&params, &DecoderOptions::default())
+            .map_err(|e| Error::Asset(format!("Unsupported audio codec: {e}")))?;
+
+        let source_rate = params
+            .sample_rate
+            .ok_or_else(|| Error::Asset("Audio track has no sample rate".to_string()))?;
+        let mut stream = Self {
+            format,
+            decoder,
+            track_id,
+            channels: params.channels.map_or(0, |c| c.count() as u16),
+            source_rate,
+            frames: params.n_frames,
+            resampler: Resampler::new(source_rate, source_rate),
+            pending: Vec::new(),
+            position: 0,
+            skip_frames: 0,
+            finished: false,
+        };
+        // Some containers only reveal the channel layout once decoding starts
+        if stream.channels == 0 {
+            stream.decode_packet()?;
+        }
+        if stream.channels == 0 {
+            return Err(Error::Asset("Audio track has no channels".to_string()));
+        }
+        stream.resampler.channels = usize::from(stream.channels);
+        Ok(stream)
+    }
+
+    /// Resample the output to `sample_rate`
+    #[must_use]
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.resampler = Resampler::new(self.source_rate, sample_rate.max(1));
+        self.resampler.channels = usize::from(self.channels);
+        let decoded = std::mem::take(&mut self.pending);
+        self.resampler.process(&decoded, &mut self.pending);
+        self
+    }
+
+    /// Output sample rate
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.resampler.output_rate
+    }
+
+    /// Number of interleaved channels
+    #[must_use]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Total length, if the container reports it
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.frames
+            .map(|frames| Duration::from_secs_f64(frames as f64 / f64::from(self.source_rate)))
+    }
+
+    /// Playback position in seconds
+    #[must_use]
+    pub fn position(&self) -> f32 {
+        (self.position as f64 / f64::from(self.resampler.output_rate)) as f32
+    }
+
+    /// Whether the end of the file has been reached and every sample read
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.pending.is_empty()
+    }
+
+    /// Fill `out` with interleaved samples
+    ///
+    /// Returns the number of samples written, which is only less than
+    /// `out.len()` at the end of the stream.
+    pub fn read(&mut self, out: &mut [f32]) -> usize {
+        let channels = usize::from(self.channels);
+        // Keep whole frames so channels never shift
+        let wanted = out.len() - out.len() % channels;
+        while self.pending.len() < wanted && !self.finished {
+            if let Err(e) = self.decode_packet() {
+                tracing::warn!("Audio stream stopped: {e}");
+                self.finished = true;
+            }
+        }
+        if self.finished {
+            self.resampler.flush(&mut self.pending);
+        }
+
+        let count = wanted.min(self.pending.len());
+        out[..count].copy_from_slice(&self.pending[..count]);
+        self.pending.drain(..count);
+        self.position += (count / channels) as u64;
+        count
+    }
+
+    /// Jump to a time in seconds
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format cannot seek
+    pub fn seek(&mut self, seconds: f32) -> Result<()> {
+        let seconds = f64::from(seconds.max(0.0));
+        let seeked = self
+            .format
+            .seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: Time::from(seconds),
+                    track_id: Some(self.track_id),
+                },
+            )
+            .map_err(|e| Error::Asset(format!("Audio seek failed: {e}")))?;
+        self.skip_frames = seeked.required_ts.saturating_sub(seeked.actual_ts);
+        self.decoder.reset();
+        self.resampler.reset();
+        self.pending.clear();
+        self.finished = false;
+        self.position = (seconds * f64::from(self.resampler.output_rate)) as u64;
+        Ok(())
+    }
+
+    /// Restart from the beginning
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format cannot seek
+    pub fn rewind(&mut self) -> Result<()> {
+        self.seek(0.0)
+    }
+
+    /// Decode the rest of the stream into an [`AudioAsset`]
+    #[must_use]
+    pub fn decode_all(mut self) -> AudioAsset {
+        let mut samples = Vec::new();
+        let mut chunk = vec![0.0; 4096 * usize::from(self.channels)];
+        loop {
+            let count = self.read(&mut chunk);
+            samples.extend_from_slice(&chunk[..count]);
+            if count < chunk.len() {
+                break;
+            }
+        }
+        AudioAsset {
+            sample_rate: self.sample_rate(),
+            channels: self.channels,
+            samples,
+        }
+    }
+
+    /// Decode the next packet of the track into `pending`
+    fn decode_packet(&mut self) -> Result<()> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(e)) if e.kind() == ErrorKind::UnexpectedEof => {
+                    self.finished = true;
+                    return Ok(());
+                },
+                Err(e) => return Err(Error::Asset(format!("Failed to read audio: {e}"))),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Corrupt packets are skipped, as players do
+                Err(DecodeError::DecodeError(e)) => {
+                    tracing::warn!("Skipping corrupt audio packet: {e}");
+                    continue;
+                },
+                Err(e) => return Err(Error::Asset(format!("Failed to decode audio: {e}"))),
+            };
+            let spec = *decoded.spec();
+            if self.channels == 0 {
+                self.channels = spec.channels.count() as u16;
+            }
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+            let frames = (buffer.samples().len() / usize::from(self.channels)) as u64;
+            let skip = self.skip_frames.min(frames);
+            self.skip_frames -= skip;
+            let samples = &buffer.samples()[skip as usize * usize::from(self.channels)..];
+            self.resampler.process(samples, &mut self.pending);
+            return Ok(());
+        }
+    }
+}
+
+impl std::fmt::Debug for AudioStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioStream")
+            .field("channels", &self.channels)
+            .field("source_rate", &self.source_rate)
+            .field("sample_rate", &self.resampler.output_rate)
+            .field("position", &self.position())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Decode a complete audio file
+///
+/// A `sample_rate` of `None` keeps the file's own rate.
+///
+/// # Errors
+///
+/// Returns an error if the data is not a supported audio file
+pub fn decode(
+    bytes: &[u8],
+    extension: Option<&str>,
+    sample_rate: Option<u32>,
+) -> Result<AudioAsset> {
+    let mut stream = AudioStream::from_bytes(bytes.to_vec(), extension)?;
+    if let Some(sample_rate) = sample_rate {
+        stream = stream.with_sample_rate(sample_rate);
+    }
+    Ok(stream.decode_all())
+}
+
+/// Streaming linear-interpolation sample rate converter
+#[derive(Debug, Clone)]
+struct Resampler {
+    input_rate: u32,
+    output_rate: u32,
+    channels: usize,
+    /// Input frames not fully consumed yet
+    input: Vec<f32>,
+    /// Position of the next output frame, in input frames
+    cursor: f64,
+}
+
+impl Resampler {
+    fn new(input_rate: u32, output_rate: u32) -> Self {
+        Self {
+            input_rate,
+            output_rate,
+            channels: 1,
+            input: Vec::new(),
+            cursor: 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input.clear();
+        self.cursor = 0.0;
+    }
+
+    /// Convert a block of interleaved input samples
+    fn process(&mut self, samples: &[f32], out: &mut Vec<f32>) {
+        if self.input_rate == self.output_rate {
+            out.extend_from_slice(samples);
+            return;
+        }
+        self.input.extend_from_slice(samples);
+        self.resample(out, false);
+    }
+
+    /// Emit the frames held back for interpolation at the end of the stream
+    fn flush(&mut self, out: &mut Vec<f32>) {
+        if self.input_rate != self.output_rate {
+            self.resample(out, true);
+        }
+        self.reset();
+    }
+
+    fn resample(&mut self, out: &mut Vec<f32>, end: bool) {
+        let channels = self.channels;
+        let frames = self.input.len() / channels;
+        let step = f64::from(self.input_rate) / f64::from(self.output_rate);
+
+        // Interpolating needs the frame after the cursor, except at the end
+        let limit = if end {
+            frames
+        } else {
+            frames.saturating_sub(1)
+        };
+        while (self.cursor as usize) < limit {
+            let index = self.cursor as usize;
+            let next = (index + 1).min(frames - 1);
+            let t = (self.cursor - index as f64) as f32;
+            for c in 0..channels {
+                let a = self.input[index * channels + c];
+                let b = self.input[next * channels + c];
+                out.push(a + (b - a) * t);
+            }
+            self.cursor += step;
+        }
+
+        let consumed = (self.cursor as usize).min(frames);
+        self.input.drain(..consumed * channels);
+        self.cursor -= consumed as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 16-bit stereo PCM WAV with a ramp on the left and silence on the right
+    fn wav(sample_rate: u32, frames: u32) -> Vec<u8> {
+        let data_len = frames * 4;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 4).to_le_bytes());
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for i in 0..frames {
+            let left = (i * 16_000 / frames) as i16;
+            bytes.extend_from_slice(&left.to_le_bytes());
+            bytes.extend_from_slice(&0i16.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn decodes_wav() {
+        let asset = decode(&wav(22_050, 11_025), Some("wav"), None).unwrap();
+        assert_eq!(asset.sample_rate, 22_050);
+        assert_eq!(asset.channels, 2);
+        assert_eq!(asset.samples.len(), 22_050);
+        assert!((asset.duration().as_secs_f32() - 0.5).abs() < 1e-3);
+        assert!(asset.samples.chunks(2).all(|frame| frame[1] == 0.0));
+        assert!(asset.samples[2] > asset.samples[0]);
+    }
+
+    #[test]
+    fn resamples_to_target_rate() {
+        let asset = decode(&wav(22_050, 11_025), Some("wav"), Some(44_100)).unwrap();
+        assert_eq!(asset.sample_rate, 44_100);
+        let frames = asset.samples.len() / 2;
+        assert!((frames as i64 - 22_050).abs() <= 2, "{frames} frames");
+        // Interpolated samples stay on the ramp
+        let left: Vec<f32> = asset.samples.iter().step_by(2).copied().collect();
+        assert!(left.windows(2).all(|w| w[1] >= w[0]));
+    }
+
+    #[test]
+    fn streams_in_chunks_and_seeks() {
+        let bytes = wav(8_000, 8_000);
+        let whole = decode(&bytes, Some("wav"), Some(16_000)).unwrap();
+
+        let mut stream = AudioStream::from_bytes(bytes, Some("wav"))
+            .unwrap()
+            .with_sample_rate(16_000);
+        assert_eq!(stream.duration(), Some(Duration::from_secs(1)));
+        let mut streamed = Vec::new();
+        let mut chunk = [0.0; 301];
+        loop {
+            let count = stream.read(&mut chunk);
+            assert_eq!(count % 2, 0);
+            streamed.extend_from_slice(&chunk[..count]);
+            if count == 0 {
+                break;
+            }
+        }
+        assert!(stream.is_finished());
+        assert_eq!(streamed, whole.samples);
+
+        stream.seek(0.5).unwrap();
+        assert!((stream.position() - 0.5).abs() < 1e-3);
+        let mut frame = [0.0; 2];
+        assert_eq!(stream.read(&mut frame), 2);
+        assert!((frame[0] - whole.samples[16_000]).abs() < 1e-3);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(AudioStream::from_bytes(b"not audio".to_vec(), Some("ogg")).is_err());
+    }
+}
@@ -0,0 +1,751 @@
+//! Block compression encoders
+//!
+//! CPU encoders for the GPU block formats the texture cooker writes. They aim
+//! for predictable quality at offline speeds rather than matching dedicated
+//! compressors: BC7 uses mode 6 only, ETC2 uses the ETC1-compatible individual
+//! and differential modes and ASTC uses a single partition with a 4x4 weight
+//! grid.
+
+use lunaris_renderer::GpuTextureFormat;
+
+/// Compress an RGBA8 image into blocks of `format`
+///
+/// Partial blocks at the right and bottom edges repeat the last row and column.
+pub(crate) fn compress(format: GpuTextureFormat, width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let (block_width, block_height) = format.block_size();
+    let (bw, bh) = (block_width as usize, block_height as usize);
+    let (width, height) = (width as usize, height as usize);
+    if !format.is_compressed() {
+        return rgba[..width * height * 4].to_vec();
+    }
+
+    let mut out = Vec::with_capacity(format.level_bytes(width as u32, height as u32));
+    let mut texels = vec![[0u8; 4]; bw * bh];
+    for by in (0..height).step_by(bh) {
+        for bx in (0..width).step_by(bw) {
+            for y in 0..bh {
+                for x in 0..bw {
+                    let sx = (bx + x).min(width - 1);
+                    let sy = (by + y).min(height - 1);
+                    let i = (sy * width + sx) * 4;
+                    texels[y * bw + x] = [rgba[i], rgba[i + 1], rgba[i + 2], rgba[i + 3]];
+                }
+            }
+            match format {
+                GpuTextureFormat::Rgba8 => unreachable!(),
+                GpuTextureFormat::Bc1 => out.extend(encode_bc1(block4(&texels), true)),
+                GpuTextureFormat::Bc3 => out.extend(encode_bc3(block4(&texels))),
+                GpuTextureFormat::Bc4 => out.extend(encode_bc4(&channel(block4(&texels), 0))),
+                GpuTextureFormat::Bc5 => out.extend(encode_bc5(block4(&texels))),
+                GpuTextureFormat::Bc7 => out.extend(encode_bc7(block4(&texels))),
+                GpuTextureFormat::Etc2Rgb8 => out.extend(encode_etc2_rgb(block4(&texels))),
+                GpuTextureFormat::Etc2Rgba8 => out.extend(encode_etc2_rgba(block4(&texels))),
+                GpuTextureFormat::Astc4x4
+                | GpuTextureFormat::Astc6x6
+                | GpuTextureFormat::Astc8x8 => {
+                    out.extend(encode_astc(&texels, bw, bh));
+                },
+            }
+        }
+    }
+    out
+}
+
+fn block4(texels: &[[u8; 4]]) -> &[[u8; 4]; 16] {
+    texels.try_into().expect("4x4 block")
+}
+
+fn channel(block: &[[u8; 4]; 16], c: usize) -> [u8; 16] {
+    std::array::from_fn(|i| block[i][c])
+}
+
+/// Endpoints spanning the principal axis of the texels' first `channels`
+/// channels
+fn principal_endpoints(texels: &[[u8; 4]], channels: usize) -> ([f32; 4], [f32; 4]) {
+    let n = texels.len() as f32;
+    let mut mean = [0.0f32; 4];
+    for t in texels {
+        for c in 0..channels {
+            mean[c] += f32::from(t[c]) / n;
+        }
+    }
+
+    let mut cov = [[0.0f32; 4]; 4];
+    for t in texels {
+        for i in 0..channels {
+            for j in 0..channels {
+                cov[i][j] += (f32::from(t[i]) - mean[i]) * (f32::from(t[j]) - mean[j]);
+            }
+        }
+    }
+
+    // Power iteration for the dominant eigenvector
+    let mut axis = [1.0f32; 4];
+    for _ in 0..8 {
+        let mut next = [0.0f32; 4];
+        for i in 0..channels {
+            for j in 0..channels {
+                next[i] += cov[i][j] * axis[j];
+            }
+        }
+        let len = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if len < 1e-6 {
+            break;
+        }
+        axis = next.map(|v| v / len);
+    }
+
+    let project = |t: &[u8; 4]| {
+        (0..channels)
+            .map(|c| (f32::from(t[c]) - mean[c]) * axis[c])
+            .sum::<f32>()
+    };
+    let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+    for t in texels {
+        let p = project(t);
+        lo = lo.min(p);
+        hi = hi.max(p);
+    }
+    if lo > hi {
+        return (mean, mean);
+    }
+    let point =
+        |p: f32| -> [f32; 4] { std::array::from_fn(|c| (mean[c] + axis[c] * p).clamp(0.0, 255.0)) };
+    (point(lo), point(hi))
+}
+
+fn distance(a: [i32; 4], b: [u8; 4], channels: usize) -> i32 {
+    (0..channels).map(|c| (a[c] - i32::from(b[c])).pow(2)).sum()
+}
+
+/// Index of the closest palette entry
+fn nearest(palette: &[[i32; 4]], texel: [u8; 4], channels: usize) -> usize {
+    let mut best = (0, i32::MAX);
+    for (i, entry) in palette.iter().enumerate() {
+        let d = distance(*entry, texel, channels);
+        if d < best.1 {
+            best = (i, d);
+        }
+    }
+    best.0
+}
+
+fn to_565(color: [f32; 4]) -> u16 {
+    let r = (color[0] * 31.0 / 255.0).round() as u16;
+    let g = (color[1] * 63.0 / 255.0).round() as u16;
+    let b = (color[2] * 31.0 / 255.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn from_565(color: u16) -> [i32; 4] {
+    let r = i32::from(color >> 11) & 31;
+    let g = i32::from(color >> 5) & 63;
+    let b = i32::from(color) & 31;
+    [
+        (r << 3) | (r >> 2),
+        (g << 2) | (g >> 4),
+        (b << 3) | (b >> 2),
+        255,
+    ]
+}
+
+fn lerp(a: [i32; 4], b: [i32; 4], wa: i32, wb: i32) -> [i32; 4] {
+    std::array::from_fn(|c| (a[c] * wa + b[c] * wb) / (wa + wb))
+}
+
+/// Encode a BC1 block
+///
+/// With `punch_through`, blocks containing texels with alpha below 128 use the
+/// three color mode and mark those texels transparent.
+pub(crate) fn encode_bc1(block: &[[u8; 4]; 16], punch_through: bool) -> [u8; 8] {
+    let transparent = punch_through && block.iter().any(|t| t[3] < 128);
+    let opaque: Vec<[u8; 4]> = block
+        .iter()
+        .copied()
+        .filter(|t| !transparent || t[3] >= 128)
+        .collect();
+    let (lo, hi) = if opaque.is_empty() {
+        ([0.0; 4], [0.0; 4])
+    } else {
+        principal_endpoints(&opaque, 3)
+    };
+    let (mut c0, mut c1) = (to_565(hi), to_565(lo));
+
+    let indices: Vec<usize>;
+    if transparent {
+        // c0 <= c1 selects three colors plus transparent black
+        if c0 > c1 {
+            std::mem::swap(&mut c0, &mut c1);
+        }
+        let (e0, e1) = (from_565(c0), from_565(c1));
+        let palette = [e0, e1, lerp(e0, e1, 1, 1)];
+        indices = block
+            .iter()
+            .map(|&t| {
+                if t[3] < 128 {
+                    3
+                } else {
+                    nearest(&palette, t, 3)
+                }
+            })
+            .collect();
+    } else {
+        if c0 < c1 {
+            std::mem::swap(&mut c0, &mut c1);
+        }
+        let (e0, e1) = (from_565(c0), from_565(c1));
+        if c0 == c1 {
+            indices = vec![0; 16];
+        } else {
+            let palette = [e0, e1, lerp(e0, e1, 2, 1), lerp(e0, e1, 1, 2)];
+            indices = block.iter().map(|&t| nearest(&palette, t, 3)).collect();
+        }
+    }
+
+    let bits = indices
+        .iter()
+        .enumerate()
+        .fold(0u32, |bits, (i, &index)| bits | (index as u32) << (2 * i));
+    let mut out = [0u8; 8];
+    out[0..2].copy_from_slice(&c0.to_le_bytes());
+    out[2..4].copy_from_slice(&c1.to_le_bytes());
+    out[4..8].copy_from_slice(&bits.to_le_bytes());
+    out
+}
+
+/// Encode a BC4 block from one channel
+pub(crate) fn encode_bc4(values: &[u8; 16]) -> [u8; 8] {
+    let max = values.iter().copied().max().unwrap_or(0);
+    let min = values.iter().copied().min().unwrap_or(0);
+    let mut out = [0u8; 8];
+    out[0] = max;
+    out[1] = min;
+    if max == min {
+        return out;
+    }
+
+    // r0 > r1 selects six interpolated values between the endpoints
+    let (r0, r1) = (i32::from(max), i32::from(min));
+    let palette: Vec<[i32; 4]> = (0..8)
+        .map(|i| {
+            let v = match i {
+                0 => r0,
+                1 => r1,
+                _ => ((8 - i) * r0 + (i - 1) * r1) / 7,
+            };
+            [v, 0, 0, 0]
+        })
+        .collect();
+    let bits = values.iter().enumerate().fold(0u64, |bits, (i, &v)| {
+        bits | (nearest(&palette, [v, 0, 0, 0], 1) as u64) << (3 * i)
+    });
+    out[2..8].copy_from_slice(&bits.to_le_bytes()[..6]);
+    out
+}
+
+/// Encode a BC3 block: BC4 alpha followed by four color BC1
+pub(crate) fn encode_bc3(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    out[..8].copy_from_slice(&encode_bc4(&channel(block, 3)));
+    out[8..].copy_from_slice(&encode_bc1(block, false));
+    out
+}
+
+/// Encode a BC5 block from the red and green channels
+pub(crate) fn encode_bc5(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    out[..8].copy_from_slice(&encode_bc4(&channel(block, 0)));
+    out[8..].copy_from_slice(&encode_bc4(&channel(block, 1)));
+    out
+}
+
+/// Little-endian bit writer for BC7 and ASTC blocks
+struct BitWriter {
+    bits: u128,
+    offset: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self { bits: 0, offset: 0 }
+    }
+
+    fn write(&mut self, value: u32, count: u32) {
+        let mask = (1u128 << count) - 1;
+        self.bits |= (u128::from(value) & mask) << self.offset;
+        self.offset += count;
+    }
+}
+
+const BC7_WEIGHTS: [i32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+/// Quantize an endpoint to seven bits plus a shared p-bit
+fn bc7_endpoint(color: [f32; 4]) -> ([u32; 4], u32) {
+    let mut best = ([0; 4], 0, f32::MAX);
+    for p in 0..2u32 {
+        let mut error = 0.0;
+        let q = color.map(|v| {
+            let q = ((v - p as f32) / 2.0).round().clamp(0.0, 127.0) as u32;
+            error += (((q << 1) | p) as f32 - v).powi(2);
+            q
+        });
+        if error < best.2 {
+            best = (q, p, error);
+        }
+    }
+    (best.0, best.1)
+}
+
+/// Encode a BC7 block in mode 6: one RGBA subset with 4-bit indices
+pub(crate) fn encode_bc7(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let (lo, hi) = principal_endpoints(block, 4);
+    let (mut q0, mut p0) = bc7_endpoint(lo);
+    let (mut q1, mut p1) = bc7_endpoint(hi);
+
+    let palette = |q0: [u32; 4], p0: u32, q1: [u32; 4], p1: u32| -> Vec<[i32; 4]> {
+        let e0 = q0.map(|v| ((v << 1) | p0) as i32);
+        let e1 = q1.map(|v| ((v << 1) | p1) as i32);
+        BC7_WEIGHTS
+            .iter()
+            .map(|&w| std::array::from_fn(|c| ((64 - w) * e0[c] + w * e1[c] + 32) >> 6))
+            .collect()
+    };
+    let colors = palette(q0, p0, q1, p1);
+    let mut indices: Vec<u32> = block
+        .iter()
+        .map(|&t| nearest(&colors, t, 4) as u32)
+        .collect();
+
+    // The anchor index has an implicit zero high bit
+    if indices[0] >= 8 {
+        std::mem::swap(&mut q0, &mut q1);
+        std::mem::swap(&mut p0, &mut p1);
+        for index in &mut indices {
+            *index = 15 - *index;
+        }
+    }
+
+    let mut writer = BitWriter::new();
+    writer.write(1 << 6, 7);
+    for c in 0..4 {
+        writer.write(q0[c], 7);
+        writer.write(q1[c], 7);
+    }
+    writer.write(p0, 1);
+    writer.write(p1, 1);
+    for (i, &index) in indices.iter().enumerate() {
+        writer.write(index, if i == 0 { 3 } else { 4 });
+    }
+    writer.bits.to_le_bytes()
+}
+
+const ETC_MODIFIERS: [[i32; 2]; 8] = [
+    [2, 8],
+    [5, 17],
+    [9, 29],
+    [13, 42],
+    [18, 60],
+    [24, 80],
+    [33, 106],
+    [47, 183],
+];
+
+/// Texel indices of the two ETC sub-blocks, in pixel order `x * 4 + y`
+fn etc_subblocks(flip: bool) -> [[usize; 8]; 2] {
+    let mut subblocks = [[0; 8]; 2];
+    let mut counts = [0; 2];
+    for x in 0..4 {
+        for y in 0..4 {
+            let half = if flip {
+                usize::from(y >= 2)
+            } else {
+                usize::from(x >= 2)
+            };
+            subblocks[half][counts[half]] = x * 4 + y;
+            counts[half] += 1;
+        }
+    }
+    subblocks
+}
+
+/// Texel of a block at pixel index `x * 4 + y`
+fn etc_texel(block: &[[u8; 4]; 16], pixel: usize) -> [u8; 4] {
+    let (x, y) = (pixel / 4, pixel % 4);
+    block[y * 4 + x]
+}
+
+/// Best modifier table and per-pixel indices for a sub-block around `base`
+fn etc_fit(block: &[[u8; 4]; 16], pixels: &[usize; 8], base: [i32; 3]) -> (u32, [u32; 8], i32) {
+    let mut best = (0, [0; 8], i32::MAX);
+    for (table, &[a, b]) in ETC_MODIFIERS.iter().enumerate() {
+        let modifiers = [a, b, -a, -b];
+        let mut indices = [0; 8];
+        let mut error = 0;
+        for (slot, &pixel) in pixels.iter().enumerate() {
+            let texel = etc_texel(block, pixel);
+            let mut best_index = (0, i32::MAX);
+            for (index, &m) in modifiers.iter().enumerate() {
+                let color = [
+                    (base[0] + m).clamp(0, 255),
+                    (base[1] + m).clamp(0, 255),
+                    (base[2] + m).clamp(0, 255),
+                    0,
+                ];
+                let d = distance(color, texel, 3);
+                if d < best_index.1 {
+                    best_index = (index as u32, d);
+                }
+            }
+            indices[slot] = best_index.0;
+            error += best_index.1;
+        }
+        if error < best.2 {
+            best = (table as u32, indices, error);
+        }
+    }
+    best
+}
+
+fn etc_average(block: &[[u8; 4]; 16], pixels: &[usize; 8]) -> [f32; 3] {
+    let mut sum = [0.0f32; 3];
+    for &pixel in pixels {
+        let texel = etc_texel(block, pixel);
+        for c in 0..3 {
+            sum[c] += f32::from(texel[c]);
+        }
+    }
+    sum.map(|v| v / 8.0)
+}
+
+/// Encode an ETC2 RGB block using the individual or differential mode
+pub(crate) fn encode_etc2_rgb(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let mut best = (0u64, i32::MAX);
+    for flip in [false, true] {
+        let subblocks = etc_subblocks(flip);
+        let averages = subblocks.map(|pixels| etc_average(block, &pixels));
+
+        // Differential mode: 5-bit base plus a 3-bit signed delta
+        let q5 = averages.map(|avg| avg.map(|v| (v * 31.0 / 255.0).round() as i32));
+        let delta: [i32; 3] = std::array::from_fn(|c| q5[1][c] - q5[0][c]);
+        let differential = delta.iter().all(|d| (-4..=3).contains(d));
+
+        let (bases, mut bits) = if differential {
+            let expand = |v: i32| (v << 3) | (v >> 2);
+            let mut bits = 1u64 << 33;
+            for c in 0..3 {
+                let shift = 59 - 8 * c as u64;
+                bits |= (q5[0][c] as u64) << shift;
+                bits |= ((delta[c] & 7) as u64) << (shift - 3);
+            }
+            (q5.map(|q| q.map(expand)), bits)
+        } else {
+            let q4 = averages.map(|avg| avg.map(|v| (v * 15.0 / 255.0).round() as i32));
+            let mut bits = 0u64;
+            for (c, (a, b)) in q4[0].iter().zip(q4[1]).enumerate() {
+                let shift = 60 - 8 * c as u64;
+                bits |= (*a as u64) << shift;
+                bits |= (b as u64) << (shift - 4);
+            }
+            (q4.map(|q| q.map(|v| (v << 4) | v)), bits)
+        };
+        if flip {
+            bits |= 1 << 32;
+        }
+
+        let mut error = 0;
+        for (half, pixels) in subblocks.iter().enumerate() {
+            let (table, indices, e) = etc_fit(block, pixels, bases[half]);
+            error += e;
+            bits |= u64::from(table) << (37 - 3 * half as u64);
+            for (slot, &pixel) in pixels.iter().enumerate() {
+                let index = u64::from(indices[slot]);
+                bits |= (index >> 1) << (16 + pixel);
+                bits |= (index & 1) << pixel;
+            }
+        }
+        if error < best.1 {
+            best = (bits, error);
+        }
+    }
+    best.0.to_be_bytes()
+}
+
+const EAC_MODIFIERS: [[i32; 8]; 16] = [
+    [-3, -6, -9, -15, 2, 5, 8, 14],
+    [-3, -7, -10, -13, 2, 6, 9, 12],
+    [-2, -5, -8, -13, 1, 4, 7, 12],
+    [-2, -4, -6, -13, 1, 3, 5, 12],
+    [-3, -6, -8, -12, 2, 5, 7, 11],
+    [-3, -7, -9, -11, 2, 6, 8, 10],
+    [-4, -7, -8, -11, 3, 6, 7, 10],
+    [-3, -5, -8, -11, 2, 4, 7, 10],
+    [-2, -6, -8, -10, 1, 5, 7, 9],
+    [-2, -5, -8, -10, 1, 4, 7, 9],
+    [-2, -4, -8, -10, 1, 3, 7, 9],
+    [-2, -5, -7, -10, 1, 4, 6, 9],
+    [-3, -4, -7, -10, 2, 3, 6, 9],
+    [-1, -2, -3, -10, 0, 1, 2, 9],
+    [-4, -6, -8, -9, 3, 5, 7, 8],
+    [-3, -5, -7, -9, 2, 4, 6, 8],
+];
+
+/// Encode an EAC alpha block
+fn encode_eac(block: &[[u8; 4]; 16]) -> [u8; 8] {
+    let alpha: [i32; 16] = std::array::from_fn(|pixel| i32::from(etc_texel(block, pixel)[3]));
+    let min = alpha.iter().copied().min().unwrap_or(0);
+    let max = alpha.iter().copied().max().unwrap_or(0);
+    let base = (min + max + 1) / 2;
+
+    let mut best = (0u64, i32::MAX);
+    for (table, modifiers) in EAC_MODIFIERS.iter().enumerate() {
+        let span = modifiers[7] - modifiers[3];
+        let ideal = ((max - min) as f32 / span as f32).round() as i32;
+        for multiplier in (ideal - 1).max(1)..=(ideal + 1).clamp(1, 15) {
+            let mut bits = (base as u64) << 56 | (multiplier as u64) << 52 | (table as u64) << 48;
+            let mut error = 0;
+            for (pixel, &a) in alpha.iter().enumerate() {
+                let (index, e) = modifiers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, m)| (i, ((base + m * multiplier).clamp(0, 255) - a).pow(2)))
+                    .min_by_key(|&(_, e)| e)
+                    .unwrap_or((0, 0));
+                error += e;
+                bits |= (index as u64) << (45 - 3 * pixel);
+            }
+            if error < best.1 {
+                best = (bits, error);
+            }
+        }
+    }
+    best.0.to_be_bytes()
+}
+
+/// Encode an ETC2 RGBA block: EAC alpha followed by ETC2 color
+pub(crate) fn encode_etc2_rgba(block: &[[u8; 4]; 16]) -> [u8; 16] {
+    let mut out = [0u8; 16];
+    out[..8].copy_from_slice(&encode_eac(block));
+    out[8..].copy_from_slice(&encode_etc2_rgb(block));
+    out
+}
+
+/// ASTC weight grid used for every block size
+const ASTC_GRID: usize = 4;
+/// Block mode for a 4x4 grid of 2-bit weights, single plane
+const ASTC_BLOCK_MODE: u32 = 66;
+/// Color endpoint mode: LDR RGBA, direct
+const ASTC_CEM_RGBA: u32 = 12;
+const ASTC_WEIGHTS: [i32; 4] = [0, 21, 43, 64];
+
+/// Bilinear contributions of grid points to each texel, as the decoder infills
+/// them, in sixteenths
+fn astc_infill(width: usize, height: usize) -> Vec<[(usize, u32); 4]> {
+    let n = ASTC_GRID;
+    let ds = (1024 + width / 2) / (width - 1);
+    let dt = (1024 + height / 2) / (height - 1);
+    let mut contributions = Vec::with_capacity(width * height);
+    for t in 0..height {
+        for s in 0..width {
+            let gs = (ds * s * (n - 1) + 32) >> 6;
+            let gt = (dt * t * (n - 1) + 32) >> 6;
+            let (js, fs) = (gs >> 4, (gs & 15) as u32);
+            let (jt, ft) = (gt >> 4, (gt & 15) as u32);
+            let w11 = (fs * ft + 8) >> 4;
+            let v0 = jt * n + js;
+            let clamp = |i: usize| i.min(n * n - 1);
+            contributions.push([
+                (v0, 16 + w11 - fs - ft),
+                (clamp(v0 + 1), fs - w11),
+                (clamp(v0 + n), ft - w11),
+                (clamp(v0 + n + 1), w11),
+            ]);
+        }
+    }
+    contributions
+}
+
+/// Encode an ASTC LDR block of any size with one RGBA partition
+pub(crate) fn encode_astc(texels: &[[u8; 4]], width: usize, height: usize) -> [u8; 16] {
+    let (lo, hi) = principal_endpoints(texels, 4);
+    let mut e0 = lo.map(|v| v.round() as u32);
+    let mut e1 = hi.map(|v| v.round() as u32);
+
+    // Ideal position of each texel along the endpoint line
+    let axis: [f32; 4] = std::array::from_fn(|c| e1[c] as f32 - e0[c] as f32);
+    let length = axis.iter().map(|v| v * v).sum::<f32>();
+    let ideal: Vec<f32> = texels
+        .iter()
+        .map(|t| {
+            if length < 1e-6 {
+                return 0.0;
+            }
+            let dot: f32 = (0..4)
+                .map(|c| (f32::from(t[c]) - e0[c] as f32) * axis[c])
+                .sum();
+            (dot / length).clamp(0.0, 1.0)
+        })
+        .collect();
+
+    // Grid weights as the infill-weighted average of the texels they cover
+    let mut sums = [(0.0f32, 0.0f32); ASTC_GRID * ASTC_GRID];
+    for (texel, contributions) in astc_infill(width, height).iter().enumerate() {
+        for &(grid, weight) in contributions {
+            sums[grid].0 += ideal[texel] * weight as f32;
+            sums[grid].1 += weight as f32;
+        }
+    }
+    let mut weights = sums.map(|(sum, total)| {
+        let w = if total > 0.0 { sum / total } else { 0.0 };
+        let target = w * 64.0;
+        (0..4)
+            .min_by(|&a, &b| {
+                (ASTC_WEIGHTS[a] as f32 - target)
+                    .abs()
+                    .total_cmp(&(ASTC_WEIGHTS[b] as f32 - target).abs())
+            })
+            .unwrap_or(0) as u32
+    });
+
+    // The decoder swaps endpoints whose second color sums lower
+    if e1[..3].iter().sum::<u32>() < e0[..3].iter().sum::<u32>() {
+        std::mem::swap(&mut e0, &mut e1);
+        weights = weights.map(|w| 3 - w);
+    }
+
+    let mut writer = BitWriter::new();
+    writer.write(ASTC_BLOCK_MODE, 11);
+    writer.write(0, 2);
+    writer.write(ASTC_CEM_RGBA, 4);
+    for c in 0..4 {
+        writer.write(e0[c], 8);
+        writer.write(e1[c], 8);
+    }
+
+    // Weights fill the block from the top bit down
+    let mut weight_bits = 0u128;
+    for (i, &w) in weights.iter().enumerate() {
+        weight_bits |= u128::from(w) << (2 * i);
+    }
+    let bits = writer.bits | weight_bits.reverse_bits();
+    bits.to_le_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_bc1(block: &[u8]) -> Vec<[i32; 4]> {
+        let c0 = u16::from_le_bytes([block[0], block[1]]);
+        let c1 = u16::from_le_bytes([block[2], block[3]]);
+        let (e0, e1) = (from_565(c0), from_565(c1));
+        let palette = if c0 > c1 {
+            [e0, e1, lerp(e0, e1, 2, 1), lerp(e0, e1, 1, 2)]
+        } else {
+            [e0, e1, lerp(e0, e1, 1, 1), [0; 4]]
+        };
+        let bits = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+        (0..16)
+            .map(|i| palette[(bits >> (2 * i) & 3) as usize])
+            .collect()
+    }
+
+    fn decode_bc4(block: &[u8]) -> Vec<i32> {
+        let (r0, r1) = (i32::from(block[0]), i32::from(block[1]));
+        let mut bytes = [0u8; 8];
+        bytes[..6].copy_from_slice(&block[2..8]);
+        let bits = u64::from_le_bytes(bytes);
+        (0..16)
+            .map(|i| match (bits >> (3 * i)) & 7 {
+                0 => r0,
+                1 => r1,
+                k if r0 > r1 => ((8 - k as i32) * r0 + (k as i32 - 1) * r1) / 7,
+                k => ((6 - k as i32) * r0 + (k as i32 - 1) * r1) / 5,
+            })
+            .collect()
+    }
+
+    fn decode_bc7_mode6(block: &[u8]) -> Vec<[i32; 4]> {
+        let bits = u128::from_le_bytes(block.try_into().unwrap());
+        let read = |offset: u32, count: u32| ((bits >> offset) & ((1 << count) - 1)) as i32;
+        assert_eq!(read(0, 7), 1 << 6);
+        let (p0, p1) = (read(63, 1), read(64, 1));
+        let e0: [i32; 4] = std::array::from_fn(|c| (read(7 + 14 * c as u32, 7) << 1) | p0);
+        let e1: [i32; 4] = std::array::from_fn(|c| (read(14 + 14 * c as u32, 7) << 1) | p1);
+        (0..16)
+            .map(|i| {
+                let index = if i == 0 {
+                    read(65, 3)
+                } else {
+                    read(64 + 4 * i, 4)
+                };
+                let w = BC7_WEIGHTS[index as usize];
+                std::array::from_fn(|c| ((64 - w) * e0[c] + w * e1[c] + 32) >> 6)
+            })
+            .collect()
+    }
+
+    fn gradient() -> [[u8; 4]; 16] {
+        std::array::from_fn(|i| {
+            let v = (i * 16) as u8;
+            [v, 255 - v, v / 2, 255]
+        })
+    }
+
+    fn max_error(decoded: &[[i32; 4]], block: &[[u8; 4]; 16], channels: usize) -> i32 {
+        decoded
+            .iter()
+            .zip(block)
+            .flat_map(|(d, t)| (0..channels).map(move |c| (d[c] - i32::from(t[c])).abs()))
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn bc1_reproduces_gradients_and_punch_through() {
+        let block = gradient();
+        let decoded = decode_bc1(&encode_bc1(&block, true));
+        assert!(max_error(&decoded, &block, 3) <= 48);
+
+        let mut cutout = block;
+        cutout[5][3] = 0;
+        let encoded = encode_bc1(&cutout, true);
+        assert!(
+            u16::from_le_bytes([encoded[0], encoded[1]])
+                <= u16::from_le_bytes([encoded[2], encoded[3]])
+        );
+        assert_eq!(decode_bc1(&encoded)[5], [0; 4]);
+    }
+
+    #[test]
+    fn bc4_is_exact_for_two_values() {
+        let values: [u8; 16] = std::array::from_fn(|i| if i % 3 == 0 { 200 } else { 17 });
+        let decoded = decode_bc4(&encode_bc4(&values));
+        assert!(decoded.iter().zip(values).all(|(&d, v)| d == i32::from(v)));
+    }
+
+    #[test]
+    fn bc7_mode6_keeps_anchor_bit_clear() {
+        let mut block = gradient();
+        block.reverse();
+        for (i, texel) in block.iter_mut().enumerate() {
+            texel[3] = (i * 10) as u8;
+        }
+        let decoded = decode_bc7_mode6(&encode_bc7(&block));
+        assert!(max_error(&decoded, &block, 4) <= 8);
+    }
+
+    #[test]
+    fn astc_blocks_use_expected_mode_and_weight_layout() {
+        let texels = vec![[10, 20, 30, 255]; 36];
+        let block = u128::from_le_bytes(encode_astc(&texels, 6, 6));
+        assert_eq!(block & 0x7ff, u128::from(ASTC_BLOCK_MODE));
+        assert_eq!((block >> 13) & 0xf, u128::from(ASTC_CEM_RGBA));
+        assert_eq!((block >> 17) & 0xff, 10);
+
+        // Every grid point contributes to the texels of a smaller grid exactly
+        for contributions in astc_infill(4, 4) {
+            assert_eq!(contributions.iter().map(|c| c.1).sum::<u32>(), 16);
+            assert!(contributions.iter().filter(|c| c.1 > 0).count() == 1);
+        }
+    }
+}
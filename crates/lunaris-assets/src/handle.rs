@@ -1,7 +1,9 @@
 //! Asset handles and identifiers
 
 use lunaris_core::id::Id;
-use std::sync::Arc;
+use std::any::Any;
+use std::marker::PhantomData;
+use std::sync::{Arc, RwLock, Weak};
 
 /// Unique identifier for an asset
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -45,42 +47,152 @@ pub enum AssetState {
     Unloaded,
 }
 
-/// Handle to a loaded asset
-#[derive(Debug)]
-pub struct AssetHandle<T> {
+/// Type-erased asset data
+pub(crate) type AssetData = Arc<dyn Any + Send + Sync>;
+
+/// Load state shared by the manager and every handle to an asset
+pub(crate) struct AssetSlot {
+    inner: RwLock<SlotData>,
+}
+
+struct SlotData {
+    state: AssetState,
+    data: Option<AssetData>,
+    error: Option<String>,
+}
+
+impl AssetSlot {
+    pub(crate) fn new(state: AssetState) -> Self {
+        Self {
+            inner: RwLock::new(SlotData {
+                state,
+                data: None,
+                error: None,
+            }),
+        }
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, SlotData> {
+        self.inner
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, SlotData> {
+        self.inner
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    pub(crate) fn state(&self) -> AssetState {
+        self.read().state
+    }
+
+    pub(crate) fn data(&self) -> Option<AssetData> {
+        self.read().data.clone()
+    }
+
+    pub(crate) fn error(&self) -> Option<String> {
+        self.read().error.clone()
+    }
+
+    /// Store freshly loaded data, keeping the current state
+    pub(crate) fn set_data(&self, data: AssetData) {
+        let mut slot = self.write();
+        slot.data = Some(data);
+        slot.error = None;
+    }
+
+    pub(crate) fn publish(&self, state: AssetState) {
+        let mut slot = self.write();
+        slot.state = state;
+        if state != AssetState::Failed {
+            slot.error = None;
+        }
+    }
+
+    pub(crate) fn fail(&self, error: impl Into<String>) {
+        let mut slot = self.write();
+        slot.state = AssetState::Failed;
+        slot.error = Some(error.into());
+    }
+
+    pub(crate) fn clear(&self, state: AssetState) {
+        let mut slot = self.write();
+        slot.state = state;
+        slot.data = None;
+        slot.error = None;
+    }
+}
+
+impl std::fmt::Debug for AssetSlot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let slot = self.read();
+        f.debug_struct("AssetSlot")
+            .field("state", &slot.state)
+            .field("loaded", &slot.data.is_some())
+            .field("error", &slot.error)
+            .finish()
+    }
+}
+
+/// Handle to an asset
+///
+/// Handles share the asset's load slot with the [`AssetManager`](crate::AssetManager),
+/// so they observe background loads as the manager completes them. While any
+/// handle is alive the manager keeps the asset and its dependencies resident.
+pub struct AssetHandle<T: ?Sized> {
     /// Asset ID
     pub id: AssetId,
     /// Asset path
     pub path: String,
-    /// Current state
-    pub state: AssetState,
-    /// The actual asset data (None if not loaded)
-    pub data: Option<Arc<T>>,
+    slot: Arc<AssetSlot>,
+    _marker: PhantomData<fn() -> Box<T>>,
 }
 
-impl<T> AssetHandle<T> {
+/// Strong handle whose asset type is not known statically
+pub type UntypedHandle = AssetHandle<dyn Any + Send + Sync>;
+
+impl<T: ?Sized> AssetHandle<T> {
     /// Create a new handle for an asset path
+    ///
+    /// The handle is not attached to a manager and stays
+    /// [`AssetState::NotLoaded`].
     #[must_use]
     pub fn new(path: impl Into<String>) -> Self {
         let path = path.into();
+        Self::from_slot(
+            AssetId::from_path(&path),
+            path,
+            Arc::new(AssetSlot::new(AssetState::NotLoaded)),
+        )
+    }
+
+    pub(crate) fn from_slot(id: AssetId, path: String, slot: Arc<AssetSlot>) -> Self {
         Self {
-            id: AssetId::from_path(&path),
+            id,
             path,
-            state: AssetState::NotLoaded,
-            data: None,
+            slot,
+            _marker: PhantomData,
         }
     }
 
+    /// Current load state
+    #[must_use]
+    pub fn state(&self) -> AssetState {
+        self.slot.state()
+    }
+
     /// Check if the asset is loaded
     #[must_use]
     pub fn is_loaded(&self) -> bool {
-        self.state == AssetState::Loaded && self.data.is_some()
+        self.state() == AssetState::Loaded
     }
 
-    /// Get the asset data (if loaded)
+    /// Why the asset failed to load, if it did
     #[must_use]
-    pub fn get(&self) -> Option<&Arc<T>> {
-        self.data.as_ref()
+    pub fn error(&self) -> Option<String> {
+        self.slot.error()
     }
 
     /// Get the asset path
@@ -88,16 +200,54 @@ impl<T> AssetHandle<T> {
     pub fn path(&self) -> &str {
         &self.path
     }
+
+    /// Create a weak handle that does not keep the asset resident
+    #[must_use]
+    pub fn downgrade(&self) -> WeakHandle {
+        WeakHandle::from_strong(self)
+    }
+
+    /// Erase the asset type
+    #[must_use]
+    pub fn untyped(&self) -> UntypedHandle {
+        AssetHandle::from_slot(self.id, self.path.clone(), Arc::clone(&self.slot))
+    }
 }
 
-impl<T> Clone for AssetHandle<T> {
-    fn clone(&self) -> Self {
-        Self {
-            id: self.id,
-            path: self.path.clone(),
-            state: self.state,
-            data: self.data.clone(),
+impl<T: Send + Sync + 'static> AssetHandle<T> {
+    /// Get the asset data (if loaded)
+    #[must_use]
+    pub fn get(&self) -> Option<Arc<T>> {
+        if !self.is_loaded() {
+            return None;
         }
+        self.slot.data()?.downcast::<T>().ok()
+    }
+}
+
+impl UntypedHandle {
+    /// View the asset as `T`
+    ///
+    /// [`get`](AssetHandle::get) returns `None` if the asset is not a `T`.
+    #[must_use]
+    pub fn typed<T>(&self) -> AssetHandle<T> {
+        AssetHandle::from_slot(self.id, self.path.clone(), Arc::clone(&self.slot))
+    }
+}
+
+impl<T: ?Sized> Clone for AssetHandle<T> {
+    fn clone(&self) -> Self {
+        Self::from_slot(self.id, self.path.clone(), Arc::clone(&self.slot))
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for AssetHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AssetHandle")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .field("state", &self.state())
+            .finish()
     }
 }
 
@@ -111,15 +261,38 @@ pub struct WeakHandle {
     pub id: AssetId,
     /// Asset path
     pub path: String,
+    slot: Weak<AssetSlot>,
 }
 
 impl WeakHandle {
     /// Create a weak handle from a strong handle
     #[must_use]
-    pub fn from_strong<T>(handle: &AssetHandle<T>) -> Self {
+    pub fn from_strong<T: ?Sized>(handle: &AssetHandle<T>) -> Self {
         Self {
             id: handle.id,
             path: handle.path.clone(),
+            slot: Arc::downgrade(&handle.slot),
         }
     }
+
+    /// Get a strong handle if the asset is still resident
+    #[must_use]
+    pub fn upgrade<T>(&self) -> Option<AssetHandle<T>> {
+        let slot = self.slot.upgrade()?;
+        matches!(slot.state(), AssetState::Loading | AssetState::Loaded)
+            .then(|| AssetHandle::from_slot(self.id, self.path.clone(), slot))
+    }
+
+    /// Whether the asset is still resident
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.slot
+            .upgrade()
+            .is_some_and(|slot| matches!(slot.state(), AssetState::Loading | AssetState::Loaded))
+    }
+}
+
+/// Number of live handles sharing a manager-owned slot
+pub(crate) fn handle_count(slot: &Arc<AssetSlot>) -> usize {
+    Arc::strong_count(slot).saturating_sub(1)
 }
@@ -0,0 +1,438 @@
+//! Import cache
+//!
+//! Stores [`ImportResult`]s under a key derived from the source contents and
+//! its import settings, so unchanged sources are not processed again. Per-source
+//! settings overrides live in a `.meta` sidecar next to the source file.
+
+use crate::asset_pipeline::{
+    AudioImportSettings, ImportError, ImportResult, ModelImportSettings, TextureImportSettings,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Bump when importer output changes so old cache entries are ignored
+const IMPORTER_VERSION: u32 = 2;
+
+/// File name of the file stamp manifest inside the cache directory
+const MANIFEST_FILE: &str = "import_cache.json";
+
+/// Import settings overrides stored in a `.meta` sidecar
+///
+/// A sidecar for `hero.gltf` is named `hero.gltf.meta`. Sections that are not
+/// present use the importer's settings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssetMeta {
+    /// Model settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<ModelImportSettings>,
+    /// Texture settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub texture: Option<TextureImportSettings>,
+    /// Audio settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioImportSettings>,
+}
+
+impl AssetMeta {
+    /// Path of the sidecar for a source file
+    #[must_use]
+    pub fn path_for(source: &Path) -> PathBuf {
+        let mut name = source.as_os_str().to_owned();
+        name.push(".meta");
+        PathBuf::from(name)
+    }
+
+    /// Whether a path is a sidecar rather than a source
+    #[must_use]
+    pub fn is_meta(path: &Path) -> bool {
+        path.extension().is_some_and(|e| e == "meta")
+    }
+
+    /// Read the sidecar of a source, if it has one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sidecar exists but cannot be read or parsed.
+    pub fn load(source: &Path) -> Result<Option<Self>, ImportError> {
+        let path = Self::path_for(source);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(ImportError::IoError(e.to_string())),
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|e| ImportError::ParseError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Write the sidecar of a source
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sidecar cannot be written.
+    pub fn save(&self, source: &Path) -> Result<(), ImportError> {
+        let text = serde_json::to_string_pretty(self)
+            .map_err(|e| ImportError::ParseError(e.to_string()))?;
+        std::fs::write(Self::path_for(source), text)
+            .map_err(|e| ImportError::IoError(e.to_string()))
+    }
+}
+
+/// Size, modification time and content hash of a file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    modified_secs: u64,
+    modified_nanos: u32,
+    hash: String,
+}
+
+/// A cached import and the dependency contents it was produced from
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedImport {
+    result: ImportResult,
+    dependencies: Vec<(PathBuf, String)>,
+}
+
+/// Content-addressed store of import results
+///
+/// Content hashes are remembered together with each file's size and
+/// modification time, so a file is only read again after it changed on disk.
+#[derive(Debug)]
+pub struct ImportCache {
+    dir: PathBuf,
+    files: HashMap<PathBuf, FileStamp>,
+    dirty: bool,
+}
+
+impl ImportCache {
+    /// Open the cache in a directory, reading its manifest if present
+    ///
+    /// A missing or unreadable manifest starts an empty cache.
+    #[must_use]
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let files = std::fs::read(dir.join(MANIFEST_FILE))
+            .ok()
+            .and_then(|bytes| match serde_json::from_slice(&bytes) {
+                Ok(files) => Some(files),
+                Err(e) => {
+                    tracing::warn!("Ignoring corrupt import cache manifest: {}", e);
+                    None
+                },
+            })
+            .unwrap_or_default();
+        Self {
+            dir,
+            files,
+            dirty: false,
+        }
+    }
+
+    /// Cache directory
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Content hash of a file, read from disk only if it changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn content_hash(&mut self, path: &Path) -> Result<String, ImportError> {
+        let metadata = std::fs::metadata(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => ImportError::FileNotFound,
+            _ => ImportError::IoError(e.to_string()),
+        })?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        if let Some(stamp) = self.files.get(path) {
+            if stamp.size == metadata.len()
+                && stamp.modified_secs == modified.as_secs()
+                && stamp.modified_nanos == modified.subsec_nanos()
+            {
+                return Ok(stamp.hash.clone());
+            }
+        }
+
+        let bytes = std::fs::read(path).map_err(|e| ImportError::IoError(e.to_string()))?;
+        let hash = format!("{:032x}", xxhash_rust::xxh3::xxh3_128(&bytes));
+        self.files.insert(
+            path.to_path_buf(),
+            FileStamp {
+                size: metadata.len(),
+                modified_secs: modified.as_secs(),
+                modified_nanos: modified.subsec_nanos(),
+                hash: hash.clone(),
+            },
+        );
+        self.dirty = true;
+        Ok(hash)
+    }
+
+    /// Cache key of a source imported into `output_dir` with the given
+    /// settings hash
+    fn key(
+        &mut self,
+        source: &Path,
+        output_dir: &Path,
+        settings_hash: u64,
+    ) -> Result<String, ImportError> {
+        let content = self.content_hash(source)?;
+        // Outputs are named after the file stem, so it is part of the key
+        let stem = source.file_stem().unwrap_or_default().to_string_lossy();
+        let output_dir = output_dir.display();
+        let key =
+            format!("{IMPORTER_VERSION}:{content}:{settings_hash:016x}:{stem}:{output_dir}");
+        Ok(format!(
+            "{:032x}",
+            xxhash_rust::xxh3::xxh3_128(key.as_bytes())
+        ))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join("imports").join(format!("{key}.json"))
+    }
+
+    /// Cached result for a source, if neither it, its settings nor any of its
+    /// dependencies changed since it was stored and all of its outputs still
+    /// exist in `output_dir`
+    pub fn lookup(
+        &mut self,
+        source: &Path,
+        output_dir: &Path,
+        settings_hash: u64,
+    ) -> Option<ImportResult> {
+        let key = self.key(source, output_dir, settings_hash).ok()?;
+        let bytes = std::fs::read(self.entry_path(&key)).ok()?;
+        let cached: CachedImport = serde_json::from_slice(&bytes).ok()?;
+        for (dependency, hash) in &cached.dependencies {
+            if self.content_hash(dependency).ok().as_ref() != Some(hash) {
+                tracing::debug!(
+                    "Import of {} is stale: {} changed",
+                    source.display(),
+                    dependency.display()
+                );
+                return None;
+            }
+        }
+        if let Some(missing) = cached.result.outputs.iter().find(|output| !output.is_file()) {
+            tracing::debug!(
+                "Import of {} is stale: {} is missing",
+                source.display(),
+                missing.display()
+            );
+            return None;
+        }
+
+        let mut result = cached.result;
+        result.source = source.to_path_buf();
+        Some(result)
+    }
+
+    /// Store the result of importing a source
+    ///
+    /// Files listed in [`ImportResult::dependencies`] are hashed so that
+    /// changing one of them invalidates the entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source cannot be hashed or the entry cannot be
+    /// written.
+    pub fn store(
+        &mut self,
+        source: &Path,
+        output_dir: &Path,
+        settings_hash: u64,
+        result: &ImportResult,
+    ) -> Result<(), ImportError> {
+        let key = self.key(source, output_dir, settings_hash)?;
+        let mut dependencies = Vec::new();
+        for dependency in &result.dependencies {
+            // A missing dependency is recorded so that creating it invalidates
+            let hash = self.content_hash(dependency).unwrap_or_default();
+            dependencies.push((dependency.clone(), hash));
+        }
+
+        let entry = CachedImport {
+            result: result.clone(),
+            dependencies,
+        };
+        let path = self.entry_path(&key);
+        let bytes =
+            serde_json::to_vec(&entry).map_err(|e| ImportError::ParseError(e.to_string()))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| ImportError::IoError(e.to_string()))?;
+        }
+        std::fs::write(path, bytes).map_err(|e| ImportError::IoError(e.to_string()))
+    }
+
+    /// Write the file stamp manifest if it changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest cannot be written.
+    pub fn save(&mut self) -> Result<(), ImportError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&self.dir).map_err(|e| ImportError::IoError(e.to_string()))?;
+        let bytes =
+            serde_json::to_vec(&self.files).map_err(|e| ImportError::ParseError(e.to_string()))?;
+        std::fs::write(self.dir.join(MANIFEST_FILE), bytes)
+            .map_err(|e| ImportError::IoError(e.to_string()))?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Remove every cached import
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be removed.
+    pub fn clear(&mut self) -> Result<(), ImportError> {
+        self.files.clear();
+        self.dirty = false;
+        match std::fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(ImportError::IoError(e.to_string()))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Hash of a settings value, used in cache keys
+#[must_use]
+pub fn settings_hash<T: Serialize>(settings: &T) -> u64 {
+    let json = serde_json::to_vec(settings).unwrap_or_default();
+    xxhash_rust::xxh3::xxh3_64(&json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset_pipeline::AssetImporter;
+    use tempfile::TempDir;
+
+    fn png(shade: u8) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(4, 4, image::Rgba([shade, shade, shade, 255]));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    /// glTF with an embedded triangle and an external texture
+    fn textured_triangle() -> String {
+        let mut bin = Vec::new();
+        for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        use base64::Engine as _;
+        let data = base64::engine::general_purpose::STANDARD.encode(&bin);
+        serde_json::json!({
+            "asset": { "version": "2.0" },
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "mesh": 0 }],
+            "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "material": 0 }] }],
+            "materials": [{ "pbrMetallicRoughness": { "baseColorTexture": { "index": 0 } } }],
+            "textures": [{ "source": 0 }],
+            "images": [{ "uri": "albedo.png" }],
+            "accessors": [{
+                "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0]
+            }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 36 }],
+            "buffers": [{
+                "byteLength": 36,
+                "uri": format!("data:application/octet-stream;base64,{data}")
+            }]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn reimports_only_when_source_or_settings_change() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        let source = dir.join("rock.png");
+        std::fs::write(&source, png(10)).unwrap();
+        let mut importer = AssetImporter::new(dir.join("out"), dir.join("cache"));
+
+        assert!(!importer.import(&source).unwrap().cached);
+        assert!(importer.import(&source).unwrap().cached);
+
+        std::fs::write(&source, png(200)).unwrap();
+        assert!(!importer.import(&source).unwrap().cached);
+
+        let meta = AssetMeta {
+            texture: Some(TextureImportSettings {
+                srgb: false,
+                ..TextureImportSettings::default()
+            }),
+            ..AssetMeta::default()
+        };
+        meta.save(&source).unwrap();
+        assert!(importer.is_stale(&source));
+        assert!(!importer.import(&source).unwrap().cached);
+        assert!(importer.import(&source).unwrap().cached);
+
+        // The manifest survives a restart
+        drop(importer);
+        let mut importer = AssetImporter::new(dir.join("out"), dir.join("cache"));
+        assert!(importer.import(&source).unwrap().cached);
+        let results = importer.import_directory(dir);
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn reimports_when_outputs_move_or_disappear() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        let source = dir.join("rock.png");
+        std::fs::write(&source, png(10)).unwrap();
+        let mut importer = AssetImporter::new(dir.join("out"), dir.join("cache"));
+
+        let first = importer.import(&source).unwrap();
+        assert!(importer.import(&source).unwrap().cached);
+
+        importer.output_dir = dir.join("other");
+        let moved = importer.import(&source).unwrap();
+        assert!(!moved.cached);
+        assert!(moved.outputs.iter().all(|output| output.starts_with(dir.join("other"))));
+
+        importer.output_dir = dir.join("out");
+        assert!(importer.import(&source).unwrap().cached);
+        std::fs::remove_file(&first.outputs[0]).unwrap();
+        assert!(importer.is_stale(&source));
+        assert!(!importer.import(&source).unwrap().cached);
+        assert!(first.outputs[0].is_file());
+    }
+
+    #[test]
+    fn dependency_changes_invalidate_dependents() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        let model = dir.join("crate.gltf");
+        let texture = dir.join("albedo.png");
+        std::fs::write(&model, textured_triangle()).unwrap();
+        std::fs::write(&texture, b"pixels").unwrap();
+        let mut importer = AssetImporter::new(dir.join("out"), dir.join("cache"));
+
+        let first = importer.import(&model).unwrap();
+        assert_eq!(first.dependencies, vec![texture.clone()]);
+        assert!(importer.import(&model).unwrap().cached);
+
+        std::fs::write(&texture, b"new pixels").unwrap();
+        assert!(importer.is_stale(&model));
+        assert!(!importer.import(&model).unwrap().cached);
+        assert!(importer.import(&model).unwrap().cached);
+    }
+}
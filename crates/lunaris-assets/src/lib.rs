@@ -15,16 +15,25 @@
 #![warn(clippy::all)]
 
 pub mod asset_pipeline;
+pub mod audio;
+pub(crate) mod block_compression;
 pub mod handle;
+pub mod import_cache;
 pub mod loader;
 pub mod manager;
+pub mod model;
 pub mod starter_pack;
 pub mod streaming;
+pub mod texture_cooker;
 
-pub use handle::{AssetHandle, AssetId, AssetState};
+pub use audio::AudioStream;
+pub use handle::{AssetHandle, AssetId, AssetState, UntypedHandle, WeakHandle};
+pub use import_cache::{AssetMeta, ImportCache};
 pub use loader::AssetLoader;
-pub use manager::AssetManager;
+pub use manager::{AssetEvent, AssetManager, Retainer};
+pub use model::{ImportedModel, ModelLoader};
 pub use streaming::*;
+pub use texture_cooker::{CookOutput, TextureCooker};
 
 use lunaris_core::Result;
 
@@ -60,7 +69,9 @@ impl AssetType {
     #[must_use]
     pub fn from_extension(ext: &str) -> Option<Self> {
         match ext.to_lowercase().as_str() {
-            "png" | "jpg" | "jpeg" | "bmp" | "tga" | "webp" => Some(Self::Texture),
+            "png" | "jpg" | "jpeg" | "bmp" | "tga" | "webp" | "hdr" | "exr" | "tex" => {
+                Some(Self::Texture)
+            },
             "wav" | "ogg" | "mp3" | "flac" => Some(Self::Audio),
             "gltf" | "glb" | "obj" | "fbx" => Some(Self::Model),
             "wgsl" | "glsl" | "hlsl" => Some(Self::Shader),
@@ -1,7 +1,12 @@
 //! Asset loaders
 
-use lunaris_core::Result;
-use std::path::Path;
+use crate::handle::AssetData;
+use image::DynamicImage;
+use lunaris_core::{Error, Result};
+use lunaris_renderer::CookedTexture;
+use std::any::TypeId;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Trait for loading assets of a specific type
 pub trait AssetLoader: Send + Sync {
@@ -13,31 +18,162 @@ pub trait AssetLoader: Send + Sync {
 
     /// Load an asset from bytes
     fn load(&self, path: &Path, bytes: &[u8]) -> Result<Self::Asset>;
+
+    /// Files referenced by a loaded asset
+    ///
+    /// Paths are resolved the same way as the path passed to [`load`](Self::load).
+    /// The [`AssetManager`](crate::AssetManager) loads them before reporting the
+    /// asset as loaded.
+    fn dependencies(&self, _asset: &Self::Asset) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Output of a type-erased loader
+pub(crate) struct LoadedAsset {
+    pub data: AssetData,
+    pub dependencies: Vec<PathBuf>,
+}
+
+/// Object-safe view of an [`AssetLoader`], used by the manager's registry
+pub(crate) trait ErasedLoader: Send + Sync {
+    fn asset_type(&self) -> TypeId;
+    fn asset_type_name(&self) -> &'static str;
+    fn load_erased(&self, path: &Path, bytes: &[u8]) -> Result<LoadedAsset>;
+}
+
+impl<L: AssetLoader> ErasedLoader for L {
+    fn asset_type(&self) -> TypeId {
+        TypeId::of::<L::Asset>()
+    }
+
+    fn asset_type_name(&self) -> &'static str {
+        std::any::type_name::<L::Asset>()
+    }
+
+    fn load_erased(&self, path: &Path, bytes: &[u8]) -> Result<LoadedAsset> {
+        let asset = self.load(path, bytes)?;
+        let dependencies = self.dependencies(&asset);
+        Ok(LoadedAsset {
+            data: Arc::new(asset),
+            dependencies,
+        })
+    }
 }
 
 /// Built-in texture loader
-#[derive(Debug, Default)]
-pub struct TextureLoader;
+///
+/// Decodes PNG, JPEG, TGA, BMP, WebP, HDR and EXR images. Grayscale images become
+/// [`TextureFormat::R8`] or [`TextureFormat::R16`], other 8-bit and 16-bit
+/// images are expanded to RGBA and HDR/EXR images load as
+/// [`TextureFormat::Rgba32F`].
+#[derive(Debug, Clone)]
+pub struct TextureLoader {
+    /// Treat 8-bit color images as sRGB encoded
+    pub srgb: bool,
+    /// Generate a full mip chain
+    pub generate_mips: bool,
+}
+
+impl Default for TextureLoader {
+    fn default() -> Self {
+        Self {
+            srgb: true,
+            generate_mips: true,
+        }
+    }
+}
+
+impl TextureLoader {
+    /// Set whether 8-bit color images are sRGB (disable for normal maps
+    /// and other data textures)
+    #[must_use]
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Set whether a mip chain is generated
+    #[must_use]
+    pub fn with_mips(mut self, generate_mips: bool) -> Self {
+        self.generate_mips = generate_mips;
+        self
+    }
+}
 
 impl AssetLoader for TextureLoader {
     type Asset = TextureAsset;
 
     fn extensions(&self) -> &[&str] {
-        &["png", "jpg", "jpeg", "bmp", "tga", "webp"]
+        &["png", "jpg", "jpeg", "bmp", "tga", "webp", "hdr", "exr"]
     }
 
     fn load(&self, path: &Path, bytes: &[u8]) -> Result<Self::Asset> {
-        // In real implementation, would decode image
         tracing::debug!("Loading texture: {:?} ({} bytes)", path, bytes.len());
-        Ok(TextureAsset {
-            width: 256,
-            height: 256,
-            format: TextureFormat::Rgba8,
-            data: bytes.to_vec(),
-        })
+
+        // TGA has no magic number, so fall back to the extension
+        let format = image::guess_format(bytes)
+            .or_else(|_| image::ImageFormat::from_path(path))
+            .map_err(|e| Error::Asset(format!("Unknown image format {}: {e}", path.display())))?;
+        let image = image::load_from_memory_with_format(bytes, format)
+            .map_err(|e| Error::Asset(format!("Failed to decode {}: {e}", path.display())))?;
+
+        let (width, height) = (image.width(), image.height());
+        let (format, data) = match image {
+            DynamicImage::ImageLuma8(image) => (TextureFormat::R8, image.into_raw()),
+            DynamicImage::ImageLuma16(image) => (TextureFormat::R16, le_bytes(image.as_raw())),
+            DynamicImage::ImageLumaA16(_)
+            | DynamicImage::ImageRgb16(_)
+            | DynamicImage::ImageRgba16(_) => {
+                (TextureFormat::Rgba16, le_bytes(image.to_rgba16().as_raw()))
+            },
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_) => {
+                let pixels = image.to_rgba32f();
+                let data = pixels
+                    .as_raw()
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect();
+                (TextureFormat::Rgba32F, data)
+            },
+            _ => (TextureFormat::Rgba8, image.to_rgba8().into_raw()),
+        };
+
+        let mut texture = TextureAsset::new(width, height, format, data);
+        texture.srgb = self.srgb && format.supports_srgb();
+        if self.generate_mips {
+            texture.generate_mips();
+        }
+        Ok(texture)
+    }
+}
+
+/// Loader for `.tex` files written by the [`TextureCooker`](crate::TextureCooker)
+///
+/// Cooked textures are already in their GPU format, so loading only validates
+/// the container.
+#[derive(Debug, Default)]
+pub struct CookedTextureLoader;
+
+impl AssetLoader for CookedTextureLoader {
+    type Asset = CookedTexture;
+
+    fn extensions(&self) -> &[&str] {
+        &["tex"]
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Self::Asset> {
+        tracing::debug!("Loading cooked texture: {:?}", path);
+        CookedTexture::from_bytes(bytes)
+            .map_err(|e| Error::Asset(format!("{}: {e}", path.display())))
     }
 }
 
+/// Little-endian bytes of 16-bit channels
+fn le_bytes(channels: &[u16]) -> Vec<u8> {
+    channels.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
 /// Texture asset data
 #[derive(Debug, Clone)]
 pub struct TextureAsset {
@@ -47,10 +183,107 @@ pub struct TextureAsset {
     pub height: u32,
     /// Pixel format
     pub format: TextureFormat,
-    /// Raw pixel data
+    /// Color channels are sRGB encoded
+    pub srgb: bool,
+    /// Number of mip levels stored in `data`
+    pub mip_levels: u32,
+    /// Raw pixel data of every mip level, largest first
     pub data: Vec<u8>,
 }
 
+impl TextureAsset {
+    /// Create a linear texture with a single mip level
+    #[must_use]
+    pub fn new(width: u32, height: u32, format: TextureFormat, data: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            format,
+            srgb: false,
+            mip_levels: 1,
+            data,
+        }
+    }
+
+    /// Dimensions of a mip level
+    #[must_use]
+    pub fn mip_size(&self, level: u32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Pixel data of a mip level
+    #[must_use]
+    pub fn mip_data(&self, level: u32) -> Option<&[u8]> {
+        if level >= self.mip_levels {
+            return None;
+        }
+        let offset: usize = (0..level).map(|l| self.mip_len(l)).sum();
+        self.data.get(offset..offset + self.mip_len(level))
+    }
+
+    fn mip_len(&self, level: u32) -> usize {
+        let (width, height) = self.mip_size(level);
+        width as usize * height as usize * self.format.bytes_per_pixel()
+    }
+
+    /// Replace any existing mips with a full chain down to 1x1
+    ///
+    /// Each level is a 2x2 box filter of the previous one. sRGB colors are
+    /// averaged in linear space.
+    pub fn generate_mips(&mut self) {
+        let levels = 32 - self.width.max(self.height).max(1).leading_zeros();
+        self.data.truncate(self.mip_len(0));
+        self.mip_levels = 1;
+
+        let mut texels = self.format.decode(&self.data, self.srgb);
+        for level in 1..levels {
+            let (width, height) = self.mip_size(level - 1);
+            texels = downsample(&texels, width, height, self.format.channels());
+            self.data.extend(self.format.encode(&texels, self.srgb));
+            self.mip_levels += 1;
+        }
+    }
+}
+
+/// Halve an image with a 2x2 box filter
+pub(crate) fn downsample(texels: &[f32], width: u32, height: u32, channels: usize) -> Vec<f32> {
+    let (width, height) = (width as usize, height as usize);
+    let (out_width, out_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut out = Vec::with_capacity(out_width * out_height * channels);
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let xs = [2 * x, (2 * x + 1).min(width - 1)];
+            let ys = [2 * y, (2 * y + 1).min(height - 1)];
+            for c in 0..channels {
+                let mut sum = 0.0;
+                for sy in ys {
+                    for sx in xs {
+                        sum += texels[(sy * width + sx) * channels + c];
+                    }
+                }
+                out.push(sum * 0.25);
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 /// Texture formats
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextureFormat {
@@ -60,25 +293,132 @@ pub enum TextureFormat {
     Rgb8,
     /// Grayscale
     R8,
+    /// Grayscale 16-bit
+    R16,
+    /// RGBA 16-bit
+    Rgba16,
+    /// RGBA 32-bit float
+    Rgba32F,
+}
+
+impl TextureFormat {
+    /// Number of channels per pixel
+    #[must_use]
+    pub fn channels(self) -> usize {
+        match self {
+            Self::R8 | Self::R16 => 1,
+            Self::Rgb8 => 3,
+            Self::Rgba8 | Self::Rgba16 | Self::Rgba32F => 4,
+        }
+    }
+
+    /// Size of a pixel in bytes
+    #[must_use]
+    pub fn bytes_per_pixel(self) -> usize {
+        self.channels() * self.bytes_per_channel()
+    }
+
+    fn bytes_per_channel(self) -> usize {
+        match self {
+            Self::Rgba8 | Self::Rgb8 | Self::R8 => 1,
+            Self::R16 | Self::Rgba16 => 2,
+            Self::Rgba32F => 4,
+        }
+    }
+
+    /// Whether the format has an sRGB variant
+    #[must_use]
+    pub fn supports_srgb(self) -> bool {
+        matches!(self, Self::Rgba8 | Self::Rgb8)
+    }
+
+    /// Decode pixel data into linear float channels
+    fn decode(self, data: &[u8], srgb: bool) -> Vec<f32> {
+        let channels = self.channels();
+        let mut texels: Vec<f32> = match self.bytes_per_channel() {
+            1 => data.iter().map(|&v| f32::from(v) / 255.0).collect(),
+            2 => data
+                .chunks_exact(2)
+                .map(|b| f32::from(u16::from_le_bytes([b[0], b[1]])) / 65535.0)
+                .collect(),
+            _ => data
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        };
+        if srgb {
+            // Alpha is always linear
+            for (i, texel) in texels.iter_mut().enumerate() {
+                if i % channels < 3 {
+                    *texel = srgb_to_linear(*texel);
+                }
+            }
+        }
+        texels
+    }
+
+    /// Encode linear float channels into pixel data
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn encode(self, texels: &[f32], srgb: bool) -> Vec<u8> {
+        let channels = self.channels();
+        let value = |i: usize, v: f32| {
+            if srgb && i % channels < 3 {
+                linear_to_srgb(v)
+            } else {
+                v
+            }
+        };
+        match self.bytes_per_channel() {
+            1 => texels
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| (value(i, v).clamp(0.0, 1.0) * 255.0).round() as u8)
+                .collect(),
+            2 => texels
+                .iter()
+                .enumerate()
+                .flat_map(|(i, &v)| {
+                    ((value(i, v).clamp(0.0, 1.0) * 65535.0).round() as u16).to_le_bytes()
+                })
+                .collect(),
+            _ => texels.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
 }
 
 /// Built-in audio loader
-#[derive(Debug, Default)]
-pub struct AudioLoader;
+///
+/// Decodes WAV, OGG Vorbis, MP3 and FLAC files up front. Long music tracks
+/// should be played through an [`AudioStream`](crate::audio::AudioStream)
+/// instead.
+#[derive(Debug, Clone, Default)]
+pub struct AudioLoader {
+    /// Convert to this sample rate (`None` keeps the file's rate)
+    pub sample_rate: Option<u32>,
+}
+
+impl AudioLoader {
+    /// Resample decoded audio to `sample_rate`
+    #[must_use]
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+}
 
 impl AssetLoader for AudioLoader {
     type Asset = AudioAsset;
 
     fn extensions(&self) -> &[&str] {
-        &["wav", "ogg", "mp3"]
+        &["wav", "ogg", "mp3", "flac"]
     }
 
     fn load(&self, path: &Path, bytes: &[u8]) -> Result<Self::Asset> {
         tracing::debug!("Loading audio: {:?} ({} bytes)", path, bytes.len());
-        Ok(AudioAsset {
-            sample_rate: 44100,
-            channels: 2,
-            samples: Vec::new(), // Would decode audio
+        let extension = path.extension().and_then(|e| e.to_str());
+        crate::audio::decode(bytes, extension, self.sample_rate).map_err(|e| match e {
+            Error::Asset(message) => Error::Asset(format!("{}: {message}", path.display())),
+            other => other,
         })
     }
 }
@@ -90,10 +430,19 @@ pub struct AudioAsset {
     pub sample_rate: u32,
     /// Number of channels
     pub channels: u16,
-    /// Audio samples
+    /// Audio samples (interleaved)
     pub samples: Vec<f32>,
 }
 
+impl AudioAsset {
+    /// Playback length
+    #[must_use]
+    pub fn duration(&self) -> std::time::Duration {
+        let frames = self.samples.len() / usize::from(self.channels.max(1));
+        std::time::Duration::from_secs_f64(frames as f64 / f64::from(self.sample_rate.max(1)))
+    }
+}
+
 /// Built-in JSON loader
 #[derive(Debug, Default)]
 pub struct JsonLoader;
@@ -137,3 +486,91 @@ pub struct ScriptAsset {
     /// Lua source code
     pub source: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageFormat, Rgba32FImage, RgbaImage};
+    use std::io::Cursor;
+
+    fn encode(image: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn decodes_png_with_srgb_mips() {
+        // Black and white columns average to 50% linear gray
+        let image = RgbaImage::from_fn(4, 2, |x, _| {
+            let v = if x % 2 == 0 { 0 } else { 255 };
+            image::Rgba([v, v, v, 255])
+        });
+        let bytes = encode(&DynamicImage::ImageRgba8(image), ImageFormat::Png);
+
+        let texture = TextureLoader::default()
+            .load(Path::new("a.png"), &bytes)
+            .unwrap();
+        assert_eq!((texture.width, texture.height), (4, 2));
+        assert_eq!(texture.format, TextureFormat::Rgba8);
+        assert!(texture.srgb);
+        assert_eq!(texture.mip_levels, 3);
+        assert_eq!(texture.mip_size(2), (1, 1));
+        assert_eq!(texture.mip_data(0).unwrap()[4..8], [255, 255, 255, 255]);
+        assert_eq!(
+            texture.mip_data(1).unwrap(),
+            [188, 188, 188, 255, 188, 188, 188, 255]
+        );
+        assert!(texture.mip_data(3).is_none());
+
+        let linear = TextureLoader::default()
+            .with_srgb(false)
+            .load(Path::new("a.png"), &bytes)
+            .unwrap();
+        assert_eq!(linear.mip_data(1).unwrap()[0], 128);
+    }
+
+    #[test]
+    fn audio_errors_name_the_file_once() {
+        let error = AudioLoader::default()
+            .load(Path::new("music/theme.ogg"), b"not audio")
+            .unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with("Asset error: music/theme.ogg: "), "{message}");
+        assert_eq!(message.matches("Asset error").count(), 1);
+    }
+
+    #[test]
+    fn decodes_16_bit_and_float_images() {
+        let gray = image::ImageBuffer::<image::Luma<u16>, _>::from_pixel(2, 2, image::Luma([1000]));
+        let bytes = encode(&DynamicImage::ImageLuma16(gray), ImageFormat::Png);
+        let texture = TextureLoader::default()
+            .with_mips(false)
+            .load(Path::new("h.png"), &bytes);
+        let texture = texture.unwrap();
+        assert_eq!(texture.format, TextureFormat::R16);
+        assert!(!texture.srgb);
+        assert_eq!(texture.mip_levels, 1);
+        assert_eq!(texture.data[..2], 1000u16.to_le_bytes());
+
+        let hdr = Rgba32FImage::from_pixel(2, 2, image::Rgba([4.5, 0.25, 1.0, 1.0]));
+        let bytes = encode(&DynamicImage::ImageRgba32F(hdr), ImageFormat::OpenExr);
+        let texture = TextureLoader::default()
+            .load(Path::new("sky.exr"), &bytes)
+            .unwrap();
+        assert_eq!(texture.format, TextureFormat::Rgba32F);
+        assert_eq!(texture.mip_levels, 2);
+        let level = texture.mip_data(1).unwrap();
+        assert_eq!(
+            f32::from_le_bytes([level[0], level[1], level[2], level[3]]),
+            4.5
+        );
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(TextureLoader::default()
+            .load(Path::new("x.png"), b"not an image")
+            .is_err());
+    }
+}
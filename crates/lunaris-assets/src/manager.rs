@@ -1,21 +1,74 @@
 //! Asset manager for loading and caching assets
 
+use crate::handle::{handle_count, AssetSlot, UntypedHandle};
+use crate::loader::{
+    AssetLoader, AudioLoader, CookedTextureLoader, ErasedLoader, JsonLoader, LoadedAsset,
+    ScriptLoader, TextureLoader,
+};
+use crate::model::ModelLoader;
 use crate::{AssetHandle, AssetId, AssetState, AssetType};
-use lunaris_core::Result;
-use std::collections::HashMap;
-use std::path::{Path, PathBuf};
+use lunaris_core::pack::{PackArchive, PackSet};
+use lunaris_core::{Error, Result};
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+
+/// Notification produced by [`AssetManager::update`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetEvent {
+    /// The asset and all of its dependencies finished loading
+    Loaded(AssetId),
+    /// A hot-reloaded asset finished loading again
+    Reloaded(AssetId),
+    /// The asset or one of its dependencies failed to load
+    Failed {
+        /// Asset that failed
+        id: AssetId,
+        /// Reason for the failure
+        error: String,
+    },
+}
+
+/// Reason an asset is resident, see [`AssetManager::retained_by`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retainer {
+    /// Live handles to the asset
+    Handles(usize),
+    /// A resident asset that depends on it
+    Dependent(AssetId),
+}
 
 /// Asset manager handles loading, caching, and unloading of assets
+///
+/// Files are read from mounted pack archives, falling back to loose files under
+/// the base path, and decoded on a background worker pool. Results are
+/// published to [`AssetHandle`]s when [`update`](Self::update) runs, once every
+/// dependency the loader reported has loaded as well. Assets stay resident while
+/// a handle to them or to an asset depending on them is alive.
 pub struct AssetManager {
     /// Base path for assets
     base_path: PathBuf,
     /// Asset metadata cache
     metadata: HashMap<AssetId, AssetMetadata>,
-    /// Pending load requests
+    /// Loaded assets waiting for their dependencies
     pending: Vec<AssetId>,
     /// Hot reload enabled
     hot_reload: bool,
+    /// Mounted pack archives, searched before loose files
+    mounts: Arc<PackSet>,
+    /// Loaders by lowercase file extension
+    loaders: HashMap<String, Arc<dyn ErasedLoader>>,
+    /// Worker pool for file IO and decoding
+    runtime: Option<tokio::runtime::Runtime>,
+    results_tx: mpsc::UnboundedSender<LoadResult>,
+    results_rx: mpsc::UnboundedReceiver<LoadResult>,
+    /// Loads submitted to the worker pool and not yet collected
+    in_flight: usize,
+    /// Events from the last update
+    events: Vec<AssetEvent>,
 }
 
 /// Asset metadata
@@ -26,17 +79,89 @@ struct AssetMetadata {
     state: AssetState,
     load_time: Option<std::time::Instant>,
     file_modified: Option<std::time::SystemTime>,
+    /// Slot shared with every handle
+    slot: Arc<AssetSlot>,
+    /// Assets this one references
+    dependencies: Vec<AssetId>,
+    /// Bumped per load request so stale results are dropped
+    generation: u64,
+    /// Data has arrived, dependencies may still be loading
+    ready: bool,
+    /// Reloading an asset that was already loaded
+    reloading: bool,
+}
+
+/// What a worker needs to load an asset
+struct LoadJob {
+    generation: u64,
+    /// Loose file path
+    path: PathBuf,
+    /// Path inside mounted archives
+    logical: String,
+    loader: Option<Arc<dyn ErasedLoader>>,
+    mounts: Arc<PackSet>,
+}
+
+/// Result sent back from a worker
+struct LoadResult {
+    id: AssetId,
+    generation: u64,
+    result: Result<LoadedAsset>,
+    modified: Option<SystemTime>,
 }
 
 impl AssetManager {
     /// Create a new asset manager
+    ///
+    /// The built-in texture, cooked texture, audio, model, JSON and script
+    /// loaders are registered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the worker threads cannot be spawned.
     #[must_use]
     pub fn new(base_path: impl Into<PathBuf>) -> Self {
-        Self {
+        let workers = std::thread::available_parallelism().map_or(2, |n| n.get().clamp(1, 4));
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(workers)
+            .thread_name("lunaris-assets")
+            .enable_all()
+            .build()
+            .expect("failed to start asset worker pool");
+        let (results_tx, results_rx) = mpsc::unbounded_channel();
+
+        let mut manager = Self {
             base_path: base_path.into(),
             metadata: HashMap::new(),
             pending: Vec::new(),
             hot_reload: cfg!(debug_assertions),
+            mounts: Arc::new(PackSet::new()),
+            loaders: HashMap::new(),
+            runtime: Some(runtime),
+            results_tx,
+            results_rx,
+            in_flight: 0,
+            events: Vec::new(),
+        };
+        manager.register_loader(TextureLoader::default());
+        manager.register_loader(CookedTextureLoader);
+        manager.register_loader(AudioLoader::default());
+        manager.register_loader(ModelLoader::default());
+        manager.register_loader(JsonLoader);
+        manager.register_loader(ScriptLoader);
+        manager
+    }
+
+    /// Register a loader for its extensions, replacing previous loaders
+    pub fn register_loader<L: AssetLoader + 'static>(&mut self, loader: L) {
+        let extensions: Vec<String> = loader
+            .extensions()
+            .iter()
+            .map(|e| e.to_lowercase())
+            .collect();
+        let loader: Arc<dyn ErasedLoader> = Arc::new(loader);
+        for extension in extensions {
+            self.loaders.insert(extension, Arc::clone(&loader));
         }
     }
 
@@ -51,88 +176,125 @@ impl AssetManager {
         &self.base_path
     }
 
+    /// Mount a pack archive above those already mounted
+    ///
+    /// Assets found in a mounted archive are read from it instead of from the
+    /// base path. Loads that already started are not affected.
+    pub fn mount(&mut self, archive: impl Into<Arc<PackArchive>>) {
+        Arc::make_mut(&mut self.mounts).mount(archive);
+    }
+
+    /// Open and mount a pack archive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive cannot be opened.
+    pub fn mount_path(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+        self.mount(PackArchive::open(path)?);
+        Ok(())
+    }
+
+    /// Unmount an archive by file path, returning whether it was mounted
+    pub fn unmount(&mut self, path: &Path) -> bool {
+        Arc::make_mut(&mut self.mounts).unmount(path)
+    }
+
+    /// Mounted pack archives
+    #[must_use]
+    pub fn mounts(&self) -> &PackSet {
+        &self.mounts
+    }
+
     /// Enable or disable hot reloading
     pub fn set_hot_reload(&mut self, enabled: bool) {
         self.hot_reload = enabled;
     }
 
-    /// Request an asset to be loaded
-    pub fn load<T>(&mut self, path: &str) -> AssetHandle<T> {
-        let full_path = self.base_path.join(path);
-        let id = AssetId::from_path(path);
-
-        // Check if already tracked
-        if !self.metadata.contains_key(&id) {
-            let asset_type = full_path
-                .extension()
-                .and_then(|e| e.to_str())
-                .and_then(AssetType::from_extension)
-                .unwrap_or(AssetType::Binary);
-
-            self.metadata.insert(
-                id,
-                AssetMetadata {
-                    path: path.to_string(),
-                    asset_type,
-                    state: AssetState::NotLoaded,
-                    load_time: None,
-                    file_modified: None,
-                },
+    /// Request an asset to be loaded in the background
+    ///
+    /// The handle reports [`AssetState::Loading`] until a later
+    /// [`update`](Self::update) publishes the result. If the registered loader
+    /// produces a different type than `T`, the handle fails immediately.
+    pub fn load<T: Send + Sync + 'static>(&mut self, path: &str) -> AssetHandle<T> {
+        if let Err(e) = self.check_type::<T>(path) {
+            let slot = AssetSlot::new(AssetState::Failed);
+            slot.fail(e.to_string());
+            return AssetHandle::from_slot(
+                AssetId::from_path(path),
+                path.to_string(),
+                Arc::new(slot),
             );
-
-            self.pending.push(id);
         }
+        let id = self.request(path);
+        AssetHandle::from_slot(id, path.to_string(), Arc::clone(&self.metadata[&id].slot))
+    }
 
-        AssetHandle {
-            id,
-            path: path.to_string(),
-            state: AssetState::Loading,
-            data: None,
-        }
+    /// Request an asset without naming its type
+    ///
+    /// Files without a registered loader load as raw `Vec<u8>` bytes.
+    pub fn load_untyped(&mut self, path: &str) -> UntypedHandle {
+        let id = self.request(path);
+        AssetHandle::from_slot(id, path.to_string(), Arc::clone(&self.metadata[&id].slot))
     }
 
-    /// Load an asset synchronously
-    pub fn load_sync<T: Default>(&mut self, path: &str) -> Result<AssetHandle<T>> {
-        let full_path = self.base_path.join(path);
+    /// Start loading an asset unless it is loaded or loading
+    fn request(&mut self, path: &str) -> AssetId {
         let id = AssetId::from_path(path);
+        let state = self.track(id, path);
+        if matches!(
+            state,
+            AssetState::NotLoaded | AssetState::Unloaded | AssetState::Failed
+        ) {
+            self.start_load(id);
+        }
+        id
+    }
 
-        // Read file
-        let bytes = std::fs::read(&full_path)
-            .map_err(|e| lunaris_core::Error::Asset(format!("Failed to read {}: {}", path, e)))?;
-
-        tracing::info!("Loaded asset: {} ({} bytes)", path, bytes.len());
-
-        let asset_type = full_path
-            .extension()
-            .and_then(|e| e.to_str())
-            .and_then(AssetType::from_extension)
-            .unwrap_or(AssetType::Binary);
+    /// Load an asset and its dependencies on the calling thread
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, the loader fails, the
+    /// loader produces a different type than `T`, or a dependency fails.
+    pub fn load_sync<T: Send + Sync + 'static>(&mut self, path: &str) -> Result<AssetHandle<T>> {
+        self.check_type::<T>(path)?;
+        let id = AssetId::from_path(path);
+        if self.track(id, path) != AssetState::Loaded {
+            self.load_blocking(id);
+            self.resolve_pending();
+        }
 
-        self.metadata.insert(
-            id,
-            AssetMetadata {
-                path: path.to_string(),
-                asset_type,
-                state: AssetState::Loaded,
-                load_time: Some(std::time::Instant::now()),
-                file_modified: std::fs::metadata(&full_path).ok().and_then(|m| m.modified().ok()),
+        let meta = &self.metadata[&id];
+        match meta.state {
+            AssetState::Loaded => {
+                tracing::info!("Loaded asset: {}", path);
+                Ok(AssetHandle::from_slot(
+                    id,
+                    path.to_string(),
+                    Arc::clone(&meta.slot),
+                ))
             },
-        );
-
-        // In real implementation, would use appropriate loader
-        Ok(AssetHandle {
-            id,
-            path: path.to_string(),
-            state: AssetState::Loaded,
-            data: Some(Arc::new(T::default())),
-        })
+            _ => Err(Error::Asset(
+                meta.slot
+                    .error()
+                    .unwrap_or_else(|| format!("Failed to load {}", path)),
+            )),
+        }
     }
 
-    /// Unload an asset
+    /// Unload an asset immediately, even if handles to it remain
+    ///
+    /// Assets without handles are released by [`update`](Self::update), so
+    /// this is only needed to force an unload.
     pub fn unload(&mut self, id: AssetId) {
         if let Some(meta) = self.metadata.get_mut(&id) {
             meta.state = AssetState::Unloaded;
+            meta.generation += 1;
+            meta.ready = false;
+            meta.reloading = false;
+            meta.slot.clear(AssetState::Unloaded);
         }
+        self.pending.retain(|p| *p != id);
     }
 
     /// Check if an asset is loaded
@@ -153,35 +315,383 @@ impl AssetManager {
             .unwrap_or(AssetState::NotLoaded)
     }
 
-    /// Process pending loads (call each frame)
+    /// Why an asset failed to load, if it did
+    #[must_use]
+    pub fn error(&self, id: AssetId) -> Option<String> {
+        self.metadata.get(&id).and_then(|m| m.slot.error())
+    }
+
+    /// Assets the loader reported as referenced by this asset
+    #[must_use]
+    pub fn dependencies(&self, id: AssetId) -> &[AssetId] {
+        self.metadata
+            .get(&id)
+            .map_or(&[], |m| m.dependencies.as_slice())
+    }
+
+    /// Resident assets that depend on this asset
+    #[must_use]
+    pub fn dependents(&self, id: AssetId) -> Vec<AssetId> {
+        self.metadata
+            .iter()
+            .filter(|(_, m)| m.state != AssetState::Unloaded && m.dependencies.contains(&id))
+            .map(|(other, _)| *other)
+            .collect()
+    }
+
+    /// Number of live handles to an asset
+    #[must_use]
+    pub fn handle_count(&self, id: AssetId) -> usize {
+        self.metadata.get(&id).map_or(0, |m| handle_count(&m.slot))
+    }
+
+    /// Why an asset is still resident
+    ///
+    /// Empty if nothing retains the asset, in which case the next
+    /// [`update`](Self::update) releases it.
+    #[must_use]
+    pub fn retained_by(&self, id: AssetId) -> Vec<Retainer> {
+        let mut retainers = Vec::new();
+        let handles = self.handle_count(id);
+        if handles > 0 {
+            retainers.push(Retainer::Handles(handles));
+        }
+        retainers.extend(self.dependents(id).into_iter().map(Retainer::Dependent));
+        retainers
+    }
+
+    /// Events produced by the last [`update`](Self::update)
+    #[must_use]
+    pub fn events(&self) -> &[AssetEvent] {
+        &self.events
+    }
+
+    /// Number of loads still running on the worker pool
+    #[must_use]
+    pub fn loading_count(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Process finished loads (call each frame)
     pub fn update(&mut self) {
-        // Process pending loads
-        let pending: Vec<_> = self.pending.drain(..).collect();
-        for id in pending {
-            if let Some(meta) = self.metadata.get_mut(&id) {
-                let full_path = self.base_path.join(&meta.path);
-                
-                match std::fs::read(&full_path) {
-                    Ok(bytes) => {
-                        tracing::debug!("Loaded: {} ({} bytes)", meta.path, bytes.len());
+        self.events.clear();
+
+        while let Ok(result) = self.results_rx.try_recv() {
+            self.in_flight = self.in_flight.saturating_sub(1);
+            self.complete(result);
+        }
+        self.resolve_pending();
+        self.release_unused();
+
+        // Check for hot reload
+        if self.hot_reload {
+            self.check_hot_reload();
+        }
+    }
+
+    /// Run [`update`](Self::update) until an asset stops loading or the timeout
+    /// elapses, returning its state
+    pub fn wait(&mut self, id: AssetId, timeout: Duration) -> AssetState {
+        let start = Instant::now();
+        loop {
+            self.update();
+            let state = self.get_state(id);
+            if state != AssetState::Loading || start.elapsed() >= timeout {
+                return state;
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Start tracking an asset, returning its current state
+    fn track(&mut self, id: AssetId, path: &str) -> AssetState {
+        let meta = self.metadata.entry(id).or_insert_with(|| {
+            let asset_type = Path::new(path)
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(AssetType::from_extension)
+                .unwrap_or(AssetType::Binary);
+            AssetMetadata {
+                path: path.to_string(),
+                asset_type,
+                state: AssetState::NotLoaded,
+                load_time: None,
+                file_modified: None,
+                slot: Arc::new(AssetSlot::new(AssetState::NotLoaded)),
+                dependencies: Vec::new(),
+                generation: 0,
+                ready: false,
+                reloading: false,
+            }
+        });
+        meta.state
+    }
+
+    fn loader_for(&self, path: &Path) -> Option<Arc<dyn ErasedLoader>> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        self.loaders.get(&extension).cloned()
+    }
+
+    /// Fail if `path` loads as a different type than `T`
+    fn check_type<T: 'static>(&self, path: &str) -> Result<()> {
+        let (expected, name) = self
+            .loader_for(Path::new(path))
+            .map_or((TypeId::of::<Vec<u8>>(), "Vec<u8>"), |l| {
+                (l.asset_type(), l.asset_type_name())
+            });
+        if expected == TypeId::of::<T>() {
+            Ok(())
+        } else {
+            Err(Error::Asset(format!(
+                "{} loads as {}, not {}",
+                path,
+                name,
+                std::any::type_name::<T>()
+            )))
+        }
+    }
+
+    /// Mark an asset as loading and return what the worker needs
+    fn begin_load(&mut self, id: AssetId) -> Option<LoadJob> {
+        let base_path = self.base_path.clone();
+        let mounts = Arc::clone(&self.mounts);
+        let meta = self.metadata.get_mut(&id)?;
+        meta.reloading = meta.state == AssetState::Loaded;
+        meta.state = AssetState::Loading;
+        meta.generation += 1;
+        meta.ready = false;
+        // Handles keep seeing the old data while a reload is in progress
+        if !meta.reloading {
+            meta.slot.publish(AssetState::Loading);
+        }
+        let generation = meta.generation;
+        let logical = meta.path.clone();
+        let path = base_path.join(&meta.path);
+        let loader = self.loader_for(&path);
+        Some(LoadJob {
+            generation,
+            path,
+            logical,
+            loader,
+            mounts,
+        })
+    }
+
+    /// Submit a load to the worker pool
+    fn start_load(&mut self, id: AssetId) {
+        let Some(job) = self.begin_load(id) else {
+            return;
+        };
+        let Some(runtime) = &self.runtime else {
+            return;
+        };
+        let results = self.results_tx.clone();
+        let generation = job.generation;
+        runtime.spawn(async move {
+            let (result, modified) = read_and_load(job).await;
+            // The manager may have been dropped; nothing is waiting then
+            let _ = results.send(LoadResult {
+                id,
+                generation,
+                result,
+                modified,
+            });
+        });
+        self.in_flight += 1;
+    }
+
+    /// Load an asset and its missing dependencies on the calling thread
+    fn load_blocking(&mut self, id: AssetId) {
+        let Some(job) = self.begin_load(id) else {
+            return;
+        };
+        let (result, modified) = job.run();
+        self.complete(LoadResult {
+            id,
+            generation: job.generation,
+            result,
+            modified,
+        });
+
+        // Dependencies were queued on the worker pool, load them here instead
+        let dependencies = self.dependencies(id).to_vec();
+        for dependency in dependencies {
+            let ready = self.metadata.get(&dependency).is_some_and(|m| {
+                m.state == AssetState::Loaded || (m.state == AssetState::Loading && m.ready)
+            });
+            if !ready {
+                self.load_blocking(dependency);
+            }
+        }
+    }
+
+    /// Store a worker result and request the asset's dependencies
+    fn complete(&mut self, result: LoadResult) {
+        let LoadResult {
+            id,
+            generation,
+            result,
+            modified,
+        } = result;
+        let Some(meta) = self.metadata.get_mut(&id) else {
+            return;
+        };
+        if meta.generation != generation {
+            return;
+        }
+
+        match result {
+            Ok(loaded) => {
+                tracing::debug!("Loaded: {}", meta.path);
+                meta.slot.set_data(loaded.data);
+                meta.load_time = Some(Instant::now());
+                meta.file_modified = modified;
+                meta.ready = true;
+
+                let paths: Vec<String> = loaded
+                    .dependencies
+                    .iter()
+                    .map(|p| self.logical_path(p))
+                    .collect();
+                let dependencies = paths.iter().map(|p| self.request(p)).collect();
+                if let Some(meta) = self.metadata.get_mut(&id) {
+                    meta.dependencies = dependencies;
+                }
+                self.pending.push(id);
+            },
+            Err(e) => {
+                tracing::error!("Failed to load {}: {}", meta.path, e);
+                meta.state = AssetState::Failed;
+                meta.reloading = false;
+                meta.slot.fail(e.to_string());
+                self.events.push(AssetEvent::Failed {
+                    id,
+                    error: e.to_string(),
+                });
+            },
+        }
+    }
+
+    /// Publish assets whose dependencies have all loaded or failed
+    fn resolve_pending(&mut self) {
+        loop {
+            let mut changed = false;
+            for id in self.pending.clone() {
+                let Some(status) = self.status(id, &mut HashSet::new()) else {
+                    continue;
+                };
+                self.pending.retain(|p| *p != id);
+                changed = true;
+
+                let Some(meta) = self.metadata.get_mut(&id) else {
+                    continue;
+                };
+                match status {
+                    Ok(()) => {
                         meta.state = AssetState::Loaded;
-                        meta.load_time = Some(std::time::Instant::now());
-                        meta.file_modified = std::fs::metadata(&full_path)
-                            .ok()
-                            .and_then(|m| m.modified().ok());
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to load {}: {}", meta.path, e);
+                        meta.slot.publish(AssetState::Loaded);
+                        self.events.push(if meta.reloading {
+                            AssetEvent::Reloaded(id)
+                        } else {
+                            AssetEvent::Loaded(id)
+                        });
+                    },
+                    Err(error) => {
+                        let error = format!("dependency {}", error);
+                        tracing::error!("Failed to load {}: {}", meta.path, error);
                         meta.state = AssetState::Failed;
-                    }
+                        meta.slot.fail(error.clone());
+                        self.events.push(AssetEvent::Failed { id, error });
+                    },
                 }
+                meta.reloading = false;
+            }
+            if !changed {
+                break;
             }
         }
+    }
 
-        // Check for hot reload
-        if self.hot_reload {
-            self.check_hot_reload();
+    /// Whether an asset and its dependencies are ready, `None` while loading
+    ///
+    /// Dependency cycles count as resolved.
+    fn status(
+        &self,
+        id: AssetId,
+        visiting: &mut HashSet<AssetId>,
+    ) -> Option<std::result::Result<(), String>> {
+        let Some(meta) = self.metadata.get(&id) else {
+            return Some(Err("unknown asset".to_string()));
+        };
+        match meta.state {
+            AssetState::Loaded => return Some(Ok(())),
+            AssetState::Failed => {
+                let error = meta.slot.error().unwrap_or_default();
+                return Some(Err(format!("{}: {}", meta.path, error)));
+            },
+            AssetState::NotLoaded | AssetState::Unloaded => {
+                return Some(Err(format!("{} was unloaded", meta.path)));
+            },
+            AssetState::Loading if !meta.ready => return None,
+            AssetState::Loading => {},
+        }
+        if !visiting.insert(id) {
+            return Some(Ok(()));
         }
+        for dependency in &meta.dependencies {
+            if let Err(e) = self.status(*dependency, visiting)? {
+                return Some(Err(e));
+            }
+        }
+        Some(Ok(()))
+    }
+
+    /// Unload assets that no handle or resident asset references
+    fn release_unused(&mut self) {
+        let mut resident = HashSet::new();
+        let mut stack: Vec<AssetId> = self
+            .metadata
+            .iter()
+            .filter(|(_, m)| m.state != AssetState::Unloaded && handle_count(&m.slot) > 0)
+            .map(|(id, _)| *id)
+            .collect();
+        while let Some(id) = stack.pop() {
+            if resident.insert(id) {
+                stack.extend_from_slice(self.dependencies(id));
+            }
+        }
+
+        let unused: Vec<AssetId> = self
+            .metadata
+            .iter()
+            .filter(|(id, m)| m.state != AssetState::Unloaded && !resident.contains(*id))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in unused {
+            tracing::debug!("Releasing unused asset: {}", self.metadata[&id].path);
+            self.unload(id);
+        }
+
+        // Forget unloaded assets that no handle can observe any more
+        self.metadata.retain(|id, m| {
+            m.state != AssetState::Unloaded || handle_count(&m.slot) > 0 || resident.contains(id)
+        });
+    }
+
+    /// Asset path of a file reported by a loader
+    fn logical_path(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.base_path).unwrap_or(path);
+        let mut parts: Vec<String> = Vec::new();
+        for component in relative.components() {
+            match component {
+                Component::CurDir => {},
+                Component::ParentDir if parts.last().is_some_and(|p| p != "..") => {
+                    parts.pop();
+                },
+                component => parts.push(component.as_os_str().to_string_lossy().into_owned()),
+            }
+        }
+        parts.join("/")
     }
 
     /// Check for modified files and reload
@@ -206,10 +716,9 @@ impl AssetManager {
         }
 
         for id in to_reload {
-            if let Some(meta) = self.metadata.get_mut(&id) {
+            if let Some(meta) = self.metadata.get(&id) {
                 tracing::info!("Hot reloading: {}", meta.path);
-                meta.state = AssetState::NotLoaded;
-                self.pending.push(id);
+                self.start_load(id);
             }
         }
     }
@@ -235,3 +744,244 @@ impl Default for AssetManager {
         Self::new("assets")
     }
 }
+
+impl Drop for AssetManager {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which panics inside async contexts
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl LoadJob {
+    /// Read and decode on the calling thread
+    fn run(&self) -> (Result<LoadedAsset>, Option<SystemTime>) {
+        let (bytes, modified) = match self.mounts.read(&self.logical) {
+            Some(bytes) => (bytes, None),
+            None => {
+                let modified = std::fs::metadata(&self.path)
+                    .ok()
+                    .and_then(|m| m.modified().ok());
+                let bytes = std::fs::read(&self.path).map_err(|e| {
+                    Error::Asset(format!("Failed to read {}: {}", self.path.display(), e))
+                });
+                (bytes, modified)
+            },
+        };
+        let result = bytes.and_then(|bytes| decode(&self.path, bytes, self.loader.as_deref()));
+        (result, modified)
+    }
+}
+
+/// Read a file and decode it on the worker pool
+async fn read_and_load(job: LoadJob) -> (Result<LoadedAsset>, Option<SystemTime>) {
+    // Packed files are memory mapped, so reading them is not async IO
+    if job.mounts.lookup(&job.logical).is_some() {
+        return tokio::task::spawn_blocking(move || job.run())
+            .await
+            .unwrap_or_else(|e| (Err(Error::Asset(format!("Loader panicked: {}", e))), None));
+    }
+
+    let LoadJob { path, loader, .. } = job;
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let error = Error::Asset(format!("Failed to read {}: {}", path.display(), e));
+            return (Err(error), None);
+        },
+    };
+    let modified = tokio::fs::metadata(&path)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok());
+
+    // Decoding is CPU bound, keep it off the IO threads
+    let result = tokio::task::spawn_blocking(move || decode(&path, bytes, loader.as_deref()))
+        .await
+        .unwrap_or_else(|e| Err(Error::Asset(format!("Loader panicked: {}", e))));
+    (result, modified)
+}
+
+/// Run a loader, or keep the raw bytes if there is none
+fn decode(path: &Path, bytes: Vec<u8>, loader: Option<&dyn ErasedLoader>) -> Result<LoadedAsset> {
+    match loader {
+        Some(loader) => loader.load_erased(path, &bytes),
+        None => Ok(LoadedAsset {
+            data: Arc::new(bytes),
+            dependencies: Vec::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Loads a list of file names, one per line, as dependencies
+    struct ListLoader;
+
+    impl AssetLoader for ListLoader {
+        type Asset = Vec<String>;
+
+        fn extensions(&self) -> &[&str] {
+            &["list"]
+        }
+
+        fn load(&self, _path: &Path, bytes: &[u8]) -> Result<Self::Asset> {
+            let text = String::from_utf8_lossy(bytes);
+            Ok(text.lines().map(str::to_string).collect())
+        }
+
+        fn dependencies(&self, asset: &Self::Asset) -> Vec<PathBuf> {
+            asset.iter().map(PathBuf::from).collect()
+        }
+    }
+
+    /// Manager over a temporary directory holding the given files
+    fn manager(files: &[(&str, &str)]) -> (TempDir, AssetManager) {
+        let dir = TempDir::new().unwrap();
+        for (name, contents) in files {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let mut manager = AssetManager::new(dir.path());
+        manager.set_hot_reload(false);
+        manager.register_loader(ListLoader);
+        (dir, manager)
+    }
+
+    #[test]
+    fn loads_in_background_with_registered_loader() {
+        let (_dir, mut manager) = manager(&[
+            ("config.json", r#"{ "speed": 3 }"#),
+            ("raw.bin", "abc"),
+        ]);
+        let config = manager.load::<serde_json::Value>("config.json");
+        let raw = manager.load::<Vec<u8>>("raw.bin");
+        assert_eq!(config.state(), AssetState::Loading);
+
+        manager.wait(config.id, Duration::from_secs(5));
+        manager.wait(raw.id, Duration::from_secs(5));
+        assert!(config.is_loaded());
+        assert_eq!(config.get().unwrap()["speed"], 3);
+        assert_eq!(raw.get().unwrap().as_slice(), b"abc");
+
+        let wrong = manager.load::<String>("config.json");
+        assert_eq!(wrong.state(), AssetState::Failed);
+    }
+
+    #[test]
+    fn waits_for_dependencies_and_reports_failures() {
+        let (_dir, mut manager) = manager(&[
+            ("a.list", "b.list\nc.json"),
+            ("b.list", ""),
+            ("c.json", "[1]"),
+            ("broken.list", "missing.json"),
+            ("d.list", "e.json"),
+            ("e.json", "{}"),
+        ]);
+        let d = manager.load_sync::<Vec<String>>("d.list").unwrap();
+        assert!(d.is_loaded());
+        assert!(manager.is_loaded(manager.dependencies(d.id)[0]));
+
+        let a = manager.load::<Vec<String>>("a.list");
+        assert_eq!(
+            manager.wait(a.id, Duration::from_secs(5)),
+            AssetState::Loaded
+        );
+        assert_eq!(manager.dependencies(a.id).len(), 2);
+        assert!(manager
+            .dependencies(a.id)
+            .iter()
+            .all(|d| manager.is_loaded(*d)));
+
+        let broken = manager.load::<Vec<String>>("broken.list");
+        assert_eq!(
+            manager.wait(broken.id, Duration::from_secs(5)),
+            AssetState::Failed
+        );
+        assert!(broken.get().is_none());
+        assert!(broken.error().unwrap().contains("missing.json"));
+
+        assert!(manager.load_sync::<Vec<String>>("a.list").is_ok());
+        assert!(manager.load_sync::<Vec<String>>("broken.list").is_err());
+    }
+
+    #[test]
+    fn releases_unreferenced_assets_and_their_dependencies() {
+        let (_dir, mut manager) = manager(&[
+            ("mesh.list", "material.list"),
+            ("material.list", "albedo.bin"),
+            ("albedo.bin", "pixels"),
+        ]);
+        let mesh = manager.load::<Vec<String>>("mesh.list");
+        manager.wait(mesh.id, Duration::from_secs(5));
+        let material = manager.dependencies(mesh.id)[0];
+        let albedo = manager.dependencies(material)[0];
+        assert!(manager.is_loaded(albedo));
+        assert_eq!(
+            manager.retained_by(material),
+            vec![Retainer::Dependent(mesh.id)]
+        );
+
+        let texture = manager.load::<Vec<u8>>("albedo.bin");
+        let weak = mesh.downgrade();
+        assert!(weak.upgrade::<Vec<String>>().is_some());
+        assert_eq!(manager.handle_count(mesh.id), 1);
+
+        drop(mesh);
+        manager.update();
+        assert!(!weak.is_alive());
+        assert!(weak.upgrade::<Vec<String>>().is_none());
+        assert_eq!(manager.get_state(material), AssetState::NotLoaded);
+        assert!(texture.is_loaded());
+        assert_eq!(manager.retained_by(albedo), vec![Retainer::Handles(1)]);
+
+        drop(texture);
+        manager.update();
+        assert_eq!(manager.total_count(), 0);
+    }
+
+    #[test]
+    fn reads_mounted_packs_before_loose_files() {
+        use lunaris_core::pack::PackWriter;
+
+        let (_dir, mut manager) = manager(&[
+            ("loose.bin", "loose"),
+            ("props/crate.bin", "on disk"),
+        ]);
+        let base_path = manager.base_path().join("base.lpak");
+        let mut writer = PackWriter::create(&base_path).unwrap();
+        writer.add("level.list", b"props/crate.bin").unwrap();
+        writer.add("props/crate.bin", b"v1").unwrap();
+        writer.add("old.bin", b"old").unwrap();
+        writer.finish().unwrap();
+        let base = PackArchive::open(&base_path).unwrap();
+
+        let patch_path = manager.base_path().join("patch.lpak");
+        let mut writer = PackWriter::patch(&patch_path, &base).unwrap();
+        writer.add("level.list", b"props/crate.bin").unwrap();
+        writer.add("props/crate.bin", b"v2").unwrap();
+        writer.finish().unwrap();
+        manager.mount(base);
+        manager.mount_path(&patch_path).unwrap();
+
+        let level = manager.load::<Vec<String>>("level.list");
+        assert_eq!(
+            manager.wait(level.id, Duration::from_secs(5)),
+            AssetState::Loaded
+        );
+        let prop = manager.load_sync::<Vec<u8>>("props/crate.bin").unwrap();
+        assert_eq!(prop.get().unwrap().as_slice(), b"v2");
+        assert_eq!(manager.dependencies(level.id), &[prop.id]);
+        assert!(manager.load_sync::<Vec<u8>>("old.bin").is_err());
+        let loose = manager.load_sync::<Vec<u8>>("loose.bin").unwrap();
+        assert_eq!(loose.get().unwrap().as_slice(), b"loose");
+
+        assert!(manager.unmount(&patch_path));
+        assert!(manager.mounts().contains("old.bin"));
+    }
+}
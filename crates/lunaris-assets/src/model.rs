@@ -0,0 +1,1693 @@
+//! glTF model import
+//!
+//! Converts glTF 2.0 files (`.gltf` and `.glb`) into renderer meshes, PBR
+//! materials, a skeleton, blend shapes and animation clips. Geometry is
+//! converted to the engine's Y-up space and scaled according to
+//! [`ModelImportSettings`].
+
+use crate::asset_pipeline::{ModelImportSettings, UpAxis};
+use crate::loader::AssetLoader;
+use base64::Engine as _;
+use glam::{EulerRot, Mat3, Mat4, Quat, Vec3 as GlamVec3, Vec4};
+use gltf::animation::util::ReadOutputs;
+use gltf::animation::{Interpolation, Property};
+use lunaris_core::{
+    id::Id,
+    math::{Color, Vec3},
+    Error, Result,
+};
+use lunaris_renderer::animation::{AnimationChannel, AnimationClip, Bone, Keyframe, Skeleton};
+use lunaris_renderer::facial::BlendShape;
+use lunaris_renderer::gpu::Vertex3D;
+use lunaris_renderer::material::{BlendMode, Material, MaterialProperty, ShaderId};
+use lunaris_renderer::mesh::{Mesh, SubMesh};
+use lunaris_renderer::texture::TextureId;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Loader for glTF 2.0 models
+#[derive(Debug, Clone)]
+pub struct ModelLoader {
+    /// Import settings (scale, up axis, LODs, ...)
+    pub settings: ModelImportSettings,
+    /// Shader assigned to imported materials
+    pub shader: ShaderId,
+}
+
+impl Default for ModelLoader {
+    fn default() -> Self {
+        Self::new(ModelImportSettings::default())
+    }
+}
+
+impl ModelLoader {
+    /// Create a loader with the given import settings
+    #[must_use]
+    pub fn new(settings: ModelImportSettings) -> Self {
+        Self {
+            settings,
+            shader: ShaderId(Id::NULL),
+        }
+    }
+
+    /// Set the shader assigned to imported materials
+    #[must_use]
+    pub fn with_shader(mut self, shader: ShaderId) -> Self {
+        self.shader = shader;
+        self
+    }
+
+    /// Import a `.gltf` or `.glb` file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file or one of its external buffers cannot be
+    /// read, or if it is not valid glTF.
+    pub fn import(&self, path: &Path) -> Result<ImportedModel> {
+        let bytes = std::fs::read(path)?;
+        self.import_bytes(&bytes, path)
+    }
+
+    /// Import a model from memory
+    ///
+    /// `path` names the model and resolves relative buffer and image URIs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not valid glTF or a referenced buffer
+    /// is missing.
+    pub fn import_bytes(&self, bytes: &[u8], path: &Path) -> Result<ImportedModel> {
+        let gltf = gltf::Gltf::from_slice(bytes)
+            .map_err(|e| Error::Asset(format!("{}: {e}", path.display())))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let buffers = load_buffers(&gltf.document, gltf.blob.clone(), base)?;
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("model")
+            .to_string();
+
+        let mut importer = Importer::new(&gltf.document, &buffers, base, &self.settings);
+        importer.import_textures();
+        if self.settings.import_materials {
+            importer.import_materials(self.shader);
+        }
+        importer.import_skeleton();
+        importer.import_meshes(self.shader);
+        if self.settings.import_animations {
+            importer.import_animations();
+        }
+
+        for warning in &importer.warnings {
+            tracing::warn!("{}: {warning}", path.display());
+        }
+        tracing::debug!(
+            "Imported model '{name}': {} meshes, {} materials, {} bones, {} animations",
+            importer.meshes.len(),
+            importer.materials.len(),
+            importer.skeleton.as_ref().map_or(0, Skeleton::bone_count),
+            importer.animations.len()
+        );
+
+        let mut files: Vec<PathBuf> = gltf
+            .document
+            .buffers()
+            .filter_map(|buffer| match buffer.source() {
+                gltf::buffer::Source::Uri(uri) if !uri.starts_with("data:") => {
+                    resolve_uri(uri, base).ok()
+                },
+                _ => None,
+            })
+            .collect();
+        files.extend(importer.textures.iter().filter_map(|t| match &t.source {
+            TextureSource::File(path) => Some(path.clone()),
+            TextureSource::Embedded { .. } => None,
+        }));
+
+        Ok(ImportedModel {
+            name,
+            files,
+            meshes: importer.meshes,
+            materials: importer.materials,
+            textures: importer.textures,
+            skeleton: importer.skeleton,
+            animations: importer.animations,
+            warnings: importer.warnings,
+        })
+    }
+}
+
+impl AssetLoader for ModelLoader {
+    type Asset = ImportedModel;
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf", "glb"]
+    }
+
+    fn load(&self, path: &Path, bytes: &[u8]) -> Result<Self::Asset> {
+        self.import_bytes(bytes, path)
+    }
+
+    fn dependencies(&self, asset: &Self::Asset) -> Vec<PathBuf> {
+        asset
+            .textures
+            .iter()
+            .filter_map(|t| match &t.source {
+                TextureSource::File(path) => Some(path.clone()),
+                TextureSource::Embedded { .. } => None,
+            })
+            .collect()
+    }
+}
+
+/// An imported model
+#[derive(Debug, Clone)]
+pub struct ImportedModel {
+    /// Model name (file stem)
+    pub name: String,
+    /// External buffer and image files the model references
+    pub files: Vec<PathBuf>,
+    /// Meshes, one per mesh node in the scene
+    pub meshes: Vec<ModelMesh>,
+    /// Materials referenced by [`SubMesh::material_index`]
+    pub materials: Vec<Material>,
+    /// Textures referenced by the materials
+    pub textures: Vec<ModelTexture>,
+    /// Skeleton built from all skins in the file
+    pub skeleton: Option<Skeleton>,
+    /// Skeletal animation clips
+    pub animations: Vec<AnimationClip>,
+    /// Non-fatal problems found during import
+    pub warnings: Vec<String>,
+}
+
+impl ImportedModel {
+    /// Triangle count of the full detail meshes
+    #[must_use]
+    pub fn triangle_count(&self) -> usize {
+        self.meshes.iter().map(|m| m.mesh.triangle_count()).sum()
+    }
+
+    /// Triangle count of all meshes including generated LODs
+    #[must_use]
+    pub fn total_triangle_count(&self) -> usize {
+        self.meshes
+            .iter()
+            .map(|m| {
+                m.mesh.triangle_count()
+                    + m.lods
+                        .iter()
+                        .map(|l| l.mesh.triangle_count())
+                        .sum::<usize>()
+            })
+            .sum()
+    }
+}
+
+/// A mesh with its skinning data, blend shapes and LODs
+#[derive(Debug, Clone)]
+pub struct ModelMesh {
+    /// Full detail mesh
+    pub mesh: Mesh,
+    /// Simplified meshes, from most to least detailed
+    pub lods: Vec<MeshLod>,
+    /// Per-vertex bone influences, if the mesh is skinned
+    pub skin: Option<SkinWeights>,
+    /// Morph targets
+    pub blend_shapes: Vec<BlendShape>,
+}
+
+/// Per-vertex bone influences
+#[derive(Debug, Clone, Default)]
+pub struct SkinWeights {
+    /// Bone indices into [`ImportedModel::skeleton`]
+    pub joints: Vec<[u16; 4]>,
+    /// Normalized weights of each influence
+    pub weights: Vec<[f32; 4]>,
+}
+
+/// A generated level of detail
+#[derive(Debug, Clone)]
+pub struct MeshLod {
+    /// Simplified mesh
+    pub mesh: Mesh,
+    /// Full detail vertex each LOD vertex was taken from
+    ///
+    /// Used to look up skin weights and blend shape deltas.
+    pub source_vertices: Vec<u32>,
+    /// Screen size (fraction of the full detail size) at which to switch
+    pub screen_size: f32,
+}
+
+/// A texture referenced by an imported material
+#[derive(Debug, Clone)]
+pub struct ModelTexture {
+    /// Id used in material texture properties
+    pub id: TextureId,
+    /// Texture name
+    pub name: String,
+    /// Where the image data lives
+    pub source: TextureSource,
+    /// Whether the texture holds color data
+    pub srgb: bool,
+}
+
+/// Image data of a model texture
+#[derive(Debug, Clone)]
+pub enum TextureSource {
+    /// External image file
+    File(PathBuf),
+    /// Image embedded in a buffer or data URI
+    Embedded {
+        /// Encoded image bytes
+        bytes: Vec<u8>,
+        /// MIME type, if known
+        mime_type: Option<String>,
+    },
+}
+
+/// Simplify a mesh to at most `target_triangles` by vertex clustering
+///
+/// Returns `None` if the mesh cannot be reduced below its current triangle
+/// count. Submesh ranges are preserved.
+#[must_use]
+pub fn simplify(mesh: &Mesh, target_triangles: usize) -> Option<MeshLod> {
+    if mesh.vertices.is_empty() || target_triangles >= mesh.triangle_count() {
+        return None;
+    }
+
+    let min = GlamVec3::new(mesh.bounds_min.x, mesh.bounds_min.y, mesh.bounds_min.z);
+    let max = GlamVec3::new(mesh.bounds_max.x, mesh.bounds_max.y, mesh.bounds_max.z);
+    let extent = (max - min).max_element().max(f32::EPSILON);
+
+    // Triangle count grows with grid resolution, so search for the finest
+    // grid that still meets the budget.
+    let (mut lo, mut hi) = (1u32, 1024u32);
+    let mut best = None;
+    while lo <= hi {
+        let cells = lo + (hi - lo) / 2;
+        let lod = cluster(mesh, min, extent / cells as f32);
+        if lod.mesh.triangle_count() <= target_triangles {
+            best = Some(lod);
+            lo = cells + 1;
+        } else {
+            hi = cells - 1;
+        }
+    }
+
+    best.filter(|lod| lod.mesh.triangle_count() > 0)
+}
+
+/// Collapse all vertices sharing a grid cell into one representative
+fn cluster(mesh: &Mesh, origin: GlamVec3, cell: f32) -> MeshLod {
+    let key = |p: [f32; 3]| {
+        let c = ((GlamVec3::from(p) - origin) / cell).floor();
+        (c.x as i32, c.y as i32, c.z as i32)
+    };
+
+    let mut sums: HashMap<(i32, i32, i32), (GlamVec3, u32)> = HashMap::new();
+    for v in &mesh.vertices {
+        let entry = sums.entry(key(v.position)).or_insert((GlamVec3::ZERO, 0));
+        entry.0 += GlamVec3::from(v.position);
+        entry.1 += 1;
+    }
+
+    // The representative is the member closest to the cell's centroid, so it
+    // stays on the surface and keeps valid attributes.
+    let mut best: HashMap<(i32, i32, i32), (u32, f32)> = HashMap::new();
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        let k = key(v.position);
+        let (sum, count) = sums[&k];
+        let distance = GlamVec3::from(v.position).distance_squared(sum / count as f32);
+        let entry = best.entry(k).or_insert((i as u32, distance));
+        if distance < entry.1 {
+            *entry = (i as u32, distance);
+        }
+    }
+
+    let mut lod = Mesh::new(format!("{}_lod", mesh.name));
+    let mut source_vertices = Vec::new();
+    let mut remap: HashMap<u32, u32> = HashMap::new();
+    let mut vertex_of = |index: u32, lod: &mut Mesh| {
+        let representative = best[&key(mesh.vertices[index as usize].position)].0;
+        *remap.entry(representative).or_insert_with(|| {
+            lod.vertices.push(mesh.vertices[representative as usize]);
+            source_vertices.push(representative);
+            lod.vertices.len() as u32 - 1
+        })
+    };
+
+    for submesh in &mesh.submeshes {
+        let start = submesh.start_index as usize;
+        let end = start + submesh.index_count as usize;
+        let first = lod.indices.len() as u32;
+        let mut seen = HashSet::new();
+        for tri in mesh.indices[start..end].chunks_exact(3) {
+            let a = vertex_of(tri[0], &mut lod);
+            let b = vertex_of(tri[1], &mut lod);
+            let c = vertex_of(tri[2], &mut lod);
+            if a == b || b == c || a == c {
+                continue;
+            }
+            let mut sorted = [a, b, c];
+            sorted.sort_unstable();
+            if seen.insert(sorted) {
+                lod.indices.extend_from_slice(&[a, b, c]);
+            }
+        }
+        lod.submeshes.push(SubMesh {
+            start_index: first,
+            index_count: lod.indices.len() as u32 - first,
+            material_index: submesh.material_index,
+        });
+    }
+    lod.calculate_bounds();
+
+    MeshLod {
+        mesh: lod,
+        source_vertices,
+        screen_size: 1.0,
+    }
+}
+
+/// Magic bytes of a cooked mesh file
+const MESH_MAGIC: &[u8; 4] = b"LMSH";
+/// Version of the cooked mesh layout
+const MESH_VERSION: u32 = 1;
+
+/// Serialize meshes into a cooked `.mesh` file
+///
+/// All values are little endian: the magic, the version and the mesh count,
+/// then per mesh its name, vertices, indices, submeshes and bounds.
+#[must_use]
+pub fn encode_meshes(meshes: &[&Mesh]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let put_u32 = |out: &mut Vec<u8>, v: usize| out.extend_from_slice(&(v as u32).to_le_bytes());
+    let put_f32s = |out: &mut Vec<u8>, values: &[f32]| {
+        for v in values {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    };
+
+    out.extend_from_slice(MESH_MAGIC);
+    out.extend_from_slice(&MESH_VERSION.to_le_bytes());
+    put_u32(&mut out, meshes.len());
+    for mesh in meshes {
+        put_u32(&mut out, mesh.name.len());
+        out.extend_from_slice(mesh.name.as_bytes());
+        put_u32(&mut out, mesh.vertices.len());
+        for v in &mesh.vertices {
+            put_f32s(&mut out, &v.position);
+            put_f32s(&mut out, &v.normal);
+            put_f32s(&mut out, &v.tex_coords);
+        }
+        put_u32(&mut out, mesh.indices.len());
+        for &i in &mesh.indices {
+            out.extend_from_slice(&i.to_le_bytes());
+        }
+        put_u32(&mut out, mesh.submeshes.len());
+        for submesh in &mesh.submeshes {
+            out.extend_from_slice(&submesh.start_index.to_le_bytes());
+            out.extend_from_slice(&submesh.index_count.to_le_bytes());
+            out.extend_from_slice(&submesh.material_index.to_le_bytes());
+        }
+        let (min, max) = (mesh.bounds_min, mesh.bounds_max);
+        put_f32s(&mut out, &[min.x, min.y, min.z, max.x, max.y, max.z]);
+    }
+    out
+}
+
+/// Read meshes written by [`encode_meshes`]
+///
+/// # Errors
+///
+/// Returns error if the data is truncated, has the wrong magic or version,
+/// or an index or submesh is out of range
+pub fn decode_meshes(bytes: &[u8]) -> Result<Vec<Mesh>> {
+    struct Reader<'a>(&'a [u8]);
+    impl Reader<'_> {
+        fn take(&mut self, len: usize) -> Result<&[u8]> {
+            if self.0.len() < len {
+                return Err(Error::Asset("Mesh file is truncated".to_string()));
+            }
+            let (head, tail) = self.0.split_at(len);
+            self.0 = tail;
+            Ok(head)
+        }
+        fn u32(&mut self) -> Result<u32> {
+            Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default()))
+        }
+        fn f32s<const N: usize>(&mut self) -> Result<[f32; N]> {
+            let mut out = [0.0; N];
+            for v in &mut out {
+                *v = f32::from_bits(self.u32()?);
+            }
+            Ok(out)
+        }
+        /// Element count, checked against the bytes left so corrupt counts
+        /// cannot force huge allocations
+        fn count(&mut self, element_size: usize) -> Result<usize> {
+            let count = self.u32()? as usize;
+            if count.saturating_mul(element_size) > self.0.len() {
+                return Err(Error::Asset("Mesh file is truncated".to_string()));
+            }
+            Ok(count)
+        }
+    }
+
+    let mut reader = Reader(bytes);
+    if reader.take(4)? != MESH_MAGIC {
+        return Err(Error::Asset("Not a mesh file".to_string()));
+    }
+    let version = reader.u32()?;
+    if version != MESH_VERSION {
+        return Err(Error::Asset(format!("Unsupported mesh version {version}")));
+    }
+
+    let mesh_count = reader.count(4)?;
+    let mut meshes = Vec::with_capacity(mesh_count);
+    for _ in 0..mesh_count {
+        let name_len = reader.count(1)?;
+        let name = String::from_utf8(reader.take(name_len)?.to_vec())
+            .map_err(|e| Error::Asset(format!("Invalid mesh name: {e}")))?;
+        let mut mesh = Mesh::new(name);
+
+        let vertex_count = reader.count(32)?;
+        mesh.vertices.reserve(vertex_count);
+        for _ in 0..vertex_count {
+            let [px, py, pz, nx, ny, nz, u, v] = reader.f32s::<8>()?;
+            mesh.vertices.push(Vertex3D {
+                position: [px, py, pz],
+                normal: [nx, ny, nz],
+                tex_coords: [u, v],
+            });
+        }
+        let index_count = reader.count(4)?;
+        mesh.indices.reserve(index_count);
+        for _ in 0..index_count {
+            let index = reader.u32()?;
+            if index as usize >= vertex_count {
+                return Err(Error::Asset(format!("Mesh index {index} is out of range")));
+            }
+            mesh.indices.push(index);
+        }
+        let submesh_count = reader.count(12)?;
+        for _ in 0..submesh_count {
+            let submesh = SubMesh {
+                start_index: reader.u32()?,
+                index_count: reader.u32()?,
+                material_index: reader.u32()?,
+            };
+            if submesh.start_index as u64 + submesh.index_count as u64 > index_count as u64 {
+                return Err(Error::Asset("Submesh is out of range".to_string()));
+            }
+            mesh.submeshes.push(submesh);
+        }
+        let [min_x, min_y, min_z, max_x, max_y, max_z] = reader.f32s::<6>()?;
+        mesh.bounds_min = Vec3::new(min_x, min_y, min_z);
+        mesh.bounds_max = Vec3::new(max_x, max_y, max_z);
+        meshes.push(mesh);
+    }
+    Ok(meshes)
+}
+
+/// Read every buffer of a document into memory
+fn load_buffers(
+    document: &gltf::Document,
+    mut blob: Option<Vec<u8>>,
+    base: &Path,
+) -> Result<Vec<Vec<u8>>> {
+    let mut buffers = Vec::new();
+    for buffer in document.buffers() {
+        let mut data = match buffer.source() {
+            gltf::buffer::Source::Bin => blob
+                .take()
+                .ok_or_else(|| Error::Asset("glTF binary chunk is missing".to_string()))?,
+            gltf::buffer::Source::Uri(uri) => read_uri(uri, base)?.0,
+        };
+        if data.len() < buffer.length() {
+            return Err(Error::Asset(format!(
+                "glTF buffer {} is {} bytes, expected {}",
+                buffer.index(),
+                data.len(),
+                buffer.length()
+            )));
+        }
+        data.truncate(buffer.length());
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+/// Read a data URI or a file relative to `base`, returning its MIME type if
+/// the URI declares one
+fn read_uri(uri: &str, base: &Path) -> Result<(Vec<u8>, Option<String>)> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (header, payload) = data
+            .split_once(',')
+            .ok_or_else(|| Error::Asset("Malformed data URI".to_string()))?;
+        let mime_type = header
+            .split(';')
+            .next()
+            .filter(|m| !m.is_empty())
+            .map(str::to_string);
+        let bytes = if header.ends_with(";base64") {
+            base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .map_err(|e| Error::Asset(format!("Invalid base64 data URI: {e}")))?
+        } else {
+            percent_decode(payload).into_bytes()
+        };
+        return Ok((bytes, mime_type));
+    }
+    Ok((std::fs::read(resolve_uri(uri, base)?)?, None))
+}
+
+/// Resolve a relative file URI against `base`
+///
+/// Absolute paths and paths that climb out of `base` are rejected, so a
+/// model can only reference files next to or below itself.
+fn resolve_uri(uri: &str, base: &Path) -> Result<PathBuf> {
+    use std::path::Component;
+
+    let decoded = percent_decode(uri);
+    let mut relative = PathBuf::new();
+    for component in Path::new(&decoded).components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {},
+            Component::ParentDir if relative.pop() => {},
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(Error::Asset(format!(
+                    "glTF URI {uri} points outside the model directory"
+                )));
+            },
+        }
+    }
+    Ok(base.join(relative))
+}
+
+/// Decode `%XX` escapes in a URI
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            },
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Import state shared by the conversion passes
+struct Importer<'a> {
+    document: &'a gltf::Document,
+    buffers: &'a [Vec<u8>],
+    base: &'a Path,
+    settings: &'a ModelImportSettings,
+    /// Source to engine space: scale and up axis conversion
+    correction: Mat4,
+    /// World transform of every node at rest
+    world: Vec<Mat4>,
+    /// Parent of every node
+    parents: Vec<Option<usize>>,
+    /// Scene nodes, parents before children
+    order: Vec<usize>,
+    /// Bone index of every joint node
+    bones: HashMap<usize, usize>,
+    /// Node of every bone
+    bone_nodes: Vec<usize>,
+    /// Material index of primitives without a material
+    default_material: Option<u32>,
+    texture_ids: Vec<TextureId>,
+    meshes: Vec<ModelMesh>,
+    materials: Vec<Material>,
+    textures: Vec<ModelTexture>,
+    skeleton: Option<Skeleton>,
+    animations: Vec<AnimationClip>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Importer<'a> {
+    fn new(
+        document: &'a gltf::Document,
+        buffers: &'a [Vec<u8>],
+        base: &'a Path,
+        settings: &'a ModelImportSettings,
+    ) -> Self {
+        let up = match settings.up_axis {
+            UpAxis::Y => Quat::IDENTITY,
+            UpAxis::Z => Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
+        };
+        let correction = Mat4::from_scale_rotation_translation(
+            GlamVec3::splat(settings.scale),
+            up,
+            GlamVec3::ZERO,
+        );
+
+        let count = document.nodes().count();
+        let mut parents = vec![None; count];
+        for node in document.nodes() {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+
+        let roots: Vec<usize> = match document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+        {
+            Some(scene) => scene.nodes().map(|n| n.index()).collect(),
+            None => (0..count).filter(|&i| parents[i].is_none()).collect(),
+        };
+
+        let mut world = vec![Mat4::IDENTITY; count];
+        let mut order = Vec::with_capacity(count);
+        let mut stack: Vec<(usize, Mat4)> =
+            roots.iter().rev().map(|&i| (i, Mat4::IDENTITY)).collect();
+        while let Some((index, parent_world)) = stack.pop() {
+            let Some(node) = document.nodes().nth(index) else {
+                continue;
+            };
+            world[index] = parent_world * Mat4::from_cols_array_2d(&node.transform().matrix());
+            order.push(index);
+            let children: Vec<usize> = node.children().map(|c| c.index()).collect();
+            stack.extend(children.into_iter().rev().map(|c| (c, world[index])));
+        }
+
+        Self {
+            document,
+            buffers,
+            base,
+            settings,
+            correction,
+            world,
+            parents,
+            order,
+            bones: HashMap::new(),
+            bone_nodes: Vec::new(),
+            default_material: None,
+            texture_ids: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            textures: Vec::new(),
+            skeleton: None,
+            animations: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Express a source-space transform in engine space
+    fn convert(&self, matrix: Mat4) -> Mat4 {
+        self.correction * matrix * self.correction.inverse()
+    }
+
+    fn import_textures(&mut self) {
+        for texture in self.document.textures() {
+            let image = texture.source();
+            let source = match image.source() {
+                gltf::image::Source::View { view, mime_type } => {
+                    let bytes = self
+                        .buffers
+                        .get(view.buffer().index())
+                        .and_then(|b| b.get(view.offset()..view.offset() + view.length()))
+                        .map(<[u8]>::to_vec)
+                        .unwrap_or_default();
+                    TextureSource::Embedded {
+                        bytes,
+                        mime_type: Some(mime_type.to_string()),
+                    }
+                },
+                gltf::image::Source::Uri { uri, mime_type } if uri.starts_with("data:") => {
+                    match read_uri(uri, self.base) {
+                        Ok((bytes, declared)) => TextureSource::Embedded {
+                            bytes,
+                            mime_type: declared.or_else(|| mime_type.map(str::to_string)),
+                        },
+                        Err(e) => {
+                            self.warnings
+                                .push(format!("Texture {}: {e}", texture.index()));
+                            TextureSource::Embedded {
+                                bytes: Vec::new(),
+                                mime_type: None,
+                            }
+                        },
+                    }
+                },
+                gltf::image::Source::Uri { uri, .. } => match resolve_uri(uri, self.base) {
+                    Ok(path) => TextureSource::File(path),
+                    Err(e) => {
+                        self.warnings
+                            .push(format!("Texture {}: {e}", texture.index()));
+                        TextureSource::Embedded {
+                            bytes: Vec::new(),
+                            mime_type: None,
+                        }
+                    },
+                },
+            };
+            let name = texture
+                .name()
+                .or_else(|| image.name())
+                .map_or_else(|| format!("texture_{}", texture.index()), str::to_string);
+            let id = TextureId(Id::new());
+            self.texture_ids.push(id);
+            self.textures.push(ModelTexture {
+                id,
+                name,
+                source,
+                srgb: false,
+            });
+        }
+    }
+
+    fn texture(&mut self, index: usize, srgb: bool) -> TextureId {
+        let texture = &mut self.textures[index];
+        texture.srgb |= srgb;
+        texture.id
+    }
+
+    fn import_materials(&mut self, shader: ShaderId) {
+        for material in self.document.materials() {
+            let name = material.name().map_or_else(
+                || format!("material_{}", self.materials.len()),
+                str::to_string,
+            );
+            let mut out = Material::new(name, shader);
+            let pbr = material.pbr_metallic_roughness();
+            let [r, g, b, a] = pbr.base_color_factor();
+            out.set_color("base_color", Color::new(r, g, b, a));
+            out.set_float("metallic", pbr.metallic_factor());
+            out.set_float("roughness", pbr.roughness_factor());
+            out.properties.insert(
+                "emissive".to_string(),
+                MaterialProperty::Vec3(material.emissive_factor()),
+            );
+
+            if let Some(info) = pbr.base_color_texture() {
+                let id = self.texture(info.texture().index(), true);
+                out.set_texture("base_color_texture", id);
+            }
+            if let Some(info) = pbr.metallic_roughness_texture() {
+                let id = self.texture(info.texture().index(), false);
+                out.set_texture("metallic_roughness_texture", id);
+            }
+            if let Some(normal) = material.normal_texture() {
+                let id = self.texture(normal.texture().index(), false);
+                out.set_texture("normal_texture", id);
+                out.set_float("normal_scale", normal.scale());
+            }
+            if let Some(occlusion) = material.occlusion_texture() {
+                let id = self.texture(occlusion.texture().index(), false);
+                out.set_texture("occlusion_texture", id);
+                out.set_float("occlusion_strength", occlusion.strength());
+            }
+            if let Some(info) = material.emissive_texture() {
+                let id = self.texture(info.texture().index(), true);
+                out.set_texture("emissive_texture", id);
+            }
+
+            match material.alpha_mode() {
+                gltf::material::AlphaMode::Opaque => {},
+                gltf::material::AlphaMode::Mask => {
+                    out.set_float("alpha_cutoff", material.alpha_cutoff().unwrap_or(0.5));
+                },
+                gltf::material::AlphaMode::Blend => out.blend_mode = BlendMode::Alpha,
+            }
+            out.double_sided = material.double_sided();
+            self.materials.push(out);
+        }
+    }
+
+    fn material_index(&mut self, primitive: &gltf::Primitive<'_>, shader: ShaderId) -> u32 {
+        if !self.settings.import_materials {
+            return 0;
+        }
+        if let Some(index) = primitive.material().index() {
+            return index as u32;
+        }
+        *self.default_material.get_or_insert_with(|| {
+            self.materials.push(Material::new("default", shader));
+            self.materials.len() as u32 - 1
+        })
+    }
+
+    /// Build one skeleton from the joints of every skin
+    fn import_skeleton(&mut self) {
+        let buffers = self.buffers;
+        let joints: HashSet<usize> = self
+            .document
+            .skins()
+            .flat_map(|skin| skin.joints().map(|j| j.index()).collect::<Vec<_>>())
+            .collect();
+        if joints.is_empty() {
+            return;
+        }
+
+        let mut inverse_binds: HashMap<usize, Mat4> = HashMap::new();
+        for skin in self.document.skins() {
+            let matrices: Vec<Mat4> = skin
+                .reader(|b| buffers.get(b.index()).map(Vec::as_slice))
+                .read_inverse_bind_matrices()
+                .map(|iter| iter.map(|m| Mat4::from_cols_array_2d(&m)).collect())
+                .unwrap_or_default();
+            for (i, joint) in skin.joints().enumerate() {
+                let matrix = matrices.get(i).copied().unwrap_or(Mat4::IDENTITY);
+                inverse_binds.entry(joint.index()).or_insert(matrix);
+            }
+        }
+
+        let mut skeleton = Skeleton::new(
+            self.document
+                .skins()
+                .find_map(|s| s.name().map(str::to_string))
+                .unwrap_or_else(|| "skeleton".to_string()),
+        );
+        let mut names = HashSet::new();
+        let nodes: Vec<gltf::Node<'_>> = self.document.nodes().collect();
+        for &index in &self.order {
+            if !joints.contains(&index) {
+                continue;
+            }
+            let parent = self.parent_bone(index);
+            let parent_world = parent.map_or(Mat4::IDENTITY, |p| self.world[self.bone_nodes[p]]);
+            let local = self.convert(parent_world.inverse() * self.world[index]);
+            let (scale, rotation, position) = local.to_scale_rotation_translation();
+
+            let mut name = nodes[index].name().map_or_else(
+                || format!("joint_{}", self.bone_nodes.len()),
+                str::to_string,
+            );
+            if !names.insert(name.clone()) {
+                name = format!("{name}_{}", self.bone_nodes.len());
+                names.insert(name.clone());
+            }
+
+            let inverse_bind = self.convert(inverse_binds[&index]);
+            self.bones.insert(
+                index,
+                skeleton.add_bone(Bone {
+                    name,
+                    parent: parent.map_or(-1, |p| p as i32),
+                    local_position: to_vec3(position),
+                    local_rotation: bone_euler(rotation),
+                    local_scale: to_vec3(scale),
+                    inverse_bind: inverse_bind.to_cols_array(),
+                }),
+            );
+            self.bone_nodes.push(index);
+        }
+        self.skeleton = Some(skeleton);
+    }
+
+    /// Nearest ancestor of a node that is a bone
+    fn parent_bone(&self, node: usize) -> Option<usize> {
+        let mut current = self.parents[node];
+        while let Some(index) = current {
+            if let Some(&bone) = self.bones.get(&index) {
+                return Some(bone);
+            }
+            current = self.parents[index];
+        }
+        None
+    }
+
+    fn import_meshes(&mut self, shader: ShaderId) {
+        let nodes: Vec<gltf::Node<'a>> = self.document.nodes().collect();
+        for index in self.order.clone() {
+            let node = &nodes[index];
+            let Some(mesh) = node.mesh() else {
+                continue;
+            };
+            let skin = node.skin();
+            // Skinned vertices are posed by their joints, so only the node
+            // transform of static meshes is baked in.
+            let transform = if skin.is_some() {
+                self.correction
+            } else {
+                self.correction * self.world[index]
+            };
+            let name = mesh
+                .name()
+                .or_else(|| node.name())
+                .map_or_else(|| format!("mesh_{}", mesh.index()), str::to_string);
+            let imported = self.import_mesh(&mesh, skin.as_ref(), transform, name, shader);
+            self.meshes.push(imported);
+        }
+    }
+
+    fn import_mesh(
+        &mut self,
+        mesh: &gltf::Mesh<'_>,
+        skin: Option<&gltf::Skin<'_>>,
+        transform: Mat4,
+        name: String,
+        shader: ShaderId,
+    ) -> ModelMesh {
+        let buffers = self.buffers;
+        let linear = Mat3::from_mat4(transform);
+        let normal_matrix = linear.inverse().transpose();
+        let flip = linear.determinant() < 0.0;
+        let skin_joints: Vec<u16> = skin
+            .map(|s| {
+                s.joints()
+                    .map(|j| self.bones.get(&j.index()).map_or(0, |&b| b as u16))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut out = Mesh::new(name.clone());
+        let mut weights = SkinWeights::default();
+        let mut targets: Vec<(Vec<GlamVec3>, Vec<GlamVec3>)> = Vec::new();
+
+        for primitive in mesh.primitives() {
+            if primitive.mode() != gltf::mesh::Mode::Triangles {
+                self.warnings.push(format!(
+                    "Mesh '{name}': skipped primitive with unsupported mode {:?}",
+                    primitive.mode()
+                ));
+                continue;
+            }
+            let reader = primitive.reader(|b| buffers.get(b.index()).map(Vec::as_slice));
+            let Some(positions) = reader.read_positions() else {
+                self.warnings.push(format!(
+                    "Mesh '{name}': skipped primitive without positions"
+                ));
+                continue;
+            };
+            let positions: Vec<GlamVec3> = positions
+                .map(|p| transform.transform_point3(p.into()))
+                .collect();
+            let count = positions.len();
+            let base = out.vertices.len() as u32;
+
+            let normals: Option<Vec<GlamVec3>> = reader.read_normals().map(|n| {
+                n.map(|n| (normal_matrix * GlamVec3::from(n)).normalize_or_zero())
+                    .collect()
+            });
+            let tex_coords: Vec<[f32; 2]> = reader
+                .read_tex_coords(0)
+                .map(|t| t.into_f32().collect())
+                .unwrap_or_else(|| vec![[0.0; 2]; count]);
+            let mut indices: Vec<u32> = reader
+                .read_indices()
+                .map_or_else(|| (0..count as u32).collect(), |i| i.into_u32().collect());
+            indices.truncate(indices.len() / 3 * 3);
+            if let Some(bad) = indices.iter().find(|&&i| i as usize >= count) {
+                self.warnings.push(format!(
+                    "Mesh '{name}': skipped primitive with index {bad} out of {count} vertices"
+                ));
+                continue;
+            }
+            if flip {
+                for tri in indices.chunks_exact_mut(3) {
+                    tri.swap(1, 2);
+                }
+            }
+            let normals = normals.unwrap_or_else(|| face_normals(&positions, &indices));
+
+            for (i, position) in positions.iter().enumerate() {
+                out.vertices.push(Vertex3D {
+                    position: position.to_array(),
+                    normal: normals.get(i).copied().unwrap_or(GlamVec3::Y).to_array(),
+                    tex_coords: tex_coords.get(i).copied().unwrap_or([0.0; 2]),
+                });
+            }
+            let material_index = self.material_index(&primitive, shader);
+            out.submeshes.push(SubMesh {
+                start_index: out.indices.len() as u32,
+                index_count: indices.len() as u32,
+                material_index,
+            });
+            out.indices.extend(indices.into_iter().map(|i| base + i));
+
+            if skin.is_some() {
+                match (reader.read_joints(0), reader.read_weights(0)) {
+                    (Some(joints), Some(influences)) => {
+                        weights.joints.extend(
+                            joints.into_u16().map(|j| {
+                                j.map(|j| skin_joints.get(j as usize).copied().unwrap_or(0))
+                            }),
+                        );
+                        weights
+                            .weights
+                            .extend(influences.into_f32().map(normalize_weights));
+                    },
+                    _ => {
+                        self.warnings
+                            .push(format!("Mesh '{name}': skinned primitive without weights"));
+                    },
+                }
+                weights.joints.resize(out.vertices.len(), [0; 4]);
+                weights
+                    .weights
+                    .resize(out.vertices.len(), [1.0, 0.0, 0.0, 0.0]);
+            }
+
+            for (t, (deltas, normal_deltas, _)) in reader.read_morph_targets().enumerate() {
+                if targets.len() <= t {
+                    targets.push((Vec::new(), Vec::new()));
+                }
+                let (positions_out, normals_out) = &mut targets[t];
+                positions_out.resize(base as usize, GlamVec3::ZERO);
+                normals_out.resize(base as usize, GlamVec3::ZERO);
+                if let Some(deltas) = deltas {
+                    positions_out.extend(deltas.map(|d| linear * GlamVec3::from(d)));
+                }
+                if let Some(deltas) = normal_deltas {
+                    normals_out.extend(deltas.map(|d| normal_matrix * GlamVec3::from(d)));
+                }
+            }
+        }
+        out.calculate_bounds();
+
+        let target_names: Vec<String> = mesh
+            .extras()
+            .as_ref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw.get()).ok())
+            .and_then(|extras| {
+                extras["targetNames"].as_array().map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|n| n.as_str().map(str::to_string))
+                        .collect()
+                })
+            })
+            .unwrap_or_default();
+        let default_weights = mesh.weights().unwrap_or(&[]);
+        let vertex_count = out.vertices.len();
+        let blend_shapes = targets
+            .into_iter()
+            .enumerate()
+            .map(|(t, (mut deltas, mut normal_deltas))| {
+                deltas.resize(vertex_count, GlamVec3::ZERO);
+                let has_normals = normal_deltas.iter().any(|n| *n != GlamVec3::ZERO);
+                normal_deltas.resize(vertex_count, GlamVec3::ZERO);
+                let name = target_names
+                    .get(t)
+                    .cloned()
+                    .unwrap_or_else(|| format!("target_{t}"));
+                let mut shape = BlendShape::new(&name, deltas);
+                shape.normal_deltas = has_normals.then_some(normal_deltas);
+                shape.weight = default_weights.get(t).copied().unwrap_or(0.0);
+                shape
+            })
+            .collect();
+
+        let lods = self.generate_lods(&out);
+        ModelMesh {
+            mesh: out,
+            lods,
+            skin: skin.map(|_| weights),
+            blend_shapes,
+        }
+    }
+
+    fn generate_lods(&self, mesh: &Mesh) -> Vec<MeshLod> {
+        let mut lods: Vec<MeshLod> = Vec::new();
+        if !self.settings.generate_lods {
+            return lods;
+        }
+        let full = mesh.triangle_count();
+        for level in 1..self.settings.lod_count {
+            let previous = lods.last().map_or(full, |l| l.mesh.triangle_count());
+            let target =
+                (full as f32 * self.settings.lod_reduction.powi(i32::from(level))) as usize;
+            let Some(mut lod) = simplify(mesh, target.min(previous.saturating_sub(1))) else {
+                break;
+            };
+            lod.mesh.name = format!("{}_lod{level}", mesh.name);
+            lod.screen_size = 0.5f32.powi(i32::from(level));
+            lods.push(lod);
+        }
+        lods
+    }
+
+    fn import_animations(&mut self) {
+        if self.skeleton.is_none() {
+            if self.document.animations().next().is_some() {
+                self.warnings
+                    .push("Animations skipped: only skeletal animation is supported".to_string());
+            }
+            return;
+        }
+        let buffers = self.buffers;
+        let nodes: Vec<gltf::Node<'a>> = self.document.nodes().collect();
+        for animation in self.document.animations() {
+            let name = animation.name().map_or_else(
+                || format!("animation_{}", animation.index()),
+                str::to_string,
+            );
+            let mut tracks: HashMap<usize, [Option<Track>; 3]> = HashMap::new();
+            let mut duration = 0.0f32;
+
+            for channel in animation.channels() {
+                let target = channel.target();
+                let node = target.node().index();
+                let slot = match target.property() {
+                    Property::Translation => 0,
+                    Property::Rotation => 1,
+                    Property::Scale => 2,
+                    Property::MorphTargetWeights => {
+                        self.warnings.push(format!(
+                            "Animation '{name}': morph weight channels are not supported"
+                        ));
+                        continue;
+                    },
+                };
+                let Some(&bone) = self.bones.get(&node) else {
+                    self.warnings.push(format!(
+                        "Animation '{name}': skipped channel on non-joint node {node}"
+                    ));
+                    continue;
+                };
+                let reader = channel.reader(|b| buffers.get(b.index()).map(Vec::as_slice));
+                let (Some(times), Some(outputs)) = (reader.read_inputs(), reader.read_outputs())
+                else {
+                    continue;
+                };
+                let values: Vec<Vec4> = match outputs {
+                    ReadOutputs::Translations(v) => {
+                        v.map(|[x, y, z]| Vec4::new(x, y, z, 0.0)).collect()
+                    },
+                    ReadOutputs::Scales(v) => v.map(|[x, y, z]| Vec4::new(x, y, z, 0.0)).collect(),
+                    ReadOutputs::Rotations(v) => v.into_f32().map(Vec4::from).collect(),
+                    ReadOutputs::MorphTargetWeights(_) => continue,
+                };
+                let track = Track::new(
+                    times.collect(),
+                    values,
+                    channel.sampler().interpolation(),
+                    slot == 1,
+                );
+                duration = duration.max(track.times.last().copied().unwrap_or(0.0));
+                tracks.entry(bone).or_default()[slot] = Some(track);
+            }
+
+            let mut clip = AnimationClip::new(name, duration);
+            let mut animated: Vec<usize> = tracks.keys().copied().collect();
+            animated.sort_unstable();
+            for bone in animated {
+                let node = self.bone_nodes[bone];
+                let channel = self.bone_channel(bone, &nodes[node], &tracks[&bone]);
+                clip.add_channel(channel);
+            }
+            self.animations.push(clip);
+        }
+    }
+
+    /// Resample a bone's tracks at the union of their key times
+    fn bone_channel(
+        &self,
+        bone: usize,
+        node: &gltf::Node<'_>,
+        tracks: &[Option<Track>; 3],
+    ) -> AnimationChannel {
+        let skeleton = self.skeleton.as_ref().expect("bones imply a skeleton");
+        let mut channel = AnimationChannel::new(skeleton.bones[bone].name.clone());
+
+        // Transform from the parent bone to the node's parent, which stays at
+        // rest when intermediate nodes are not joints.
+        let node_index = self.bone_nodes[bone];
+        let parent_world = self.parents[node_index].map_or(Mat4::IDENTITY, |p| self.world[p]);
+        let bone_world = self
+            .parent_bone(node_index)
+            .map_or(Mat4::IDENTITY, |p| self.world[self.bone_nodes[p]]);
+        let offset = bone_world.inverse() * parent_world;
+
+        let mut times: Vec<f32> = tracks
+            .iter()
+            .flatten()
+            .flat_map(|t| t.times.clone())
+            .collect();
+        times.sort_by(f32::total_cmp);
+        times.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+
+        let (rest_t, rest_r, rest_s) = node.transform().decomposed();
+        let mut previous: Option<GlamVec3> = None;
+        for time in times {
+            let sample =
+                |slot: usize, rest: Vec4| tracks[slot].as_ref().map_or(rest, |t| t.sample(time));
+            let translation = sample(0, GlamVec3::from(rest_t).extend(0.0)).truncate();
+            let rotation = Quat::from_vec4(sample(1, Vec4::from(rest_r))).normalize();
+            let scale = sample(2, GlamVec3::from(rest_s).extend(0.0)).truncate();
+            let local = self.convert(
+                offset * Mat4::from_scale_rotation_translation(scale, rotation, translation),
+            );
+            let (scale, rotation, position) = local.to_scale_rotation_translation();
+
+            let mut euler = glam_euler(rotation);
+            if let Some(prev) = previous {
+                euler = unwrap_angles(prev, euler);
+            }
+            previous = Some(euler);
+
+            channel.add_keyframe(Keyframe {
+                time,
+                position: Some(to_vec3(position)),
+                rotation: Some(to_vec3(euler)),
+                scale: Some(to_vec3(scale)),
+            });
+        }
+        channel
+    }
+}
+
+/// Keyframes of one animated property
+struct Track {
+    times: Vec<f32>,
+    values: Vec<Vec4>,
+    /// In and out tangents for cubic spline tracks
+    tangents: Vec<(Vec4, Vec4)>,
+    interpolation: Interpolation,
+    rotation: bool,
+}
+
+impl Track {
+    fn new(
+        times: Vec<f32>,
+        values: Vec<Vec4>,
+        interpolation: Interpolation,
+        rotation: bool,
+    ) -> Self {
+        let (values, tangents) = if interpolation == Interpolation::CubicSpline {
+            let triplets: Vec<&[Vec4]> = values.chunks_exact(3).collect();
+            (
+                triplets.iter().map(|t| t[1]).collect(),
+                triplets.iter().map(|t| (t[0], t[2])).collect(),
+            )
+        } else {
+            (values, Vec::new())
+        };
+        let len = times.len().min(values.len());
+        Self {
+            times: times[..len].to_vec(),
+            values: values[..len].to_vec(),
+            tangents,
+            interpolation,
+            rotation,
+        }
+    }
+
+    fn sample(&self, time: f32) -> Vec4 {
+        let last = self.times.len() - 1;
+        if time <= self.times[0] {
+            return self.values[0];
+        }
+        if time >= self.times[last] {
+            return self.values[last];
+        }
+        let i = self.times.partition_point(|&t| t <= time) - 1;
+        let dt = self.times[i + 1] - self.times[i];
+        let f = (time - self.times[i]) / dt;
+        let (a, b) = (self.values[i], self.values[i + 1]);
+
+        let value = match self.interpolation {
+            Interpolation::Step => a,
+            Interpolation::Linear if self.rotation => {
+                Vec4::from(Quat::from_vec4(a).slerp(Quat::from_vec4(b), f))
+            },
+            Interpolation::Linear => a.lerp(b, f),
+            Interpolation::CubicSpline => {
+                let (f2, f3) = (f * f, f * f * f);
+                let out_tangent = self.tangents[i].1 * dt;
+                let in_tangent = self.tangents[i + 1].0 * dt;
+                a * (2.0 * f3 - 3.0 * f2 + 1.0)
+                    + out_tangent * (f3 - 2.0 * f2 + f)
+                    + b * (-2.0 * f3 + 3.0 * f2)
+                    + in_tangent * (f3 - f2)
+            },
+        };
+        if self.rotation {
+            value.normalize_or_zero()
+        } else {
+            value
+        }
+    }
+}
+
+/// Smooth vertex normals from triangle faces
+fn face_normals(positions: &[GlamVec3], indices: &[u32]) -> Vec<GlamVec3> {
+    let mut normals = vec![GlamVec3::ZERO; positions.len()];
+    for tri in indices.chunks_exact(3) {
+        let [a, b, c] = [tri[0], tri[1], tri[2]].map(|i| i as usize);
+        if a.max(b).max(c) >= positions.len() {
+            continue;
+        }
+        let normal = (positions[b] - positions[a]).cross(positions[c] - positions[a]);
+        normals[a] += normal;
+        normals[b] += normal;
+        normals[c] += normal;
+    }
+    normals
+        .into_iter()
+        .map(|n| n.try_normalize().unwrap_or(GlamVec3::Y))
+        .collect()
+}
+
+fn normalize_weights(weights: [f32; 4]) -> [f32; 4] {
+    let sum: f32 = weights.iter().sum();
+    if sum > f32::EPSILON {
+        weights.map(|w| w / sum)
+    } else {
+        [1.0, 0.0, 0.0, 0.0]
+    }
+}
+
+/// Renderer euler angles (x, y, z) of a rotation
+fn glam_euler(rotation: Quat) -> GlamVec3 {
+    let (z, y, x) = rotation.to_euler(EulerRot::ZYX);
+    GlamVec3::new(x, y, z)
+}
+
+fn bone_euler(rotation: Quat) -> Vec3 {
+    to_vec3(glam_euler(rotation))
+}
+
+/// Shift each angle by whole turns to stay closest to the previous key
+fn unwrap_angles(previous: GlamVec3, angles: GlamVec3) -> GlamVec3 {
+    let tau = std::f32::consts::TAU;
+    let delta = angles - previous;
+    angles - (delta / tau).round() * tau
+}
+
+fn to_vec3(v: GlamVec3) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two-joint quad with a bend animation and one morph target, as glTF JSON
+    fn rigged_quad() -> Vec<u8> {
+        let mut bin = Vec::new();
+        let mut push = |values: &[f32]| {
+            for v in values {
+                bin.extend_from_slice(&v.to_le_bytes());
+            }
+        };
+        // positions (4 * vec3)
+        push(&[0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]);
+        // morph deltas (4 * vec3)
+        push(&[0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0]);
+        // weights (4 * vec4)
+        push(&[
+            1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0,
+        ]);
+        // inverse bind matrices (2 * mat4)
+        push(&[
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        ]);
+        push(&[
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 1.0,
+        ]);
+        // key times and rotations (identity, 90 degrees about X)
+        let s = std::f32::consts::FRAC_1_SQRT_2;
+        push(&[0.0, 1.0]);
+        push(&[0.0, 0.0, 0.0, 1.0, s, 0.0, 0.0, s]);
+        // joints (4 * u8x4) and indices (6 * u16)
+        bin.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+        for i in [0u16, 1, 2, 0, 2, 3] {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(&bin);
+
+        serde_json::json!({
+            "asset": { "version": "2.0" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0, 1] }],
+            "nodes": [
+                { "name": "quad", "mesh": 0, "skin": 0 },
+                { "name": "root", "children": [2] },
+                { "name": "tip", "translation": [0.0, 1.0, 0.0] }
+            ],
+            "skins": [{ "joints": [1, 2], "inverseBindMatrices": 3 }],
+            "meshes": [{
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "JOINTS_0": 6, "WEIGHTS_0": 2 },
+                    "indices": 7,
+                    "targets": [{ "POSITION": 1 }]
+                }],
+                "extras": { "targetNames": ["lift"] }
+            }],
+            "animations": [{
+                "name": "bend",
+                "channels": [{ "sampler": 0, "target": { "node": 2, "path": "rotation" } }],
+                "samplers": [{ "input": 4, "output": 5 }]
+            }],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                  "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0] },
+                { "bufferView": 1, "componentType": 5126, "count": 4, "type": "VEC3" },
+                { "bufferView": 2, "componentType": 5126, "count": 4, "type": "VEC4" },
+                { "bufferView": 3, "componentType": 5126, "count": 2, "type": "MAT4" },
+                { "bufferView": 4, "componentType": 5126, "count": 2, "type": "SCALAR",
+                  "min": [0.0], "max": [1.0] },
+                { "bufferView": 5, "componentType": 5126, "count": 2, "type": "VEC4" },
+                { "bufferView": 6, "componentType": 5121, "count": 4, "type": "VEC4" },
+                { "bufferView": 7, "componentType": 5123, "count": 6, "type": "SCALAR" }
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 48, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 96, "byteLength": 64 },
+                { "buffer": 0, "byteOffset": 160, "byteLength": 128 },
+                { "buffer": 0, "byteOffset": 288, "byteLength": 8 },
+                { "buffer": 0, "byteOffset": 296, "byteLength": 32 },
+                { "buffer": 0, "byteOffset": 328, "byteLength": 16 },
+                { "buffer": 0, "byteOffset": 344, "byteLength": 12 }
+            ],
+            "buffers": [{
+                "byteLength": bin.len(),
+                "uri": format!("data:application/octet-stream;base64,{data}")
+            }]
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// One mesh with a positions-only triangle, an out of range index and a
+    /// line strip
+    fn mixed_primitives() -> Vec<u8> {
+        let mut bin = Vec::new();
+        for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            bin.extend_from_slice(&v.to_le_bytes());
+        }
+        for i in [0u16, 1, 2, 0, 1, 7] {
+            bin.extend_from_slice(&i.to_le_bytes());
+        }
+        let data = base64::engine::general_purpose::STANDARD.encode(&bin);
+
+        serde_json::json!({
+            "asset": { "version": "2.0" },
+            "scene": 0,
+            "scenes": [{ "nodes": [0] }],
+            "nodes": [{ "name": "mixed", "mesh": 0 }],
+            "meshes": [{
+                "primitives": [
+                    { "attributes": { "POSITION": 0 }, "indices": 1 },
+                    { "attributes": { "POSITION": 0 }, "indices": 2 },
+                    { "attributes": { "POSITION": 0 }, "mode": 3 }
+                ]
+            }],
+            "accessors": [
+                { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+                  "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0] },
+                { "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" },
+                { "bufferView": 1, "byteOffset": 6, "componentType": 5123, "count": 3,
+                  "type": "SCALAR" }
+            ],
+            "bufferViews": [
+                { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 12 }
+            ],
+            "buffers": [{
+                "byteLength": bin.len(),
+                "uri": format!("data:application/octet-stream;base64,{data}")
+            }]
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn assert_close(a: Vec3, b: [f32; 3]) {
+        assert!(
+            (a.x - b[0]).abs() < 1e-4 && (a.y - b[1]).abs() < 1e-4 && (a.z - b[2]).abs() < 1e-4,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn imports_skinned_morphed_model() {
+        let settings = ModelImportSettings {
+            scale: 2.0,
+            up_axis: UpAxis::Z,
+            ..ModelImportSettings::default()
+        };
+        let model = ModelLoader::new(settings)
+            .import_bytes(&rigged_quad(), Path::new("models/quad.gltf"))
+            .unwrap();
+
+        assert_eq!(model.name, "quad");
+        assert_eq!(model.meshes.len(), 1);
+        let mesh = &model.meshes[0];
+        assert_eq!(mesh.mesh.triangle_count(), 2);
+        // Z-up source: +Y becomes -Z, and everything is scaled by 2.
+        let corner = GlamVec3::from(mesh.mesh.vertices[2].position);
+        assert!((corner - GlamVec3::new(2.0, 0.0, -2.0)).length() < 1e-4);
+        assert!((mesh.mesh.vertices[0].normal[1] - 1.0).abs() < 1e-4);
+        assert_eq!(model.materials.len(), 1);
+
+        let skin = mesh.skin.as_ref().unwrap();
+        assert_eq!(skin.joints[2][0], 1);
+        assert_eq!(skin.weights[2], [1.0, 0.0, 0.0, 0.0]);
+
+        assert_eq!(mesh.blend_shapes.len(), 1);
+        assert_eq!(mesh.blend_shapes[0].name, "lift");
+        let lift = mesh.blend_shapes[0].deltas[3];
+        assert!((lift - GlamVec3::new(0.0, 2.0, 0.0)).length() < 1e-4);
+
+        let skeleton = model.skeleton.as_ref().unwrap();
+        assert_eq!(skeleton.bone_count(), 2);
+        let tip = &skeleton.bones[skeleton.bone_index("tip").unwrap()];
+        assert_eq!(tip.parent, 0);
+        assert_close(tip.local_position, [0.0, 0.0, -2.0]);
+        assert!((tip.inverse_bind[14] - 2.0).abs() < 1e-4);
+
+        let clip = &model.animations[0];
+        assert_eq!(clip.name, "bend");
+        assert!((clip.duration - 1.0).abs() < 1e-6);
+        let keys = &clip.channel("tip").unwrap().keyframes;
+        assert_eq!(keys.len(), 2);
+        assert_close(keys[1].position.unwrap(), [0.0, 0.0, -2.0]);
+        assert_close(
+            keys[1].rotation.unwrap(),
+            [std::f32::consts::FRAC_PI_2, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn cooks_model_files_with_safe_clip_names() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("quad.gltf");
+        let gltf = String::from_utf8(rigged_quad()).unwrap();
+        std::fs::write(&source, gltf.replace("\"bend\"", "\"../../bend:1\"")).unwrap();
+        let out = dir.path().join("out");
+        let mut importer =
+            crate::asset_pipeline::AssetImporter::new(out.clone(), dir.path().join("cache"));
+
+        let result = importer.import(&source).unwrap();
+        assert!(result.outputs.iter().all(|output| output.exists()));
+        assert!(result.warnings.iter().all(|w| !w.contains("Nanite")));
+        let names: Vec<_> = result
+            .outputs
+            .iter()
+            .map(|o| o.strip_prefix(&out).unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(names, ["quad.mesh", "quad.skeleton", "quad_______bend_1.anim"]);
+
+        let meshes = decode_meshes(&std::fs::read(&result.outputs[0]).unwrap()).unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].triangle_count(), 2);
+        let skeleton: Skeleton =
+            serde_json::from_slice(&std::fs::read(&result.outputs[1]).unwrap()).unwrap();
+        assert_eq!(skeleton.bone_count(), 2);
+    }
+
+    #[test]
+    fn file_uris_stay_inside_the_model_directory() {
+        let base = Path::new("assets/models");
+        assert_eq!(
+            resolve_uri("textures/../rock%20albedo.png", base).unwrap(),
+            base.join("rock albedo.png")
+        );
+        assert_eq!(resolve_uri("./a/b.bin", base).unwrap(), base.join("a/b.bin"));
+        for uri in ["../secret.bin", "a/../../secret.bin", "/etc/passwd", "%2E%2E/secret.bin"] {
+            assert!(resolve_uri(uri, base).is_err(), "{uri}");
+        }
+
+        let gltf = String::from_utf8(rigged_quad()).unwrap();
+        let escaping = serde_json::json!({ "byteLength": 4, "uri": "../../../etc/passwd" });
+        let mut document: serde_json::Value = serde_json::from_str(&gltf).unwrap();
+        document["buffers"][0] = escaping;
+        let result = ModelLoader::new(ModelImportSettings::default())
+            .import_bytes(document.to_string().as_bytes(), Path::new("models/quad.gltf"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn mesh_files_round_trip_and_reject_corruption() {
+        let mut sphere = Mesh::sphere(1.0, 8, 4);
+        sphere.name = "ball".to_string();
+        sphere.submeshes = vec![SubMesh {
+            start_index: 0,
+            index_count: sphere.indices.len() as u32,
+            material_index: 2,
+        }];
+        let bytes = encode_meshes(&[&sphere, &Mesh::new("empty")]);
+
+        let meshes = decode_meshes(&bytes).unwrap();
+        assert_eq!(meshes.len(), 2);
+        assert_eq!(meshes[0].name, "ball");
+        assert_eq!(meshes[0].indices, sphere.indices);
+        assert_eq!(meshes[0].vertices[5].position, sphere.vertices[5].position);
+        assert_eq!(meshes[0].submeshes[0].material_index, 2);
+        assert_eq!(meshes[0].bounds_max, sphere.bounds_max);
+        assert!(meshes[1].vertices.is_empty());
+
+        assert!(decode_meshes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode_meshes(b"LMSH").is_err());
+        let mut huge = bytes.clone();
+        huge[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_meshes(&huge).is_err());
+    }
+
+    #[test]
+    fn skips_unusable_primitives_with_warnings() {
+        let model = ModelLoader::new(ModelImportSettings::default())
+            .import_bytes(&mixed_primitives(), Path::new("models/mixed.gltf"))
+            .unwrap();
+
+        let mesh = &model.meshes[0].mesh;
+        assert_eq!(mesh.submeshes.len(), 1);
+        assert_eq!(mesh.vertices.len(), 3);
+        assert_eq!(mesh.indices, [0, 1, 2]);
+        // Missing normals and UVs fall back to face normals and zeros
+        assert_eq!(mesh.vertices[1].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(mesh.vertices[2].tex_coords, [0.0, 0.0]);
+        assert_eq!(model.warnings.len(), 2);
+        assert!(model.warnings[0].contains("index 7 out of 3"));
+        assert!(model.warnings[1].contains("unsupported mode"));
+    }
+
+    #[test]
+    fn generates_lods_with_decreasing_detail() {
+        let mut sphere = Mesh::sphere(1.0, 32, 16);
+        sphere.calculate_bounds();
+        let full = sphere.triangle_count();
+
+        let lod = simplify(&sphere, full / 4).unwrap();
+        assert!(lod.mesh.triangle_count() <= full / 4);
+        assert!(lod.mesh.triangle_count() > 0);
+        assert_eq!(lod.source_vertices.len(), lod.mesh.vertices.len());
+        assert!(lod
+            .mesh
+            .indices
+            .iter()
+            .all(|&i| (i as usize) < lod.mesh.vertices.len()));
+        assert!(simplify(&sphere, full).is_none());
+    }
+}
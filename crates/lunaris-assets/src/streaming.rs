@@ -1,14 +1,27 @@
 //! Asset Streaming System
 //!
 //! Background streaming of assets with priority and LOD management.
+//!
+//! Each update scores assets by how large they appear on screen, whether they
+//! are inside the view frustum and their [`StreamPriority`]. Queued assets are
+//! read asynchronously in score order, and when the memory budget runs out the
+//! lowest scoring unreferenced assets are evicted first.
 
+use glam::Vec3;
+use lunaris_core::pack::{PackArchive, PackSet};
+use lunaris_core::{Error, Result};
+use lunaris_renderer::Frustum;
 use std::collections::{HashMap, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 /// Streaming priority
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum StreamPriority {
-    /// Immediate - block until loaded
+    /// Immediate - load ahead of everything queued, pair with
+    /// [`StreamingManager::wait`] to block until loaded
     Immediate = 0,
     /// Critical - load next frame
     Critical = 1,
@@ -22,6 +35,21 @@ pub enum StreamPriority {
     Prefetch = 5,
 }
 
+impl StreamPriority {
+    /// Multiplier applied to an asset's streaming score
+    #[must_use]
+    pub fn boost(self) -> f32 {
+        match self {
+            Self::Immediate => 1000.0,
+            Self::Critical => 100.0,
+            Self::High => 4.0,
+            Self::Normal => 1.0,
+            Self::Low => 0.5,
+            Self::Prefetch => 0.1,
+        }
+    }
+}
+
 /// Streaming state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StreamState {
@@ -39,6 +67,50 @@ pub enum StreamState {
     Unloading,
 }
 
+/// World-space bounds of a streamable asset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StreamBounds {
+    /// Minimum corner
+    pub min: Vec3,
+    /// Maximum corner
+    pub max: Vec3,
+}
+
+impl StreamBounds {
+    /// Create bounds from two corners
+    #[must_use]
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self {
+            min: min.min(max),
+            max: min.max(max),
+        }
+    }
+
+    /// Create bounds enclosing a sphere
+    #[must_use]
+    pub fn from_sphere(center: Vec3, radius: f32) -> Self {
+        Self::new(center - Vec3::splat(radius), center + Vec3::splat(radius))
+    }
+
+    /// Center point
+    #[must_use]
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Radius of the enclosing sphere
+    #[must_use]
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).length() * 0.5
+    }
+
+    /// Distance from a point to the closest point on the bounds, zero inside
+    #[must_use]
+    pub fn distance(&self, point: Vec3) -> f32 {
+        point.clamp(self.min, self.max).distance(point)
+    }
+}
+
 /// Streamable asset
 #[derive(Debug, Clone)]
 pub struct StreamableAsset {
@@ -54,8 +126,10 @@ pub struct StreamableAsset {
     pub priority: StreamPriority,
     /// Size in memory (bytes)
     pub memory_size: u64,
-    /// Size on disk (bytes)
+    /// Size on disk (bytes), what a load costs in bandwidth
     pub disk_size: u64,
+    /// Expected size in memory once loaded (bytes), reserved from the budget
+    pub load_size: u64,
     /// LOD levels available
     pub lod_levels: u8,
     /// Currently loaded LOD
@@ -64,8 +138,16 @@ pub struct StreamableAsset {
     pub last_access: std::time::Instant,
     /// Reference count
     pub ref_count: u32,
+    /// World bounds, `None` for assets that are not placed in the world
+    pub bounds: Option<StreamBounds>,
     /// Distance to camera (for priority)
     pub distance: f32,
+    /// Fraction of the screen height covered (0-1)
+    pub screen_size: f32,
+    /// Inside the view frustum
+    pub visible: bool,
+    /// Streaming score, higher loads first and unloads last
+    pub score: f32,
 }
 
 /// Streamable asset type
@@ -95,18 +177,30 @@ pub struct StreamRequest {
     pub on_complete: Option<fn(u64, bool)>,
 }
 
+/// Bytes read by a worker
+struct StreamResult {
+    asset_id: u64,
+    result: Result<Vec<u8>>,
+}
+
 /// Streaming manager
 pub struct StreamingManager {
     /// All assets
     assets: HashMap<u64, StreamableAsset>,
     /// Load queue
     load_queue: VecDeque<StreamRequest>,
-    /// Unload queue
-    unload_queue: Vec<u64>,
     /// Currently loading
-    loading: Vec<u64>,
+    loading: Vec<StreamRequest>,
+    /// Data of loaded assets
+    resident: HashMap<u64, Arc<Vec<u8>>>,
     /// Next ID
     next_id: u64,
+    /// Mounted pack archives, searched before loose files
+    mounts: Arc<PackSet>,
+    /// Worker pool for file IO
+    runtime: Option<tokio::runtime::Runtime>,
+    results_tx: mpsc::UnboundedSender<StreamResult>,
+    results_rx: mpsc::UnboundedReceiver<StreamResult>,
     /// Memory budget
     pub memory_budget: u64,
     /// Current memory
@@ -119,6 +213,10 @@ pub struct StreamingManager {
     pub unload_distance: f32,
     /// Min time before unload (seconds)
     pub min_loaded_time: f32,
+    /// Vertical field of view (radians) used for screen size without a frustum
+    pub fov: f32,
+    /// Score multiplier for assets outside the view frustum
+    pub offscreen_factor: f32,
 }
 
 impl Default for StreamingManager {
@@ -129,25 +227,68 @@ impl Default for StreamingManager {
 
 impl StreamingManager {
     /// Create new manager
+    ///
+    /// # Panics
+    ///
+    /// Panics if the IO worker threads cannot be spawned.
     #[must_use]
     pub fn new() -> Self {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("lunaris-streaming")
+            .enable_all()
+            .build()
+            .expect("failed to start streaming worker pool");
+        let (results_tx, results_rx) = mpsc::unbounded_channel();
+
         Self {
             assets: HashMap::new(),
             load_queue: VecDeque::new(),
-            unload_queue: Vec::new(),
             loading: Vec::new(),
+            resident: HashMap::new(),
             next_id: 1,
+            mounts: Arc::new(PackSet::new()),
+            runtime: Some(runtime),
+            results_tx,
+            results_rx,
             memory_budget: 4 * 1024 * 1024 * 1024, // 4GB
             current_memory: 0,
             max_concurrent: 8,
             bytes_per_frame: 32 * 1024 * 1024, // 32MB per frame
             unload_distance: 500.0,
             min_loaded_time: 5.0,
+            fov: 60.0_f32.to_radians(),
+            offscreen_factor: 0.25,
         }
     }
 
     /// Register asset
     pub fn register(&mut self, path: PathBuf, asset_type: StreamAssetType, disk_size: u64) -> u64 {
+        self.insert(path, asset_type, disk_size, disk_size)
+    }
+
+    /// Register an asset, taking its size from the mounted archives or disk
+    ///
+    /// Compressed pack entries cost their stored size in bandwidth and their
+    /// unpacked size in memory.
+    pub fn register_file(&mut self, path: PathBuf, asset_type: StreamAssetType) -> u64 {
+        let (disk_size, load_size) = match self.mounts.lookup(&path.to_string_lossy()) {
+            Some((_, entry)) => (entry.stored_size, entry.size),
+            None => {
+                let size = std::fs::metadata(&path).map_or(0, |m| m.len());
+                (size, size)
+            },
+        };
+        self.insert(path, asset_type, disk_size, load_size)
+    }
+
+    fn insert(
+        &mut self,
+        path: PathBuf,
+        asset_type: StreamAssetType,
+        disk_size: u64,
+        load_size: u64,
+    ) -> u64 {
         let id = self.next_id;
         self.next_id += 1;
 
@@ -159,31 +300,50 @@ impl StreamingManager {
             priority: StreamPriority::Normal,
             memory_size: 0,
             disk_size,
+            load_size,
             lod_levels: 1,
             loaded_lod: None,
             last_access: std::time::Instant::now(),
             ref_count: 0,
+            bounds: None,
             distance: f32::MAX,
+            screen_size: 0.0,
+            visible: false,
+            score: 0.0,
         };
 
         self.assets.insert(id, asset);
         id
     }
 
+    /// Set the world bounds used to prioritize an asset
+    ///
+    /// Assets without bounds are treated as always visible and full screen.
+    pub fn set_bounds(&mut self, id: u64, bounds: Option<StreamBounds>) {
+        if let Some(asset) = self.assets.get_mut(&id) {
+            asset.bounds = bounds;
+        }
+    }
+
+    /// Mount a pack archive above those already mounted
+    pub fn mount(&mut self, archive: impl Into<Arc<PackArchive>>) {
+        Arc::make_mut(&mut self.mounts).mount(archive);
+    }
+
+    /// Unmount an archive by file path, returning whether it was mounted
+    pub fn unmount(&mut self, path: &Path) -> bool {
+        Arc::make_mut(&mut self.mounts).unmount(path)
+    }
+
+    /// Mounted pack archives
+    #[must_use]
+    pub fn mounts(&self) -> &PackSet {
+        &self.mounts
+    }
+
     /// Request load
     pub fn request(&mut self, asset_id: u64, priority: StreamPriority) {
-        if let Some(asset) = self.assets.get_mut(&asset_id) {
-            if asset.state == StreamState::Unloaded || asset.state == StreamState::Failed {
-                asset.state = StreamState::Queued;
-                asset.priority = priority;
-                self.load_queue.push_back(StreamRequest {
-                    asset_id,
-                    target_lod: 0,
-                    priority,
-                    on_complete: None,
-                });
-            }
-        }
+        self.enqueue(asset_id, priority, None);
     }
 
     /// Request load with callback
@@ -193,113 +353,298 @@ impl StreamingManager {
         priority: StreamPriority,
         callback: fn(u64, bool),
     ) {
+        self.enqueue(asset_id, priority, Some(callback));
+    }
+
+    fn enqueue(&mut self, asset_id: u64, priority: StreamPriority, on_complete: Option<fn(u64, bool)>) {
         if let Some(asset) = self.assets.get_mut(&asset_id) {
             if asset.state == StreamState::Unloaded || asset.state == StreamState::Failed {
                 asset.state = StreamState::Queued;
                 asset.priority = priority;
-                self.load_queue.push_back(StreamRequest {
+                asset.score = score(asset, self.offscreen_factor);
+                let request = StreamRequest {
                     asset_id,
                     target_lod: 0,
                     priority,
-                    on_complete: Some(callback),
-                });
+                    on_complete,
+                };
+                if priority == StreamPriority::Immediate {
+                    self.load_queue.push_front(request);
+                } else {
+                    self.load_queue.push_back(request);
+                }
             }
         }
     }
 
-    /// Update streaming
-    pub fn update(&mut self, _camera_position: glam::Vec3) {
-        // Update distances
+    /// Update streaming from the camera position, without frustum culling
+    pub fn update(&mut self, camera_position: Vec3) {
+        self.prioritize(camera_position, None);
+        self.process();
+    }
+
+    /// Update streaming from a camera frustum
+    ///
+    /// Assets outside the frustum have their score scaled by
+    /// [`offscreen_factor`](Self::offscreen_factor).
+    pub fn update_view(&mut self, frustum: &Frustum) {
+        let position = Vec3::new(frustum.position.x, frustum.position.y, frustum.position.z);
+        self.prioritize(position, Some(frustum));
+        self.process();
+    }
+
+    /// Collect finished loads until an asset stops loading or the timeout
+    /// elapses, returning its state
+    pub fn wait(&mut self, id: u64, timeout: Duration) -> StreamState {
+        let start = Instant::now();
+        loop {
+            self.collect();
+            let state = self.get(id).map_or(StreamState::Unloaded, |a| a.state);
+            let pending = state == StreamState::Queued || state == StreamState::Loading;
+            if !pending || start.elapsed() >= timeout {
+                return state;
+            }
+            if state == StreamState::Queued {
+                self.start_loads();
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Recompute distance, screen size, visibility and score for every asset
+    fn prioritize(&mut self, camera_position: Vec3, frustum: Option<&Frustum>) {
+        let half_fov_tan = (frustum.map_or(self.fov, |f| f.fov) * 0.5).tan().max(f32::EPSILON);
+
         for asset in self.assets.values_mut() {
-            // Simplified - would use asset position
-            asset.distance = 100.0; // Placeholder
+            match asset.bounds {
+                Some(bounds) => {
+                    let radius = bounds.radius();
+                    let center_distance = bounds.center().distance(camera_position);
+                    asset.distance = bounds.distance(camera_position);
+                    asset.screen_size = if center_distance <= radius {
+                        1.0
+                    } else {
+                        (radius / (center_distance * half_fov_tan)).min(1.0)
+                    };
+                    asset.visible = frustum.map_or(true, |f| {
+                        f.contains_aabb(to_core(bounds.min), to_core(bounds.max))
+                    });
+                },
+                None => {
+                    asset.distance = 0.0;
+                    asset.screen_size = 1.0;
+                    asset.visible = true;
+                },
+            }
+            asset.score = score(asset, self.offscreen_factor);
         }
+    }
 
-        // Sort queue by priority and distance
-        let mut queue_vec: Vec<_> = self.load_queue.drain(..).collect();
-        queue_vec.sort_by(|a, b| {
-            a.priority.cmp(&b.priority).then_with(|| {
-                let dist_a = self.assets.get(&a.asset_id).map(|a| a.distance).unwrap_or(f32::MAX);
-                let dist_b = self.assets.get(&b.asset_id).map(|a| a.distance).unwrap_or(f32::MAX);
-                dist_a.partial_cmp(&dist_b).unwrap_or(std::cmp::Ordering::Equal)
-            })
+    fn process(&mut self) {
+        self.collect();
+
+        // Highest score first, nearest first on ties
+        let assets = &self.assets;
+        self.load_queue
+            .retain(|r| assets.get(&r.asset_id).is_some_and(|a| a.state == StreamState::Queued));
+        self.load_queue.make_contiguous().sort_by(|a, b| {
+            let a = &assets[&a.asset_id];
+            let b = &assets[&b.asset_id];
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.distance.total_cmp(&b.distance))
         });
-        self.load_queue = queue_vec.into();
 
-        // Process loads
+        self.start_loads();
+        self.check_unloads();
+    }
+
+    /// Publish reads finished by the worker pool
+    fn collect(&mut self) {
+        while let Ok(StreamResult { asset_id, result }) = self.results_rx.try_recv() {
+            let Some(index) = self.loading.iter().position(|r| r.asset_id == asset_id) else {
+                continue;
+            };
+            let request = self.loading.swap_remove(index);
+            let Some(asset) = self.assets.get_mut(&asset_id) else {
+                continue;
+            };
+
+            let success = match result {
+                Ok(bytes) => {
+                    asset.state = StreamState::Loaded;
+                    asset.loaded_lod = Some(request.target_lod);
+                    asset.memory_size = bytes.len() as u64;
+                    asset.load_size = asset.memory_size;
+                    asset.last_access = Instant::now();
+                    self.current_memory += asset.memory_size;
+                    self.resident.insert(asset_id, Arc::new(bytes));
+                    true
+                },
+                Err(e) => {
+                    tracing::warn!("Failed to stream {}: {}", asset.path.display(), e);
+                    asset.state = StreamState::Failed;
+                    false
+                },
+            };
+            if let Some(callback) = request.on_complete {
+                callback(asset_id, success);
+            }
+        }
+    }
+
+    /// Submit queued loads within the concurrency, bandwidth and memory limits
+    fn start_loads(&mut self) {
         let mut bytes_this_frame = 0u64;
 
-        while self.loading.len() < self.max_concurrent && !self.load_queue.is_empty() {
-            if let Some(request) = self.load_queue.pop_front() {
-                if let Some(asset) = self.assets.get_mut(&request.asset_id) {
-                    if bytes_this_frame + asset.disk_size <= self.bytes_per_frame {
-                        asset.state = StreamState::Loading;
-                        self.loading.push(request.asset_id);
-                        bytes_this_frame += asset.disk_size;
-                    } else {
-                        // Put back in queue
-                        self.load_queue.push_front(request);
-                        break;
-                    }
-                }
+        while self.loading.len() < self.max_concurrent {
+            let Some(request) = self.load_queue.front() else {
+                break;
+            };
+            let Some(asset) = self.assets.get(&request.asset_id) else {
+                self.load_queue.pop_front();
+                continue;
+            };
+            let (read, size, score) = (asset.disk_size, asset.load_size, asset.score);
+
+            // An oversized asset still loads when it is the only one this frame
+            if bytes_this_frame > 0 && bytes_this_frame + read > self.bytes_per_frame {
+                break;
+            }
+            if !self.make_room(size, score) {
+                break;
             }
+
+            let Some(request) = self.load_queue.pop_front() else {
+                break;
+            };
+            bytes_this_frame += read;
+            self.spawn_load(request);
         }
+    }
+
+    fn spawn_load(&mut self, request: StreamRequest) {
+        let Some(runtime) = &self.runtime else {
+            return;
+        };
+        let Some(asset) = self.assets.get_mut(&request.asset_id) else {
+            return;
+        };
+        asset.state = StreamState::Loading;
+
+        let asset_id = request.asset_id;
+        let path = asset.path.clone();
+        let mounts = Arc::clone(&self.mounts);
+        let results = self.results_tx.clone();
+        runtime.spawn(async move {
+            let result = read(path, mounts).await;
+            // The manager may have been dropped; nothing is waiting then
+            let _ = results.send(StreamResult { asset_id, result });
+        });
+        self.loading.push(request);
+    }
 
-        // Complete loads (simulated)
-        let completed: Vec<_> = self.loading.drain(..).collect();
-        for id in completed {
-            if let Some(asset) = self.assets.get_mut(&id) {
-                asset.state = StreamState::Loaded;
-                asset.loaded_lod = Some(0);
-                asset.memory_size = asset.disk_size; // Simplified
-                asset.last_access = std::time::Instant::now();
-                self.current_memory += asset.memory_size;
+    /// Memory committed to loaded assets and loads in flight
+    fn committed_memory(&self) -> u64 {
+        let in_flight: u64 = self
+            .loading
+            .iter()
+            .filter_map(|r| self.assets.get(&r.asset_id))
+            .map(|a| a.load_size)
+            .sum();
+        self.current_memory + in_flight
+    }
+
+    /// Evict assets scoring below `score` until `needed` more bytes fit the
+    /// budget, lowest score first, returning whether they fit
+    fn make_room(&mut self, needed: u64, score: f32) -> bool {
+        let committed = self.committed_memory();
+        if committed + needed <= self.memory_budget {
+            return true;
+        }
+
+        let mut candidates: Vec<_> = self
+            .evictable()
+            .filter(|a| a.score < score)
+            .map(|a| (a.id, a.score, a.memory_size))
+            .collect();
+        candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        // Evict only if enough can be freed, a partial eviction helps nobody
+        let excess = committed + needed - self.memory_budget;
+        let freeable: u64 = candidates.iter().map(|c| c.2).sum();
+        if freeable < excess {
+            return false;
+        }
+
+        let mut freed = 0;
+        for (id, _, size) in candidates {
+            if freed >= excess {
+                break;
             }
+            self.unload(id);
+            freed += size;
         }
+        true
+    }
 
-        // Check for unloads
-        self.check_unloads();
+    /// Loaded, unreferenced assets that have been resident long enough
+    fn evictable(&self) -> impl Iterator<Item = &StreamableAsset> {
+        let now = Instant::now();
+        self.assets.values().filter(move |a| {
+            a.state == StreamState::Loaded
+                && a.ref_count == 0
+                && now.duration_since(a.last_access).as_secs_f32() >= self.min_loaded_time
+        })
     }
 
     fn check_unloads(&mut self) {
-        // Find assets to unload
+        // Unreferenced assets beyond the unload distance go regardless of budget
+        let distant: Vec<_> = self
+            .evictable()
+            .filter(|a| a.distance > self.unload_distance)
+            .map(|a| a.id)
+            .collect();
+        for id in distant {
+            self.unload(id);
+        }
+
+        // Trim back under budget, lowest score first
         if self.current_memory > self.memory_budget {
-            let now = std::time::Instant::now();
-            let mut candidates: Vec<_> = self.assets.values()
-                .filter(|a| {
-                    a.state == StreamState::Loaded 
-                    && a.ref_count == 0
-                    && now.duration_since(a.last_access).as_secs_f32() > self.min_loaded_time
-                })
-                .map(|a| (a.id, a.distance, a.last_access))
-                .collect();
-
-            // Sort by distance (furthest first)
-            candidates.sort_by(|a, b| {
-                b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
-            });
-
-            // Unload until under budget
-            for (id, _, _) in candidates {
+            let mut candidates: Vec<_> = self.evictable().map(|a| (a.id, a.score)).collect();
+            candidates.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+            for (id, _) in candidates {
                 if self.current_memory <= self.memory_budget {
                     break;
                 }
-                if let Some(asset) = self.assets.get_mut(&id) {
-                    self.current_memory -= asset.memory_size;
-                    asset.state = StreamState::Unloaded;
-                    asset.loaded_lod = None;
-                    asset.memory_size = 0;
-                }
+                self.unload(id);
             }
         }
     }
 
+    fn unload(&mut self, id: u64) {
+        if let Some(asset) = self.assets.get_mut(&id) {
+            self.current_memory = self.current_memory.saturating_sub(asset.memory_size);
+            asset.state = StreamState::Unloaded;
+            asset.loaded_lod = None;
+            asset.memory_size = 0;
+            self.resident.remove(&id);
+        }
+    }
+
     /// Get asset
     #[must_use]
     pub fn get(&self, id: u64) -> Option<&StreamableAsset> {
         self.assets.get(&id)
     }
 
+    /// Data of a loaded asset
+    #[must_use]
+    pub fn data(&self, id: u64) -> Option<Arc<Vec<u8>>> {
+        self.resident.get(&id).cloned()
+    }
+
     /// Is asset loaded
     #[must_use]
     pub fn is_loaded(&self, id: u64) -> bool {
@@ -335,6 +680,44 @@ impl StreamingManager {
     }
 }
 
+impl Drop for StreamingManager {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks, which panics inside async contexts
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// Score from screen coverage, visibility and priority boost
+fn score(asset: &StreamableAsset, offscreen_factor: f32) -> f32 {
+    let visibility = if asset.visible { 1.0 } else { offscreen_factor };
+    asset.priority.boost() * asset.screen_size * visibility
+}
+
+fn to_core(v: Vec3) -> lunaris_core::math::Vec3 {
+    lunaris_core::math::Vec3::new(v.x, v.y, v.z)
+}
+
+/// Read an asset from the mounted archives or disk
+async fn read(path: PathBuf, mounts: Arc<PackSet>) -> Result<Vec<u8>> {
+    let logical = path.to_string_lossy().into_owned();
+    // Packed files are memory mapped, so reading them is not async IO
+    if mounts.lookup(&logical).is_some() {
+        return tokio::task::spawn_blocking(move || {
+            mounts
+                .read(&logical)
+                .unwrap_or_else(|| Err(Error::Asset(format!("{} is not mounted", logical))))
+        })
+        .await
+        .unwrap_or_else(|e| Err(Error::Asset(format!("Read panicked: {}", e))));
+    }
+
+    tokio::fs::read(&path)
+        .await
+        .map_err(|e| Error::Asset(format!("Failed to read {}: {}", path.display(), e)))
+}
+
 /// Streaming statistics
 #[derive(Debug, Clone)]
 pub struct StreamingStats {
@@ -345,3 +728,173 @@ pub struct StreamingStats {
     pub memory_used: u64,
     pub memory_budget: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lunaris_core::pack::{PackCompression, PackWriter};
+    use std::path::Path;
+    use tempfile::{NamedTempFile, TempDir};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn temp_file(dir: &Path, size: usize) -> PathBuf {
+        let (_, path) = NamedTempFile::new_in(dir).unwrap().keep().unwrap();
+        std::fs::write(&path, vec![7u8; size]).unwrap();
+        path
+    }
+
+    fn register_at(manager: &mut StreamingManager, dir: &Path, center: Vec3, size: usize) -> u64 {
+        let id = manager.register_file(temp_file(dir, size), StreamAssetType::Mesh);
+        manager.set_bounds(id, Some(StreamBounds::from_sphere(center, 1.0)));
+        id
+    }
+
+    fn manager() -> (TempDir, StreamingManager) {
+        let mut manager = StreamingManager::new();
+        manager.min_loaded_time = 0.0;
+        (TempDir::new().unwrap(), manager)
+    }
+
+    #[test]
+    fn loads_near_visible_assets_first() {
+        let (dir, mut manager) = manager();
+        manager.max_concurrent = 1;
+        let far = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -30.0), 16);
+        let behind = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, 10.0), 16);
+        let near = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -20.0), 16);
+        for id in [far, behind, near] {
+            manager.request(id, StreamPriority::Normal);
+        }
+
+        let frustum = Frustum::default();
+        manager.update_view(&frustum);
+        assert_eq!(manager.get(near).unwrap().state, StreamState::Loading);
+        assert!(manager.get(near).unwrap().visible);
+        assert!(!manager.get(behind).unwrap().visible);
+        assert!(manager.get(near).unwrap().score > manager.get(far).unwrap().score);
+        assert_eq!(manager.wait(near, TIMEOUT), StreamState::Loaded);
+        assert_eq!(manager.data(near).unwrap().len(), 16);
+
+        // Behind the camera it is closer, but still loses to the visible asset
+        manager.update_view(&frustum);
+        assert_eq!(manager.get(far).unwrap().state, StreamState::Loading);
+        assert_eq!(manager.get(behind).unwrap().state, StreamState::Queued);
+    }
+
+    #[test]
+    fn priority_boost_outranks_distance() {
+        let (dir, mut manager) = manager();
+        manager.max_concurrent = 1;
+        let near = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -5.0), 16);
+        let far = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -200.0), 16);
+        manager.request(near, StreamPriority::Normal);
+        manager.request(far, StreamPriority::Critical);
+
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.get(far).unwrap().state, StreamState::Loading);
+        assert_eq!(manager.get(near).unwrap().state, StreamState::Queued);
+    }
+
+    #[test]
+    fn limits_bytes_started_per_frame() {
+        let (dir, mut manager) = manager();
+        manager.bytes_per_frame = 100;
+        let ids: Vec<_> = (0..3)
+            .map(|i| {
+                let center = Vec3::new(0.0, 0.0, -10.0 * (i + 1) as f32);
+                register_at(&mut manager, dir.path(), center, 60)
+            })
+            .collect();
+        for &id in &ids {
+            manager.request(id, StreamPriority::Normal);
+        }
+
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.stats().loading_assets + manager.stats().loaded_assets, 1);
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.stats().loading_assets + manager.stats().loaded_assets, 2);
+        for id in ids {
+            assert_eq!(manager.wait(id, TIMEOUT), StreamState::Loaded);
+        }
+        assert_eq!(manager.current_memory, 180);
+    }
+
+    #[test]
+    fn evicts_lowest_priority_unreferenced_assets() {
+        let (dir, mut manager) = manager();
+        manager.memory_budget = 200;
+        let near = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -5.0), 100);
+        let far = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -100.0), 100);
+        let mid = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -20.0), 100);
+        let failing = manager.register(PathBuf::from("missing.bin"), StreamAssetType::Mesh, 0);
+
+        for id in [near, far] {
+            manager.request(id, StreamPriority::Normal);
+            manager.update(Vec3::ZERO);
+            assert_eq!(manager.wait(id, TIMEOUT), StreamState::Loaded);
+        }
+        manager.add_ref(near);
+
+        // The farthest asset makes room for a closer one
+        manager.request(mid, StreamPriority::Normal);
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.get(far).unwrap().state, StreamState::Unloaded);
+        assert!(manager.data(far).is_none());
+        assert_eq!(manager.wait(mid, TIMEOUT), StreamState::Loaded);
+        assert!(manager.is_loaded(near));
+        assert_eq!(manager.current_memory, 200);
+
+        // Nothing referenced or higher scoring is evicted for a lower one
+        manager.request(far, StreamPriority::Low);
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.get(far).unwrap().state, StreamState::Queued);
+        assert!(manager.is_loaded(near) && manager.is_loaded(mid));
+
+        manager.request(failing, StreamPriority::Normal);
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.wait(failing, TIMEOUT), StreamState::Failed);
+    }
+
+    #[test]
+    fn packed_assets_reserve_their_unpacked_size() {
+        let (dir, mut manager) = manager();
+        let pack = dir.path().join("assets.pak");
+        let mut writer = PackWriter::create(&pack)
+            .unwrap()
+            .with_compression(PackCompression::Zstd);
+        writer.add("meshes/rock.bin", &[0u8; 4096]).unwrap();
+        writer.finish().unwrap();
+        manager.mount(PackArchive::open(&pack).unwrap());
+
+        let rock = manager.register_file(PathBuf::from("meshes/rock.bin"), StreamAssetType::Mesh);
+        let asset = manager.get(rock).unwrap();
+        assert!(asset.disk_size < 4096);
+        assert_eq!(asset.load_size, 4096);
+
+        // Reading it fits the bandwidth limit, unpacking it does not fit the budget
+        manager.bytes_per_frame = 1024;
+        manager.memory_budget = 1024;
+        manager.request(rock, StreamPriority::Normal);
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.get(rock).unwrap().state, StreamState::Queued);
+
+        manager.memory_budget = 8192;
+        manager.update(Vec3::ZERO);
+        assert_eq!(manager.wait(rock, TIMEOUT), StreamState::Loaded);
+        assert_eq!(manager.current_memory, 4096);
+    }
+
+    #[test]
+    fn immediate_requests_jump_the_queue() {
+        let (dir, mut manager) = manager();
+        manager.max_concurrent = 1;
+        let queued = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -5.0), 16);
+        let urgent = register_at(&mut manager, dir.path(), Vec3::new(0.0, 0.0, -200.0), 16);
+        manager.request(queued, StreamPriority::Critical);
+        manager.request(urgent, StreamPriority::Immediate);
+
+        assert_eq!(manager.wait(urgent, TIMEOUT), StreamState::Loaded);
+        assert_eq!(manager.get(queued).unwrap().state, StreamState::Queued);
+    }
+}
@@ -0,0 +1,410 @@
+//! Texture cooker
+//!
+//! Offline processing of source images into [`CookedTexture`]s: resizing, alpha
+//! handling, mip generation and block compression. Cooked textures are written
+//! as `.tex` files by the [`AssetImporter`](crate::asset_pipeline::AssetImporter).
+
+use crate::asset_pipeline::{AlphaMode, TextureCompression, TextureImportSettings};
+use crate::block_compression::compress;
+use crate::loader::{downsample, linear_to_srgb, srgb_to_linear};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use lunaris_renderer::{CookedTexture, GpuTextureFormat};
+
+/// Alpha value alpha-tested materials compare against
+const CUTOUT_THRESHOLD: f32 = 0.5;
+
+/// File name suffixes that mark normal maps
+const NORMAL_MAP_SUFFIXES: [&str; 5] = ["_n", "_nrm", "_normal", "_norm", "_normalmap"];
+
+/// Result of cooking one image
+#[derive(Debug, Clone)]
+pub struct CookOutput {
+    /// Cooked texture
+    pub texture: CookedTexture,
+    /// Adjustments made along the way, such as resizing
+    pub warnings: Vec<String>,
+}
+
+/// Turns decoded images into GPU-ready textures
+///
+/// Filtering happens in linear space on floating point texels. Normal maps are
+/// renormalized after every downsample, premultiplied alpha is applied before
+/// mips are built and cutout textures keep their alpha-tested coverage across
+/// the mip chain.
+#[derive(Debug, Clone, Default)]
+pub struct TextureCooker {
+    /// Import settings
+    pub settings: TextureImportSettings,
+}
+
+impl TextureCooker {
+    /// Create a cooker with the given settings
+    #[must_use]
+    pub fn new(settings: TextureImportSettings) -> Self {
+        Self { settings }
+    }
+
+    /// GPU format used for a compression setting
+    #[must_use]
+    pub fn format_for(compression: TextureCompression, has_alpha: bool) -> GpuTextureFormat {
+        match compression {
+            TextureCompression::None => GpuTextureFormat::Rgba8,
+            TextureCompression::BC1 => GpuTextureFormat::Bc1,
+            TextureCompression::BC3 => GpuTextureFormat::Bc3,
+            TextureCompression::BC4 => GpuTextureFormat::Bc4,
+            TextureCompression::BC5 => GpuTextureFormat::Bc5,
+            TextureCompression::BC7 => GpuTextureFormat::Bc7,
+            TextureCompression::ETC2 if has_alpha => GpuTextureFormat::Etc2Rgba8,
+            TextureCompression::ETC2 => GpuTextureFormat::Etc2Rgb8,
+            TextureCompression::ASTC4x4 => GpuTextureFormat::Astc4x4,
+            TextureCompression::ASTC6x6 => GpuTextureFormat::Astc6x6,
+            TextureCompression::ASTC8x8 => GpuTextureFormat::Astc8x8,
+        }
+    }
+
+    /// Whether an image looks like a tangent-space normal map, judged by its
+    /// file name or by its texels decoding to unit vectors facing +Z
+    #[must_use]
+    pub fn is_normal_map(name: &str, image: &DynamicImage) -> bool {
+        let name = name.to_lowercase();
+        if NORMAL_MAP_SUFFIXES.iter().any(|s| name.ends_with(s)) {
+            return true;
+        }
+
+        let pixels = image.to_rgb8();
+        let step = (pixels.len() / 3 / 1024).max(1);
+        let (mut count, mut unit, mut facing) = (0usize, 0usize, 0usize);
+        for pixel in pixels.pixels().step_by(step) {
+            let n = pixel.0.map(|v| f32::from(v) / 127.5 - 1.0);
+            let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+            count += 1;
+            unit += usize::from((length - 1.0).abs() < 0.1);
+            facing += usize::from(n[2] > 0.3);
+        }
+        count > 0 && unit * 10 >= count * 9 && facing * 10 >= count * 9
+    }
+
+    /// Cook a decoded image
+    ///
+    /// `name` is the source file stem, used for normal map detection.
+    #[must_use]
+    pub fn cook(&self, name: &str, image: &DynamicImage) -> CookOutput {
+        let settings = &self.settings;
+        let mut warnings = Vec::new();
+
+        let normal_map =
+            settings.normal_map || (settings.detect_normal_map && Self::is_normal_map(name, image));
+        if normal_map && !settings.normal_map {
+            warnings.push("Detected normal map".to_string());
+        }
+
+        let mut pixels = image.to_rgba32f();
+        let has_alpha = image.color().has_alpha() && pixels.pixels().any(|p| p.0[3] < 1.0);
+        let alpha_mode = match settings.alpha_mode {
+            AlphaMode::Auto if has_alpha => AlphaMode::Transparent,
+            AlphaMode::Auto => AlphaMode::Opaque,
+            mode => mode,
+        };
+        let format = Self::format_for(settings.compression, alpha_mode != AlphaMode::Opaque);
+        // Float sources are already linear
+        let srgb = settings.srgb && !normal_map && format.supports_srgb();
+        let linearize = srgb
+            && !matches!(
+                image,
+                DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+            );
+
+        for pixel in pixels.pixels_mut() {
+            let [r, g, b, a] = &mut pixel.0;
+            if linearize {
+                for c in [&mut *r, &mut *g, &mut *b] {
+                    *c = srgb_to_linear(*c);
+                }
+            }
+            match alpha_mode {
+                AlphaMode::Opaque => *a = 1.0,
+                AlphaMode::Premultiplied => {
+                    *r *= *a;
+                    *g *= *a;
+                    *b *= *a;
+                },
+                _ => {},
+            }
+        }
+
+        let (width, height) = self.target_size(image.width(), image.height(), format);
+        if (width, height) != (image.width(), image.height()) {
+            warnings.push(format!(
+                "Resized from {}x{} to {}x{}",
+                image.width(),
+                image.height(),
+                width,
+                height
+            ));
+            pixels = image::imageops::resize(&pixels, width, height, FilterType::Triangle);
+        }
+
+        let mut texels = pixels.into_raw();
+        if normal_map {
+            renormalize(&mut texels);
+        }
+        let coverage = (alpha_mode == AlphaMode::Cutout).then(|| alpha_coverage(&texels, 1.0));
+
+        let levels = if settings.generate_mipmaps {
+            32 - width.max(height).leading_zeros()
+        } else {
+            1
+        };
+        let mut mips = Vec::with_capacity(levels as usize);
+        let (mut level_width, mut level_height) = (width, height);
+        for level in 0..levels {
+            if level > 0 {
+                texels = downsample(&texels, level_width, level_height, 4);
+                level_width = (level_width / 2).max(1);
+                level_height = (level_height / 2).max(1);
+                if normal_map {
+                    renormalize(&mut texels);
+                }
+                if let Some(coverage) = coverage {
+                    preserve_coverage(&mut texels, coverage);
+                }
+            }
+            let rgba = encode_rgba8(&texels, srgb);
+            mips.push(compress(format, level_width, level_height, &rgba));
+        }
+
+        if levels > 1 {
+            warnings.push(format!("Generated {} mipmap levels", levels));
+        }
+        if format.is_compressed() {
+            warnings.push(format!("Compressed with {:?}", format));
+        }
+
+        CookOutput {
+            texture: CookedTexture {
+                width,
+                height,
+                format,
+                srgb,
+                normal_map,
+                mips,
+            },
+            warnings,
+        }
+    }
+
+    /// Size after the power of two, max resolution and block alignment rules
+    fn target_size(&self, width: u32, height: u32, format: GpuTextureFormat) -> (u32, u32) {
+        let (mut width, mut height) = (width.max(1), height.max(1));
+        if self.settings.power_of_two {
+            width = width.next_power_of_two();
+            height = height.next_power_of_two();
+        }
+
+        let max = self.settings.max_resolution;
+        if max > 0 && width.max(height) > max {
+            let scale = max as f32 / width.max(height) as f32;
+            width = ((width as f32 * scale).round() as u32).max(1);
+            height = ((height as f32 * scale).round() as u32).max(1);
+        }
+
+        // GPUs need the top level to be whole blocks. Round up, unless that
+        // would exceed the max resolution, then round down to a whole block.
+        let (block_width, block_height) = format.block_size();
+        let align = |size: u32, block: u32| {
+            let up = size.div_ceil(block) * block;
+            if max > 0 && up > max {
+                (max / block).max(1) * block
+            } else {
+                up
+            }
+        };
+        (align(width, block_width), align(height, block_height))
+    }
+}
+
+/// Rescale encoded normals back to unit length
+fn renormalize(texels: &mut [f32]) {
+    for texel in texels.chunks_exact_mut(4) {
+        let n = [0, 1, 2].map(|c| texel[c] * 2.0 - 1.0);
+        let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+        let n = if length > 1e-6 {
+            n.map(|v| v / length)
+        } else {
+            [0.0, 0.0, 1.0]
+        };
+        for c in 0..3 {
+            texel[c] = n[c] * 0.5 + 0.5;
+        }
+    }
+}
+
+/// Fraction of texels that pass the cutout test after scaling alpha
+fn alpha_coverage(texels: &[f32], scale: f32) -> f32 {
+    let count = texels.len() / 4;
+    let passing = texels
+        .chunks_exact(4)
+        .filter(|t| t[3] * scale >= CUTOUT_THRESHOLD)
+        .count();
+    passing as f32 / count.max(1) as f32
+}
+
+/// Scale alpha so the share of texels passing the cutout test matches the top
+/// level, keeping foliage from thinning out in the distance
+fn preserve_coverage(texels: &mut [f32], coverage: f32) {
+    let (mut lo, mut hi) = (0.0f32, 4.0f32);
+    for _ in 0..16 {
+        let mid = (lo + hi) * 0.5;
+        if alpha_coverage(texels, mid) < coverage {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    for texel in texels.chunks_exact_mut(4) {
+        texel[3] = (texel[3] * hi).min(1.0);
+    }
+}
+
+/// Quantize linear float texels to RGBA8
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn encode_rgba8(texels: &[f32], srgb: bool) -> Vec<u8> {
+    texels
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            let v = if srgb && i % 4 < 3 {
+                linear_to_srgb(v)
+            } else {
+                v
+            };
+            (v.clamp(0.0, 1.0) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+/// Decode a source file into an image
+pub(crate) fn open_image(path: &std::path::Path) -> lunaris_core::Result<DynamicImage> {
+    let bytes = std::fs::read(path)?;
+    let format = image::guess_format(&bytes)
+        .or_else(|_| image::ImageFormat::from_path(path))
+        .map_err(|e| {
+            lunaris_core::Error::Asset(format!("Unknown image format {}: {e}", path.display()))
+        })?;
+    image::load_from_memory_with_format(&bytes, format).map_err(|e| {
+        lunaris_core::Error::Asset(format!("Failed to decode {}: {e}", path.display()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgba};
+
+    fn cooker(compression: TextureCompression) -> TextureCooker {
+        TextureCooker::new(TextureImportSettings {
+            compression,
+            ..TextureImportSettings::default()
+        })
+    }
+
+    fn checker(width: u32, height: u32, alpha: bool) -> DynamicImage {
+        DynamicImage::ImageRgba8(ImageBuffer::from_fn(width, height, |x, y| {
+            let on = (x / 2 + y / 2) % 2 == 0;
+            let a = if alpha && !on { 0 } else { 255 };
+            Rgba([if on { 230 } else { 20 }, 120, 40, a])
+        }))
+    }
+
+    #[test]
+    fn cooks_full_mip_chains_in_every_format() {
+        let image = checker(16, 8, true);
+        for compression in [
+            TextureCompression::None,
+            TextureCompression::BC1,
+            TextureCompression::BC3,
+            TextureCompression::BC4,
+            TextureCompression::BC5,
+            TextureCompression::BC7,
+            TextureCompression::ETC2,
+            TextureCompression::ASTC4x4,
+            TextureCompression::ASTC8x8,
+        ] {
+            let texture = cooker(compression).cook("crate", &image).texture;
+            assert_eq!(texture.mip_levels(), 5, "{:?}", compression);
+            for (level, data) in texture.mips.iter().enumerate() {
+                let (w, h) = texture.mip_size(level as u32);
+                assert_eq!(data.len(), texture.format.level_bytes(w, h));
+            }
+            assert_eq!(
+                CookedTexture::from_bytes(&texture.to_bytes()).unwrap(),
+                texture
+            );
+        }
+        let etc = cooker(TextureCompression::ETC2)
+            .cook("crate", &image)
+            .texture;
+        assert_eq!(etc.format, GpuTextureFormat::Etc2Rgba8);
+    }
+
+    #[test]
+    fn resizes_to_whole_blocks_and_max_resolution() {
+        let mut cooker = cooker(TextureCompression::ASTC6x6);
+        cooker.settings.max_resolution = 32;
+        let output = cooker.cook("wall", &checker(100, 50, false));
+        assert_eq!((output.texture.width, output.texture.height), (30, 18));
+        assert!(output.warnings.iter().any(|w| w.starts_with("Resized")));
+
+        // A max below the block size still leaves one whole block
+        cooker.settings.max_resolution = 4;
+        let output = cooker.cook("wall", &checker(100, 50, false));
+        assert_eq!((output.texture.width, output.texture.height), (6, 6));
+    }
+
+    #[test]
+    fn normal_maps_stay_linear_and_unit_length() {
+        let bumpy = DynamicImage::ImageRgb8(ImageBuffer::from_fn(8, 8, |x, _| {
+            if x % 2 == 0 {
+                image::Rgb([218, 128, 218])
+            } else {
+                image::Rgb([38, 128, 218])
+            }
+        }));
+        assert!(TextureCooker::is_normal_map("bumpy", &bumpy));
+        assert!(!TextureCooker::is_normal_map(
+            "albedo",
+            &checker(8, 8, false)
+        ));
+
+        let texture = cooker(TextureCompression::None)
+            .cook("bumpy", &bumpy)
+            .texture;
+        assert!(texture.normal_map && !texture.srgb);
+        // Opposing tilts average to a flat normal instead of a short one
+        let top = &texture.mips[1][..4];
+        assert_eq!(&top[..3], &[128, 128, 255]);
+    }
+
+    #[test]
+    fn premultiplies_and_preserves_cutout_coverage() {
+        let image = checker(8, 8, true);
+        let mut premultiplied = cooker(TextureCompression::None);
+        premultiplied.settings.alpha_mode = AlphaMode::Premultiplied;
+        let texture = premultiplied.cook("leaf", &image).texture;
+        assert!(texture.mips[0]
+            .chunks(4)
+            .filter(|t| t[3] == 0)
+            .all(|t| t[..3] == [0, 0, 0]));
+
+        // A quarter of the texels are opaque, which a plain box filter fades out
+        let sparse = DynamicImage::ImageRgba8(ImageBuffer::from_fn(8, 8, |x, y| {
+            Rgba([90, 160, 60, if x % 4 < 2 && y % 4 < 2 { 255 } else { 0 }])
+        }));
+        let mut cutout = cooker(TextureCompression::None);
+        cutout.settings.alpha_mode = AlphaMode::Cutout;
+        let texture = cutout.cook("leaf", &sparse).texture;
+        let last = texture.mips.last().unwrap();
+        assert!(last[3] >= 128);
+    }
+}
@@ -5,8 +5,10 @@
 
 pub mod metasounds;
 pub mod procedural;
+pub mod source;
 pub mod spatial;
 
 pub use metasounds::*;
 pub use procedural::*;
+pub use source::*;
 pub use spatial::*;
@@ -39,6 +39,13 @@ impl AudioClip {
         }
     }
 
+    /// Create a clip from decoded audio. Needs the `assets` feature.
+    #[cfg(feature = "assets")]
+    #[must_use]
+    pub fn from_asset(name: impl Into<String>, asset: lunaris_assets::loader::AudioAsset) -> Self {
+        Self::new(name, asset.sample_rate, asset.channels, asset.samples)
+    }
+
     /// Generate a sine wave for testing
     #[must_use]
     pub fn generate_sine(frequency: f32, duration: Duration, sample_rate: u32) -> Self {
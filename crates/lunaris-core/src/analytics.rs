@@ -1,8 +1,25 @@
 //! Analytics System
 //!
 //! Gameplay telemetry, heatmaps, and A/B testing.
+//!
+//! `Analytics::flush` hands batched events to every registered
+//! `AnalyticsSink`. `JsonLinesSink` appends to a local file, `DiskQueue`
+//! stores batches in rotating segment files that survive crashes until they
+//! are drained into another sink, and `HttpBatchSink` posts batches to a
+//! collection endpoint. Recorded events can be aggregated offline into an
+//! `ExperimentReport`.
+
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use std::collections::HashMap;
+/// Event tracked by `Analytics::track_exposure`
+pub const EXPOSURE_EVENT: &str = "experiment_exposure";
 
 /// Analytics system
 pub struct Analytics {
@@ -12,9 +29,18 @@ pub struct Analytics {
     pub heatmaps: HashMap<String, Heatmap>,
     pub experiments: Vec<ABExperiment>,
     pub config: AnalyticsConfig,
+    sinks: Vec<SinkSlot>,
+    started: Instant,
+}
+
+/// A sink with the events it has not accepted yet
+struct SinkSlot {
+    sink: Box<dyn AnalyticsSink>,
+    retry: Vec<EventRecord>,
 }
 
 /// Analytics event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct AnalyticsEvent {
     pub name: String,
     pub timestamp: u64,
@@ -23,6 +49,8 @@ pub struct AnalyticsEvent {
 }
 
 /// Event value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
 pub enum EventValue { String(String), Int(i64), Float(f64), Bool(bool) }
 
 /// Analytics config
@@ -32,17 +60,46 @@ pub struct AnalyticsConfig {
     pub batch_size: usize,
     pub flush_interval: f32,
     pub sample_rate: f32,
+    /// Most events kept per sink while it keeps failing; the oldest are dropped
+    pub max_retained: usize,
 }
 
 impl Default for AnalyticsConfig {
     fn default() -> Self {
-        Self { enabled: true, endpoint: "".into(), batch_size: 50, flush_interval: 30.0, sample_rate: 1.0 }
+        Self {
+            enabled: true,
+            endpoint: "".into(),
+            batch_size: 50,
+            flush_interval: 30.0,
+            sample_rate: 1.0,
+            max_retained: 10_000,
+        }
     }
 }
 
 impl Analytics {
     pub fn new(session_id: &str) -> Self {
-        Self { session_id: session_id.into(), user_id: None, events: Vec::new(), heatmaps: HashMap::new(), experiments: Vec::new(), config: AnalyticsConfig::default() }
+        Self {
+            session_id: session_id.into(),
+            user_id: None,
+            events: Vec::new(),
+            heatmaps: HashMap::new(),
+            experiments: Vec::new(),
+            config: AnalyticsConfig::default(),
+            sinks: Vec::new(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Send flushed events to a sink
+    pub fn add_sink(&mut self, sink: impl AnalyticsSink + 'static) {
+        self.sinks.push(SinkSlot { sink: Box::new(sink), retry: Vec::new() });
+    }
+
+    /// Events waiting to be resent to sinks that failed, summed over sinks
+    #[must_use]
+    pub fn retained_events(&self) -> usize {
+        self.sinks.iter().map(|slot| slot.retry.len()).sum()
     }
 
     pub fn track(&mut self, name: &str) {
@@ -51,13 +108,58 @@ impl Analytics {
 
     pub fn track_with_props(&mut self, name: &str, properties: HashMap<String, EventValue>) {
         if !self.config.enabled { return; }
-        self.events.push(AnalyticsEvent { name: name.into(), timestamp: 0, properties, session_time: 0.0 });
-        if self.events.len() >= self.config.batch_size { self.flush(); }
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let session_time = self.started.elapsed().as_secs_f32();
+        self.events.push(AnalyticsEvent { name: name.into(), timestamp, properties, session_time });
+        if self.events.len() >= self.config.batch_size {
+            if let Err(e) = self.flush() { tracing::warn!("Analytics flush failed: {e}"); }
+        }
     }
 
-    pub fn flush(&mut self) {
-        // Would send to analytics server
-        self.events.clear();
+    /// Send pending events to every sink
+    ///
+    /// All sinks are tried even if one fails. A sink that fails keeps the
+    /// events it did not accept and gets them again, ahead of newer events,
+    /// on the next flush; accepted events are never sent twice.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first sink error
+    pub fn flush(&mut self) -> Result<()> {
+        if self.events.is_empty() && self.retained_events() == 0 { return Ok(()); }
+        let records: Vec<EventRecord> = self.events.drain(..)
+            .map(|event| EventRecord {
+                session_id: self.session_id.clone(),
+                user_id: self.user_id.clone(),
+                event,
+            })
+            .collect();
+        let mut result = Ok(());
+        for slot in &mut self.sinks {
+            slot.retry.extend_from_slice(&records);
+            let excess = slot.retry.len().saturating_sub(self.config.max_retained);
+            if excess > 0 {
+                tracing::warn!("Analytics sink backlog full, dropping {excess} events");
+                slot.retry.drain(..excess);
+            }
+            let total = slot.retry.len();
+            match slot.sink.send(&slot.retry) {
+                Ok(accepted) if accepted >= total => slot.retry.clear(),
+                Ok(accepted) => {
+                    slot.retry.drain(..accepted);
+                    if result.is_ok() {
+                        result = Err(Error::Internal(format!(
+                            "Analytics sink accepted {accepted} of {total} events"
+                        )));
+                    }
+                },
+                Err(e) => {
+                    if result.is_ok() { result = Err(e); }
+                },
+            }
+        }
+        result
     }
 
     pub fn set_user(&mut self, user_id: &str) { self.user_id = Some(user_id.into()); }
@@ -121,6 +223,70 @@ impl Heatmap {
         let max = self.data.iter().cloned().fold(0.0f32, f32::max);
         if max > 0.0 { for v in &mut self.data { *v /= max; } }
     }
+
+    /// Cell values as CSV, one row per Z cell from `min_z` up
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in self.data.chunks(self.resolution.0.max(1) as usize) {
+            let cells: Vec<String> = row.iter().map(f32::to_string).collect();
+            csv.push_str(&cells.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// Write the cell values as a CSV file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_csv())?;
+        Ok(())
+    }
+
+    /// Render as an image, one pixel per cell with `max_z` at the top
+    ///
+    /// Empty cells are transparent. The rest go from translucent blue to
+    /// opaque red relative to the busiest cell, so the image can be laid over
+    /// a top-down capture of the level. Needs the `heatmap-image` feature.
+    #[cfg(feature = "heatmap-image")]
+    #[must_use]
+    pub fn to_image(&self) -> image::RgbaImage {
+        let (width, height) = self.resolution;
+        let max = self.data.iter().cloned().fold(0.0f32, f32::max);
+        image::RgbaImage::from_fn(width, height, |x, y| {
+            let value = self.data[((height - 1 - y) * width + x) as usize];
+            heat_color(if max > 0.0 { value / max } else { 0.0 })
+        })
+    }
+
+    /// Write the heatmap as an image, in the format given by the extension
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the image cannot be encoded or written
+    #[cfg(feature = "heatmap-image")]
+    pub fn save_image(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.to_image().save(path).map_err(|e| Error::Asset(e.to_string()))
+    }
+}
+
+/// Color ramp from blue through green and yellow to red
+#[cfg(feature = "heatmap-image")]
+fn heat_color(t: f32) -> image::Rgba<u8> {
+    if t <= 0.0 {
+        return image::Rgba([0, 0, 0, 0]);
+    }
+    const STOPS: [[f32; 3]; 4] =
+        [[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0]];
+    let scaled = t.min(1.0) * (STOPS.len() - 1) as f32;
+    let index = (scaled as usize).min(STOPS.len() - 2);
+    let f = scaled - index as f32;
+    let (a, b) = (STOPS[index], STOPS[index + 1]);
+    let channel = |i: usize| ((a[i] + (b[i] - a[i]) * f) * 255.0).round() as u8;
+    image::Rgba([channel(0), channel(1), channel(2), (96.0 + 159.0 * t.min(1.0)) as u8])
 }
 
 impl Analytics {
@@ -185,4 +351,821 @@ impl Analytics {
     pub fn get_variant(&self, experiment: &str) -> Option<&str> {
         self.experiments.iter().find(|e| e.name == experiment)?.assigned_variant.as_deref()
     }
+
+    /// Record that the player saw their variant of an experiment
+    ///
+    /// `ExperimentReport` uses these events to group outcomes by variant.
+    pub fn track_exposure(&mut self, experiment: &str) {
+        let Some(variant) = self.get_variant(experiment).map(str::to_string) else { return };
+        self.track_with_props(EXPOSURE_EVENT, [
+            ("experiment".into(), EventValue::String(experiment.into())),
+            ("variant".into(), EventValue::String(variant)),
+        ].into());
+    }
+}
+
+/// Event with the session it was recorded in, as stored and sent by sinks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// Session the event was tracked in
+    pub session_id: String,
+    /// Signed-in player, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// The event itself, flattened into the record
+    #[serde(flatten)]
+    pub event: AnalyticsEvent,
+}
+
+impl EventRecord {
+    /// Player identity used to group events: the user id, or the session id
+    #[must_use]
+    pub fn unit(&self) -> &str {
+        self.user_id.as_deref().unwrap_or(&self.session_id)
+    }
+
+    /// Get a string property
+    #[must_use]
+    pub fn string(&self, key: &str) -> Option<&str> {
+        match self.event.properties.get(key)? {
+            EventValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get a numeric property
+    #[must_use]
+    pub fn number(&self, key: &str) -> Option<f64> {
+        match self.event.properties.get(key)? {
+            EventValue::Int(i) => Some(*i as f64),
+            EventValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+}
+
+/// Destination for flushed analytics events
+pub trait AnalyticsSink: Send {
+    /// Deliver a batch of events, returning how many were accepted
+    ///
+    /// A sink that delivers in several parts stops at the first failure and
+    /// returns the number of leading events that went through, so callers
+    /// resend only the rest.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if none of the batch could be stored or sent
+    fn send(&mut self, events: &[EventRecord]) -> Result<usize>;
+}
+
+fn write_json_lines(writer: &mut impl Write, events: &[EventRecord]) -> Result<()> {
+    let mut lines = Vec::new();
+    for event in events {
+        serde_json::to_writer(&mut lines, event).map_err(|e| Error::Internal(e.to_string()))?;
+        lines.push(b'\n');
+    }
+    // One write per batch, so a crash loses at most a partial last line
+    writer.write_all(&lines)?;
+    Ok(())
+}
+
+/// Read events from a JSON Lines file, skipping lines that do not parse
+///
+/// # Errors
+///
+/// Returns error if the file cannot be read
+pub fn read_json_lines(path: impl AsRef<Path>) -> Result<Vec<EventRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = Vec::new();
+    for line in reader.lines() {
+        if let Ok(event) = serde_json::from_str(&line?) {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// Appends events to a local JSON Lines file
+pub struct JsonLinesSink {
+    path: PathBuf,
+    file: File,
+}
+
+impl JsonLinesSink {
+    /// Open a file for appending, creating it if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be opened
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file })
+    }
+
+    /// Path of the file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AnalyticsSink for JsonLinesSink {
+    fn send(&mut self, events: &[EventRecord]) -> Result<usize> {
+        write_json_lines(&mut self.file, events)?;
+        Ok(events.len())
+    }
+}
+
+/// On-disk queue of events in rotating JSON Lines segment files
+///
+/// Batches are appended to the newest segment, which rotates once it grows
+/// past `max_segment_bytes`. When there are more than `max_segments`
+/// segments the oldest is deleted. Queued events survive crashes and are
+/// delivered with `drain`, which deletes each segment once sent.
+pub struct DiskQueue {
+    dir: PathBuf,
+    max_segment_bytes: u64,
+    max_segments: usize,
+    current: Option<(u64, File, u64)>,
+}
+
+impl DiskQueue {
+    /// Open a queue directory, creating it if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be created or listed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir, max_segment_bytes: 1024 * 1024, max_segments: 64, current: None })
+    }
+
+    /// Rotate segments larger than this many bytes
+    #[must_use]
+    pub fn with_max_segment_bytes(mut self, bytes: u64) -> Self {
+        self.max_segment_bytes = bytes;
+        self
+    }
+
+    /// Keep at most this many segments, dropping the oldest
+    #[must_use]
+    pub fn with_max_segments(mut self, segments: usize) -> Self {
+        self.max_segments = segments.max(1);
+        self
+    }
+
+    fn segment_path(&self, sequence: u64) -> PathBuf {
+        self.dir.join(format!("{sequence:010}.jsonl"))
+    }
+
+    /// Sequence numbers of segments on disk, oldest first
+    fn segments(&self) -> Result<Vec<u64>> {
+        let mut sequences: Vec<u64> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "jsonl" {
+                    return None;
+                }
+                path.file_stem()?.to_str()?.parse().ok()
+            })
+            .collect();
+        sequences.sort_unstable();
+        Ok(sequences)
+    }
+
+    /// Number of segment files on disk
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be listed
+    pub fn segment_count(&self) -> Result<usize> {
+        Ok(self.segments()?.len())
+    }
+
+    /// Stop appending to the current segment
+    fn rotate(&mut self) {
+        self.current = None;
+    }
+
+    /// Deliver queued events to a sink, oldest segment first
+    ///
+    /// Each segment is deleted once the sink accepts it; a segment the sink
+    /// only partly accepts is rewritten with the rest. Returns the number of
+    /// events delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns the sink error; undelivered events stay queued
+    pub fn drain(&mut self, sink: &mut dyn AnalyticsSink) -> Result<usize> {
+        self.rotate();
+        let mut delivered = 0;
+        for sequence in self.segments()? {
+            let path = self.segment_path(sequence);
+            let events = read_json_lines(&path)?;
+            let accepted = if events.is_empty() { 0 } else { sink.send(&events)? };
+            if accepted < events.len() {
+                let mut rest = Vec::new();
+                write_json_lines(&mut rest, &events[accepted..])?;
+                let temp = path.with_extension("tmp");
+                std::fs::write(&temp, rest)?;
+                std::fs::rename(&temp, &path)?;
+                return Err(Error::Internal(format!(
+                    "Analytics sink accepted {accepted} of {} queued events",
+                    events.len()
+                )));
+            }
+            std::fs::remove_file(&path)?;
+            delivered += events.len();
+        }
+        Ok(delivered)
+    }
+}
+
+impl AnalyticsSink for DiskQueue {
+    fn send(&mut self, events: &[EventRecord]) -> Result<usize> {
+        if self.current.as_ref().is_some_and(|(_, _, size)| *size >= self.max_segment_bytes) {
+            self.rotate();
+        }
+        if self.current.is_none() {
+            let segments = self.segments()?;
+            let sequence = segments.last().map_or(0, |last| last + 1);
+            let path = self.segment_path(sequence);
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            self.current = Some((sequence, file, 0));
+            let excess = (segments.len() + 1).saturating_sub(self.max_segments);
+            for old in &segments[..excess] {
+                std::fs::remove_file(self.segment_path(*old))?;
+            }
+        }
+        let Some((_, file, size)) = self.current.as_mut() else { return Ok(0) };
+        let mut lines = Vec::new();
+        write_json_lines(&mut lines, events)?;
+        file.write_all(&lines)?;
+        *size += lines.len() as u64;
+        Ok(events.len())
+    }
+}
+
+/// Posts batches as a JSON array to an `http://` endpoint
+///
+/// Any 2xx response counts as delivered. TLS is not supported; put a local
+/// forwarding proxy in front of HTTPS collectors.
+pub struct HttpBatchSink {
+    host: String,
+    port: u16,
+    path: String,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    max_batch: usize,
+}
+
+impl HttpBatchSink {
+    /// Create a sink for an endpoint URL such as `http://localhost:8080/events`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL is not a valid `http://` URL
+    pub fn new(url: &str) -> Result<Self> {
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| Error::Config(format!("Unsupported analytics endpoint: {url}")))?;
+        let (authority, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| Error::Config(format!("Invalid port in {url}")))?;
+                (host, port)
+            },
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(Error::Config(format!("Missing host in {url}")));
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+            headers: Vec::new(),
+            timeout: Duration::from_secs(10),
+            max_batch: 500,
+        })
+    }
+
+    /// Send an extra header, e.g. an API key
+    ///
+    /// # Errors
+    ///
+    /// Returns a config error if the name is empty or contains a colon, or
+    /// either part contains a line break
+    pub fn with_header(mut self, name: &str, value: &str) -> Result<Self> {
+        let breaks = |s: &str| s.contains(['\r', '\n']);
+        if name.is_empty() || name.contains(':') || breaks(name) || breaks(value) {
+            return Err(Error::Config(format!("Invalid analytics header: {name:?}")));
+        }
+        self.headers.push((name.to_string(), value.to_string()));
+        Ok(self)
+    }
+
+    /// Connect and read timeout
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Split larger batches into requests of at most this many events
+    ///
+    /// A failed request ends the batch; `send` reports the events posted
+    /// before it as accepted.
+    #[must_use]
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    fn post(&self, body: &[u8]) -> Result<()> {
+        let address = (self.host.as_str(), self.port)
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| Error::Config(format!("Cannot resolve {}", self.host)))?;
+        let mut stream = TcpStream::connect_timeout(&address, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let mut request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n",
+            self.path,
+            self.host,
+            self.port,
+            body.len()
+        );
+        for (name, value) in &self.headers {
+            request.push_str(&format!("{name}: {value}\r\n"));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+        stream.write_all(body)?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        let status: u16 = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| Error::Internal("Malformed HTTP response".into()))?;
+        if (200..300).contains(&status) {
+            Ok(())
+        } else {
+            Err(Error::Internal(format!("Analytics endpoint returned HTTP {status}")))
+        }
+    }
+}
+
+impl AnalyticsSink for HttpBatchSink {
+    fn send(&mut self, events: &[EventRecord]) -> Result<usize> {
+        let mut sent = 0;
+        for chunk in events.chunks(self.max_batch) {
+            let posted = serde_json::to_vec(chunk)
+                .map_err(|e| Error::Internal(e.to_string()))
+                .and_then(|body| self.post(&body));
+            match posted {
+                Ok(()) => sent += chunk.len(),
+                Err(e) if sent == 0 => return Err(e),
+                Err(e) => {
+                    tracing::warn!("Analytics endpoint failed after {sent} events: {e}");
+                    break;
+                },
+            }
+        }
+        Ok(sent)
+    }
+}
+
+/// Aggregated outcome of one experiment variant
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantOutcome {
+    /// Variant name
+    pub variant: String,
+    /// Players exposed to the variant
+    pub exposed: u64,
+    /// Exposed players with at least one outcome event
+    pub converted: u64,
+    /// Share of exposed players who converted
+    pub conversion_rate: f64,
+    /// Mean of the metric property over outcome events
+    pub metric_mean: Option<f64>,
+    /// Relative change in conversion rate against the control
+    pub lift: Option<f64>,
+    /// Two-proportion z-score against the control
+    pub z_score: Option<f64>,
+}
+
+/// Offline A/B experiment results built from recorded events
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExperimentReport {
+    /// Experiment name
+    pub experiment: String,
+    /// Event counted as a conversion
+    pub outcome_event: String,
+    /// Variants sorted by name, with the control first
+    pub variants: Vec<VariantOutcome>,
+}
+
+impl ExperimentReport {
+    /// Aggregate outcomes by variant
+    ///
+    /// Players (user id, or session id without one) are assigned to the
+    /// variant of their earliest `EXPOSURE_EVENT` for the experiment, and
+    /// convert when they record `outcome_event` at or after that exposure;
+    /// outcomes from before it are ignored. The control is the variant named
+    /// "control", or the first variant by name.
+    #[must_use]
+    pub fn aggregate(
+        records: &[EventRecord],
+        experiment: &str,
+        outcome_event: &str,
+        metric: Option<&str>,
+    ) -> Self {
+        // Variant and time of each player's earliest exposure
+        let mut assignment: HashMap<&str, (&str, u64)> = HashMap::new();
+        for record in records {
+            let exposure = record.event.name == EXPOSURE_EVENT
+                && record.string("experiment") == Some(experiment);
+            if exposure {
+                if let Some(variant) = record.string("variant") {
+                    let timestamp = record.event.timestamp;
+                    let first = assignment.entry(record.unit()).or_insert((variant, timestamp));
+                    if timestamp < first.1 {
+                        *first = (variant, timestamp);
+                    }
+                }
+            }
+        }
+
+        let mut exposed: BTreeMap<&str, u64> = BTreeMap::new();
+        for (variant, _) in assignment.values() {
+            *exposed.entry(variant).or_default() += 1;
+        }
+        let mut converted: HashMap<&str, HashSet<&str>> = HashMap::new();
+        let mut metrics: HashMap<&str, (f64, u64)> = HashMap::new();
+        for record in records.iter().filter(|r| r.event.name == outcome_event) {
+            let Some(&(variant, exposed_at)) = assignment.get(record.unit()) else { continue };
+            if record.event.timestamp < exposed_at {
+                continue;
+            }
+            converted.entry(variant).or_default().insert(record.unit());
+            if let Some(value) = metric.and_then(|m| record.number(m)) {
+                let entry = metrics.entry(variant).or_default();
+                entry.0 += value;
+                entry.1 += 1;
+            }
+        }
+
+        let mut variants: Vec<VariantOutcome> = exposed
+            .into_iter()
+            .map(|(variant, exposed)| {
+                let converted = converted.get(variant).map_or(0, |units| units.len() as u64);
+                VariantOutcome {
+                    variant: variant.to_string(),
+                    exposed,
+                    converted,
+                    conversion_rate: converted as f64 / exposed as f64,
+                    metric_mean: metrics.get(variant).map(|(sum, n)| sum / *n as f64),
+                    lift: None,
+                    z_score: None,
+                }
+            })
+            .collect();
+        if let Some(index) = variants.iter().position(|v| v.variant == "control") {
+            let control = variants.remove(index);
+            variants.insert(0, control);
+        }
+
+        if let Some((control, rest)) = variants.split_first_mut() {
+            for variant in rest {
+                if control.conversion_rate > 0.0 {
+                    variant.lift = Some(variant.conversion_rate / control.conversion_rate - 1.0);
+                }
+                let pooled = (control.converted + variant.converted) as f64
+                    / (control.exposed + variant.exposed) as f64;
+                let error = (pooled
+                    * (1.0 - pooled)
+                    * (1.0 / control.exposed as f64 + 1.0 / variant.exposed as f64))
+                    .sqrt();
+                if error > 0.0 {
+                    let difference = variant.conversion_rate - control.conversion_rate;
+                    variant.z_score = Some(difference / error);
+                }
+            }
+        }
+
+        Self {
+            experiment: experiment.to_string(),
+            outcome_event: outcome_event.to_string(),
+            variants,
+        }
+    }
+
+    /// Get a variant's outcome
+    #[must_use]
+    pub fn variant(&self, name: &str) -> Option<&VariantOutcome> {
+        self.variants.iter().find(|v| v.variant == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use tempfile::TempDir;
+
+    struct Collect(Vec<EventRecord>);
+
+    impl AnalyticsSink for Collect {
+        fn send(&mut self, events: &[EventRecord]) -> Result<usize> {
+            self.0.extend_from_slice(events);
+            Ok(events.len())
+        }
+    }
+
+    struct Offline;
+
+    impl AnalyticsSink for Offline {
+        fn send(&mut self, _events: &[EventRecord]) -> Result<usize> {
+            Err(Error::Internal("offline".into()))
+        }
+    }
+
+    /// Fails a number of times, then records what it receives
+    struct Flaky {
+        failures: usize,
+        received: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl AnalyticsSink for Flaky {
+        fn send(&mut self, events: &[EventRecord]) -> Result<usize> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(Error::Internal("endpoint down".into()));
+            }
+            let mut received = self.received.lock().unwrap();
+            received.extend(events.iter().map(|r| r.event.name.clone()));
+            Ok(events.len())
+        }
+    }
+
+    /// Accepts one event per send
+    struct Trickle(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+    impl AnalyticsSink for Trickle {
+        fn send(&mut self, events: &[EventRecord]) -> Result<usize> {
+            let mut received = self.0.lock().unwrap();
+            received.extend(events.iter().take(1).map(|r| r.event.name.clone()));
+            Ok(events.len().min(1))
+        }
+    }
+
+    #[test]
+    fn failed_batches_are_retried() {
+        let flaky = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let steady = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut analytics = Analytics::new("s");
+        analytics.add_sink(Flaky { failures: 1, received: flaky.clone() });
+        analytics.add_sink(Flaky { failures: 0, received: steady.clone() });
+
+        analytics.track("first");
+        assert!(analytics.flush().is_err());
+        assert_eq!(analytics.retained_events(), 1);
+        assert!(flaky.lock().unwrap().is_empty());
+
+        analytics.track("second");
+        analytics.flush().unwrap();
+        assert_eq!(analytics.retained_events(), 0);
+        assert_eq!(*flaky.lock().unwrap(), ["first", "second"]);
+        assert_eq!(*steady.lock().unwrap(), ["first", "second"]);
+    }
+
+    #[test]
+    fn partly_accepted_batches_resend_only_the_rest() {
+        let received = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut analytics = Analytics::new("s");
+        analytics.add_sink(Trickle(received.clone()));
+        analytics.track("first");
+        analytics.track("second");
+        assert!(analytics.flush().is_err());
+        assert_eq!(analytics.retained_events(), 1);
+        analytics.flush().unwrap();
+        assert_eq!(*received.lock().unwrap(), ["first", "second"]);
+
+        let dir = TempDir::new().unwrap();
+        let mut queue = DiskQueue::open(dir.path()).unwrap();
+        analytics.track("third");
+        analytics.track("fourth");
+        let records: Vec<EventRecord> = analytics.events.drain(..)
+            .map(|event| EventRecord { session_id: "s".into(), user_id: None, event })
+            .collect();
+        queue.send(&records).unwrap();
+        let mut trickle = Trickle(received.clone());
+        assert!(queue.drain(&mut trickle).is_err());
+        assert_eq!(queue.drain(&mut trickle).unwrap(), 1);
+        assert_eq!(*received.lock().unwrap(), ["first", "second", "third", "fourth"]);
+        assert_eq!(queue.segment_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn flush_writes_json_lines() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let mut analytics = Analytics::new("session-1");
+        analytics.add_sink(JsonLinesSink::open(&path).unwrap());
+        analytics.track_level_complete("forest", 42.5, 3);
+        analytics.set_user("player-9");
+        analytics.track("quit");
+        analytics.flush().unwrap();
+        assert!(analytics.events.is_empty());
+
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{broken").unwrap();
+        let records = read_json_lines(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].event.name, "level_complete");
+        assert_eq!(records[0].number("deaths"), Some(3.0));
+        assert_eq!(records[0].event.properties["deaths"], EventValue::Int(3));
+        assert_eq!(records[1].unit(), "player-9");
+    }
+
+    #[test]
+    fn disk_queue_survives_reopen_and_rotates() {
+        let dir = TempDir::new().unwrap();
+        let mut analytics = Analytics::new("s");
+        for i in 0..3 {
+            analytics.track_purchase(&format!("item-{i}"), 1.0, "EUR");
+        }
+        let records: Vec<EventRecord> = analytics.events.drain(..)
+            .map(|event| EventRecord { session_id: "s".into(), user_id: None, event })
+            .collect();
+
+        {
+            let mut queue = DiskQueue::open(dir.path()).unwrap().with_max_segment_bytes(1);
+            for record in &records {
+                queue.send(std::slice::from_ref(record)).unwrap();
+            }
+            assert_eq!(queue.segment_count().unwrap(), 3);
+        }
+
+        let mut queue = DiskQueue::open(dir.path()).unwrap().with_max_segments(2);
+        assert!(queue.drain(&mut Offline).is_err());
+        assert_eq!(queue.segment_count().unwrap(), 3);
+        queue.send(&records[..1]).unwrap();
+        assert_eq!(queue.segment_count().unwrap(), 2);
+
+        let mut collected = Collect(Vec::new());
+        assert_eq!(queue.drain(&mut collected).unwrap(), 2);
+        assert_eq!(collected.0[0].string("item"), Some("item-2"));
+        assert_eq!(queue.segment_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn http_sink_posts_batches() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let mut bodies = Vec::new();
+            let unavailable = "503 Service Unavailable";
+            for status in ["200 OK", "200 OK", unavailable, unavailable] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    line.clear();
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                bodies.push(String::from_utf8(body).unwrap());
+                write!(stream, "HTTP/1.1 {status}\r\nContent-Length: 0\r\n\r\n").unwrap();
+            }
+            bodies
+        });
+
+        let mut sink = HttpBatchSink::new(&format!("http://127.0.0.1:{port}/collect"))
+            .unwrap()
+            .with_header("X-Api-Key", "test")
+            .unwrap();
+        let mut analytics = Analytics::new("s");
+        analytics.track("boot");
+        analytics.track("menu");
+        let records: Vec<EventRecord> = analytics.events.drain(..)
+            .map(|event| EventRecord { session_id: "s".into(), user_id: None, event })
+            .collect();
+        assert_eq!(sink.send(&records[..1]).unwrap(), 1);
+        let mut sink = sink.with_max_batch(1);
+        // The second request fails, so only the first event counts as sent
+        assert_eq!(sink.send(&records).unwrap(), 1);
+        assert!(sink.send(&records[1..]).is_err());
+
+        let bodies = server.join().unwrap();
+        let sent: Vec<EventRecord> = serde_json::from_str(&bodies[0]).unwrap();
+        assert_eq!(sent, records[..1]);
+        let sent: Vec<EventRecord> = serde_json::from_str(&bodies[2]).unwrap();
+        assert_eq!(sent, records[1..]);
+        assert!(HttpBatchSink::new("https://example.com").is_err());
+        let local = || HttpBatchSink::new("http://localhost").unwrap();
+        assert!(local().with_header("X-Key", "a\r\nX-Injected: 1").is_err());
+        assert!(local().with_header("X\nKey", "a").is_err());
+    }
+
+    fn sample_heatmap() -> Heatmap {
+        let mut heatmap = Heatmap::new("deaths", (2, 2), (0.0, 0.0, 2.0, 2.0));
+        heatmap.record(0.5, 1.5);
+        heatmap.record(0.5, 1.5);
+        heatmap.record(1.5, 0.5);
+        heatmap
+    }
+
+    #[test]
+    fn heatmap_exports_csv() {
+        assert_eq!(sample_heatmap().to_csv(), "0,1\n2,0\n");
+    }
+
+    #[cfg(feature = "heatmap-image")]
+    #[test]
+    fn heatmap_exports_image() {
+        let heatmap = sample_heatmap();
+        let image = heatmap.to_image();
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 0).0[3], 0);
+        assert_eq!(image.get_pixel(1, 1).0[1], 255);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("deaths.png");
+        heatmap.save_image(&path).unwrap();
+        assert_eq!(image::open(&path).unwrap().width(), 2);
+    }
+
+    #[test]
+    fn aggregates_experiment_outcomes() {
+        let mut records = Vec::new();
+        let players = [
+            ("a", "control", false),
+            ("b", "control", true),
+            ("c", "red", true),
+            ("d", "red", true),
+        ];
+        for (player, variant, buys) in players {
+            let mut analytics = Analytics::new(player);
+            let experiment = analytics.create_experiment("shop_color");
+            experiment.assigned_variant = Some(variant.to_string());
+            analytics.track_exposure("shop_color");
+            analytics.track_exposure("shop_color");
+            if buys {
+                analytics.track_purchase("gem", if variant == "red" { 4.0 } else { 2.0 }, "EUR");
+            }
+            records.extend(analytics.events.drain(..).map(|event| EventRecord {
+                session_id: player.into(),
+                user_id: None,
+                event,
+            }));
+        }
+
+        let report = ExperimentReport::aggregate(&records, "shop_color", "purchase", Some("price"));
+        assert_eq!(report.variants[0].variant, "control");
+        let control = report.variant("control").unwrap();
+        assert_eq!((control.exposed, control.converted), (2, 1));
+        let red = report.variant("red").unwrap();
+        assert_eq!(red.conversion_rate, 1.0);
+        assert_eq!(red.metric_mean, Some(4.0));
+        assert_eq!(red.lift, Some(1.0));
+        assert!(red.z_score.unwrap() > 0.0);
+    }
+
+    #[test]
+    fn outcomes_before_exposure_do_not_convert() {
+        let mut analytics = Analytics::new("early");
+        let experiment = analytics.create_experiment("shop_color");
+        experiment.assigned_variant = Some("red".to_string());
+        analytics.track_purchase("gem", 4.0, "EUR");
+        analytics.track_exposure("shop_color");
+        let mut records: Vec<EventRecord> = analytics.events.drain(..)
+            .map(|event| EventRecord { session_id: "early".into(), user_id: None, event })
+            .collect();
+        records[0].event.timestamp = 1_000;
+        records[1].event.timestamp = 2_000;
+
+        let report = ExperimentReport::aggregate(&records, "shop_color", "purchase", Some("price"));
+        let red = report.variant("red").unwrap();
+        assert_eq!((red.exposed, red.converted), (1, 0));
+        assert_eq!(red.metric_mean, None);
+
+        records[0].event.timestamp = 2_000;
+        let report = ExperimentReport::aggregate(&records, "shop_color", "purchase", None);
+        assert_eq!(report.variant("red").unwrap().converted, 1);
+    }
 }
@@ -2,8 +2,8 @@
 //!
 //! One-click builds, asset bundling, and platform deployment.
 
-use std::collections::HashMap;
-use std::path::PathBuf;
+use crate::pack::{normalize_path, PackCompression, PackWriter, PACK_EXTENSION};
+use std::path::{Path, PathBuf};
 
 /// Build pipeline
 pub struct BuildPipeline {
@@ -19,6 +19,8 @@ pub struct BuildConfig {
     pub version: String,
     pub company: String,
     pub output_dir: PathBuf,
+    /// Root that bundle asset paths are relative to
+    pub asset_dir: PathBuf,
     pub compression: CompressionLevel,
     pub strip_debug: bool,
     pub optimize_assets: bool,
@@ -37,13 +39,15 @@ pub struct BuildTarget {
 }
 
 /// Platform
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
 pub enum Platform { Windows, Linux, MacOS, Android, iOS, WebGL, PS5, XboxSeriesX, Switch }
 
 /// Architecture
 pub enum Architecture { X64, ARM64, WASM }
 
 /// Platform settings
+#[derive(Default)]
 pub struct PlatformSettings {
     pub icon: Option<PathBuf>,
     pub splash: Option<PathBuf>,
@@ -57,7 +61,7 @@ pub struct SigningConfig {
     pub password: String,
 }
 
-/// Asset bundle
+/// Asset bundle, written as one pack archive per bundle
 pub struct AssetBundle {
     pub name: String,
     pub assets: Vec<PathBuf>,
@@ -85,6 +89,7 @@ impl BuildPipeline {
                 version: "1.0.0".into(),
                 company: "".into(),
                 output_dir: PathBuf::from("builds"),
+                asset_dir: PathBuf::from("assets"),
                 compression: CompressionLevel::Default,
                 strip_debug: true,
                 optimize_assets: true,
@@ -108,11 +113,59 @@ impl BuildPipeline {
         self.step("Compiling scripts...", 0.3)?;
         self.step("Processing assets...", 0.5)?;
         self.step("Bundling...", 0.7)?;
+        let output = self.config.output_dir.join(format!("{}_{:?}", self.config.project_name, platform));
+        if let Err(e) = self.write_bundles(&output.join("content")) {
+            self.state.status = BuildStatus::Failed;
+            self.state.errors.push(e.to_string());
+            return Err(e.to_string());
+        }
         self.step("Packaging...", 0.9)?;
         self.step("Done!", 1.0)?;
 
         self.state.status = BuildStatus::Succeeded;
-        Ok(self.config.output_dir.join(format!("{}_{:?}", self.config.project_name, platform)))
+        Ok(output)
+    }
+
+    /// Write each asset bundle to `<dir>/<bundle name>.lpak`
+    ///
+    /// Characters in the bundle name other than ASCII letters, digits, `-`
+    /// and `_` become `_`, so every pack lands directly in `dir`.
+    /// Bundle asset paths are relative to [`BuildConfig::asset_dir`];
+    /// directories are packed recursively.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an asset cannot be read or a pack cannot be written.
+    pub fn write_bundles(&self, dir: &Path) -> crate::Result<Vec<PathBuf>> {
+        let (compression, level) = match self.config.compression {
+            CompressionLevel::None => (PackCompression::None, 0),
+            CompressionLevel::Fast => (PackCompression::Lz4, 0),
+            CompressionLevel::Default => (PackCompression::Zstd, 3),
+            CompressionLevel::Best => (PackCompression::Zstd, 19),
+        };
+
+        let mut written = Vec::new();
+        for bundle in &self.asset_bundles {
+            std::fs::create_dir_all(dir)?;
+            let path = dir.join(format!("{}.{}", file_safe_name(&bundle.name), PACK_EXTENSION));
+            let compression = if bundle.compressed { compression } else { PackCompression::None };
+            let mut writer = PackWriter::create(&path)?
+                .with_compression(compression)
+                .with_zstd_level(level);
+
+            let mut files = Vec::new();
+            for asset in &bundle.assets {
+                collect_files(&self.config.asset_dir.join(asset), &mut files)?;
+            }
+            files.sort();
+            for file in files {
+                let name = file.strip_prefix(&self.config.asset_dir).unwrap_or(&file);
+                writer.add_file(&normalize_path(&name.to_string_lossy()), &file)?;
+            }
+            writer.finish()?;
+            written.push(path);
+        }
+        Ok(written)
     }
 
     fn step(&mut self, name: &str, progress: f32) -> Result<(), String> {
@@ -122,12 +175,32 @@ impl BuildPipeline {
     }
 
     pub fn build_all(&mut self) -> Vec<Result<PathBuf, String>> {
-        self.targets.iter().filter(|t| t.enabled).map(|t| self.build(t.platform)).collect()
+        let platforms: Vec<Platform> = self.targets.iter().filter(|t| t.enabled).map(|t| t.platform).collect();
+        platforms.into_iter().map(|p| self.build(p)).collect()
     }
 }
 
-impl Default for PlatformSettings {
-    fn default() -> Self { Self { icon: None, splash: None, app_id: None, signing: None } }
+/// Reduce a bundle name to characters that are safe in a file name
+fn file_safe_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Files under a path, or the path itself if it is a file
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> crate::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            collect_files(&entry?.path(), files)?;
+        }
+    } else {
+        // Name the missing asset, IO errors alone do not
+        if !path.is_file() {
+            return Err(crate::Error::Asset(format!("{} not found", path.display())));
+        }
+        files.push(path.to_path_buf());
+    }
+    Ok(())
 }
 
 /// Steam integration
@@ -143,3 +216,40 @@ pub struct EpicIntegration {
     pub sandbox_id: String,
     pub deployment_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::PackArchive;
+    use tempfile::TempDir;
+
+    #[test]
+    fn bundle_names_cannot_escape_the_output_dir() {
+        let dir = TempDir::new().unwrap();
+        let assets = dir.path().join("assets");
+        std::fs::create_dir_all(assets.join("levels")).unwrap();
+        std::fs::write(assets.join("levels/intro.scene"), b"intro").unwrap();
+
+        let mut pipeline = BuildPipeline::new("Game");
+        pipeline.config.asset_dir = assets;
+        for name in ["base", "../../escaped", "/tmp/absolute"] {
+            pipeline.asset_bundles.push(AssetBundle {
+                name: name.into(),
+                assets: vec![PathBuf::from("levels")],
+                load_on_start: true,
+                compressed: true,
+            });
+        }
+
+        let content = dir.path().join("content");
+        let written = pipeline.write_bundles(&content).unwrap();
+        let names: Vec<_> = written
+            .iter()
+            .map(|p| p.strip_prefix(&content).unwrap().to_string_lossy())
+            .collect();
+        assert_eq!(names, ["base.lpak", "______escaped.lpak", "_tmp_absolute.lpak"]);
+        let archive = PackArchive::open(&written[1]).unwrap();
+        assert_eq!(archive.read("levels/intro.scene").unwrap(), b"intro");
+        assert!(!dir.path().join("escaped.lpak").exists());
+    }
+}
@@ -1,9 +1,10 @@
 //! Input handling system
 
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{btree_map::Entry, BTreeMap, HashSet};
 
 /// Keyboard key codes
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum Key {
     // Letters
@@ -32,7 +33,7 @@ pub enum Key {
 }
 
 /// Mouse button
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MouseButton {
     /// Left mouse button
     Left,
@@ -46,6 +47,351 @@ pub enum MouseButton {
     Extra2,
 }
 
+/// Identifier of a connected gamepad, assigned by the platform backend
+pub type GamepadId = u32;
+
+/// Gamepad button, using the standard (Xbox-style) layout
+///
+/// The discriminant is the index used by `InputBinding::GamepadButton`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum GamepadButton {
+    /// Bottom face button (A / Cross)
+    South,
+    /// Right face button (B / Circle)
+    East,
+    /// Left face button (X / Square)
+    West,
+    /// Top face button (Y / Triangle)
+    North,
+    /// Left shoulder bumper
+    LeftBumper,
+    /// Right shoulder bumper
+    RightBumper,
+    /// Back / Select / Share
+    Select,
+    /// Start / Options
+    Start,
+    /// Home / Guide
+    Guide,
+    /// Left stick click
+    LeftStick,
+    /// Right stick click
+    RightStick,
+    /// D-pad up
+    DPadUp,
+    /// D-pad down
+    DPadDown,
+    /// D-pad left
+    DPadLeft,
+    /// D-pad right
+    DPadRight,
+}
+
+impl GamepadButton {
+    /// All buttons in index order
+    pub const ALL: [Self; 15] = [
+        Self::South,
+        Self::East,
+        Self::West,
+        Self::North,
+        Self::LeftBumper,
+        Self::RightBumper,
+        Self::Select,
+        Self::Start,
+        Self::Guide,
+        Self::LeftStick,
+        Self::RightStick,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+    ];
+
+    /// Binding index of the button
+    #[must_use]
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+
+    /// Button for a binding index
+    #[must_use]
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+}
+
+/// Gamepad analog axis
+///
+/// The discriminant is the index used by `InputBinding::GamepadAxis`.
+/// Stick axes range from -1 to 1 with Y pointing up, triggers from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum GamepadAxis {
+    /// Left stick horizontal
+    LeftStickX,
+    /// Left stick vertical
+    LeftStickY,
+    /// Right stick horizontal
+    RightStickX,
+    /// Right stick vertical
+    RightStickY,
+    /// Left trigger
+    LeftTrigger,
+    /// Right trigger
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// All axes in index order
+    pub const ALL: [Self; 6] = [
+        Self::LeftStickX,
+        Self::LeftStickY,
+        Self::RightStickX,
+        Self::RightStickY,
+        Self::LeftTrigger,
+        Self::RightTrigger,
+    ];
+
+    /// Binding index of the axis
+    #[must_use]
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+
+    /// Axis for a binding index
+    #[must_use]
+    pub fn from_index(index: u32) -> Option<Self> {
+        Self::ALL.get(index as usize).copied()
+    }
+
+    /// Check if this is a trigger axis
+    #[must_use]
+    pub fn is_trigger(self) -> bool {
+        matches!(self, Self::LeftTrigger | Self::RightTrigger)
+    }
+}
+
+/// How a dead zone is applied to a stick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeadZoneShape {
+    /// Applied to the stick's distance from center, preserving direction
+    Radial,
+    /// Applied to each axis separately
+    Axial,
+}
+
+/// Dead zone for analog input
+///
+/// Values below `inner` read as zero, values above `outer` as full
+/// deflection, and the range in between is rescaled linearly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeadZone {
+    /// Inner threshold (0 to 1)
+    pub inner: f32,
+    /// Outer threshold (0 to 1)
+    pub outer: f32,
+    /// Shape, used for sticks only
+    pub shape: DeadZoneShape,
+}
+
+impl DeadZone {
+    /// Create a radial dead zone
+    #[must_use]
+    pub fn radial(inner: f32, outer: f32) -> Self {
+        Self {
+            inner,
+            outer,
+            shape: DeadZoneShape::Radial,
+        }
+    }
+
+    /// Create an axial dead zone
+    #[must_use]
+    pub fn axial(inner: f32, outer: f32) -> Self {
+        Self {
+            inner,
+            outer,
+            shape: DeadZoneShape::Axial,
+        }
+    }
+
+    /// Rescale a single magnitude (0 to 1)
+    #[must_use]
+    pub fn apply_magnitude(&self, magnitude: f32) -> f32 {
+        if magnitude <= self.inner {
+            return 0.0;
+        }
+        let span = (self.outer - self.inner).max(f32::EPSILON);
+        ((magnitude - self.inner) / span).min(1.0)
+    }
+
+    /// Apply to a single signed axis value
+    #[must_use]
+    pub fn apply_axis(&self, value: f32) -> f32 {
+        self.apply_magnitude(value.abs()).copysign(value)
+    }
+
+    /// Apply to a stick position
+    #[must_use]
+    pub fn apply_stick(&self, x: f32, y: f32) -> (f32, f32) {
+        match self.shape {
+            DeadZoneShape::Radial => {
+                let magnitude = x.hypot(y);
+                if magnitude <= self.inner {
+                    return (0.0, 0.0);
+                }
+                let scale = self.apply_magnitude(magnitude) / magnitude;
+                (x * scale, y * scale)
+            },
+            DeadZoneShape::Axial => (self.apply_axis(x), self.apply_axis(y)),
+        }
+    }
+}
+
+/// Dead zone settings for gamepads
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GamepadSettings {
+    /// Dead zone for both sticks
+    pub stick_dead_zone: DeadZone,
+    /// Dead zone for triggers (shape is ignored)
+    pub trigger_dead_zone: DeadZone,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self {
+            stick_dead_zone: DeadZone::radial(0.15, 0.95),
+            trigger_dead_zone: DeadZone::axial(0.05, 1.0),
+        }
+    }
+}
+
+/// State of one connected gamepad
+#[derive(Debug, Clone, Default)]
+pub struct GamepadState {
+    /// Device name reported by the backend
+    pub name: String,
+    /// Buttons currently held down
+    buttons_down: HashSet<GamepadButton>,
+    /// Buttons pressed this frame
+    buttons_pressed: HashSet<GamepadButton>,
+    /// Buttons released this frame
+    buttons_released: HashSet<GamepadButton>,
+    /// Raw axis values, indexed by `GamepadAxis`
+    raw_axes: [f32; 6],
+    /// Axis values with dead zones applied
+    axes: [f32; 6],
+}
+
+impl GamepadState {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+
+    /// Check if a button is currently held down
+    #[must_use]
+    pub fn is_button_down(&self, button: GamepadButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// Check if a button was just pressed this frame
+    #[must_use]
+    pub fn is_button_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Buttons pressed this frame
+    pub fn pressed_buttons(&self) -> impl Iterator<Item = GamepadButton> + '_ {
+        self.buttons_pressed.iter().copied()
+    }
+
+    /// Check if a button was just released this frame
+    #[must_use]
+    pub fn is_button_released(&self, button: GamepadButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    /// Get an axis value with dead zones applied
+    #[must_use]
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+
+    /// Get an axis value as reported by the device
+    #[must_use]
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.raw_axes[axis as usize]
+    }
+
+    /// Get the left stick position with dead zones applied
+    #[must_use]
+    pub fn left_stick(&self) -> (f32, f32) {
+        (self.axis(GamepadAxis::LeftStickX), self.axis(GamepadAxis::LeftStickY))
+    }
+
+    /// Get the right stick position with dead zones applied
+    #[must_use]
+    pub fn right_stick(&self) -> (f32, f32) {
+        (self.axis(GamepadAxis::RightStickX), self.axis(GamepadAxis::RightStickY))
+    }
+
+    fn begin_frame(&mut self) {
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+    }
+
+    /// Recompute processed axis values from the raw ones
+    fn apply_dead_zones(&mut self, settings: &GamepadSettings) {
+        for (x, y) in [
+            (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY),
+            (GamepadAxis::RightStickX, GamepadAxis::RightStickY),
+        ] {
+            let (px, py) = settings
+                .stick_dead_zone
+                .apply_stick(self.raw_axis(x), self.raw_axis(y));
+            self.axes[x as usize] = px;
+            self.axes[y as usize] = py;
+        }
+        for trigger in [GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger] {
+            self.axes[trigger as usize] = settings
+                .trigger_dead_zone
+                .apply_magnitude(self.raw_axis(trigger).max(0.0));
+        }
+    }
+}
+
+/// Gamepad connection change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GamepadEvent {
+    /// A gamepad was connected
+    Connected(GamepadId),
+    /// A gamepad was disconnected
+    Disconnected(GamepadId),
+}
+
+/// Request to rumble a gamepad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RumbleRequest {
+    /// Target gamepad
+    pub gamepad: GamepadId,
+    /// Low-frequency (heavy) motor intensity (0 to 1)
+    pub strong: f32,
+    /// High-frequency (light) motor intensity (0 to 1)
+    pub weak: f32,
+    /// Duration in seconds; zero stops rumble
+    pub duration: f32,
+}
+
+/// Haptic output implemented by the platform gamepad backend
+pub trait HapticOutput {
+    /// Start or stop rumble, returning false if the device cannot rumble
+    fn rumble(&mut self, request: &RumbleRequest) -> bool;
+}
+
 /// Input state for the current frame
 #[derive(Debug, Default)]
 pub struct Input {
@@ -69,6 +415,15 @@ pub struct Input {
     mouse_delta: (f32, f32),
     /// Scroll wheel delta
     scroll_delta: f32,
+
+    /// Connected gamepads
+    gamepads: BTreeMap<GamepadId, GamepadState>,
+    /// Gamepad connection events this frame
+    gamepad_events: Vec<GamepadEvent>,
+    /// Gamepad dead zone settings
+    gamepad_settings: GamepadSettings,
+    /// Rumble requests not yet sent to the backend
+    rumble_requests: Vec<RumbleRequest>,
 }
 
 impl Input {
@@ -86,6 +441,10 @@ impl Input {
         self.mouse_released.clear();
         self.mouse_delta = (0.0, 0.0);
         self.scroll_delta = 0.0;
+        self.gamepad_events.clear();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.begin_frame();
+        }
     }
 
     /// Register a key press
@@ -120,6 +479,11 @@ impl Input {
         self.keys_released.contains(&key)
     }
 
+    /// Keys pressed this frame
+    pub fn pressed_keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.keys_pressed.iter().copied()
+    }
+
     /// Register a mouse button press
     pub fn mouse_press(&mut self, button: MouseButton) {
         if !self.mouse_down.contains(&button) {
@@ -152,6 +516,11 @@ impl Input {
         self.mouse_released.contains(&button)
     }
 
+    /// Mouse buttons pressed this frame
+    pub fn pressed_mouse_buttons(&self) -> impl Iterator<Item = MouseButton> + '_ {
+        self.mouse_pressed.iter().copied()
+    }
+
     /// Set mouse position
     pub fn set_mouse_position(&mut self, x: f32, y: f32) {
         let old = self.mouse_position;
@@ -182,6 +551,154 @@ impl Input {
         self.scroll_delta
     }
 
+    /// Register a connected gamepad
+    ///
+    /// A gamepad that is already connected keeps its state and only has its
+    /// name updated.
+    pub fn gamepad_connected(&mut self, id: GamepadId, name: impl Into<String>) {
+        match self.gamepads.entry(id) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().name = name.into();
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(GamepadState::new(name.into()));
+                self.gamepad_events.push(GamepadEvent::Connected(id));
+            },
+        }
+    }
+
+    /// Register a disconnected gamepad, dropping its state
+    pub fn gamepad_disconnected(&mut self, id: GamepadId) {
+        if self.gamepads.remove(&id).is_some() {
+            self.gamepad_events.push(GamepadEvent::Disconnected(id));
+        }
+        self.rumble_requests.retain(|r| r.gamepad != id);
+    }
+
+    /// Register a gamepad button press
+    pub fn gamepad_button_press(&mut self, id: GamepadId, button: GamepadButton) {
+        if let Some(gamepad) = self.gamepads.get_mut(&id) {
+            if gamepad.buttons_down.insert(button) {
+                gamepad.buttons_pressed.insert(button);
+            }
+        }
+    }
+
+    /// Register a gamepad button release
+    pub fn gamepad_button_release(&mut self, id: GamepadId, button: GamepadButton) {
+        if let Some(gamepad) = self.gamepads.get_mut(&id) {
+            if gamepad.buttons_down.remove(&button) {
+                gamepad.buttons_released.insert(button);
+            }
+        }
+    }
+
+    /// Set a raw gamepad axis value
+    pub fn set_gamepad_axis(&mut self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        if let Some(gamepad) = self.gamepads.get_mut(&id) {
+            gamepad.raw_axes[axis as usize] = if axis.is_trigger() {
+                value.clamp(0.0, 1.0)
+            } else {
+                value.clamp(-1.0, 1.0)
+            };
+            gamepad.apply_dead_zones(&self.gamepad_settings);
+        }
+    }
+
+    /// Get a connected gamepad
+    #[must_use]
+    pub fn gamepad(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+
+    /// Replace gamepad state with a copy of another input's gamepads
+    pub(crate) fn copy_gamepads_from(&mut self, other: &Self) {
+        self.gamepads.clone_from(&other.gamepads);
+    }
+
+    /// Iterate connected gamepads in ID order
+    pub fn gamepads(&self) -> impl Iterator<Item = (GamepadId, &GamepadState)> {
+        self.gamepads.iter().map(|(id, state)| (*id, state))
+    }
+
+    /// Gamepad connection events this frame
+    #[must_use]
+    pub fn gamepad_events(&self) -> &[GamepadEvent] {
+        &self.gamepad_events
+    }
+
+    /// Check if a gamepad button is currently held down
+    #[must_use]
+    pub fn is_gamepad_button_down(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepad(id).is_some_and(|g| g.is_button_down(button))
+    }
+
+    /// Check if a gamepad button was just pressed this frame
+    #[must_use]
+    pub fn is_gamepad_button_pressed(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepad(id).is_some_and(|g| g.is_button_pressed(button))
+    }
+
+    /// Check if a gamepad button was just released this frame
+    #[must_use]
+    pub fn is_gamepad_button_released(&self, id: GamepadId, button: GamepadButton) -> bool {
+        self.gamepad(id).is_some_and(|g| g.is_button_released(button))
+    }
+
+    /// Get a gamepad axis value with dead zones applied
+    #[must_use]
+    pub fn gamepad_axis(&self, id: GamepadId, axis: GamepadAxis) -> f32 {
+        self.gamepad(id).map_or(0.0, |g| g.axis(axis))
+    }
+
+    /// Get gamepad dead zone settings
+    #[must_use]
+    pub fn gamepad_settings(&self) -> &GamepadSettings {
+        &self.gamepad_settings
+    }
+
+    /// Change gamepad dead zone settings
+    pub fn set_gamepad_settings(&mut self, settings: GamepadSettings) {
+        self.gamepad_settings = settings;
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.apply_dead_zones(&settings);
+        }
+    }
+
+    /// Queue a rumble on a gamepad, returning false if it is not connected
+    pub fn rumble(&mut self, id: GamepadId, strong: f32, weak: f32, duration: f32) -> bool {
+        if !self.gamepads.contains_key(&id) {
+            return false;
+        }
+        self.rumble_requests.push(RumbleRequest {
+            gamepad: id,
+            strong: strong.clamp(0.0, 1.0),
+            weak: weak.clamp(0.0, 1.0),
+            duration: duration.max(0.0),
+        });
+        true
+    }
+
+    /// Queue a request stopping rumble on a gamepad
+    pub fn stop_rumble(&mut self, id: GamepadId) -> bool {
+        self.rumble(id, 0.0, 0.0, 0.0)
+    }
+
+    /// Take queued rumble requests
+    pub fn take_rumble_requests(&mut self) -> Vec<RumbleRequest> {
+        std::mem::take(&mut self.rumble_requests)
+    }
+
+    /// Send queued rumble requests to a haptic output
+    ///
+    /// Returns the number of requests the output accepted.
+    pub fn flush_rumble(&mut self, output: &mut dyn HapticOutput) -> usize {
+        self.take_rumble_requests()
+            .iter()
+            .filter(|request| output.rumble(request))
+            .count()
+    }
+
     /// Get horizontal axis input (-1 to 1) from arrow keys or WASD
     #[must_use]
     pub fn get_axis_horizontal(&self) -> f32 {
@@ -240,4 +757,81 @@ mod tests {
         input.key_press(Key::A);
         assert!((input.get_axis_horizontal()).abs() < f32::EPSILON);
     }
+
+    #[test]
+    fn gamepad_buttons_and_events() {
+        let mut input = Input::new();
+        input.gamepad_connected(1, "Pad");
+        input.gamepad_button_press(2, GamepadButton::South);
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Connected(1)]);
+
+        input.gamepad_button_press(1, GamepadButton::South);
+        assert!(input.is_gamepad_button_pressed(1, GamepadButton::South));
+        input.begin_frame();
+        assert!(input.gamepad_events().is_empty());
+        assert!(input.is_gamepad_button_down(1, GamepadButton::South));
+
+        // Reconnecting a known pad only renames it
+        input.gamepad_connected(1, "Renamed Pad");
+        assert!(input.gamepad_events().is_empty());
+        assert_eq!(input.gamepad(1).unwrap().name, "Renamed Pad");
+        assert!(input.is_gamepad_button_down(1, GamepadButton::South));
+        assert!(!input.is_gamepad_button_pressed(1, GamepadButton::South));
+
+        input.gamepad_button_release(1, GamepadButton::South);
+        assert!(input.is_gamepad_button_released(1, GamepadButton::South));
+        input.gamepad_disconnected(1);
+        assert!(input.gamepad(1).is_none());
+        assert_eq!(input.gamepad_events(), &[GamepadEvent::Disconnected(1)]);
+    }
+
+    #[test]
+    fn gamepad_dead_zones() {
+        let mut input = Input::new();
+        input.gamepad_connected(0, "Pad");
+
+        input.set_gamepad_axis(0, GamepadAxis::LeftStickX, 0.1);
+        input.set_gamepad_axis(0, GamepadAxis::LeftStickY, 0.1);
+        assert_eq!(input.gamepad(0).unwrap().left_stick(), (0.0, 0.0));
+
+        // Radial keeps the direction of a diagonal outside the dead zone
+        input.set_gamepad_axis(0, GamepadAxis::LeftStickY, 0.5);
+        let (x, y) = input.gamepad(0).unwrap().left_stick();
+        assert!((y / x - 5.0).abs() < 1e-4);
+
+        // Axial zeroes the small axis independently
+        input.set_gamepad_settings(GamepadSettings {
+            stick_dead_zone: DeadZone::axial(0.15, 0.95),
+            ..GamepadSettings::default()
+        });
+        let (x, y) = input.gamepad(0).unwrap().left_stick();
+        assert_eq!(x, 0.0);
+        assert!((y - 0.4375).abs() < 1e-4);
+
+        input.set_gamepad_axis(0, GamepadAxis::RightTrigger, 1.0);
+        assert_eq!(input.gamepad_axis(0, GamepadAxis::RightTrigger), 1.0);
+        input.set_gamepad_axis(0, GamepadAxis::RightTrigger, 0.02);
+        assert_eq!(input.gamepad_axis(0, GamepadAxis::RightTrigger), 0.0);
+    }
+
+    #[test]
+    fn rumble_requests() {
+        struct Motors(Vec<RumbleRequest>);
+        impl HapticOutput for Motors {
+            fn rumble(&mut self, request: &RumbleRequest) -> bool {
+                self.0.push(*request);
+                true
+            }
+        }
+
+        let mut input = Input::new();
+        assert!(!input.rumble(0, 1.0, 1.0, 0.5));
+        input.gamepad_connected(0, "Pad");
+        assert!(input.rumble(0, 2.0, 0.25, 0.5));
+
+        let mut motors = Motors(Vec::new());
+        assert_eq!(input.flush_rumble(&mut motors), 1);
+        assert_eq!(motors.0[0].strong, 1.0);
+        assert!(input.take_rumble_requests().is_empty());
+    }
 }
@@ -1,9 +1,23 @@
 //! Input Handling System
 //!
-//! Advanced input management with action mapping and rebinding.
+//! Advanced input management with action mapping, contexts and rebinding.
+//!
+//! Actions live in input contexts (gameplay, UI, vehicle, ...). Each frame
+//! `InputMap::update` polls `Input` and evaluates enabled contexts from the
+//! highest priority down; a consuming context hides the inputs it binds from
+//! the contexts below it.
+
+use crate::input::{GamepadAxis, GamepadButton, GamepadId, Input, Key, MouseButton};
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+/// Analog value at which an action counts as pressed
+pub const PRESS_THRESHOLD: f32 = 0.5;
 
-use crate::input::{Key, MouseButton};
-use std::collections::HashMap;
+/// Name of the context that `InputMap::add_action` adds to
+pub const DEFAULT_CONTEXT: &str = "default";
 
 /// Input action
 #[derive(Debug, Clone)]
@@ -14,6 +28,8 @@ pub struct InputAction {
     pub primary: InputBinding,
     /// Secondary binding
     pub secondary: Option<InputBinding>,
+    /// How the bindings trigger the action
+    pub interaction: Interaction,
     /// Is pressed
     pressed: bool,
     /// Just pressed this frame
@@ -22,27 +38,50 @@ pub struct InputAction {
     just_released: bool,
     /// Axis value (-1 to 1)
     value: f32,
+    /// 2D value
+    vector: (f32, f32),
+    /// Bindings are past the press threshold
+    down: bool,
+    /// Seconds the bindings have been held
+    held_time: f32,
+    /// Seconds since the last tap, while waiting for a double tap
+    since_tap: Option<f32>,
 }
 
 /// Input binding
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputBinding {
     /// Keyboard key
     Key(Key),
     /// Mouse button
     Mouse(MouseButton),
-    /// Gamepad button
+    /// Gamepad button, by `GamepadButton` index
     GamepadButton(u32),
-    /// Gamepad axis
+    /// Gamepad axis, by `GamepadAxis` index
     GamepadAxis(u32, bool), // axis index, positive direction
     /// Mouse axis
     MouseAxis(MouseAxis),
     /// Composite (two keys for axis)
     Composite(Key, Key), // negative, positive
+    /// Another binding with modifiers held, e.g. Shift+Click
+    Chord(Vec<Modifier>, Box<InputBinding>),
+    /// Four keys as a 2D vector
+    Composite2D {
+        /// Positive Y
+        up: Key,
+        /// Negative Y
+        down: Key,
+        /// Negative X
+        left: Key,
+        /// Positive X
+        right: Key,
+    },
+    /// Gamepad stick as a 2D vector
+    Stick(GamepadStick),
 }
 
 /// Mouse axis
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum MouseAxis {
     /// X movement
     X,
@@ -52,6 +91,85 @@ pub enum MouseAxis {
     Scroll,
 }
 
+/// Modifier key, matching either side of the keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Modifier {
+    /// Shift
+    Shift,
+    /// Control
+    Ctrl,
+    /// Alt
+    Alt,
+}
+
+impl Modifier {
+    /// Left and right keys of the modifier
+    #[must_use]
+    pub fn keys(self) -> [Key; 2] {
+        match self {
+            Self::Shift => [Key::LeftShift, Key::RightShift],
+            Self::Ctrl => [Key::LeftCtrl, Key::RightCtrl],
+            Self::Alt => [Key::LeftAlt, Key::RightAlt],
+        }
+    }
+
+    /// Modifier a key belongs to
+    #[must_use]
+    pub fn of_key(key: Key) -> Option<Self> {
+        [Self::Shift, Self::Ctrl, Self::Alt]
+            .into_iter()
+            .find(|m| m.keys().contains(&key))
+    }
+
+    /// Check if either key of the modifier is held
+    #[must_use]
+    pub fn is_held(self, input: &Input) -> bool {
+        self.keys().iter().any(|k| input.is_key_down(*k))
+    }
+}
+
+/// Gamepad stick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadStick {
+    /// Left stick
+    Left,
+    /// Right stick
+    Right,
+}
+
+/// How an action's bindings trigger it
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Interaction {
+    /// Pressed while the binding is held
+    #[default]
+    Press,
+    /// Pressed once the binding has been held for a duration (seconds)
+    Hold {
+        /// Seconds to hold
+        duration: f32,
+    },
+    /// Pressed by a second press within a window (seconds) of the first
+    DoubleTap {
+        /// Maximum seconds between taps
+        window: f32,
+    },
+    /// Value grows from 0 to 1 while held and is kept on the release frame
+    Charge {
+        /// Seconds to reach full charge
+        max_duration: f32,
+    },
+}
+
+/// Physical input, used for consumption and conflict checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Source {
+    Key(Key),
+    Mouse(MouseButton),
+    GamepadButton(u32),
+    GamepadAxis(u32),
+    MouseAxis(MouseAxis),
+}
+
 impl InputAction {
     /// Create a new action
     #[must_use]
@@ -60,10 +178,15 @@ impl InputAction {
             name: name.into(),
             primary,
             secondary: None,
+            interaction: Interaction::Press,
             pressed: false,
             just_pressed: false,
             just_released: false,
             value: 0.0,
+            vector: (0.0, 0.0),
+            down: false,
+            held_time: 0.0,
+            since_tap: None,
         }
     }
 
@@ -74,6 +197,13 @@ impl InputAction {
         self
     }
 
+    /// With an interaction
+    #[must_use]
+    pub fn with_interaction(mut self, interaction: Interaction) -> Self {
+        self.interaction = interaction;
+        self
+    }
+
     /// Is action pressed
     #[must_use]
     pub fn is_pressed(&self) -> bool {
@@ -92,21 +222,363 @@ impl InputAction {
         self.just_released
     }
 
-    /// Get axis value
+    /// Get axis value, or the magnitude for 2D bindings
     #[must_use]
     pub fn value(&self) -> f32 {
         self.value
     }
+
+    /// Get 2D value
+    #[must_use]
+    pub fn vector(&self) -> (f32, f32) {
+        self.vector
+    }
+
+    /// Seconds the bindings have been held
+    #[must_use]
+    pub fn held_time(&self) -> f32 {
+        self.held_time
+    }
+
+    /// Get a binding slot
+    #[must_use]
+    pub fn binding(&self, slot: BindingSlot) -> Option<&InputBinding> {
+        match slot {
+            BindingSlot::Primary => Some(&self.primary),
+            BindingSlot::Secondary => self.secondary.as_ref(),
+        }
+    }
+
+    fn bindings(&self) -> impl Iterator<Item = &InputBinding> {
+        std::iter::once(&self.primary).chain(self.secondary.as_ref())
+    }
+
+    /// Clear all state, e.g. when the context is disabled
+    fn reset(&mut self) {
+        self.pressed = false;
+        self.just_pressed = false;
+        self.just_released = false;
+        self.value = 0.0;
+        self.vector = (0.0, 0.0);
+        self.down = false;
+        self.held_time = 0.0;
+        self.since_tap = None;
+    }
+
+    /// Advance the interaction with this frame's raw binding value
+    fn update(&mut self, raw: (f32, f32), is_2d: bool, dt: f32) {
+        let magnitude = raw.0.hypot(raw.1);
+        let down = magnitude >= PRESS_THRESHOLD;
+        let pressed_now = down && !self.down;
+        self.held_time = match (down, pressed_now) {
+            (true, true) => 0.0,
+            (true, false) => self.held_time + dt,
+            (false, _) => self.held_time,
+        };
+        self.down = down;
+
+        let (pressed, value) = match self.interaction {
+            Interaction::Press => (down, None),
+            Interaction::Hold { duration } => (down && self.held_time >= duration, None),
+            Interaction::DoubleTap { window } => {
+                let pressed = if pressed_now {
+                    let double = self.since_tap.is_some_and(|t| t <= window);
+                    self.since_tap = if double { None } else { Some(0.0) };
+                    double
+                } else {
+                    // Measured press to press, so a long hold is not a tap
+                    self.since_tap = self.since_tap.map(|t| t + dt).filter(|t| *t <= window);
+                    self.pressed && down
+                };
+                (pressed, None)
+            },
+            Interaction::Charge { max_duration } => {
+                let charge = if down {
+                    (self.held_time / max_duration.max(f32::EPSILON)).min(1.0)
+                } else if self.pressed {
+                    self.value
+                } else {
+                    0.0
+                };
+                (down, Some(charge))
+            },
+        };
+
+        self.just_pressed = pressed && !self.pressed;
+        self.just_released = !pressed && self.pressed;
+        self.pressed = pressed;
+        let live = matches!(self.interaction, Interaction::Press) || pressed;
+        self.vector = if live { raw } else { (0.0, 0.0) };
+        self.value = value.unwrap_or(if !live {
+            0.0
+        } else if is_2d {
+            magnitude
+        } else {
+            raw.0
+        });
+    }
+}
+
+impl InputBinding {
+    /// Current value of a gamepad binding (0 to 1), or `None` for other bindings
+    ///
+    /// With no gamepad given, the strongest value across all connected
+    /// gamepads is used.
+    #[must_use]
+    pub fn gamepad_value(&self, input: &Input, gamepad: Option<GamepadId>) -> Option<f32> {
+        let pads = input
+            .gamepads()
+            .filter(|(id, _)| gamepad.map_or(true, |g| g == *id))
+            .map(|(_, state)| state);
+        match *self {
+            Self::GamepadButton(index) => {
+                let button = GamepadButton::from_index(index)?;
+                Some(if pads.into_iter().any(|p| p.is_button_down(button)) {
+                    1.0
+                } else {
+                    0.0
+                })
+            },
+            Self::GamepadAxis(index, positive) => {
+                let axis = GamepadAxis::from_index(index)?;
+                let sign = if positive { 1.0 } else { -1.0 };
+                Some(pads.map(|p| (p.axis(axis) * sign).max(0.0)).fold(0.0, f32::max))
+            },
+            _ => None,
+        }
+    }
+
+    /// Check if the binding produces a 2D value
+    #[must_use]
+    pub fn is_2d(&self) -> bool {
+        match self {
+            Self::Composite2D { .. } | Self::Stick(_) => true,
+            Self::Chord(_, inner) => inner.is_2d(),
+            _ => false,
+        }
+    }
+
+    /// Modifiers required by the binding
+    #[must_use]
+    pub fn modifiers(&self) -> &[Modifier] {
+        match self {
+            Self::Chord(modifiers, _) => modifiers,
+            _ => &[],
+        }
+    }
+
+    /// Check if two bindings would fire from the same input
+    ///
+    /// Bindings conflict when they share a key, button or axis and need the
+    /// same modifiers, so Click and Shift+Click do not conflict.
+    #[must_use]
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        let mine: HashSet<Modifier> = self.modifiers().iter().copied().collect();
+        let theirs: HashSet<Modifier> = other.modifiers().iter().copied().collect();
+        if mine != theirs {
+            return false;
+        }
+        let sources = other.sources();
+        self.sources().iter().any(|s| sources.contains(s))
+    }
+
+    /// Inputs read by the binding, excluding chord modifiers
+    fn sources(&self) -> Vec<Source> {
+        match self {
+            Self::Key(key) => vec![Source::Key(*key)],
+            Self::Mouse(button) => vec![Source::Mouse(*button)],
+            Self::GamepadButton(index) => vec![Source::GamepadButton(*index)],
+            Self::GamepadAxis(index, _) => vec![Source::GamepadAxis(*index)],
+            Self::MouseAxis(axis) => vec![Source::MouseAxis(*axis)],
+            Self::Composite(neg, pos) => vec![Source::Key(*neg), Source::Key(*pos)],
+            Self::Chord(_, inner) => inner.sources(),
+            Self::Composite2D {
+                up,
+                down,
+                left,
+                right,
+            } => [up, down, left, right]
+                .into_iter()
+                .map(|k| Source::Key(*k))
+                .collect(),
+            Self::Stick(stick) => {
+                let (x, y) = stick_axes(*stick);
+                vec![Source::GamepadAxis(x.index()), Source::GamepadAxis(y.index())]
+            },
+        }
+    }
+
+    /// Evaluate the binding against the current input
+    ///
+    /// Returns zero if any input is blocked by a higher context, or, for
+    /// bindings without modifiers, taken by an active chord.
+    fn evaluate(
+        &self,
+        input: &Input,
+        gamepad: Option<GamepadId>,
+        blocked: &HashSet<Source>,
+        chorded: &HashSet<Source>,
+    ) -> (f32, f32) {
+        let sources = self.sources();
+        if sources.iter().any(|s| blocked.contains(s))
+            || (self.modifiers().is_empty() && sources.iter().any(|s| chorded.contains(s)))
+        {
+            return (0.0, 0.0);
+        }
+        self.raw_value(input, gamepad)
+    }
+
+    fn raw_value(&self, input: &Input, gamepad: Option<GamepadId>) -> (f32, f32) {
+        let key = |k: &Key| if input.is_key_down(*k) { 1.0 } else { 0.0 };
+        match self {
+            Self::Key(k) => (key(k), 0.0),
+            Self::Mouse(button) => (if input.is_mouse_down(*button) { 1.0 } else { 0.0 }, 0.0),
+            Self::GamepadButton(_) | Self::GamepadAxis(..) => {
+                (self.gamepad_value(input, gamepad).unwrap_or(0.0), 0.0)
+            },
+            Self::MouseAxis(axis) => match axis {
+                MouseAxis::X => (input.mouse_delta().0, 0.0),
+                MouseAxis::Y => (input.mouse_delta().1, 0.0),
+                MouseAxis::Scroll => (input.scroll_delta(), 0.0),
+            },
+            Self::Composite(neg, pos) => (key(pos) - key(neg), 0.0),
+            Self::Chord(modifiers, inner) => {
+                if modifiers.iter().all(|m| m.is_held(input)) {
+                    inner.raw_value(input, gamepad)
+                } else {
+                    (0.0, 0.0)
+                }
+            },
+            Self::Composite2D {
+                up,
+                down,
+                left,
+                right,
+            } => {
+                let (x, y) = (key(right) - key(left), key(up) - key(down));
+                let length = x.hypot(y);
+                if length > 1.0 {
+                    (x / length, y / length)
+                } else {
+                    (x, y)
+                }
+            },
+            Self::Stick(stick) => input
+                .gamepads()
+                .filter(|(id, _)| gamepad.map_or(true, |g| g == *id))
+                .map(|(_, state)| match stick {
+                    GamepadStick::Left => state.left_stick(),
+                    GamepadStick::Right => state.right_stick(),
+                })
+                .fold((0.0, 0.0), |best, v| {
+                    if v.0.hypot(v.1) > best.0.hypot(best.1) {
+                        v
+                    } else {
+                        best
+                    }
+                }),
+        }
+    }
+}
+
+fn stick_axes(stick: GamepadStick) -> (GamepadAxis, GamepadAxis) {
+    match stick {
+        GamepadStick::Left => (GamepadAxis::LeftStickX, GamepadAxis::LeftStickY),
+        GamepadStick::Right => (GamepadAxis::RightStickX, GamepadAxis::RightStickY),
+    }
+}
+
+/// Set of actions that is enabled and disabled together
+#[derive(Debug, Clone)]
+pub struct InputContext {
+    /// Context name
+    pub name: String,
+    /// Higher priority contexts are evaluated first
+    pub priority: i32,
+    /// Hide inputs bound here from lower priority contexts
+    pub consume: bool,
+    /// Is evaluated
+    enabled: bool,
+    /// Actions in insertion order
+    actions: Vec<InputAction>,
+}
+
+impl InputContext {
+    /// Create an enabled, non-consuming context
+    #[must_use]
+    pub fn new(name: impl Into<String>, priority: i32) -> Self {
+        Self {
+            name: name.into(),
+            priority,
+            consume: false,
+            enabled: true,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Hide inputs bound here from lower priority contexts
+    #[must_use]
+    pub fn consuming(mut self) -> Self {
+        self.consume = true;
+        self
+    }
+
+    /// With an action
+    #[must_use]
+    pub fn with_action(mut self, action: InputAction) -> Self {
+        self.add_action(action);
+        self
+    }
+
+    /// Add an action, replacing any action with its name
+    pub fn add_action(&mut self, action: InputAction) {
+        match self.actions.iter_mut().find(|a| a.name == action.name) {
+            Some(existing) => *existing = action,
+            None => self.actions.push(action),
+        }
+    }
+
+    /// Get action
+    #[must_use]
+    pub fn action(&self, name: &str) -> Option<&InputAction> {
+        self.actions.iter().find(|a| a.name == name)
+    }
+
+    /// Get action mutably
+    pub fn action_mut(&mut self, name: &str) -> Option<&mut InputAction> {
+        self.actions.iter_mut().find(|a| a.name == name)
+    }
+
+    /// Iterate actions
+    pub fn actions(&self) -> impl Iterator<Item = &InputAction> {
+        self.actions.iter()
+    }
+
+    /// Is the context evaluated
+    #[must_use]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Names of actions whose bindings conflict with a binding
+    #[must_use]
+    pub fn conflicts(&self, binding: &InputBinding) -> Vec<String> {
+        self.actions
+            .iter()
+            .filter(|a| a.bindings().any(|b| b.conflicts_with(binding)))
+            .map(|a| a.name.clone())
+            .collect()
+    }
 }
 
 /// Input map (action mappings)
 pub struct InputMap {
-    /// Actions by name
-    actions: HashMap<String, InputAction>,
-    /// Key to actions mapping
-    key_map: HashMap<Key, Vec<String>>,
-    /// Mouse button to actions
-    mouse_map: HashMap<MouseButton, Vec<String>>,
+    /// Contexts by descending priority
+    contexts: Vec<InputContext>,
+    /// Gamepad read by gamepad bindings, or any when `None`
+    gamepad: Option<GamepadId>,
+    /// Input fed through the deprecated event methods
+    legacy: Input,
 }
 
 impl Default for InputMap {
@@ -116,141 +588,230 @@ impl Default for InputMap {
 }
 
 impl InputMap {
-    /// Create a new input map
+    /// Create a new input map with an empty default context
     #[must_use]
     pub fn new() -> Self {
         Self {
-            actions: HashMap::new(),
-            key_map: HashMap::new(),
-            mouse_map: HashMap::new(),
+            contexts: vec![InputContext::new(DEFAULT_CONTEXT, 0)],
+            gamepad: None,
+            legacy: Input::new(),
         }
     }
 
-    /// Add an action
-    pub fn add_action(&mut self, action: InputAction) {
-        let name = action.name.clone();
+    /// Restrict gamepad bindings to one gamepad, e.g. for local multiplayer
+    pub fn set_gamepad(&mut self, gamepad: Option<GamepadId>) {
+        self.gamepad = gamepad;
+    }
 
-        // Map primary binding
-        match &action.primary {
-            InputBinding::Key(key) => {
-                self.key_map.entry(*key).or_default().push(name.clone());
-            }
-            InputBinding::Mouse(button) => {
-                self.mouse_map.entry(*button).or_default().push(name.clone());
-            }
-            InputBinding::Composite(neg, pos) => {
-                self.key_map.entry(*neg).or_default().push(name.clone());
-                self.key_map.entry(*pos).or_default().push(name.clone());
-            }
-            _ => {}
+    /// Gamepad read by gamepad bindings
+    #[must_use]
+    pub fn gamepad(&self) -> Option<GamepadId> {
+        self.gamepad
+    }
+
+    /// Add an action to the default context
+    pub fn add_action(&mut self, action: InputAction) {
+        if let Some(context) = self.context_mut(DEFAULT_CONTEXT) {
+            context.add_action(action);
         }
+    }
 
-        // Map secondary binding
-        if let Some(ref secondary) = action.secondary {
-            match secondary {
-                InputBinding::Key(key) => {
-                    self.key_map.entry(*key).or_default().push(name.clone());
-                }
-                InputBinding::Mouse(button) => {
-                    self.mouse_map.entry(*button).or_default().push(name.clone());
-                }
-                _ => {}
+    /// Add a context, replacing any context with its name
+    pub fn push_context(&mut self, context: InputContext) {
+        self.remove_context(&context.name);
+        self.contexts.push(context);
+        // Stable sort keeps insertion order among equal priorities
+        self.contexts.sort_by_key(|c| std::cmp::Reverse(c.priority));
+    }
+
+    /// Remove a context
+    pub fn remove_context(&mut self, name: &str) -> Option<InputContext> {
+        let index = self.contexts.iter().position(|c| c.name == name)?;
+        Some(self.contexts.remove(index))
+    }
+
+    /// Enable or disable a context, clearing its action state when disabled
+    pub fn set_context_enabled(&mut self, name: &str, enabled: bool) {
+        if let Some(context) = self.context_mut(name) {
+            context.enabled = enabled;
+            if !enabled {
+                context.actions.iter_mut().for_each(InputAction::reset);
             }
         }
+    }
 
-        self.actions.insert(name, action);
+    /// Get a context
+    #[must_use]
+    pub fn context(&self, name: &str) -> Option<&InputContext> {
+        self.contexts.iter().find(|c| c.name == name)
     }
 
-    /// Get action
+    /// Get a context mutably
+    pub fn context_mut(&mut self, name: &str) -> Option<&mut InputContext> {
+        self.contexts.iter_mut().find(|c| c.name == name)
+    }
+
+    /// Iterate contexts by descending priority
+    pub fn contexts(&self) -> impl Iterator<Item = &InputContext> {
+        self.contexts.iter()
+    }
+
+    /// Get action from the highest priority enabled context that has it
     #[must_use]
     pub fn action(&self, name: &str) -> Option<&InputAction> {
-        self.actions.get(name)
+        self.contexts
+            .iter()
+            .filter(|c| c.enabled)
+            .find_map(|c| c.action(name))
     }
 
     /// Check if action is pressed
     #[must_use]
     pub fn is_pressed(&self, name: &str) -> bool {
-        self.actions.get(name).map_or(false, |a| a.is_pressed())
+        self.action(name).is_some_and(|a| a.is_pressed())
     }
 
     /// Check if action was just pressed
     #[must_use]
     pub fn is_just_pressed(&self, name: &str) -> bool {
-        self.actions.get(name).map_or(false, |a| a.is_just_pressed())
+        self.action(name).is_some_and(|a| a.is_just_pressed())
+    }
+
+    /// Check if action was just released
+    #[must_use]
+    pub fn is_just_released(&self, name: &str) -> bool {
+        self.action(name).is_some_and(|a| a.is_just_released())
     }
 
     /// Get action value
     #[must_use]
     pub fn value(&self, name: &str) -> f32 {
-        self.actions.get(name).map_or(0.0, |a| a.value())
+        self.action(name).map_or(0.0, |a| a.value())
     }
 
-    /// Begin frame (reset just_pressed/released)
-    pub fn begin_frame(&mut self) {
-        for action in self.actions.values_mut() {
-            action.just_pressed = false;
-            action.just_released = false;
+    /// Get action 2D value
+    #[must_use]
+    pub fn vector(&self, name: &str) -> (f32, f32) {
+        self.action(name).map_or((0.0, 0.0), |a| a.vector())
+    }
+
+    /// Replace a binding of an action, returning false if it does not exist
+    pub fn set_binding(
+        &mut self,
+        context: &str,
+        action: &str,
+        slot: BindingSlot,
+        binding: Option<InputBinding>,
+    ) -> bool {
+        let Some(action) = self
+            .context_mut(context)
+            .and_then(|c| c.action_mut(action))
+        else {
+            return false;
+        };
+        match (slot, binding) {
+            (BindingSlot::Primary, Some(binding)) => action.primary = binding,
+            (BindingSlot::Primary, None) => return false,
+            (BindingSlot::Secondary, binding) => action.secondary = binding,
         }
+        true
     }
 
-    /// Process key press
-    pub fn key_pressed(&mut self, key: Key) {
-        if let Some(action_names) = self.key_map.get(&key) {
-            for name in action_names.clone() {
-                if let Some(action) = self.actions.get_mut(&name) {
-                    if !action.pressed {
-                        action.just_pressed = true;
+    /// Evaluate all enabled contexts against the current input
+    ///
+    /// Call once per frame after the platform has fed `Input`, with the
+    /// frame time in seconds for timed interactions.
+    pub fn update(&mut self, input: &Input, dt: f32) {
+        let mut blocked = HashSet::new();
+        let mut chorded = HashSet::new();
+        let gamepad = self.gamepad;
+
+        for context in &mut self.contexts {
+            if !context.enabled {
+                continue;
+            }
+
+            // Active chords take their main input from plain bindings
+            for binding in context.actions.iter().flat_map(InputAction::bindings) {
+                if !binding.modifiers().is_empty() {
+                    let (x, y) = binding.evaluate(input, gamepad, &blocked, &chorded);
+                    if x.hypot(y) >= PRESS_THRESHOLD {
+                        chorded.extend(binding.sources());
                     }
-                    action.pressed = true;
-                    action.value = 1.0;
+                }
+            }
+
+            for action in &mut context.actions {
+                let raw = action
+                    .bindings()
+                    .map(|b| b.evaluate(input, gamepad, &blocked, &chorded))
+                    .fold((0.0, 0.0), |best: (f32, f32), v| {
+                        if v.0.hypot(v.1) > best.0.hypot(best.1) {
+                            v
+                        } else {
+                            best
+                        }
+                    });
+                let is_2d = action.bindings().any(InputBinding::is_2d);
+                action.update(raw, is_2d, dt);
+            }
+
+            if context.consume {
+                for binding in context.actions.iter().flat_map(InputAction::bindings) {
+                    blocked.extend(binding.sources());
                 }
             }
         }
     }
 
+    /// Re-evaluate against the input fed through the deprecated event methods
+    fn update_legacy(&mut self) {
+        let legacy = std::mem::take(&mut self.legacy);
+        self.update(&legacy, 0.0);
+        self.legacy = legacy;
+    }
+
+    /// Begin frame (reset just_pressed/released)
+    #[deprecated(note = "call `update` once per frame instead")]
+    pub fn begin_frame(&mut self) {
+        self.update_legacy();
+    }
+
+    /// Process key press
+    #[deprecated(note = "feed `Input` and call `update` instead")]
+    pub fn key_pressed(&mut self, key: Key) {
+        self.legacy.key_press(key);
+        self.update_legacy();
+    }
+
     /// Process key release
+    #[deprecated(note = "feed `Input` and call `update` instead")]
     pub fn key_released(&mut self, key: Key) {
-        if let Some(action_names) = self.key_map.get(&key) {
-            for name in action_names.clone() {
-                if let Some(action) = self.actions.get_mut(&name) {
-                    if action.pressed {
-                        action.just_released = true;
-                    }
-                    action.pressed = false;
-                    action.value = 0.0;
-                }
-            }
-        }
+        self.legacy.key_release(key);
+        self.update_legacy();
     }
 
     /// Process mouse button press
+    #[deprecated(note = "feed `Input` and call `update` instead")]
     pub fn mouse_pressed(&mut self, button: MouseButton) {
-        if let Some(action_names) = self.mouse_map.get(&button) {
-            for name in action_names.clone() {
-                if let Some(action) = self.actions.get_mut(&name) {
-                    if !action.pressed {
-                        action.just_pressed = true;
-                    }
-                    action.pressed = true;
-                    action.value = 1.0;
-                }
-            }
-        }
+        self.legacy.mouse_press(button);
+        self.update_legacy();
     }
 
     /// Process mouse button release
+    #[deprecated(note = "feed `Input` and call `update` instead")]
     pub fn mouse_released(&mut self, button: MouseButton) {
-        if let Some(action_names) = self.mouse_map.get(&button) {
-            for name in action_names.clone() {
-                if let Some(action) = self.actions.get_mut(&name) {
-                    if action.pressed {
-                        action.just_released = true;
-                    }
-                    action.pressed = false;
-                    action.value = 0.0;
-                }
-            }
-        }
+        self.legacy.mouse_release(button);
+        self.update_legacy();
+    }
+
+    /// Evaluate gamepad bindings against the current input state
+    ///
+    /// Keys and mouse buttons still come from the event methods.
+    #[deprecated(note = "call `update`, which reads gamepads along with everything else")]
+    pub fn update_gamepads(&mut self, input: &Input) {
+        self.legacy.copy_gamepads_from(input);
+        self.update_legacy();
     }
 
     /// Create default FPS controls
@@ -266,6 +827,18 @@ impl InputMap {
             .with_secondary(InputBinding::Key(Key::Left)));
         map.add_action(InputAction::new("move_right", InputBinding::Key(Key::D))
             .with_secondary(InputBinding::Key(Key::Right)));
+        map.add_action(
+            InputAction::new(
+                "move",
+                InputBinding::Composite2D {
+                    up: Key::W,
+                    down: Key::S,
+                    left: Key::A,
+                    right: Key::D,
+                },
+            )
+            .with_secondary(InputBinding::Stick(GamepadStick::Left)),
+        );
         map.add_action(InputAction::new("jump", InputBinding::Key(Key::Space)));
         map.add_action(InputAction::new("crouch", InputBinding::Key(Key::LeftCtrl)));
         map.add_action(InputAction::new("sprint", InputBinding::Key(Key::LeftShift)));
@@ -299,12 +872,49 @@ impl InputMap {
     }
 }
 
+/// Primary or secondary binding of an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingSlot {
+    /// Primary binding
+    Primary,
+    /// Secondary binding
+    Secondary,
+}
+
+/// Result of applying a rebind
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebindResult {
+    /// Binding applied
+    Bound,
+    /// Binding is used by these actions in the same context; nothing changed
+    Conflict(Vec<String>),
+    /// No rebind in progress, or the action no longer exists
+    Invalid,
+}
+
+/// Bindings of one rebound action, as saved in the settings file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedBindings {
+    primary: InputBinding,
+    #[serde(default)]
+    secondary: Option<InputBinding>,
+}
+
+/// Settings file contents: context name to action name to bindings
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RebindSettings {
+    #[serde(default)]
+    contexts: BTreeMap<String, BTreeMap<String, SavedBindings>>,
+}
+
 /// Input rebinding
 pub struct InputRebinder {
-    /// Currently rebinding action
-    rebinding: Option<String>,
+    /// Currently rebinding context, action and slot
+    rebinding: Option<(String, String, BindingSlot)>,
     /// Is waiting for input
     waiting: bool,
+    /// User rebinds by context and action
+    overrides: RebindSettings,
 }
 
 impl Default for InputRebinder {
@@ -320,12 +930,19 @@ impl InputRebinder {
         Self {
             rebinding: None,
             waiting: false,
+            overrides: RebindSettings::default(),
         }
     }
 
-    /// Start rebinding an action
+    /// Start rebinding the primary binding of an action in the default context
+    #[deprecated(note = "use `start_rebind_in` to choose the context and slot")]
     pub fn start_rebind(&mut self, action_name: &str) {
-        self.rebinding = Some(action_name.to_string());
+        self.start_rebind_in(DEFAULT_CONTEXT, action_name, BindingSlot::Primary);
+    }
+
+    /// Start rebinding one binding of an action
+    pub fn start_rebind_in(&mut self, context: &str, action_name: &str, slot: BindingSlot) {
+        self.rebinding = Some((context.to_string(), action_name.to_string(), slot));
         self.waiting = true;
     }
 
@@ -344,6 +961,466 @@ impl InputRebinder {
     /// Get action being rebound
     #[must_use]
     pub fn rebinding_action(&self) -> Option<&str> {
-        self.rebinding.as_deref()
+        self.rebinding.as_ref().map(|(_, action, _)| action.as_str())
+    }
+
+    /// Get context of the action being rebound
+    #[must_use]
+    pub fn rebinding_context(&self) -> Option<&str> {
+        self.rebinding.as_ref().map(|(context, _, _)| context.as_str())
+    }
+
+    /// Read the binding the player is pressing this frame, if any
+    ///
+    /// Held modifiers turn the press into a chord. A modifier pressed on its
+    /// own is returned as a plain key.
+    #[must_use]
+    pub fn capture(&self, input: &Input) -> Option<InputBinding> {
+        if !self.waiting {
+            return None;
+        }
+        let pressed_keys: Vec<Key> = input.pressed_keys().collect();
+        let main = pressed_keys
+            .iter()
+            .find(|k| Modifier::of_key(**k).is_none())
+            .map(|k| InputBinding::Key(*k))
+            .or_else(|| input.pressed_mouse_buttons().next().map(InputBinding::Mouse))
+            .or_else(|| {
+                input
+                    .gamepads()
+                    .find_map(|(_, state)| state.pressed_buttons().next())
+                    .map(|b| InputBinding::GamepadButton(b.index()))
+            });
+        let Some(main) = main else {
+            return pressed_keys.first().map(|k| InputBinding::Key(*k));
+        };
+        if matches!(main, InputBinding::GamepadButton(_)) {
+            return Some(main);
+        }
+        let modifiers: Vec<Modifier> = [Modifier::Shift, Modifier::Ctrl, Modifier::Alt]
+            .into_iter()
+            .filter(|m| m.is_held(input))
+            .collect();
+        Some(if modifiers.is_empty() {
+            main
+        } else {
+            InputBinding::Chord(modifiers, Box::new(main))
+        })
+    }
+
+    /// Apply a binding to the action being rebound
+    ///
+    /// Conflicts with other actions in the same context are reported and the
+    /// rebind stays pending, unless `swap` is set, in which case the
+    /// conflicting actions receive the binding being replaced. When the slot
+    /// being rebound is empty, a conflicting primary is replaced by that
+    /// action's secondary; actions without a free secondary are still
+    /// reported as conflicts.
+    pub fn apply(&mut self, map: &mut InputMap, binding: InputBinding, swap: bool) -> RebindResult {
+        let Some((context_name, action_name, slot)) = self.rebinding.clone() else {
+            return RebindResult::Invalid;
+        };
+        let Some(context) = map.context(&context_name) else {
+            return RebindResult::Invalid;
+        };
+        let Some(previous) = context.action(&action_name).map(|a| a.binding(slot).cloned())
+        else {
+            return RebindResult::Invalid;
+        };
+
+        let conflicts: Vec<String> = context
+            .conflicts(&binding)
+            .into_iter()
+            .filter(|name| *name != action_name)
+            .collect();
+        if !conflicts.is_empty() && !swap {
+            return RebindResult::Conflict(conflicts);
+        }
+        if previous.is_none() {
+            // A primary binding cannot be cleared, so it needs a replacement
+            let taken = |b: &InputBinding| b.conflicts_with(&binding);
+            let stuck: Vec<String> = conflicts
+                .iter()
+                .filter(|name| {
+                    context.action(name).is_some_and(|other| {
+                        taken(&other.primary) && other.secondary.as_ref().map_or(true, taken)
+                    })
+                })
+                .cloned()
+                .collect();
+            if !stuck.is_empty() {
+                return RebindResult::Conflict(stuck);
+            }
+        }
+
+        for name in &conflicts {
+            let Some(context) = map.context_mut(&context_name) else {
+                continue;
+            };
+            let Some(other) = context.action_mut(name) else {
+                continue;
+            };
+            if other.primary.conflicts_with(&binding) {
+                if let Some(replacement) = previous.clone().or_else(|| other.secondary.take()) {
+                    other.primary = replacement;
+                }
+            }
+            if other
+                .secondary
+                .as_ref()
+                .is_some_and(|b| b.conflicts_with(&binding))
+            {
+                other.secondary = previous.clone();
+            }
+            self.record(map, &context_name, name);
+        }
+
+        map.set_binding(&context_name, &action_name, slot, Some(binding));
+        self.record(map, &context_name, &action_name);
+        self.cancel();
+        RebindResult::Bound
+    }
+
+    /// Remember the current bindings of an action as a user rebind
+    pub fn record(&mut self, map: &InputMap, context: &str, action: &str) {
+        if let Some(action) = map.context(context).and_then(|c| c.action(action)) {
+            self.overrides
+                .contexts
+                .entry(context.to_string())
+                .or_default()
+                .insert(
+                    action.name.clone(),
+                    SavedBindings {
+                        primary: action.primary.clone(),
+                        secondary: action.secondary.clone(),
+                    },
+                );
+        }
+    }
+
+    /// Forget all user rebinds
+    pub fn clear(&mut self) {
+        self.overrides.contexts.clear();
+    }
+
+    /// Number of rebound actions across all contexts
+    #[must_use]
+    pub fn override_count(&self) -> usize {
+        self.overrides.contexts.values().map(BTreeMap::len).sum()
+    }
+
+    /// Apply saved rebinds to a map, skipping contexts and actions it lacks
+    ///
+    /// Returns the number of actions rebound.
+    pub fn apply_overrides(&self, map: &mut InputMap) -> usize {
+        let mut applied = 0;
+        for (context, actions) in &self.overrides.contexts {
+            for (action, saved) in actions {
+                let primary = Some(saved.primary.clone());
+                if map.set_binding(context, action, BindingSlot::Primary, primary) {
+                    let secondary = saved.secondary.clone();
+                    map.set_binding(context, action, BindingSlot::Secondary, secondary);
+                    applied += 1;
+                }
+            }
+        }
+        applied
+    }
+
+    /// Save user rebinds to a JSON settings file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.overrides)
+            .map_err(|e| Error::Config(e.to_string()))?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Load user rebinds from a JSON settings file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or parsed
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let overrides = serde_json::from_str(&json).map_err(|e| Error::Config(e.to_string()))?;
+        Ok(Self {
+            overrides,
+            ..Self::new()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.1;
+
+    #[test]
+    #[allow(deprecated)]
+    fn gamepad_bindings_fire_actions() {
+        let mut map = InputMap::new();
+        map.add_action(
+            InputAction::new("jump", InputBinding::Key(Key::Space)).with_secondary(
+                InputBinding::GamepadButton(GamepadButton::South.index()),
+            ),
+        );
+        map.add_action(InputAction::new(
+            "move_left",
+            InputBinding::GamepadAxis(GamepadAxis::LeftStickX.index(), false),
+        ));
+
+        let mut input = Input::new();
+        input.gamepad_connected(3, "Pad");
+        input.gamepad_button_press(3, GamepadButton::South);
+        input.set_gamepad_axis(3, GamepadAxis::LeftStickX, -1.0);
+        map.update_gamepads(&input);
+        assert!(map.is_just_pressed("jump"));
+        assert!((map.value("move_left") - 1.0).abs() < f32::EPSILON);
+
+        // Releasing the key keeps the action held by the gamepad
+        map.begin_frame();
+        map.key_pressed(Key::Space);
+        map.key_released(Key::Space);
+        assert!(map.is_pressed("jump"));
+
+        map.set_gamepad(Some(0));
+        map.update_gamepads(&input);
+        assert!(!map.is_pressed("jump"));
+        assert!(map.action("jump").unwrap().is_just_released());
+        assert_eq!(map.value("move_left"), 0.0);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_event_methods_drive_actions() {
+        let mut map = InputMap::default_fps();
+        map.key_pressed(Key::W);
+        assert!(map.is_just_pressed("move_forward"));
+        map.begin_frame();
+        assert!(map.is_pressed("move_forward"));
+        assert!(!map.is_just_pressed("move_forward"));
+        map.mouse_pressed(MouseButton::Left);
+        map.mouse_released(MouseButton::Left);
+        assert!(!map.is_pressed("fire"));
+
+        let mut rebinder = InputRebinder::new();
+        rebinder.start_rebind("jump");
+        assert_eq!(rebinder.rebinding_context(), Some(DEFAULT_CONTEXT));
+        let result = rebinder.apply(&mut map, InputBinding::Key(Key::J), false);
+        assert_eq!(result, RebindResult::Bound);
+        assert_eq!(map.action("jump").unwrap().primary, InputBinding::Key(Key::J));
+    }
+
+    #[test]
+    fn polled_gamepad_bindings_fire_actions() {
+        let mut map = InputMap::new();
+        map.add_action(
+            InputAction::new("jump", InputBinding::Key(Key::Space)).with_secondary(
+                InputBinding::GamepadButton(GamepadButton::South.index()),
+            ),
+        );
+        map.add_action(InputAction::new(
+            "move_left",
+            InputBinding::GamepadAxis(GamepadAxis::LeftStickX.index(), false),
+        ));
+
+        let mut input = Input::new();
+        input.gamepad_connected(3, "Pad");
+        input.gamepad_button_press(3, GamepadButton::South);
+        input.set_gamepad_axis(3, GamepadAxis::LeftStickX, -1.0);
+        map.update(&input, DT);
+        assert!(map.is_just_pressed("jump"));
+        assert!((map.value("move_left") - 1.0).abs() < f32::EPSILON);
+
+        // Releasing the key keeps the action held by the gamepad
+        input.key_press(Key::Space);
+        input.key_release(Key::Space);
+        map.update(&input, DT);
+        assert!(map.is_pressed("jump"));
+
+        map.set_gamepad(Some(0));
+        map.update(&input, DT);
+        assert!(!map.is_pressed("jump"));
+        assert!(map.is_just_released("jump"));
+        assert_eq!(map.value("move_left"), 0.0);
+    }
+
+    #[test]
+    fn contexts_consume_and_chords_take_precedence() {
+        let mut map = InputMap::new();
+        map.add_action(InputAction::new("pause", InputBinding::Key(Key::Escape)));
+        map.add_action(InputAction::new("select", InputBinding::Mouse(MouseButton::Left)));
+        map.add_action(InputAction::new(
+            "select_add",
+            InputBinding::Chord(
+                vec![Modifier::Shift],
+                Box::new(InputBinding::Mouse(MouseButton::Left)),
+            ),
+        ));
+        map.push_context(
+            InputContext::new("ui", 10)
+                .consuming()
+                .with_action(InputAction::new("back", InputBinding::Key(Key::Escape))),
+        );
+
+        let mut input = Input::new();
+        input.key_press(Key::Escape);
+        map.update(&input, DT);
+        assert!(map.is_pressed("back"));
+        assert!(!map.is_pressed("pause"));
+
+        map.set_context_enabled("ui", false);
+        map.update(&input, DT);
+        assert!(map.is_pressed("pause"));
+
+        input.mouse_press(MouseButton::Left);
+        map.update(&input, DT);
+        assert!(map.is_pressed("select"));
+        input.key_press(Key::RightShift);
+        map.update(&input, DT);
+        assert!(map.is_pressed("select_add"));
+        assert!(!map.is_pressed("select"));
+    }
+
+    #[test]
+    fn interactions() {
+        let mut map = InputMap::new();
+        map.add_action(
+            InputAction::new("heavy", InputBinding::Key(Key::E))
+                .with_interaction(Interaction::Hold { duration: 0.25 }),
+        );
+        map.add_action(
+            InputAction::new("dodge", InputBinding::Key(Key::Space))
+                .with_interaction(Interaction::DoubleTap { window: 0.3 }),
+        );
+        map.add_action(
+            InputAction::new("throw", InputBinding::Key(Key::G))
+                .with_interaction(Interaction::Charge { max_duration: 0.4 }),
+        );
+        let mut input = Input::new();
+
+        input.key_press(Key::E);
+        for _ in 0..3 {
+            map.update(&input, DT);
+        }
+        assert!(!map.is_pressed("heavy"));
+        map.update(&input, DT);
+        assert!(map.is_just_pressed("heavy"));
+
+        input.key_press(Key::Space);
+        map.update(&input, DT);
+        assert!(!map.is_pressed("dodge"));
+        input.key_release(Key::Space);
+        map.update(&input, DT);
+        input.key_press(Key::Space);
+        map.update(&input, DT);
+        assert!(map.is_just_pressed("dodge"));
+
+        // Holding past the window and tapping again is not a double tap
+        input.key_release(Key::Space);
+        map.update(&input, DT);
+        input.key_press(Key::Space);
+        for _ in 0..4 {
+            map.update(&input, DT);
+        }
+        input.key_release(Key::Space);
+        map.update(&input, DT);
+        input.key_press(Key::Space);
+        map.update(&input, DT);
+        assert!(!map.is_pressed("dodge"));
+
+        input.key_press(Key::G);
+        for _ in 0..3 {
+            map.update(&input, DT);
+        }
+        assert!((map.value("throw") - 0.5).abs() < 1e-4);
+        input.key_release(Key::G);
+        map.update(&input, DT);
+        assert!(map.is_just_released("throw"));
+        assert!((map.value("throw") - 0.5).abs() < 1e-4);
+        map.update(&input, DT);
+        assert_eq!(map.value("throw"), 0.0);
+    }
+
+    #[test]
+    fn vector_actions() {
+        let mut map = InputMap::default_fps();
+        let mut input = Input::new();
+        input.key_press(Key::W);
+        input.key_press(Key::D);
+        map.update(&input, DT);
+        let (x, y) = map.vector("move");
+        assert!((x - y).abs() < 1e-6 && (x.hypot(y) - 1.0).abs() < 1e-6);
+
+        input.key_release(Key::W);
+        input.key_release(Key::D);
+        input.gamepad_connected(0, "Pad");
+        input.set_gamepad_axis(0, GamepadAxis::LeftStickY, -1.0);
+        map.update(&input, DT);
+        assert_eq!(map.vector("move"), (0.0, -1.0));
+        assert!((map.value("move") - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rebinds_detect_conflicts_and_persist() {
+        let mut map = InputMap::default_fps();
+        let mut rebinder = InputRebinder::new();
+
+        rebinder.start_rebind_in(DEFAULT_CONTEXT, "jump", BindingSlot::Primary);
+        let mut input = Input::new();
+        input.key_press(Key::LeftCtrl);
+        input.key_press(Key::R);
+        let chord = rebinder.capture(&input).unwrap();
+        assert_eq!(
+            chord,
+            InputBinding::Chord(vec![Modifier::Ctrl], Box::new(InputBinding::Key(Key::R)))
+        );
+        assert_eq!(rebinder.apply(&mut map, chord, false), RebindResult::Bound);
+
+        rebinder.start_rebind_in(DEFAULT_CONTEXT, "reload", BindingSlot::Primary);
+        let result = rebinder.apply(&mut map, InputBinding::Key(Key::E), false);
+        assert_eq!(result, RebindResult::Conflict(vec!["interact".to_string()]));
+        assert!(rebinder.is_waiting());
+        assert_eq!(
+            rebinder.apply(&mut map, InputBinding::Key(Key::E), true),
+            RebindResult::Bound
+        );
+        let interact = map.action("interact").unwrap();
+        assert_eq!(interact.primary, InputBinding::Key(Key::R));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rebinds.json");
+        rebinder.save(&path).unwrap();
+        let loaded = InputRebinder::load(&path).unwrap();
+        assert_eq!(loaded.override_count(), 3);
+
+        let mut fresh = InputMap::default_fps();
+        assert_eq!(loaded.apply_overrides(&mut fresh), 3);
+        assert_eq!(fresh.action("reload").unwrap().primary, InputBinding::Key(Key::E));
+        assert_eq!(fresh.action("jump").unwrap().primary.modifiers(), &[Modifier::Ctrl]);
+    }
+
+    #[test]
+    fn swapping_into_an_empty_slot_keeps_primaries_bound() {
+        let mut map = InputMap::default_fps();
+        let mut rebinder = InputRebinder::new();
+
+        rebinder.start_rebind_in(DEFAULT_CONTEXT, "reload", BindingSlot::Secondary);
+        let result = rebinder.apply(&mut map, InputBinding::Key(Key::E), true);
+        assert_eq!(result, RebindResult::Conflict(vec!["interact".to_string()]));
+        assert_eq!(map.action("interact").unwrap().primary, InputBinding::Key(Key::E));
+        assert_eq!(map.action("reload").unwrap().secondary, None);
+
+        let result = rebinder.apply(&mut map, InputBinding::Key(Key::W), true);
+        assert_eq!(result, RebindResult::Bound);
+        let forward = map.action("move_forward").unwrap();
+        assert_eq!(forward.primary, InputBinding::Key(Key::Up));
+        assert_eq!(forward.secondary, None);
+        assert_eq!(map.action("reload").unwrap().secondary, Some(InputBinding::Key(Key::W)));
     }
 }
@@ -13,24 +13,35 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod analytics;
 pub mod api_stable;
+pub mod build_pipeline;
 pub mod error;
 pub mod id;
 pub mod input;
 pub mod input_action;
 pub mod logger;
 pub mod math;
+pub mod pack;
 pub mod platform;
 pub mod profiler;
 pub mod time;
+pub mod tracking_alloc;
 
 pub use error::{Error, Result};
 pub use id::{Id, TypedId};
-pub use input::{Input, Key, MouseButton};
-pub use input_action::{InputAction, InputBinding, InputMap};
+pub use input::{
+    DeadZone, DeadZoneShape, GamepadAxis, GamepadButton, GamepadEvent, GamepadId, GamepadSettings,
+    GamepadState, HapticOutput, Input, Key, MouseButton, RumbleRequest,
+};
+pub use input_action::{
+    BindingSlot, GamepadStick, InputAction, InputBinding, InputContext, InputMap, InputRebinder,
+    Interaction, Modifier, RebindResult,
+};
 pub use logger::{LogLevel, Logger};
 pub use math::{Color, Rect, Transform2D};
 pub use time::Time;
+pub use tracking_alloc::{AllocScope, HeapSnapshot, TrackingAllocator};
 
 /// Lunaris Engine version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,837 @@
+//! Packed asset archives
+//!
+//! Shipping builds read assets from `.lpak` archives instead of loose files.
+//! An archive is a fixed size header, the entry data and a table of contents:
+//!
+//! ```text
+//! header  "LPAK", version u16, flags u16, entry count u32, reserved u32,
+//!         TOC offset u64, TOC size u64, TOC hash u64
+//! data    entries, each starting at a multiple of the archive alignment
+//! toc     per entry: path length u16, path, offset u64, stored size u64,
+//!         size u64, compression u8, flags u8, hash u64
+//! ```
+//!
+//! Integers are little endian. Archives are memory mapped, so uncompressed
+//! entries are read without copying. A patch archive mounted above its base in
+//! a [`PackSet`] replaces changed entries and hides removed ones.
+
+use crate::{Error, Result};
+use memmap2::Mmap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File extension of pack archives
+pub const PACK_EXTENSION: &str = "lpak";
+
+/// Format version written by [`PackWriter`]
+pub const PACK_VERSION: u16 = 1;
+
+const MAGIC: &[u8; 4] = b"LPAK";
+const HEADER_SIZE: u64 = 40;
+const FLAG_PATCH: u16 = 1;
+const ENTRY_REMOVED: u8 = 1;
+/// Table of contents entry with an empty path
+const MIN_ENTRY_SIZE: u64 = 36;
+/// Most an LZ4 block can expand, bounding the buffer a corrupt size allocates
+const MAX_LZ4_RATIO: u64 = 255;
+
+/// How an entry is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PackCompression {
+    /// Stored as is, readable without copying
+    #[default]
+    None,
+    /// LZ4 block, fast to decompress
+    Lz4,
+    /// Zstandard, smaller but slower to decompress
+    Zstd,
+}
+
+impl PackCompression {
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Lz4 => 1,
+            Self::Zstd => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Self::None),
+            1 => Some(Self::Lz4),
+            2 => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Table of contents entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackEntry {
+    /// Normalized `/` separated path
+    pub path: String,
+    /// Offset of the stored data in the archive
+    pub offset: u64,
+    /// Size of the stored data
+    pub stored_size: u64,
+    /// Size after decompression
+    pub size: u64,
+    /// How the data is stored
+    pub compression: PackCompression,
+    /// xxh3 hash of the uncompressed data
+    pub hash: u64,
+    /// Hides the path in archives mounted below this one
+    pub removed: bool,
+}
+
+/// Normalize an asset path the way archives store it
+///
+/// Backslashes become `/`, and empty and `.` components are dropped.
+#[must_use]
+pub fn normalize_path(path: &str) -> String {
+    path.split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Sizes of a written archive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PackStats {
+    /// Entries with data
+    pub entries: usize,
+    /// Removal entries
+    pub removed: usize,
+    /// Files skipped because the base archive has the same contents
+    pub unchanged: usize,
+    /// Total uncompressed size
+    pub size: u64,
+    /// Total stored size
+    pub stored_size: u64,
+}
+
+/// Writes a pack archive
+///
+/// Entry data is streamed to disk as it is added; the table of contents and
+/// header are written by [`finish`](Self::finish).
+pub struct PackWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+    position: u64,
+    flags: u16,
+    entries: Vec<PackEntry>,
+    names: HashSet<String>,
+    compression: PackCompression,
+    zstd_level: i32,
+    alignment: u64,
+    /// Entry hashes of the archive a patch applies to
+    base: Option<HashMap<String, u64>>,
+    stats: PackStats,
+}
+
+impl PackWriter {
+    /// Create an archive, replacing an existing file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn create(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let mut file = BufWriter::new(File::create(&path)?);
+        file.write_all(&[0; HEADER_SIZE as usize])?;
+        Ok(Self {
+            path,
+            file,
+            position: HEADER_SIZE,
+            flags: 0,
+            entries: Vec::new(),
+            names: HashSet::new(),
+            compression: PackCompression::Lz4,
+            zstd_level: 3,
+            alignment: 4096,
+            base: None,
+            stats: PackStats::default(),
+        })
+    }
+
+    /// Create a patch for `base`
+    ///
+    /// Files whose contents match the base are skipped, and base entries that
+    /// are not added to the patch are removed when it is mounted above the base.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be created.
+    pub fn patch(path: impl Into<PathBuf>, base: &PackArchive) -> Result<Self> {
+        let mut writer = Self::create(path)?;
+        writer.flags |= FLAG_PATCH;
+        writer.base = Some(
+            base.entries()
+                .iter()
+                .filter(|e| !e.removed)
+                .map(|e| (e.path.clone(), e.hash))
+                .collect(),
+        );
+        Ok(writer)
+    }
+
+    /// Set the compression used by [`add`](Self::add)
+    #[must_use]
+    pub fn with_compression(mut self, compression: PackCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Set the Zstandard compression level
+    #[must_use]
+    pub fn with_zstd_level(mut self, level: i32) -> Self {
+        self.zstd_level = level;
+        self
+    }
+
+    /// Set the alignment of entry data, 4096 by default
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alignment` is not a power of two.
+    #[must_use]
+    pub fn with_alignment(mut self, alignment: u64) -> Self {
+        assert!(
+            alignment.is_power_of_two(),
+            "pack alignment must be a power of two"
+        );
+        self.alignment = alignment;
+        self
+    }
+
+    /// Add a file with the writer's compression
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or already added, or writing fails.
+    pub fn add(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        self.add_with(path, data, self.compression)
+    }
+
+    /// Add a file with a specific compression
+    ///
+    /// Data that does not shrink when compressed is stored as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or already added, or writing fails.
+    pub fn add_with(
+        &mut self,
+        path: &str,
+        data: &[u8],
+        compression: PackCompression,
+    ) -> Result<()> {
+        let path = self.claim(path)?;
+        let hash = xxhash_rust::xxh3::xxh3_64(data);
+        if self.base.as_ref().and_then(|b| b.get(&path)) == Some(&hash) {
+            self.stats.unchanged += 1;
+            return Ok(());
+        }
+
+        let compressed: Cow<'_, [u8]> = match compression {
+            PackCompression::None => Cow::Borrowed(data),
+            PackCompression::Lz4 => Cow::Owned(lz4_flex::block::compress(data)),
+            PackCompression::Zstd => Cow::Owned(zstd::bulk::compress(data, self.zstd_level)?),
+        };
+        let (stored, compression) = if compressed.len() < data.len() {
+            (compressed.as_ref(), compression)
+        } else {
+            (data, PackCompression::None)
+        };
+
+        let padding = self.position.next_multiple_of(self.alignment) - self.position;
+        self.file.write_all(&vec![0; padding as usize])?;
+        self.file.write_all(stored)?;
+        let offset = self.position + padding;
+        self.position = offset + stored.len() as u64;
+
+        self.stats.entries += 1;
+        self.stats.size += data.len() as u64;
+        self.stats.stored_size += stored.len() as u64;
+        self.entries.push(PackEntry {
+            path,
+            offset,
+            stored_size: stored.len() as u64,
+            size: data.len() as u64,
+            compression,
+            hash,
+            removed: false,
+        });
+        Ok(())
+    }
+
+    /// Add a file from disk
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or [`add`](Self::add) fails.
+    pub fn add_file(&mut self, path: &str, source: &Path) -> Result<()> {
+        let data = std::fs::read(source)?;
+        self.add(path, &data)
+    }
+
+    /// Hide a file in archives mounted below this one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path is empty or already added.
+    pub fn remove(&mut self, path: &str) -> Result<()> {
+        let path = self.claim(path)?;
+        self.push_removed(path);
+        Ok(())
+    }
+
+    fn push_removed(&mut self, path: String) {
+        self.stats.removed += 1;
+        self.entries.push(PackEntry {
+            path,
+            offset: 0,
+            stored_size: 0,
+            size: 0,
+            compression: PackCompression::None,
+            hash: 0,
+            removed: true,
+        });
+    }
+
+    /// Normalize a path and make sure it is only added once
+    fn claim(&mut self, path: &str) -> Result<String> {
+        let path = normalize_path(path);
+        if path.is_empty() || path.len() > usize::from(u16::MAX) {
+            return Err(Error::Asset(format!("invalid pack path: {:?}", path)));
+        }
+        if !self.names.insert(path.clone()) {
+            return Err(Error::Asset(format!("{} is already in the pack", path)));
+        }
+        Ok(path)
+    }
+
+    /// Write the table of contents and header
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn finish(mut self) -> Result<PackStats> {
+        if let Some(base) = self.base.take() {
+            let mut removed: Vec<String> = base
+                .into_keys()
+                .filter(|path| !self.names.contains(path))
+                .collect();
+            removed.sort();
+            for path in removed {
+                self.push_removed(path);
+            }
+        }
+
+        let mut toc = Vec::new();
+        for entry in &self.entries {
+            toc.extend_from_slice(&(entry.path.len() as u16).to_le_bytes());
+            toc.extend_from_slice(entry.path.as_bytes());
+            toc.extend_from_slice(&entry.offset.to_le_bytes());
+            toc.extend_from_slice(&entry.stored_size.to_le_bytes());
+            toc.extend_from_slice(&entry.size.to_le_bytes());
+            toc.push(entry.compression.to_byte());
+            toc.push(if entry.removed { ENTRY_REMOVED } else { 0 });
+            toc.extend_from_slice(&entry.hash.to_le_bytes());
+        }
+        self.file.write_all(&toc)?;
+
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        header.extend_from_slice(MAGIC);
+        header.extend_from_slice(&PACK_VERSION.to_le_bytes());
+        header.extend_from_slice(&self.flags.to_le_bytes());
+        header.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&self.position.to_le_bytes());
+        header.extend_from_slice(&(toc.len() as u64).to_le_bytes());
+        header.extend_from_slice(&xxhash_rust::xxh3::xxh3_64(&toc).to_le_bytes());
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)?;
+        self.file.flush()?;
+
+        tracing::info!(
+            "Wrote pack {}: {} entries, {} removed, {} unchanged, {} -> {} bytes",
+            self.path.display(),
+            self.stats.entries,
+            self.stats.removed,
+            self.stats.unchanged,
+            self.stats.size,
+            self.stats.stored_size
+        );
+        Ok(self.stats)
+    }
+}
+
+/// A memory mapped pack archive
+#[derive(Debug)]
+pub struct PackArchive {
+    path: PathBuf,
+    map: Mmap,
+    patch: bool,
+    entries: Vec<PackEntry>,
+    index: HashMap<String, usize>,
+}
+
+impl PackArchive {
+    /// Open and validate an archive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, is not a pack archive, has
+    /// an unsupported version or a corrupt table of contents.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let file = File::open(&path)?;
+        // SAFETY: the map is read only. Archives are not modified while the
+        // game runs; truncating one underneath it is outside our control.
+        #[allow(unsafe_code)]
+        let map = unsafe { Mmap::map(&file)? };
+        let invalid = |reason: &str| Error::Asset(format!("{}: {}", path.display(), reason));
+
+        if map.len() < HEADER_SIZE as usize || &map[..4] != MAGIC {
+            return Err(invalid("not a pack archive"));
+        }
+        let mut header = Reader::new(&map[4..HEADER_SIZE as usize]);
+        let version = header.u16();
+        if version != PACK_VERSION {
+            return Err(invalid(&format!("unsupported pack version {}", version)));
+        }
+        let flags = header.u16();
+        let count = header.u32();
+        header.u32();
+        let toc_offset = header.u64();
+        let toc_size = header.u64();
+        let toc_hash = header.u64();
+
+        let toc = toc_offset
+            .checked_add(toc_size)
+            .filter(|end| *end <= map.len() as u64)
+            .map(|end| &map[toc_offset as usize..end as usize])
+            .ok_or_else(|| invalid("table of contents out of bounds"))?;
+        if xxhash_rust::xxh3::xxh3_64(toc) != toc_hash {
+            return Err(invalid("corrupt table of contents"));
+        }
+
+        let mut reader = Reader::new(toc);
+        // The count is not covered by the hash, so never trust it for allocation
+        let capacity = u64::from(count).min(toc_size / MIN_ENTRY_SIZE) as usize;
+        let mut entries = Vec::with_capacity(capacity);
+        let mut index = HashMap::with_capacity(capacity);
+        for _ in 0..count {
+            let entry = reader
+                .entry()
+                .ok_or_else(|| invalid("truncated table of contents"))?;
+            let in_bounds = entry
+                .offset
+                .checked_add(entry.stored_size)
+                .is_some_and(|end| end <= toc_offset);
+            if !in_bounds {
+                return Err(invalid(&format!("{} is out of bounds", entry.path)));
+            }
+            index.insert(entry.path.clone(), entries.len());
+            entries.push(entry);
+        }
+
+        Ok(Self {
+            path,
+            map,
+            patch: flags & FLAG_PATCH != 0,
+            entries,
+            index,
+        })
+    }
+
+    /// Path of the archive file
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the archive was written as a patch
+    #[must_use]
+    pub fn is_patch(&self) -> bool {
+        self.patch
+    }
+
+    /// Table of contents in write order
+    #[must_use]
+    pub fn entries(&self) -> &[PackEntry] {
+        &self.entries
+    }
+
+    /// Look up an entry, including removal entries
+    #[must_use]
+    pub fn entry(&self, path: &str) -> Option<&PackEntry> {
+        self.index
+            .get(&normalize_path(path))
+            .map(|i| &self.entries[*i])
+    }
+
+    /// Whether the archive has data for a path
+    #[must_use]
+    pub fn contains(&self, path: &str) -> bool {
+        self.entry(path).is_some_and(|e| !e.removed)
+    }
+
+    /// Borrow an uncompressed entry straight from the mapped file
+    #[must_use]
+    pub fn mapped(&self, path: &str) -> Option<&[u8]> {
+        self.entry(path)
+            .filter(|e| !e.removed && e.compression == PackCompression::None)
+            .and_then(|e| self.stored(e))
+    }
+
+    /// Read and decompress a file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive has no data for the path or the entry
+    /// is corrupt.
+    pub fn read(&self, path: &str) -> Result<Vec<u8>> {
+        match self.entry(path) {
+            Some(entry) if !entry.removed => self.read_entry(entry),
+            _ => Err(Error::Asset(format!(
+                "{} is not in {}",
+                path,
+                self.path.display()
+            ))),
+        }
+    }
+
+    /// Read and decompress an entry of this archive
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entry lies outside the archive, fails to
+    /// decompress, or its size or hash does not match.
+    pub fn read_entry(&self, entry: &PackEntry) -> Result<Vec<u8>> {
+        let corrupt = || {
+            Error::Asset(format!(
+                "{} in {} is corrupt",
+                entry.path,
+                self.path.display()
+            ))
+        };
+        let stored = self.stored(entry).ok_or_else(corrupt)?;
+        let plausible = match entry.compression {
+            PackCompression::None => entry.size == entry.stored_size,
+            PackCompression::Lz4 => entry.size <= entry.stored_size.saturating_mul(MAX_LZ4_RATIO),
+            // Zstd is decoded as a stream, so memory follows the actual output
+            PackCompression::Zstd => true,
+        };
+        let size = usize::try_from(entry.size)
+            .ok()
+            .filter(|_| plausible)
+            .ok_or_else(corrupt)?;
+        let data = match entry.compression {
+            PackCompression::None => stored.to_vec(),
+            PackCompression::Lz4 => lz4_flex::block::decompress(stored, size)
+                .map_err(|e| Error::Asset(format!("{}: {}", entry.path, e)))?,
+            PackCompression::Zstd => {
+                let mut data = Vec::new();
+                zstd::stream::read::Decoder::with_buffer(stored)?
+                    .take(entry.size.saturating_add(1))
+                    .read_to_end(&mut data)?;
+                data
+            },
+        };
+        if data.len() != size || xxhash_rust::xxh3::xxh3_64(&data) != entry.hash {
+            return Err(corrupt());
+        }
+        Ok(data)
+    }
+
+    /// Stored bytes of an entry, `None` if they lie outside the archive
+    fn stored(&self, entry: &PackEntry) -> Option<&[u8]> {
+        let start = usize::try_from(entry.offset).ok()?;
+        let end = start.checked_add(usize::try_from(entry.stored_size).ok()?)?;
+        self.map.get(start..end)
+    }
+}
+
+/// Archives mounted on top of each other
+///
+/// Archives mounted later take precedence, so a patch mounted after its base
+/// overrides it. Removal entries hide a path from the archives below.
+#[derive(Debug, Clone, Default)]
+pub struct PackSet {
+    archives: Vec<Arc<PackArchive>>,
+}
+
+impl PackSet {
+    /// Create an empty set
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mount an archive above the ones already mounted
+    pub fn mount(&mut self, archive: impl Into<Arc<PackArchive>>) {
+        let archive = archive.into();
+        tracing::info!(
+            "Mounted pack {} ({} entries)",
+            archive.path().display(),
+            archive.entries().len()
+        );
+        self.archives.push(archive);
+    }
+
+    /// Unmount an archive by file path, returning whether it was mounted
+    pub fn unmount(&mut self, path: &Path) -> bool {
+        let before = self.archives.len();
+        self.archives.retain(|a| a.path() != path);
+        self.archives.len() != before
+    }
+
+    /// Mounted archives, lowest first
+    #[must_use]
+    pub fn archives(&self) -> &[Arc<PackArchive>] {
+        &self.archives
+    }
+
+    /// Whether no archive is mounted
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.archives.is_empty()
+    }
+
+    /// The topmost entry for a path, which may be a removal entry
+    #[must_use]
+    pub fn lookup(&self, path: &str) -> Option<(&Arc<PackArchive>, &PackEntry)> {
+        let path = normalize_path(path);
+        self.archives
+            .iter()
+            .rev()
+            .find_map(|a| a.index.get(&path).map(|i| (a, &a.entries[*i])))
+    }
+
+    /// Whether the mounted archives have data for a path
+    #[must_use]
+    pub fn contains(&self, path: &str) -> bool {
+        self.lookup(path).is_some_and(|(_, e)| !e.removed)
+    }
+
+    /// Read a file from the topmost archive that has it
+    ///
+    /// Returns `None` if no archive mentions the path, so callers can fall back
+    /// to loose files, and an error if a patch removed it.
+    #[must_use]
+    pub fn read(&self, path: &str) -> Option<Result<Vec<u8>>> {
+        let (archive, entry) = self.lookup(path)?;
+        Some(if entry.removed {
+            Err(Error::Asset(format!(
+                "{} was removed by {}",
+                entry.path,
+                archive.path().display()
+            )))
+        } else {
+            archive.read_entry(entry)
+        })
+    }
+}
+
+/// Little endian cursor over the header and table of contents
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let head = self.data.get(..N)?.try_into().ok()?;
+        self.data = &self.data[N..];
+        Some(head)
+    }
+
+    fn u16(&mut self) -> u16 {
+        self.take().map_or(0, u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> u32 {
+        self.take().map_or(0, u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> u64 {
+        self.take().map_or(0, u64::from_le_bytes)
+    }
+
+    fn entry(&mut self) -> Option<PackEntry> {
+        let len = usize::from(u16::from_le_bytes(self.take()?));
+        if self.data.len() < len {
+            return None;
+        }
+        let (path, rest) = self.data.split_at(len);
+        self.data = rest;
+        let path = String::from_utf8(path.to_vec()).ok()?;
+        let offset = u64::from_le_bytes(self.take()?);
+        let stored_size = u64::from_le_bytes(self.take()?);
+        let size = u64::from_le_bytes(self.take()?);
+        let [compression] = self.take()?;
+        let [flags] = self.take()?;
+        let hash = u64::from_le_bytes(self.take()?);
+        Some(PackEntry {
+            path,
+            offset,
+            stored_size,
+            size,
+            compression: PackCompression::from_byte(compression)?,
+            hash,
+            removed: flags & ENTRY_REMOVED != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn round_trips_entries_with_each_compression() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("base.lpak");
+        let text = "hello pack ".repeat(200);
+        let noise: Vec<u8> = (0..5000u32)
+            .map(|i| xxhash_rust::xxh3::xxh3_64(&i.to_le_bytes()) as u8)
+            .collect();
+
+        let mut writer = PackWriter::create(&path).unwrap().with_alignment(256);
+        writer
+            .add_with("raw.bin", &noise, PackCompression::None)
+            .unwrap();
+        writer
+            .add_with("text/lz4.txt", text.as_bytes(), PackCompression::Lz4)
+            .unwrap();
+        writer
+            .add_with("./text\\zstd.txt", text.as_bytes(), PackCompression::Zstd)
+            .unwrap();
+        writer.add("noise.lz4", &noise).unwrap();
+        assert!(writer.add("raw.bin", b"again").is_err());
+        let stats = writer.finish().unwrap();
+        assert_eq!(stats.entries, 4);
+        assert!(stats.stored_size < stats.size);
+
+        let archive = PackArchive::open(&path).unwrap();
+        assert!(!archive.is_patch());
+        assert_eq!(archive.read("text/lz4.txt").unwrap(), text.as_bytes());
+        assert_eq!(archive.read("text/zstd.txt").unwrap(), text.as_bytes());
+        assert_eq!(
+            archive.entry("text/zstd.txt").unwrap().compression,
+            PackCompression::Zstd
+        );
+        // Incompressible data falls back to being stored as is
+        assert_eq!(
+            archive.entry("noise.lz4").unwrap().compression,
+            PackCompression::None
+        );
+        assert_eq!(archive.mapped("raw.bin").unwrap(), noise.as_slice());
+        assert!(archive.mapped("text/lz4.txt").is_none());
+        assert!(archive.entries().iter().all(|e| e.offset % 256 == 0));
+        assert!(archive.read("missing").is_err());
+    }
+
+    #[test]
+    fn rejects_corrupt_archives() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("corrupt.lpak");
+        let mut writer = PackWriter::create(&path).unwrap();
+        writer.add("a.txt", &b"abc".repeat(100)).unwrap();
+        writer.finish().unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(PackArchive::open(&path).is_err());
+
+        bytes[last] ^= 0xff;
+        bytes[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(PackArchive::open(&path).is_err());
+
+        std::fs::write(&path, b"not a pack").unwrap();
+        assert!(PackArchive::open(&path).is_err());
+    }
+
+    #[test]
+    fn rejects_entries_with_bad_bounds_or_sizes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("sizes.lpak");
+        let mut writer = PackWriter::create(&path).unwrap();
+        let text = b"abc".repeat(100);
+        writer
+            .add_with("a.lz4", &text, PackCompression::Lz4)
+            .unwrap();
+        writer
+            .add_with("a.zst", &text, PackCompression::Zstd)
+            .unwrap();
+        writer.finish().unwrap();
+        let archive = PackArchive::open(&path).unwrap();
+
+        for name in ["a.lz4", "a.zst"] {
+            let entry = archive.entry(name).unwrap();
+            assert_eq!(archive.read_entry(entry).unwrap(), text);
+
+            let outside = PackEntry {
+                offset: u64::MAX - 1,
+                ..entry.clone()
+            };
+            assert!(archive.read_entry(&outside).is_err());
+            let huge = PackEntry {
+                size: u64::MAX / 2,
+                ..entry.clone()
+            };
+            assert!(archive.read_entry(&huge).is_err());
+        }
+    }
+
+    #[test]
+    fn patch_overrides_and_removes_base_entries() {
+        let dir = TempDir::new().unwrap();
+        let base_path = dir.path().join("base.lpak");
+        let mut writer = PackWriter::create(&base_path).unwrap();
+        writer.add("keep.txt", b"same").unwrap();
+        writer.add("change.txt", b"old").unwrap();
+        writer.add("drop.txt", b"gone").unwrap();
+        writer.finish().unwrap();
+        let base = PackArchive::open(&base_path).unwrap();
+
+        let patch_path = dir.path().join("patch.lpak");
+        let mut writer = PackWriter::patch(&patch_path, &base).unwrap();
+        writer.add("keep.txt", b"same").unwrap();
+        writer.add("change.txt", b"new").unwrap();
+        writer.add("added.txt", b"added").unwrap();
+        let stats = writer.finish().unwrap();
+        assert_eq!((stats.entries, stats.removed, stats.unchanged), (2, 1, 1));
+
+        let patch = PackArchive::open(&patch_path).unwrap();
+        assert!(patch.is_patch());
+        assert!(!patch.contains("keep.txt"));
+
+        let mut set = PackSet::new();
+        set.mount(base);
+        set.mount(patch);
+        assert_eq!(set.read("keep.txt").unwrap().unwrap(), b"same");
+        assert_eq!(set.read("change.txt").unwrap().unwrap(), b"new");
+        assert_eq!(set.read("added.txt").unwrap().unwrap(), b"added");
+        assert!(set.read("drop.txt").unwrap().is_err());
+        assert!(!set.contains("drop.txt"));
+        assert!(set.read("loose.txt").is_none());
+
+        assert!(set.unmount(&patch_path));
+        assert_eq!(set.read("change.txt").unwrap().unwrap(), b"old");
+    }
+}
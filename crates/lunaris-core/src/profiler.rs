@@ -1,121 +1,466 @@
 //! Profiler
 //!
 //! GPU, CPU, and memory profiling with frame timeline.
+//!
+//! Zones are recorded from any thread through a `ProfilerHandle` and collected
+//! into the current frame by `Profiler::end_frame`. The last `max_frames`
+//! frames can be exported as Chrome trace event JSON for Perfetto or
+//! `chrome://tracing`, with GPU and memory samples as counter tracks.
+//!
+//! When `TrackingAllocator` is installed, `MemoryProfiler` is refreshed every
+//! frame, and allocations inside zones started with `alloc_zone` are tagged
+//! with the zone name.
 
-use std::collections::HashMap;
+use crate::tracking_alloc::{self, AllocScope, HeapSnapshot};
+use serde_json::{json, Map, Value};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// Thread id of the frame track in exported traces
+pub const FRAME_TRACK_ID: u64 = 0;
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_PROFILER_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    /// Open zone count on this thread, per profiler id
+    static ZONE_DEPTHS: RefCell<Vec<(u64, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Profiler id of the calling thread
+#[must_use]
+pub fn thread_id() -> u64 {
+    THREAD_ID.with(|id| *id)
+}
+
 /// Profiler
 pub struct Profiler {
+    /// Record zones; applied at the next `begin_frame`
     pub enabled: bool,
-    pub frame_data: Vec<FrameData>,
+    /// Finished frames, oldest first
+    pub frame_data: VecDeque<FrameData>,
+    /// Frame being recorded
     pub current_frame: FrameData,
+    /// Accumulated timings by zone name
     pub zones: HashMap<String, ZoneStats>,
+    /// GPU pass timings
     pub gpu_profiler: GpuProfiler,
+    /// Heap usage by category
     pub memory_profiler: MemoryProfiler,
+    /// Number of frames kept in `frame_data`
     pub max_frames: usize,
+    shared: Arc<SharedZones>,
+    frame_start: Instant,
 }
 
 /// Frame data
 #[derive(Clone, Default)]
 pub struct FrameData {
+    /// Frame number passed to `begin_frame`
     pub frame_number: u64,
+    /// Start time since profiler creation
+    pub start: Duration,
+    /// Wall time from `begin_frame` to `end_frame`, unless set by the caller
     pub total_time: Duration,
+    /// CPU time, as reported by the caller
     pub cpu_time: Duration,
+    /// GPU frame time at the end of the frame
     pub gpu_time: Duration,
+    /// Zones from every thread, in the order they ended
     pub zones: Vec<ZoneData>,
+    /// Draw calls, as reported by the renderer
     pub draw_calls: u32,
+    /// Triangles, as reported by the renderer
     pub triangles: u64,
+    /// Live heap bytes at the end of the frame
     pub memory_used: u64,
+    /// GPU and memory samples taken at the end of the frame
+    pub counters: Vec<CounterSample>,
 }
 
 /// Zone data
 #[derive(Clone)]
 pub struct ZoneData {
+    /// Zone name
     pub name: String,
+    /// Start time since profiler creation
     pub start: Duration,
+    /// Time until the guard was dropped
     pub duration: Duration,
-    pub _depth: usize,
+    /// Number of enclosing zones on the same thread
+    pub depth: usize,
+    /// Profiler id of the recording thread
+    pub thread_id: u64,
+    /// Display color
     pub color: [f32; 3],
 }
 
+/// Value of one series of a counter track
+#[derive(Clone, Debug, PartialEq)]
+pub struct CounterSample {
+    /// Track name, e.g. "Memory (bytes)"
+    pub track: String,
+    /// Series within the track, e.g. "used"
+    pub series: String,
+    /// Sampled value
+    pub value: f64,
+}
+
 /// Zone stats
 pub struct ZoneStats {
+    /// Time summed over all calls
     pub total_time: Duration,
+    /// Number of recorded zones
     pub call_count: u64,
+    /// Shortest zone
     pub min_time: Duration,
+    /// Longest zone
     pub max_time: Duration,
 }
 
 /// GPU profiler
 pub struct GpuProfiler {
+    /// Pass timings of the current frame
     pub queries: Vec<GpuQuery>,
+    /// GPU time of the whole frame
     pub frame_time: Duration,
 }
 
 /// GPU query
 pub struct GpuQuery {
+    /// Pass name
     pub name: String,
+    /// GPU time of the pass
     pub time: Duration,
 }
 
 /// Memory profiler
 pub struct MemoryProfiler {
+    /// Live allocations by category
     pub allocations: HashMap<String, AllocationInfo>,
+    /// Bytes allocated in total
     pub total_allocated: u64,
+    /// Bytes freed in total
     pub total_freed: u64,
+    /// Highest `current_usage` seen
     pub peak_usage: u64,
+    /// Bytes currently allocated
     pub current_usage: u64,
 }
 
 /// Allocation info
 pub struct AllocationInfo {
+    /// Live bytes
     pub size: u64,
+    /// Live allocation count
     pub count: u64,
+    /// Category name
     pub category: String,
 }
 
+/// Zones recorded by any thread, waiting for the end of the frame
+struct SharedZones {
+    /// Key for this profiler's zone depth on each thread
+    id: u64,
+    epoch: Instant,
+    enabled: AtomicBool,
+    zones: Mutex<Vec<ZoneData>>,
+    threads: Mutex<HashMap<u64, String>>,
+}
+
+impl SharedZones {
+    /// Open zones of this profiler on the calling thread
+    fn depth(&self) -> usize {
+        ZONE_DEPTHS
+            .try_with(|depths| {
+                depths.borrow().iter().find(|(id, _)| *id == self.id).map_or(0, |(_, d)| *d)
+            })
+            .unwrap_or(0)
+    }
+
+    fn set_depth(&self, depth: usize) {
+        let _ = ZONE_DEPTHS.try_with(|depths| {
+            let mut depths = depths.borrow_mut();
+            match depths.iter().position(|(id, _)| *id == self.id) {
+                Some(i) if depth == 0 => {
+                    depths.swap_remove(i);
+                },
+                Some(i) => depths[i].1 = depth,
+                None if depth > 0 => depths.push((self.id, depth)),
+                None => {},
+            }
+        });
+    }
+
+    fn record(&self, name: String, start: Instant, duration: Duration, depth: usize) {
+        let thread_id = thread_id();
+        if let Ok(mut threads) = self.threads.lock() {
+            threads.entry(thread_id).or_insert_with(|| {
+                let thread = std::thread::current();
+                thread.name().map_or_else(|| format!("Thread {thread_id}"), str::to_string)
+            });
+        }
+        if let Ok(mut zones) = self.zones.lock() {
+            zones.push(ZoneData {
+                name,
+                start: start.saturating_duration_since(self.epoch),
+                duration,
+                depth,
+                thread_id,
+                color: [0.5, 0.5, 1.0],
+            });
+        }
+    }
+}
+
+/// Cloneable, thread-safe handle for recording zones into a profiler
+#[derive(Clone)]
+pub struct ProfilerHandle {
+    shared: Arc<SharedZones>,
+}
+
+impl ProfilerHandle {
+    /// Start a zone that ends when the guard is dropped
+    pub fn zone(&self, name: &str) -> ZoneGuard {
+        self.start_zone(name, None)
+    }
+
+    /// Start a zone that also tags allocations with its name
+    ///
+    /// Each name becomes a `TrackingAllocator` category, so keep the set of
+    /// names small and fixed.
+    pub fn alloc_zone(&self, name: &'static str) -> ZoneGuard {
+        self.start_zone(name, Some(name))
+    }
+
+    fn start_zone(&self, name: &str, category: Option<&'static str>) -> ZoneGuard {
+        let shared = self.shared.enabled.load(Ordering::Relaxed).then(|| self.shared.clone());
+        let depth = self.shared.depth();
+        if let Some(shared) = &shared {
+            shared.set_depth(depth + 1);
+        }
+        let alloc_scope = category
+            .filter(|_| shared.is_some() && tracking_alloc::is_installed())
+            .map(|c| AllocScope::enter(tracking_alloc::static_category_id(c)));
+        ZoneGuard { shared, name: name.into(), start: Instant::now(), depth, alloc_scope }
+    }
+}
+
 impl Profiler {
+    /// Create an enabled profiler keeping 300 frames
     pub fn new() -> Self {
         Self {
             enabled: true,
-            frame_data: Vec::new(),
+            frame_data: VecDeque::new(),
             current_frame: FrameData::default(),
             zones: HashMap::new(),
             gpu_profiler: GpuProfiler { queries: Vec::new(), frame_time: Duration::ZERO },
             memory_profiler: MemoryProfiler { allocations: HashMap::new(), total_allocated: 0, total_freed: 0, peak_usage: 0, current_usage: 0 },
             max_frames: 300,
+            shared: Arc::new(SharedZones {
+                id: NEXT_PROFILER_ID.fetch_add(1, Ordering::Relaxed),
+                epoch: Instant::now(),
+                enabled: AtomicBool::new(true),
+                zones: Mutex::new(Vec::new()),
+                threads: Mutex::new(HashMap::new()),
+            }),
+            frame_start: Instant::now(),
         }
     }
 
+    /// Keep the last `max_frames` frames
+    #[must_use]
+    pub fn with_max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = max_frames;
+        self
+    }
+
+    /// Handle for recording zones from other threads
+    pub fn handle(&self) -> ProfilerHandle {
+        ProfilerHandle { shared: self.shared.clone() }
+    }
+
+    /// Start recording a frame
     pub fn begin_frame(&mut self, frame: u64) {
-        self.current_frame = FrameData { frame_number: frame, ..Default::default() };
+        self.frame_start = Instant::now();
+        self.shared.enabled.store(self.enabled, Ordering::Relaxed);
+        self.gpu_profiler.queries.clear();
+        self.gpu_profiler.frame_time = Duration::ZERO;
+        let start = self.frame_start.saturating_duration_since(self.shared.epoch);
+        self.current_frame = FrameData { frame_number: frame, start, ..Default::default() };
     }
 
+    /// Collect zones recorded since the last frame and store the frame
     pub fn end_frame(&mut self) {
-        self.frame_data.push(self.current_frame.clone());
-        if self.frame_data.len() > self.max_frames { self.frame_data.remove(0); }
+        let now = Instant::now();
+        let mut frame = std::mem::take(&mut self.current_frame);
+        if frame.total_time.is_zero() {
+            frame.total_time = now - self.frame_start;
+        }
+        let zones = self.shared.zones.lock()
+            .map(|mut zones| std::mem::take(&mut *zones))
+            .unwrap_or_default();
+        for zone in &zones {
+            self.zones.entry(zone.name.clone()).or_insert(ZoneStats { total_time: Duration::ZERO, call_count: 0, min_time: Duration::MAX, max_time: Duration::ZERO })
+                .add(zone.duration);
+        }
+        frame.zones.extend(zones);
+        if tracking_alloc::is_installed() {
+            self.memory_profiler.sync(&HeapSnapshot::take());
+        }
+        frame.gpu_time = self.gpu_profiler.frame_time;
+        frame.memory_used = self.memory_profiler.current_usage;
+        frame.counters = self.sample_counters();
+
+        self.frame_data.push_back(frame);
+        while self.frame_data.len() > self.max_frames { self.frame_data.pop_front(); }
+    }
+
+    /// Start a zone on the calling thread that ends when the guard is dropped
+    pub fn zone(&self, name: &str) -> ZoneGuard {
+        self.handle().zone(name)
     }
 
-    pub fn zone(&mut self, name: &str) -> ZoneGuard {
-        ZoneGuard { profiler: self as *mut _, name: name.into(), start: Instant::now(), _depth: 0 }
+    /// Start a zone that also tags allocations with its name
+    pub fn alloc_zone(&self, name: &'static str) -> ZoneGuard {
+        self.handle().alloc_zone(name)
     }
 
+    /// Record a zone measured elsewhere as ending now
     pub fn record_zone(&mut self, name: &str, duration: Duration) {
-        self.current_frame.zones.push(ZoneData { name: name.into(), start: Duration::ZERO, duration, _depth: 0, color: [0.5, 0.5, 1.0] });
-        self.zones.entry(name.into()).or_insert(ZoneStats { total_time: Duration::ZERO, call_count: 0, min_time: Duration::MAX, max_time: Duration::ZERO })
-            .add(duration);
+        let depth = self.shared.depth();
+        let start = Instant::now().checked_sub(duration).unwrap_or(self.shared.epoch);
+        self.shared.record(name.into(), start, duration, depth);
     }
 
+    /// Mean total time of the stored frames
     pub fn avg_frame_time(&self) -> Duration {
         if self.frame_data.is_empty() { Duration::ZERO }
         else { self.frame_data.iter().map(|f| f.total_time).sum::<Duration>() / self.frame_data.len() as u32 }
     }
 
+    /// Frames per second from `avg_frame_time`
     pub fn fps(&self) -> f32 {
         let avg = self.avg_frame_time().as_secs_f32();
         if avg > 0.0 { 1.0 / avg } else { 0.0 }
     }
+
+    /// GPU and memory counter values for the current frame
+    fn sample_counters(&self) -> Vec<CounterSample> {
+        let sample = |track: &str, series: &str, value: f64| {
+            CounterSample { track: track.into(), series: series.into(), value }
+        };
+        let mut counters = Vec::new();
+
+        let gpu = &self.gpu_profiler;
+        if !gpu.frame_time.is_zero() || !gpu.queries.is_empty() {
+            counters.push(sample("GPU (ms)", "frame", gpu.frame_time.as_secs_f64() * 1000.0));
+            for query in &gpu.queries {
+                counters.push(sample("GPU (ms)", &query.name, query.time.as_secs_f64() * 1000.0));
+            }
+        }
+
+        let memory = &self.memory_profiler;
+        counters.push(sample("Memory (bytes)", "used", memory.current_usage as f64));
+        counters.push(sample("Memory (bytes)", "peak", memory.peak_usage as f64));
+        let mut categories: Vec<_> = memory.allocations.values().collect();
+        categories.sort_by(|a, b| a.category.cmp(&b.category));
+        for info in categories {
+            counters.push(sample("Memory by category (bytes)", &info.category, info.size as f64));
+        }
+        counters
+    }
+
+    /// Build a Chrome trace event document from the stored frames
+    ///
+    /// Zones become complete events on their thread's track, frames become
+    /// events on a "Frames" track and counter samples become counter tracks.
+    /// Timestamps are in microseconds since the profiler was created.
+    pub fn chrome_trace(&self) -> Value {
+        let us = |d: Duration| d.as_secs_f64() * 1_000_000.0;
+        let mut events = vec![
+            json!({"name": "process_name", "ph": "M", "pid": 1, "args": {"name": "Lunaris"}}),
+            json!({
+                "name": "thread_name", "ph": "M", "pid": 1, "tid": FRAME_TRACK_ID,
+                "args": {"name": "Frames"},
+            }),
+        ];
+        let mut threads: Vec<(u64, String)> = self.shared.threads.lock()
+            .map(|t| t.iter().map(|(id, name)| (*id, name.clone())).collect())
+            .unwrap_or_default();
+        threads.sort();
+        for (tid, name) in threads {
+            events.push(json!({
+                "name": "thread_name", "ph": "M", "pid": 1, "tid": tid, "args": {"name": name},
+            }));
+        }
+
+        for frame in &self.frame_data {
+            events.push(json!({
+                "name": format!("Frame {}", frame.frame_number), "cat": "frame", "ph": "X",
+                "ts": us(frame.start), "dur": us(frame.total_time), "pid": 1, "tid": FRAME_TRACK_ID,
+                "args": {"draw_calls": frame.draw_calls, "triangles": frame.triangles},
+            }));
+            for zone in &frame.zones {
+                events.push(json!({
+                    "name": zone.name, "cat": "zone", "ph": "X",
+                    "ts": us(zone.start), "dur": us(zone.duration), "pid": 1, "tid": zone.thread_id,
+                    "args": {"frame": frame.frame_number, "depth": zone.depth},
+                }));
+            }
+            let mut tracks: BTreeMap<&str, Map<String, Value>> = BTreeMap::new();
+            for counter in &frame.counters {
+                tracks.entry(&counter.track).or_default()
+                    .insert(counter.series.clone(), json!(counter.value));
+            }
+            for (track, args) in tracks {
+                events.push(json!({
+                    "name": track, "ph": "C", "ts": us(frame.start + frame.total_time), "pid": 1,
+                    "args": args,
+                }));
+            }
+        }
+        json!({"traceEvents": events, "displayTimeUnit": "ms"})
+    }
+
+    /// Write the stored frames as a Chrome trace JSON file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written
+    pub fn export_chrome_trace(&self, path: impl AsRef<Path>) -> crate::Result<()> {
+        std::fs::write(path, self.chrome_trace().to_string())?;
+        Ok(())
+    }
+}
+
+impl GpuProfiler {
+    /// Record a GPU pass timing for the current frame
+    pub fn record_query(&mut self, name: &str, time: Duration) {
+        self.queries.push(GpuQuery { name: name.into(), time });
+    }
+}
+
+impl MemoryProfiler {
+    /// Replace the counters with a tracking allocator snapshot
+    pub fn sync(&mut self, snapshot: &HeapSnapshot) {
+        self.allocations = snapshot.categories.iter()
+            .map(|c| {
+                let category = c.name.clone();
+                let info = AllocationInfo { size: c.live_bytes, count: c.live_count(), category };
+                (c.name.clone(), info)
+            })
+            .collect();
+        self.total_allocated = snapshot.categories.iter().map(|c| c.allocated_bytes).sum();
+        self.total_freed = snapshot.categories.iter().map(|c| c.freed_bytes).sum();
+        self.current_usage = snapshot.live_bytes;
+        self.peak_usage = snapshot.peak_bytes;
+    }
 }
 
 impl ZoneStats {
@@ -126,6 +471,7 @@ impl ZoneStats {
         self.max_time = self.max_time.max(duration);
     }
 
+    /// Mean zone time
     pub fn avg(&self) -> Duration {
         if self.call_count > 0 { self.total_time / self.call_count as u32 } else { Duration::ZERO }
     }
@@ -133,16 +479,20 @@ impl ZoneStats {
 
 /// Zone guard for RAII profiling
 pub struct ZoneGuard {
-    profiler: *mut Profiler,
+    shared: Option<Arc<SharedZones>>,
     name: String,
     start: Instant,
-    _depth: u32,
+    depth: usize,
+    alloc_scope: Option<AllocScope>,
 }
 
 impl Drop for ZoneGuard {
     fn drop(&mut self) {
+        drop(self.alloc_scope.take());
+        let Some(shared) = self.shared.take() else { return };
         let duration = self.start.elapsed();
-        unsafe { (*self.profiler).record_zone(&self.name, duration); }
+        shared.set_depth(self.depth);
+        shared.record(std::mem::take(&mut self.name), self.start, duration, self.depth);
     }
 }
 
@@ -170,3 +520,128 @@ impl NetworkDebugger {
         Self { packets_sent: 0, packets_received: 0, bytes_sent: 0, bytes_received: 0, latency: Duration::ZERO, packet_loss: 0.0, history: Vec::new() }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn captures_nested_zones_across_threads() {
+        let mut profiler = Profiler::new().with_max_frames(2);
+        for frame in 0..3 {
+            profiler.begin_frame(frame);
+            {
+                let _outer = profiler.zone("update");
+                let _inner = profiler.zone("physics");
+            }
+            let handle = profiler.handle();
+            std::thread::Builder::new()
+                .name("worker".into())
+                .spawn(move || drop(handle.zone("stream")))
+                .unwrap()
+                .join()
+                .unwrap();
+            profiler.gpu_profiler.record_query("shadows", Duration::from_micros(500));
+            profiler.end_frame();
+        }
+
+        assert_eq!(profiler.frame_data.len(), 2);
+        assert_eq!(profiler.frame_data[0].frame_number, 1);
+        let zones = &profiler.frame_data[1].zones;
+        let depth = |name: &str| zones.iter().find(|z| z.name == name).unwrap().depth;
+        assert_eq!((depth("update"), depth("physics"), depth("stream")), (0, 1, 0));
+        let main = zones.iter().find(|z| z.name == "update").unwrap().thread_id;
+        let worker = zones.iter().find(|z| z.name == "stream").unwrap().thread_id;
+        assert_ne!(main, worker);
+        assert_eq!(profiler.zones["physics"].call_count, 3);
+
+        let trace = profiler.chrome_trace();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert!(events.iter().any(|e| e["ph"] == "M" && e["args"]["name"] == "worker"));
+        assert_eq!(events.iter().filter(|e| e["cat"] == "zone").count(), 6);
+        let gpu = events.iter().find(|e| e["ph"] == "C" && e["name"] == "GPU (ms)").unwrap();
+        assert_eq!(gpu["args"]["shadows"], 0.5);
+        assert!(events.iter().any(|e| e["ph"] == "C" && e["name"] == "Memory (bytes)"));
+    }
+
+    #[test]
+    fn gpu_frame_time_does_not_carry_over() {
+        let mut profiler = Profiler::new();
+        profiler.begin_frame(0);
+        profiler.gpu_profiler.frame_time = Duration::from_millis(4);
+        profiler.end_frame();
+        profiler.begin_frame(1);
+        profiler.end_frame();
+
+        assert_eq!(profiler.frame_data[0].gpu_time, Duration::from_millis(4));
+        assert_eq!(profiler.frame_data[1].gpu_time, Duration::ZERO);
+    }
+
+    #[test]
+    fn zone_depth_is_per_profiler_and_thread() {
+        let mut profiler = Profiler::new();
+        let other = Profiler::new();
+        profiler.begin_frame(0);
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = profiler.handle();
+                std::thread::spawn(move || {
+                    let _job = handle.zone("job");
+                    drop(handle.zone("decode"));
+                })
+            })
+            .collect();
+        {
+            let _outer = profiler.zone("update");
+            let _unrelated = other.zone("other");
+            drop(profiler.zone("inner"));
+        }
+        workers.into_iter().for_each(|w| w.join().unwrap());
+        profiler.end_frame();
+
+        let zones = &profiler.frame_data[0].zones;
+        let depths = |name: &str| -> Vec<usize> {
+            zones.iter().filter(|z| z.name == name).map(|z| z.depth).collect()
+        };
+        assert_eq!(depths("update"), [0]);
+        assert_eq!(depths("inner"), [1]);
+        assert_eq!(depths("job"), [0; 4]);
+        assert_eq!(depths("decode"), [1; 4]);
+        let threads: HashSet<u64> = zones.iter().map(|z| z.thread_id).collect();
+        assert_eq!(threads.len(), 5);
+        assert!(zones.iter().all(|z| z.name != "other"));
+    }
+
+    #[test]
+    fn exports_chrome_trace_json() {
+        let mut profiler = Profiler::new();
+        profiler.begin_frame(7);
+        drop(profiler.zone("render"));
+        profiler.end_frame();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("trace.json");
+        profiler.export_chrome_trace(&path).unwrap();
+        let trace: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(trace["displayTimeUnit"], "ms");
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events[0]["ph"], "M");
+        assert_eq!(events[0]["args"]["name"], "Lunaris");
+        let frame = events.iter().find(|e| e["cat"] == "frame").unwrap();
+        assert_eq!(frame["name"], "Frame 7");
+        assert_eq!(frame["tid"], FRAME_TRACK_ID);
+        let zone = events.iter().find(|e| e["cat"] == "zone").unwrap();
+        assert_eq!(zone["name"], "render");
+        assert_eq!(zone["ph"], "X");
+        assert_eq!(zone["pid"], 1);
+        assert_eq!(zone["args"]["frame"], 7);
+        assert!(zone["tid"].as_u64().unwrap() > FRAME_TRACK_ID);
+        let start = |e: &Value| e["ts"].as_f64().unwrap();
+        assert!(start(zone) >= start(frame));
+        assert!(zone["dur"].as_f64().unwrap() <= frame["dur"].as_f64().unwrap());
+        let named = |e: &&Value| e["name"] == "thread_name" && e["tid"] == zone["tid"];
+        assert!(events.iter().any(|e| named(&e)));
+    }
+}
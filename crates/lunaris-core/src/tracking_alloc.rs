@@ -0,0 +1,434 @@
+//! Tracking global allocator
+//!
+//! An opt-in wrapper around the system allocator that attributes every
+//! allocation to a category: the innermost `AllocScope` on the thread, the
+//! active profiler `alloc_zone`, or "untagged". Install it in the executable:
+//!
+//! ```
+//! #[global_allocator]
+//! static ALLOC: lunaris_core::TrackingAllocator = lunaris_core::TrackingAllocator;
+//!
+//! fn main() {
+//!     let _level = lunaris_core::AllocScope::new("level/forest");
+//!     let trees = vec![0u8; 1024];
+//!     let heap = lunaris_core::HeapSnapshot::take();
+//!     assert!(heap.category("level/forest").unwrap().live_bytes >= 1024);
+//! #   drop(trees);
+//! }
+//! ```
+//!
+//! Take a `HeapSnapshot` before and after loading and unloading a level and
+//! `diff` them to find categories that leak.
+
+#![allow(unsafe_code)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Maximum number of categories, including "untagged"
+///
+/// Categories registered past the limit are counted as untagged.
+pub const MAX_CATEGORIES: usize = 256;
+
+/// Name of the category for allocations outside any scope or zone
+pub const UNTAGGED: &str = "untagged";
+
+/// Bytes reserved in front of each allocation for its category
+const HEADER: usize = std::mem::size_of::<u64>();
+
+/// Counters for one category
+struct Counters {
+    live_bytes: AtomicU64,
+    peak_bytes: AtomicU64,
+    allocated_bytes: AtomicU64,
+    freed_bytes: AtomicU64,
+    allocations: AtomicU64,
+    deallocations: AtomicU64,
+}
+
+impl Counters {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ZERO: Self = Self {
+        live_bytes: AtomicU64::new(0),
+        peak_bytes: AtomicU64::new(0),
+        allocated_bytes: AtomicU64::new(0),
+        freed_bytes: AtomicU64::new(0),
+        allocations: AtomicU64::new(0),
+        deallocations: AtomicU64::new(0),
+    };
+
+    fn allocated(&self, size: u64) {
+        let live = self.live_bytes.fetch_add(size, Ordering::Relaxed) + size;
+        self.peak_bytes.fetch_max(live, Ordering::Relaxed);
+        self.allocated_bytes.fetch_add(size, Ordering::Relaxed);
+        self.allocations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn freed(&self, size: u64) {
+        self.live_bytes.fetch_sub(size, Ordering::Relaxed);
+        self.freed_bytes.fetch_add(size, Ordering::Relaxed);
+        self.deallocations.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+static COUNTERS: [Counters; MAX_CATEGORIES] = [Counters::ZERO; MAX_CATEGORIES];
+static TOTAL_LIVE: AtomicU64 = AtomicU64::new(0);
+static TOTAL_PEAK: AtomicU64 = AtomicU64::new(0);
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+/// Category names by id, excluding "untagged" at id 0
+static NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static CURRENT: Cell<u16> = const { Cell::new(0) };
+    /// Ids of static category names already looked up on this thread
+    static CACHED_IDS: RefCell<HashMap<&'static str, u16>> = RefCell::new(HashMap::new());
+}
+
+fn current_category() -> u16 {
+    CURRENT.try_with(Cell::get).unwrap_or(0)
+}
+
+/// Global allocator that tracks live bytes per category
+///
+/// Adds an 8-byte (or alignment-sized) header to every allocation.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    /// Offset from the system allocation to the returned pointer
+    fn offset(layout: Layout) -> usize {
+        layout.align().max(HEADER)
+    }
+
+    fn outer(layout: Layout) -> Option<Layout> {
+        let size = layout.size().checked_add(Self::offset(layout))?;
+        Layout::from_size_align(size, layout.align()).ok()
+    }
+
+    /// Write the header and count the allocation
+    unsafe fn track(base: *mut u8, layout: Layout) -> *mut u8 {
+        if base.is_null() {
+            return base;
+        }
+        let category = current_category();
+        let ptr = base.add(Self::offset(layout));
+        ptr.sub(HEADER).cast::<u64>().write_unaligned(u64::from(category));
+        record_alloc(category, layout.size() as u64);
+        ptr
+    }
+
+    /// Read the header and count the deallocation, returning the base pointer
+    unsafe fn untrack(ptr: *mut u8, layout: Layout) -> *mut u8 {
+        let category = ptr.sub(HEADER).cast::<u64>().read_unaligned() as u16;
+        record_free(category, layout.size() as u64);
+        ptr.sub(Self::offset(layout))
+    }
+}
+
+fn record_alloc(category: u16, size: u64) {
+    // Load first so the hot path does not keep writing a shared cache line
+    if !INSTALLED.load(Ordering::Relaxed) {
+        INSTALLED.store(true, Ordering::Relaxed);
+    }
+    COUNTERS[usize::from(category)].allocated(size);
+    let live = TOTAL_LIVE.fetch_add(size, Ordering::Relaxed) + size;
+    TOTAL_PEAK.fetch_max(live, Ordering::Relaxed);
+}
+
+fn record_free(category: u16, size: u64) {
+    COUNTERS[usize::from(category)].freed(size);
+    TOTAL_LIVE.fetch_sub(size, Ordering::Relaxed);
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match Self::outer(layout) {
+            Some(outer) => Self::track(System.alloc(outer), layout),
+            None => std::ptr::null_mut(),
+        }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        match Self::outer(layout) {
+            Some(outer) => Self::track(System.alloc_zeroed(outer), layout),
+            None => std::ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let base = Self::untrack(ptr, layout);
+        // The layout was valid when allocated, so the outer layout is too
+        System.dealloc(base, Self::outer(layout).unwrap_unchecked());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let offset = Self::offset(layout);
+        let Some(new_outer) = new_size.checked_add(offset) else {
+            return std::ptr::null_mut();
+        };
+        let category = ptr.sub(HEADER).cast::<u64>().read_unaligned() as u16;
+        let base = ptr.sub(offset);
+        let new_base = System.realloc(base, Self::outer(layout).unwrap_unchecked(), new_outer);
+        if new_base.is_null() {
+            return new_base;
+        }
+        // The block keeps its category; count it as freed and allocated again
+        record_free(category, layout.size() as u64);
+        record_alloc(category, new_size as u64);
+        new_base.add(offset)
+    }
+}
+
+/// Check if `TrackingAllocator` is the global allocator
+///
+/// True once it has served an allocation.
+#[must_use]
+pub fn is_installed() -> bool {
+    INSTALLED.load(Ordering::Relaxed)
+}
+
+/// Id of a category, registering it on first use
+///
+/// Returns 0 ("untagged") once `MAX_CATEGORIES` is reached.
+#[must_use]
+pub fn category_id(name: &str) -> u16 {
+    if name == UNTAGGED {
+        return 0;
+    }
+    let Ok(mut names) = NAMES.lock() else {
+        return 0;
+    };
+    if let Some(index) = names.iter().position(|n| n == name) {
+        return (index + 1) as u16;
+    }
+    if names.len() + 1 >= MAX_CATEGORIES {
+        return 0;
+    }
+    names.push(name.to_string());
+    names.len() as u16
+}
+
+/// Id of a category with a static name, cached per thread
+///
+/// Avoids the registry lock of `category_id` on hot paths such as profiler
+/// zones.
+#[must_use]
+pub fn static_category_id(name: &'static str) -> u16 {
+    CACHED_IDS
+        .try_with(|ids| *ids.borrow_mut().entry(name).or_insert_with(|| category_id(name)))
+        .unwrap_or_else(|_| category_id(name))
+}
+
+/// Tag allocations on this thread with a category until the scope is dropped
+///
+/// Scopes nest; dropping one restores the category that was active before.
+#[must_use = "the category is only active while the scope is alive"]
+pub struct AllocScope {
+    previous: u16,
+}
+
+impl AllocScope {
+    /// Enter a category by name
+    pub fn new(category: &str) -> Self {
+        Self::enter(category_id(category))
+    }
+
+    /// Enter a category by id
+    pub fn enter(category: u16) -> Self {
+        let previous = CURRENT.try_with(|c| c.replace(category)).unwrap_or(0);
+        Self { previous }
+    }
+}
+
+impl Drop for AllocScope {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|c| c.set(self.previous));
+    }
+}
+
+/// Run a closure with allocations tagged with a category
+pub fn with_category<R>(category: &str, f: impl FnOnce() -> R) -> R {
+    let _scope = AllocScope::new(category);
+    f()
+}
+
+/// Allocation counters of one category
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryStats {
+    /// Category name
+    pub name: String,
+    /// Bytes currently allocated
+    pub live_bytes: u64,
+    /// Highest `live_bytes` seen
+    pub peak_bytes: u64,
+    /// Bytes allocated in total
+    pub allocated_bytes: u64,
+    /// Bytes freed in total
+    pub freed_bytes: u64,
+    /// Number of allocations
+    pub allocations: u64,
+    /// Number of deallocations
+    pub deallocations: u64,
+}
+
+impl CategoryStats {
+    /// Number of allocations still alive
+    #[must_use]
+    pub fn live_count(&self) -> u64 {
+        self.allocations.saturating_sub(self.deallocations)
+    }
+}
+
+/// Allocation counters of every category at one point in time
+#[derive(Debug, Clone, Default)]
+pub struct HeapSnapshot {
+    /// Categories that have seen allocations, in id order
+    pub categories: Vec<CategoryStats>,
+    /// Bytes currently allocated across all categories
+    pub live_bytes: u64,
+    /// Highest `live_bytes` seen
+    pub peak_bytes: u64,
+}
+
+impl HeapSnapshot {
+    /// Read the current counters
+    #[must_use]
+    pub fn take() -> Self {
+        let names = NAMES.lock().map(|n| n.clone()).unwrap_or_default();
+        let categories = std::iter::once(UNTAGGED.to_string())
+            .chain(names)
+            .zip(COUNTERS.iter())
+            .map(|(name, c)| CategoryStats {
+                name,
+                live_bytes: c.live_bytes.load(Ordering::Relaxed),
+                peak_bytes: c.peak_bytes.load(Ordering::Relaxed),
+                allocated_bytes: c.allocated_bytes.load(Ordering::Relaxed),
+                freed_bytes: c.freed_bytes.load(Ordering::Relaxed),
+                allocations: c.allocations.load(Ordering::Relaxed),
+                deallocations: c.deallocations.load(Ordering::Relaxed),
+            })
+            .filter(|c| c.allocations > 0)
+            .collect();
+        Self {
+            categories,
+            live_bytes: TOTAL_LIVE.load(Ordering::Relaxed),
+            peak_bytes: TOTAL_PEAK.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Get a category by name
+    #[must_use]
+    pub fn category(&self, name: &str) -> Option<&CategoryStats> {
+        self.categories.iter().find(|c| c.name == name)
+    }
+
+    /// Changes since an earlier snapshot
+    ///
+    /// Categories whose live bytes and allocation count did not change are
+    /// left out. The rest are sorted by growth in live bytes, largest first.
+    #[must_use]
+    pub fn diff(&self, earlier: &Self) -> HeapDiff {
+        let before: HashMap<&str, &CategoryStats> =
+            earlier.categories.iter().map(|c| (c.name.as_str(), c)).collect();
+        let mut categories: Vec<CategoryDelta> = self
+            .categories
+            .iter()
+            .map(|c| {
+                let old = before.get(c.name.as_str()).copied().cloned().unwrap_or_default();
+                CategoryDelta {
+                    name: c.name.clone(),
+                    live_bytes: c.live_bytes as i64 - old.live_bytes as i64,
+                    live_count: c.live_count() as i64 - old.live_count() as i64,
+                    allocations: c.allocations - old.allocations,
+                }
+            })
+            .filter(|d| d.live_bytes != 0 || d.live_count != 0 || d.allocations != 0)
+            .collect();
+        categories.sort_by(|a, b| b.live_bytes.cmp(&a.live_bytes).then(a.name.cmp(&b.name)));
+        HeapDiff {
+            categories,
+            live_bytes: self.live_bytes as i64 - earlier.live_bytes as i64,
+        }
+    }
+}
+
+/// Change of one category between two snapshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryDelta {
+    /// Category name
+    pub name: String,
+    /// Change in live bytes
+    pub live_bytes: i64,
+    /// Change in live allocation count
+    pub live_count: i64,
+    /// Allocations made in between
+    pub allocations: u64,
+}
+
+/// Difference between two heap snapshots
+#[derive(Debug, Clone, Default)]
+pub struct HeapDiff {
+    /// Changed categories, by descending live byte growth
+    pub categories: Vec<CategoryDelta>,
+    /// Change in total live bytes
+    pub live_bytes: i64,
+}
+
+impl HeapDiff {
+    /// Categories that hold more live memory than before
+    pub fn grown(&self) -> impl Iterator<Item = &CategoryDelta> {
+        self.categories.iter().filter(|c| c.live_bytes > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_categories_and_diffs_snapshots() {
+        let allocator = TrackingAllocator;
+        let layout = Layout::from_size_align(100, 32).unwrap();
+        let before = HeapSnapshot::take();
+
+        let (kept, freed) = with_category("test/level", || unsafe {
+            let kept = allocator.alloc_zeroed(layout);
+            let freed = allocator.alloc(layout);
+            (kept, allocator.realloc(freed, layout, 300))
+        });
+        assert_eq!(kept as usize % 32, 0);
+        assert_eq!(unsafe { *kept.add(99) }, 0);
+        let after = HeapSnapshot::take();
+        let level = after.category("test/level").unwrap();
+        assert_eq!(level.live_bytes, 400);
+        assert_eq!(level.peak_bytes, 400);
+
+        // The block is freed from another category but still counted as its own
+        with_category("test/other", || unsafe { allocator.dealloc(freed, layout_of(300)) });
+        let end = HeapSnapshot::take();
+        let diff = end.diff(&before);
+        let level = diff.categories.iter().find(|c| c.name == "test/level").unwrap();
+        assert_eq!((level.live_bytes, level.live_count), (100, 1));
+        assert!(diff.grown().any(|c| c.name == "test/level"));
+        assert!(end.category("test/other").is_none());
+
+        unsafe { allocator.dealloc(kept, layout) };
+        assert_eq!(HeapSnapshot::take().category("test/level").unwrap().live_bytes, 0);
+    }
+
+    #[test]
+    fn static_ids_match_registered_ids() {
+        let id = static_category_id("test/static");
+        assert_ne!(id, 0);
+        assert_eq!(static_category_id("test/static"), id);
+        assert_eq!(category_id("test/static"), id);
+        assert_eq!(static_category_id(UNTAGGED), 0);
+    }
+
+    fn layout_of(size: usize) -> Layout {
+        Layout::from_size_align(size, 32).unwrap()
+    }
+}
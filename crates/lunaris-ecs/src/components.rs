@@ -1,7 +1,9 @@
 //! Core ECS components for game development
 
+use crate::scene::{ColliderShapeData, ComponentData};
 use bevy_ecs::prelude::*;
 use lunaris_core::math::{Color, Vec2, Vec3};
+use std::collections::HashMap;
 
 /// Entity name component
 #[derive(Component, Debug, Clone)]
@@ -298,3 +300,113 @@ pub struct Velocity3D {
     /// Angular velocity 
     pub angular: Vec3,
 }
+
+/// Stable scene identifier of an entity, kept across save and load
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SceneEntityId(pub u64);
+
+/// Entity tags
+#[derive(Component, Debug, Clone, Default)]
+pub struct Tags(pub Vec<String>);
+
+impl Tags {
+    /// Check if has a tag
+    #[must_use]
+    pub fn has(&self, tag: &str) -> bool {
+        self.0.iter().any(|t| t == tag)
+    }
+}
+
+/// Rigidbody simulation type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RigidBodyType {
+    /// Moved by the simulation
+    #[default]
+    Dynamic,
+    /// Moved by game code, pushes dynamic bodies
+    Kinematic,
+    /// Never moves
+    Static,
+}
+
+impl RigidBodyType {
+    /// Name used in scene files
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Dynamic => "dynamic",
+            Self::Kinematic => "kinematic",
+            Self::Static => "static",
+        }
+    }
+
+    /// Parse a scene file name, case insensitive
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "dynamic" => Some(Self::Dynamic),
+            "kinematic" => Some(Self::Kinematic),
+            "static" => Some(Self::Static),
+            _ => None,
+        }
+    }
+}
+
+/// Rigidbody component
+#[derive(Component, Debug, Clone, Copy)]
+pub struct RigidBody {
+    /// Simulation type
+    pub body_type: RigidBodyType,
+    /// Mass in kilograms
+    pub mass: f32,
+    /// Gravity multiplier
+    pub gravity_scale: f32,
+}
+
+impl Default for RigidBody {
+    fn default() -> Self {
+        Self {
+            body_type: RigidBodyType::Dynamic,
+            mass: 1.0,
+            gravity_scale: 1.0,
+        }
+    }
+}
+
+/// Collider component
+#[derive(Component, Debug, Clone)]
+pub struct Collider {
+    /// Collision shape
+    pub shape: ColliderShapeData,
+    /// Reports overlaps without a collision response
+    pub is_trigger: bool,
+}
+
+/// Audio source component
+#[derive(Component, Debug, Clone)]
+pub struct AudioSource {
+    /// Audio clip path
+    pub clip: String,
+    /// Volume (0.0 - 1.0)
+    pub volume: f32,
+    /// Loop playback
+    pub looping: bool,
+    /// Start playing when spawned
+    pub play_on_start: bool,
+}
+
+/// Script component
+#[derive(Component, Debug, Clone, Default)]
+pub struct Script {
+    /// Script path
+    pub path: String,
+    /// Exposed script properties
+    pub properties: HashMap<String, serde_json::Value>,
+}
+
+/// Scene components with no live counterpart, written back unchanged on save
+///
+/// Holds sprites until their texture path is resolved by the asset system, and
+/// custom extension components.
+#[derive(Component, Debug, Clone, Default)]
+pub struct SceneExtras(pub Vec<ComponentData>);
@@ -11,19 +11,32 @@
 //! - Name - Entity naming
 //! - Parent/Children - Scene hierarchy
 //! - Visibility - Rendering visibility
+//! - RigidBody / Collider / AudioSource - Physics and audio scene data
+//!
+//! User components implement `ReflectComponent` and are registered in a
+//! `ComponentRegistry` to be saved in scenes and edited in tools.
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
 pub mod components;
 pub mod hierarchy;
+pub mod migration;
+pub mod prefab;
+pub mod reflect;
 pub mod scene;
 pub mod systems;
 
 pub use bevy_ecs::prelude::*;
 pub use components::*;
 pub use hierarchy::*;
-pub use scene::{ComponentData, EntityData, Prefab, Scene, SceneId, SceneManager};
+pub use migration::{MigrationReport, SceneMigrator, SCENE_FORMAT_VERSION};
+pub use prefab::{PrefabLink, PropertyOverride};
+pub use reflect::{
+    ComponentRegistration, ComponentRegistry, ComponentSchema, FieldKind, FieldSchema,
+    ReflectComponent,
+};
+pub use scene::{ColliderShapeData, ComponentData, EntityData, Prefab, Scene, SceneId, SceneManager};
 
 /// Re-export bevy_ecs for direct access
 pub mod ecs {
@@ -0,0 +1,581 @@
+//! Scene format versions and migrations
+//!
+//! Scenes record the format version they were saved with. Loading an older
+//! scene runs every migration step between its version and
+//! [`SCENE_FORMAT_VERSION`] on the untyped document before it is deserialized,
+//! so layout changes to `ComponentData` do not break existing files.
+//!
+//! When changing the scene layout, bump [`SCENE_FORMAT_VERSION`] and add a
+//! step from the previous version to `builtin_migrations`.
+
+use crate::scene::Scene;
+use lunaris_core::{Error, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Scene format version written by this engine version
+pub const SCENE_FORMAT_VERSION: u32 = 1;
+
+/// Migration step, editing a scene document in place
+pub type MigrationFn = fn(&mut Value) -> Result<()>;
+
+/// A registered migration step
+#[derive(Debug, Clone)]
+pub struct SceneMigration {
+    /// Version the step upgrades from, to the next version
+    pub from: u32,
+    /// What the step changes
+    pub description: String,
+    /// Step function
+    pub migrate: MigrationFn,
+}
+
+/// Engine migration steps, in version order
+fn builtin_migrations() -> Vec<SceneMigration> {
+    vec![SceneMigration {
+        from: 0,
+        description: "Normalize rigidbody body types to lowercase".to_string(),
+        migrate: normalize_body_types,
+    }]
+}
+
+/// Iterate the component documents of a scene document
+fn components_mut(scene: &mut Value) -> impl Iterator<Item = &mut Value> {
+    scene
+        .get_mut("entities")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.get_mut("components").and_then(Value::as_array_mut))
+        .flatten()
+}
+
+fn normalize_body_types(scene: &mut Value) -> Result<()> {
+    for component in components_mut(scene) {
+        if component.get("type").and_then(Value::as_str) != Some("Rigidbody") {
+            continue;
+        }
+        if let Some(body_type) = component.get_mut("body_type") {
+            if let Some(name) = body_type.as_str() {
+                *body_type = Value::String(name.to_ascii_lowercase());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Scene file encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneFormat {
+    /// Pretty printed JSON
+    Json,
+    /// MessagePack with named fields
+    Binary,
+}
+
+impl SceneFormat {
+    /// Guess the encoding of scene file contents
+    #[must_use]
+    pub fn detect(data: &[u8]) -> Self {
+        match data.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{') => Self::Json,
+            _ => Self::Binary,
+        }
+    }
+}
+
+/// Result of migrating a directory of scenes
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Upgraded scenes and the version they had
+    pub migrated: Vec<(PathBuf, u32)>,
+    /// Scenes already at the current version
+    pub current: Vec<PathBuf>,
+    /// Files that are not scenes
+    pub skipped: Vec<PathBuf>,
+    /// Scenes that failed to migrate, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Upgrades scene documents to the current format version
+#[derive(Debug, Clone)]
+pub struct SceneMigrator {
+    migrations: Vec<SceneMigration>,
+    /// File extensions scanned by [`SceneMigrator::migrate_dir`]
+    pub extensions: Vec<String>,
+}
+
+impl Default for SceneMigrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneMigrator {
+    /// Create a migrator with the engine's migration steps
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            migrations: builtin_migrations(),
+            extensions: vec!["json".to_string(), "scene".to_string()],
+        }
+    }
+
+    /// Register an extra step, run after the engine steps for the same version
+    ///
+    /// Games use this to upgrade their custom component data alongside engine
+    /// format changes.
+    pub fn register(&mut self, from: u32, description: impl Into<String>, migrate: MigrationFn) {
+        self.migrations.push(SceneMigration {
+            from,
+            description: description.into(),
+            migrate,
+        });
+    }
+
+    /// Registered steps
+    #[must_use]
+    pub fn migrations(&self) -> &[SceneMigration] {
+        &self.migrations
+    }
+
+    /// Upgrade a scene document in place, returning its original version
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the document is newer than this engine or a step fails
+    pub fn migrate(&self, scene: &mut Value) -> Result<u32> {
+        let version = format_version(scene);
+        if version > SCENE_FORMAT_VERSION {
+            return Err(Error::Asset(format!(
+                "Scene format version {} is newer than supported version {}",
+                version, SCENE_FORMAT_VERSION
+            )));
+        }
+
+        for from in version..SCENE_FORMAT_VERSION {
+            for step in self.migrations.iter().filter(|m| m.from == from) {
+                (step.migrate)(scene).map_err(|e| {
+                    Error::Asset(format!(
+                        "Scene migration from version {} ({}) failed: {}",
+                        from, step.description, e
+                    ))
+                })?;
+            }
+        }
+
+        let root = scene
+            .as_object_mut()
+            .ok_or_else(|| Error::Asset("Scene document is not an object".to_string()))?;
+        let metadata = root
+            .entry("metadata")
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let Some(metadata) = metadata.as_object_mut() {
+            metadata.insert(
+                "format_version".to_string(),
+                Value::from(SCENE_FORMAT_VERSION),
+            );
+        }
+        Ok(version)
+    }
+
+    /// Upgrade and deserialize a scene document
+    ///
+    /// # Errors
+    ///
+    /// Returns error if migration or deserialization fails
+    pub fn from_value(&self, mut scene: Value) -> Result<Scene> {
+        self.migrate(&mut scene)?;
+        serde_json::from_value(scene).map_err(|e| Error::Asset(e.to_string()))
+    }
+
+    /// Read a scene from file contents in either encoding
+    ///
+    /// Returns the scene and the version it was stored with. MessagePack
+    /// scenes written before versioning store structs as arrays without field
+    /// names; they are decoded with the frozen version 0 layout and then
+    /// migrated like any other document.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if decoding or migration fails
+    pub fn read(&self, data: &[u8]) -> Result<(Scene, u32)> {
+        let value: Value = match SceneFormat::detect(data) {
+            SceneFormat::Json => {
+                serde_json::from_slice(data).map_err(|e| Error::Asset(e.to_string()))?
+            },
+            SceneFormat::Binary => {
+                rmp_serde::from_slice(data).map_err(|e| Error::Asset(e.to_string()))?
+            },
+        };
+        let value = if value.is_array() {
+            let legacy: v0::Scene =
+                rmp_serde::from_slice(data).map_err(|e| Error::Asset(e.to_string()))?;
+            serde_json::to_value(legacy).map_err(|e| Error::Asset(e.to_string()))?
+        } else {
+            value
+        };
+        let version = format_version(&value);
+        Ok((self.from_value(value)?, version))
+    }
+
+    /// Load and upgrade a scene file in either encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns error if reading, decoding or migration fails
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Scene> {
+        let data = std::fs::read(path)?;
+        self.read(&data).map(|(scene, _)| scene)
+    }
+
+    /// Upgrade one scene file in place, keeping its encoding
+    ///
+    /// Returns the version the file had. Files already at the current version
+    /// are not rewritten, and nothing is written when `dry_run` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read, migrated or written
+    pub fn migrate_file(&self, path: impl AsRef<Path>, dry_run: bool) -> Result<u32> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let (scene, version) = self.read(&data)?;
+        if version < SCENE_FORMAT_VERSION && !dry_run {
+            match SceneFormat::detect(&data) {
+                SceneFormat::Json => scene.save_json(path)?,
+                SceneFormat::Binary => scene.save_binary(path)?,
+            }
+        }
+        Ok(version)
+    }
+
+    /// Upgrade every scene file under a directory
+    ///
+    /// Files with one of [`SceneMigrator::extensions`] are checked; those that
+    /// do not look like scenes are skipped. A failing file is reported and
+    /// does not stop the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the directory cannot be walked
+    pub fn migrate_dir(&self, dir: impl AsRef<Path>, dry_run: bool) -> Result<MigrationReport> {
+        let mut files = Vec::new();
+        collect_files(dir.as_ref(), &self.extensions, &mut files)?;
+        files.sort();
+
+        let mut report = MigrationReport::default();
+        for path in files {
+            let data = match std::fs::read(&path) {
+                Ok(data) => data,
+                Err(e) => {
+                    report.failed.push((path, e.to_string()));
+                    continue;
+                },
+            };
+            if !looks_like_scene(&data) {
+                report.skipped.push(path);
+                continue;
+            }
+            match self.migrate_file(&path, dry_run) {
+                Ok(version) if version < SCENE_FORMAT_VERSION => {
+                    report.migrated.push((path, version));
+                },
+                Ok(_) => report.current.push(path),
+                Err(e) => report.failed.push((path, e.to_string())),
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Format version stored in a scene document, 0 if it predates versioning
+fn format_version(scene: &Value) -> u32 {
+    scene
+        .get("metadata")
+        .and_then(|m| m.get("format_version"))
+        .and_then(Value::as_u64)
+        .map_or(0, |v| u32::try_from(v).unwrap_or(u32::MAX))
+}
+
+/// Check for the top-level fields every scene has
+fn looks_like_scene(data: &[u8]) -> bool {
+    let value: Option<Value> = match SceneFormat::detect(data) {
+        SceneFormat::Json => serde_json::from_slice(data).ok(),
+        SceneFormat::Binary => rmp_serde::from_slice(data).ok(),
+    };
+    match value {
+        Some(Value::Object(fields)) => fields.contains_key("entities") && fields.contains_key("id"),
+        Some(Value::Array(fields)) => {
+            rmp_serde::from_slice::<v0::Scene>(data).is_ok() && !fields.is_empty()
+        },
+        _ => false,
+    }
+}
+
+/// Scene layout before format versioning
+///
+/// Compact MessagePack stores structs as arrays, so old files can only be
+/// decoded with the field order they were written with. These types are
+/// frozen at that layout and must not follow changes to the live types.
+mod v0 {
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct Scene {
+        pub id: u64,
+        pub name: String,
+        pub entities: Vec<EntityData>,
+        pub metadata: SceneMetadata,
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    pub(super) struct SceneMetadata {
+        pub author: Option<String>,
+        pub created: Option<String>,
+        pub modified: Option<String>,
+        pub description: Option<String>,
+        pub tags: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct EntityData {
+        pub id: u64,
+        pub name: String,
+        pub parent: Option<u64>,
+        pub tags: Vec<String>,
+        pub components: Vec<ComponentData>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub prefab: Option<PrefabLink>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct PrefabLink {
+        pub source: String,
+        pub entity: u64,
+        pub instance: u64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub overrides: Vec<PropertyOverride>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub(super) struct PropertyOverride {
+        pub component: String,
+        pub field: Option<String>,
+        pub value: serde_json::Value,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    pub(super) enum ComponentData {
+        Transform2D { position: [f32; 2], rotation: f32, scale: [f32; 2] },
+        Transform3D { position: [f32; 3], rotation: [f32; 3], scale: [f32; 3] },
+        Sprite { texture: String, color: [f32; 4], flip_x: bool, flip_y: bool },
+        Camera { is_active: bool, priority: i32, clear_color: Option<[f32; 4]> },
+        Camera2D { zoom: f32 },
+        Camera3D { fov: f32, near: f32, far: f32 },
+        Rigidbody { body_type: String, mass: f32, gravity_scale: f32 },
+        Collider { shape: ColliderShapeData, is_trigger: bool },
+        AudioSource { clip: String, volume: f32, looping: bool, play_on_start: bool },
+        Script { path: String, properties: HashMap<String, serde_json::Value> },
+        Custom { name: String, data: serde_json::Value },
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    pub(super) enum ColliderShapeData {
+        Circle { radius: f32 },
+        Rectangle { width: f32, height: f32 },
+        Box3D { width: f32, height: f32, depth: f32 },
+        Capsule { height: f32, radius: f32 },
+    }
+}
+
+fn collect_files(dir: &Path, extensions: &[String], files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, extensions, files)?;
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{ComponentData, EntityData};
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn legacy_scene() -> Value {
+        json!({
+            "id": 7,
+            "name": "Legacy",
+            "metadata": { "author": null, "created": null, "modified": null,
+                          "description": null, "tags": [] },
+            "entities": [{
+                "id": 1,
+                "name": "Crate",
+                "parent": null,
+                "tags": [],
+                "components": [
+                    { "type": "Rigidbody", "body_type": "Kinematic", "mass": 2.0,
+                      "gravity_scale": 1.0 },
+                    { "type": "Custom", "name": "Hp", "data": { "value": 3 } }
+                ]
+            }]
+        })
+    }
+
+    fn rename_hp(scene: &mut Value) -> Result<()> {
+        for component in components_mut(scene) {
+            if component.get("name") == Some(&json!("Hp")) {
+                component["name"] = json!("Health");
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn upgrades_legacy_documents() {
+        let mut migrator = SceneMigrator::new();
+        migrator.register(0, "Rename Hp to Health", rename_hp);
+        let scene = migrator.from_value(legacy_scene()).unwrap();
+
+        assert_eq!(scene.metadata.format_version, SCENE_FORMAT_VERSION);
+        let crate_entity = &scene.entities[0];
+        assert!(matches!(
+            &crate_entity.components[0],
+            ComponentData::Rigidbody { body_type, .. } if body_type == "kinematic"
+        ));
+        assert!(matches!(
+            &crate_entity.components[1],
+            ComponentData::Custom { name, .. } if name == "Health"
+        ));
+
+        let mut future = legacy_scene();
+        future["metadata"]["format_version"] = json!(SCENE_FORMAT_VERSION + 1);
+        assert!(migrator.from_value(future).is_err());
+    }
+
+    #[test]
+    fn loaders_run_registered_steps() {
+        let mut migrator = SceneMigrator::new();
+        migrator.register(0, "Rename Hp to Health", rename_hp);
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        let json = dir.join("legacy.json");
+        std::fs::write(&json, legacy_scene().to_string()).unwrap();
+        let binary = dir.join("legacy.scene");
+        std::fs::write(&binary, rmp_serde::to_vec_named(&legacy_scene()).unwrap()).unwrap();
+
+        let custom_name = |scene: &Scene| match &scene.entities[0].components[1] {
+            ComponentData::Custom { name, .. } => name.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(custom_name(&Scene::load_json(&json).unwrap()), "Hp");
+        assert_eq!(custom_name(&Scene::load_json_with(&json, &migrator).unwrap()), "Health");
+        let scene = Scene::load_binary_with(&binary, &migrator).unwrap();
+        assert_eq!(custom_name(&scene), "Health");
+    }
+
+    #[test]
+    fn reads_both_binary_layouts() {
+        let mut scene = Scene::new("Binary");
+        scene.add_entity(EntityData::new("Crate"));
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("level.scene");
+        scene.save_binary(&path).unwrap();
+        let loaded = Scene::load_binary(&path).unwrap();
+        assert_eq!(loaded.entities[0].name, "Crate");
+
+        let legacy = v0::Scene {
+            id: 9,
+            name: "Legacy".to_string(),
+            entities: vec![v0::EntityData {
+                id: 1,
+                name: "Crate".to_string(),
+                parent: None,
+                tags: Vec::new(),
+                components: vec![v0::ComponentData::Rigidbody {
+                    body_type: "Kinematic".to_string(),
+                    mass: 2.0,
+                    gravity_scale: 1.0,
+                }],
+                prefab: None,
+            }],
+            metadata: v0::SceneMetadata::default(),
+        };
+        let compact = rmp_serde::to_vec(&legacy).unwrap();
+        let (loaded, version) = SceneMigrator::new().read(&compact).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(loaded.metadata.format_version, SCENE_FORMAT_VERSION);
+        assert_eq!(loaded.name, "Legacy");
+        assert!(matches!(
+            &loaded.entities[0].components[0],
+            ComponentData::Rigidbody { body_type, .. } if body_type == "kinematic"
+        ));
+        assert!(looks_like_scene(&compact));
+    }
+
+    #[test]
+    fn migrates_directories() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("levels")).unwrap();
+        let legacy = dir.join("levels/old.json");
+        std::fs::write(&legacy, serde_json::to_string(&legacy_scene()).unwrap()).unwrap();
+        Scene::new("Current")
+            .save_binary(dir.join("new.scene"))
+            .unwrap();
+        std::fs::write(dir.join("settings.json"), r#"{ "volume": 1 }"#).unwrap();
+        let mut broken = legacy_scene();
+        broken["entities"][0]["components"][0]["mass"] = json!("heavy");
+        std::fs::write(dir.join("broken.json"), broken.to_string()).unwrap();
+
+        let migrator = SceneMigrator::new();
+        let report = migrator.migrate_dir(&dir, true).unwrap();
+        assert_eq!(report.migrated, vec![(legacy.clone(), 0)]);
+        assert_eq!(
+            format_version(&serde_json::from_slice(&std::fs::read(&legacy).unwrap()).unwrap()),
+            0
+        );
+
+        let report = migrator.migrate_dir(&dir, false).unwrap();
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.current, vec![dir.join("new.scene")]);
+        assert_eq!(report.skipped, vec![dir.join("settings.json")]);
+        assert_eq!(report.failed.len(), 1);
+        let upgraded = Scene::load_json(&legacy).unwrap();
+        assert_eq!(upgraded.metadata.format_version, SCENE_FORMAT_VERSION);
+
+        let report = migrator.migrate_dir(&dir, false).unwrap();
+        assert!(report.migrated.is_empty());
+        assert_eq!(report.current.len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_files_do_not_stop_the_batch() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        let legacy = dir.join("old.json");
+        std::fs::write(&legacy, serde_json::to_string(&legacy_scene()).unwrap()).unwrap();
+        let dangling = dir.join("dangling.json");
+        std::os::unix::fs::symlink(dir.join("missing.json"), &dangling).unwrap();
+
+        let report = SceneMigrator::new().migrate_dir(dir, false).unwrap();
+        assert_eq!(report.migrated, vec![(legacy, 0)]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, dangling);
+    }
+}
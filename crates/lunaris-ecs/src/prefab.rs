@@ -0,0 +1,800 @@
+//! Prefab instances, overrides and propagation
+//!
+//! Entities instantiated from a prefab carry a [`PrefabLink`] back to the
+//! prefab entity they came from, together with the properties the instance
+//! overrides. Syncing an instance rebuilds it from the prefab and re-applies
+//! those overrides, so prefab edits reach every instance without losing local
+//! changes. A prefab can itself contain instances of other prefabs; those are
+//! synced into the outer prefab first, which then syncs into scenes.
+
+use crate::scene::{ComponentData, EntityData, Prefab, Scene};
+use bevy_ecs::prelude::*;
+use lunaris_core::{id::Id, Error, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Override key for entity-level properties (`name` and `tags`)
+pub const ENTITY_PROPERTIES: &str = "Entity";
+
+/// Link from an instance entity to the prefab entity it was created from
+#[derive(Component, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefabLink {
+    /// Source prefab name
+    pub source: String,
+    /// ID of the matching entity in the prefab
+    pub entity: u64,
+    /// ID of the instance root entity, shared by the whole instance
+    pub instance: u64,
+    /// Properties this entity overrides
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overrides: Vec<PropertyOverride>,
+}
+
+impl PrefabLink {
+    /// Find the override for a property
+    #[must_use]
+    pub fn get_override(&self, component: &str, field: Option<&str>) -> Option<&PropertyOverride> {
+        self.overrides
+            .iter()
+            .find(|o| o.component == component && o.field.as_deref() == field)
+    }
+
+    /// Add or replace an override
+    pub fn set_override(&mut self, property: PropertyOverride) {
+        self.overrides
+            .retain(|o| o.component != property.component || o.field != property.field);
+        self.overrides.push(property);
+    }
+
+    /// Remove an override, returning whether it existed
+    pub fn remove_override(&mut self, component: &str, field: Option<&str>) -> bool {
+        let before = self.overrides.len();
+        self.overrides
+            .retain(|o| o.component != component || o.field.as_deref() != field);
+        self.overrides.len() != before
+    }
+}
+
+/// A property value an instance keeps instead of the prefab's
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PropertyOverride {
+    /// Component key (see [`ComponentData::key`]) or [`ENTITY_PROPERTIES`]
+    pub component: String,
+    /// Field within the component
+    ///
+    /// `None` overrides the whole component, which is how components added to
+    /// an instance are kept.
+    pub field: Option<String>,
+    /// Overriding value
+    pub value: Value,
+}
+
+impl PropertyOverride {
+    /// Override one field of a component
+    #[must_use]
+    pub fn field(component: impl Into<String>, field: impl Into<String>, value: Value) -> Self {
+        Self {
+            component: component.into(),
+            field: Some(field.into()),
+            value,
+        }
+    }
+}
+
+impl ComponentData {
+    /// Name used to address the component in overrides
+    ///
+    /// This is the variant name, or the component name for custom components.
+    #[must_use]
+    pub fn key(&self) -> &str {
+        match self {
+            Self::Transform2D { .. } => "Transform2D",
+            Self::Transform3D { .. } => "Transform3D",
+            Self::Sprite { .. } => "Sprite",
+            Self::Camera { .. } => "Camera",
+            Self::Camera2D { .. } => "Camera2D",
+            Self::Camera3D { .. } => "Camera3D",
+            Self::Rigidbody { .. } => "Rigidbody",
+            Self::Collider { .. } => "Collider",
+            Self::AudioSource { .. } => "AudioSource",
+            Self::Script { .. } => "Script",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Serialized fields, without the type tag
+    ///
+    /// For custom components these are the fields of `data`, or `None` if the
+    /// data is not an object.
+    #[must_use]
+    pub fn fields(&self) -> Option<serde_json::Map<String, Value>> {
+        match self {
+            Self::Custom { data, .. } => data.as_object().cloned(),
+            _ => {
+                let Ok(Value::Object(mut fields)) = serde_json::to_value(self) else {
+                    return None;
+                };
+                fields.remove("type");
+                Some(fields)
+            },
+        }
+    }
+
+    /// Replace one serialized field
+    ///
+    /// Custom components are captured with every field of their registered
+    /// schema, so only fields already in their data can be set.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the component has no such field or the value has the
+    /// wrong type
+    pub fn set_field(&mut self, field: &str, value: Value) -> Result<()> {
+        if let Self::Custom { data, name } = self {
+            let slot = data
+                .as_object_mut()
+                .and_then(|fields| fields.get_mut(field))
+                .ok_or_else(|| Error::Asset(format!("{} has no field {}", name, field)))?;
+            *slot = value;
+            return Ok(());
+        }
+
+        let mut json = serde_json::to_value(&*self).map_err(|e| Error::Asset(e.to_string()))?;
+        let fields = json
+            .as_object_mut()
+            .filter(|f| f.contains_key(field) && field != "type")
+            .ok_or_else(|| Error::Asset(format!("{} has no field {}", self.key(), field)))?;
+        fields.insert(field.to_string(), value);
+        *self = serde_json::from_value(json)
+            .map_err(|e| Error::Asset(format!("{}.{}: {}", self.key(), field, e)))?;
+        Ok(())
+    }
+}
+
+impl EntityData {
+    /// Find a component by override key
+    #[must_use]
+    pub fn component(&self, key: &str) -> Option<&ComponentData> {
+        self.components.iter().find(|c| c.key() == key)
+    }
+
+    /// Read a property addressed like an override
+    #[must_use]
+    pub fn property(&self, component: &str, field: Option<&str>) -> Option<Value> {
+        if component == ENTITY_PROPERTIES {
+            return match field? {
+                "name" => Some(Value::String(self.name.clone())),
+                "tags" => serde_json::to_value(&self.tags).ok(),
+                _ => None,
+            };
+        }
+        let data = self.component(component)?;
+        match field {
+            Some(field) => data.fields()?.remove(field),
+            None => serde_json::to_value(data).ok(),
+        }
+    }
+
+    /// Write a property addressed like an override
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the property does not exist or the value does not fit
+    pub fn set_property(&mut self, property: &PropertyOverride) -> Result<()> {
+        let value = property.value.clone();
+        let invalid = |e: serde_json::Error| Error::Asset(e.to_string());
+        if property.component == ENTITY_PROPERTIES {
+            match property.field.as_deref() {
+                Some("name") => self.name = serde_json::from_value(value).map_err(invalid)?,
+                Some("tags") => self.tags = serde_json::from_value(value).map_err(invalid)?,
+                _ => return Err(Error::Asset("Unknown entity property".to_string())),
+            }
+            return Ok(());
+        }
+
+        let Some(field) = &property.field else {
+            let component: ComponentData = serde_json::from_value(value).map_err(invalid)?;
+            match self
+                .components
+                .iter_mut()
+                .find(|c| c.key() == property.component)
+            {
+                Some(existing) => *existing = component,
+                None => self.components.push(component),
+            }
+            return Ok(());
+        };
+        self.components
+            .iter_mut()
+            .find(|c| c.key() == property.component)
+            .ok_or_else(|| Error::Asset(format!("Entity has no {}", property.component)))?
+            .set_field(field, value)
+    }
+}
+
+/// Properties of an instance entity that differ from its prefab entity
+///
+/// Components removed from the instance are not tracked and return on sync.
+fn diff_properties(source: &EntityData, instance: &EntityData) -> Vec<PropertyOverride> {
+    let mut overrides = Vec::new();
+    if source.name != instance.name {
+        overrides.push(PropertyOverride::field(
+            ENTITY_PROPERTIES,
+            "name",
+            Value::String(instance.name.clone()),
+        ));
+    }
+    if source.tags != instance.tags {
+        overrides.push(PropertyOverride::field(
+            ENTITY_PROPERTIES,
+            "tags",
+            serde_json::to_value(&instance.tags).unwrap_or_default(),
+        ));
+    }
+
+    for component in &instance.components {
+        let key = component.key();
+        let whole = || PropertyOverride {
+            component: key.to_string(),
+            field: None,
+            value: serde_json::to_value(component).unwrap_or_default(),
+        };
+        let Some(original) = source.component(key) else {
+            overrides.push(whole());
+            continue;
+        };
+        match (original.fields(), component.fields()) {
+            (Some(original), Some(fields)) => {
+                for (field, value) in fields {
+                    if original.get(&field) != Some(&value) {
+                        overrides.push(PropertyOverride::field(key, field, value));
+                    }
+                }
+            },
+            _ => {
+                if serde_json::to_value(original).ok() != serde_json::to_value(component).ok() {
+                    overrides.push(whole());
+                }
+            },
+        }
+    }
+    overrides
+}
+
+/// Prefab entity with an instance link and its overrides applied
+///
+/// Overrides that no longer apply, for example on a component the prefab
+/// removed, are kept but skipped.
+fn resolve(source: &EntityData, link: PrefabLink) -> EntityData {
+    let mut entity = source.clone();
+    for property in &link.overrides {
+        let _ = entity.set_property(property);
+    }
+    entity.prefab = Some(link);
+    entity
+}
+
+/// Check if an entity belongs to an instance of a prefab
+fn in_instance(entity: &EntityData, prefab: &str, instance: u64) -> bool {
+    entity
+        .prefab
+        .as_ref()
+        .is_some_and(|l| l.source == prefab && l.instance == instance)
+}
+
+/// Instance roots of a prefab in an entity list
+fn instance_roots(entities: &[EntityData], prefab: &Prefab) -> Vec<u64> {
+    entities
+        .iter()
+        .filter_map(|e| e.prefab.as_ref())
+        .filter(|l| l.source == prefab.name && l.entity == prefab.root.id)
+        .map(|l| l.instance)
+        .collect()
+}
+
+/// Rebuild one instance from its prefab, keeping overrides
+///
+/// Entities added to the prefab are created, entities removed from it are
+/// deleted and their remaining children moved to the instance root.
+fn sync_instance(entities: &mut Vec<EntityData>, prefab: &Prefab, instance: u64) {
+    let existing: HashMap<u64, usize> = entities
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| in_instance(e, &prefab.name, instance))
+        .map(|(i, e)| (e.prefab.as_ref().map_or(0, |l| l.entity), i))
+        .collect();
+
+    let mut ids: HashMap<u64, u64> = HashMap::with_capacity(prefab.children.len() + 1);
+    ids.insert(prefab.root.id, instance);
+    for child in &prefab.children {
+        let id = existing
+            .get(&child.id)
+            .map_or_else(|| Id::new().raw(), |&i| entities[i].id);
+        ids.insert(child.id, id);
+    }
+
+    for source in std::iter::once(&prefab.root).chain(&prefab.children) {
+        let id = ids[&source.id];
+        let (link, parent) = match existing.get(&source.id) {
+            Some(&i) => (entities[i].prefab.clone(), entities[i].parent),
+            None => (None, None),
+        };
+        let link = link.unwrap_or_else(|| PrefabLink {
+            source: prefab.name.clone(),
+            entity: source.id,
+            instance,
+            overrides: Vec::new(),
+        });
+        let mut entity = resolve(source, link);
+        entity.id = id;
+        entity.parent = if source.id == prefab.root.id {
+            parent
+        } else {
+            Some(
+                source
+                    .parent
+                    .and_then(|p| ids.get(&p).copied())
+                    .unwrap_or(instance),
+            )
+        };
+        match existing.get(&source.id) {
+            Some(&i) => entities[i] = entity,
+            None => entities.push(entity),
+        }
+    }
+
+    let kept: HashSet<u64> = ids.values().copied().collect();
+    let removed: HashSet<u64> = entities
+        .iter()
+        .filter(|e| in_instance(e, &prefab.name, instance) && !kept.contains(&e.id))
+        .map(|e| e.id)
+        .collect();
+    if removed.is_empty() {
+        return;
+    }
+    entities.retain(|e| !removed.contains(&e.id));
+    for entity in entities.iter_mut() {
+        if entity.parent.is_some_and(|p| removed.contains(&p)) {
+            entity.parent = Some(instance);
+        }
+    }
+}
+
+/// Sync every instance of a prefab in an entity list
+fn sync_all(entities: &mut Vec<EntityData>, prefab: &Prefab) -> usize {
+    let roots = instance_roots(entities, prefab);
+    for &instance in &roots {
+        sync_instance(entities, prefab, instance);
+    }
+    roots.len()
+}
+
+/// Find an instance entity, returning its index and link
+fn linked(entities: &[EntityData], id: u64) -> Result<(usize, &PrefabLink)> {
+    let index = entities
+        .iter()
+        .position(|e| e.id == id)
+        .ok_or_else(|| Error::Asset(format!("Entity not found: {}", id)))?;
+    let link = entities[index]
+        .prefab
+        .as_ref()
+        .ok_or_else(|| Error::Asset(format!("Entity {} is not a prefab instance", id)))?;
+    Ok((index, link))
+}
+
+impl Prefab {
+    /// Check if this prefab contains instances of another prefab
+    #[must_use]
+    pub fn contains_instances_of(&self, prefab: &str) -> bool {
+        std::iter::once(&self.root)
+            .chain(&self.children)
+            .any(|e| e.prefab.as_ref().is_some_and(|l| l.source == prefab))
+    }
+
+    /// Sync nested instances of another prefab, returning how many were found
+    pub fn sync_nested(&mut self, nested: &Prefab) -> usize {
+        let mut entities = Vec::with_capacity(self.children.len() + 1);
+        entities.push(self.root.clone());
+        entities.append(&mut self.children);
+        let count = sync_all(&mut entities, nested);
+        let root_index = entities
+            .iter()
+            .position(|e| e.id == self.root.id)
+            .unwrap_or(0);
+        self.root = entities.remove(root_index);
+        self.children = entities;
+        count
+    }
+}
+
+impl Scene {
+    /// Instantiate a prefab into the scene, returning the instance root ID
+    pub fn instantiate_prefab(&mut self, prefab: &Prefab, parent: Option<u64>) -> u64 {
+        let (mut root, children) = prefab.instantiate();
+        root.parent = parent;
+        let id = root.id;
+        self.entities.push(root);
+        self.entities.extend(children);
+        id
+    }
+
+    /// Root IDs of every instance of a prefab
+    #[must_use]
+    pub fn prefab_instances(&self, prefab: &Prefab) -> Vec<u64> {
+        instance_roots(&self.entities, prefab)
+    }
+
+    /// Change a property of an instance entity and record it as an override
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entity is not a prefab instance or the property
+    /// does not exist
+    pub fn set_override(&mut self, entity: u64, property: PropertyOverride) -> Result<()> {
+        let (index, _) = linked(&self.entities, entity)?;
+        let entity = &mut self.entities[index];
+        entity.set_property(&property)?;
+        if let Some(link) = &mut entity.prefab {
+            link.set_override(property);
+        }
+        Ok(())
+    }
+
+    /// Recompute overrides of all instances of a prefab from their current state
+    ///
+    /// Use after editing instances directly, for example after capturing a
+    /// scene back from a world.
+    pub fn record_overrides(&mut self, prefab: &Prefab) {
+        let sources: HashMap<u64, &EntityData> = std::iter::once(&prefab.root)
+            .chain(&prefab.children)
+            .map(|e| (e.id, e))
+            .collect();
+        for entity in &mut self.entities {
+            let Some(source) = entity
+                .prefab
+                .as_ref()
+                .filter(|l| l.source == prefab.name)
+                .and_then(|l| sources.get(&l.entity))
+            else {
+                continue;
+            };
+            let overrides = diff_properties(source, entity);
+            if let Some(link) = &mut entity.prefab {
+                link.overrides = overrides;
+            }
+        }
+    }
+
+    /// Rebuild every instance of a prefab, keeping overrides
+    ///
+    /// Returns the number of instances updated.
+    pub fn sync_prefab(&mut self, prefab: &Prefab) -> usize {
+        sync_all(&mut self.entities, prefab)
+    }
+
+    /// Drop overrides of an instance entity and restore the prefab values
+    ///
+    /// With `property` set only that override is reverted, otherwise all of
+    /// the entity's overrides are.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entity is not an instance of this prefab
+    pub fn revert_to_prefab(
+        &mut self,
+        entity: u64,
+        prefab: &Prefab,
+        property: Option<(&str, Option<&str>)>,
+    ) -> Result<()> {
+        let (index, link) = linked(&self.entities, entity)?;
+        if link.source != prefab.name {
+            return Err(Error::Asset(format!(
+                "Entity {} is not an instance of {}",
+                entity, prefab.name
+            )));
+        }
+        let instance = link.instance;
+        if let Some(link) = &mut self.entities[index].prefab {
+            match property {
+                Some((component, field)) => {
+                    link.remove_override(component, field);
+                },
+                None => link.overrides.clear(),
+            }
+        }
+        sync_instance(&mut self.entities, prefab, instance);
+        Ok(())
+    }
+
+    /// Write an instance's overrides and added entities into its prefab
+    ///
+    /// The instance root keeps its transform overrides, since they place the
+    /// instance rather than describe the prefab. Sync other instances
+    /// afterwards, for example with [`SceneManager::update_prefab`].
+    ///
+    /// [`SceneManager::update_prefab`]: crate::scene::SceneManager::update_prefab
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entity is not an instance of this prefab
+    pub fn apply_to_prefab(&mut self, entity: u64, prefab: &mut Prefab) -> Result<()> {
+        let (_, link) = linked(&self.entities, entity)?;
+        if link.source != prefab.name {
+            return Err(Error::Asset(format!(
+                "Entity {} is not an instance of {}",
+                entity, prefab.name
+            )));
+        }
+        let instance = link.instance;
+        let is_placement =
+            |o: &PropertyOverride| o.component == "Transform2D" || o.component == "Transform3D";
+
+        // Scene ID to prefab ID for everything that ends up in the prefab
+        let mut ids: HashMap<u64, u64> = HashMap::new();
+        for entity in &mut self.entities {
+            let Some(link) = entity
+                .prefab
+                .as_mut()
+                .filter(|l| l.source == prefab.name && l.instance == instance)
+            else {
+                continue;
+            };
+            ids.insert(entity.id, link.entity);
+            let is_root = link.entity == prefab.root.id;
+            let target = if is_root {
+                Some(&mut prefab.root)
+            } else {
+                prefab.children.iter_mut().find(|c| c.id == link.entity)
+            };
+            let Some(target) = target else {
+                continue;
+            };
+            link.overrides.retain(|o| {
+                if is_root && is_placement(o) {
+                    return true;
+                }
+                let _ = target.set_property(o);
+                false
+            });
+        }
+
+        // Entities added under the instance become prefab children
+        loop {
+            let added: Vec<usize> = self
+                .entities
+                .iter()
+                .enumerate()
+                .filter(|(_, e)| !ids.contains_key(&e.id))
+                .filter(|(_, e)| e.parent.is_some_and(|p| ids.contains_key(&p)))
+                .map(|(i, _)| i)
+                .collect();
+            if added.is_empty() {
+                break;
+            }
+            for index in added {
+                let entity = &mut self.entities[index];
+                let mut child = entity.clone();
+                child.id = Id::new().raw();
+                child.parent = entity.parent.map(|p| ids[&p]);
+                ids.insert(entity.id, child.id);
+                entity.prefab = Some(PrefabLink {
+                    source: prefab.name.clone(),
+                    entity: child.id,
+                    instance,
+                    overrides: Vec::new(),
+                });
+                prefab.children.push(child);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::SceneManager;
+    use serde_json::json;
+
+    fn turret() -> Prefab {
+        let mut root = EntityData::new("Turret");
+        root.add_component(ComponentData::Transform3D {
+            position: [0.0, 0.0, 0.0],
+            rotation: [0.0, 0.0, 0.0],
+            scale: [1.0, 1.0, 1.0],
+        });
+        root.add_component(ComponentData::Custom {
+            name: "Weapon".to_string(),
+            data: json!({ "damage": 10, "range": 20.0 }),
+        });
+        let mut prefab = Prefab::new("Turret", root);
+        let mut barrel = EntityData::new("Barrel");
+        barrel.add_component(ComponentData::Rigidbody {
+            body_type: "kinematic".to_string(),
+            mass: 5.0,
+            gravity_scale: 1.0,
+        });
+        prefab.add_child(barrel);
+        prefab
+    }
+
+    fn weapon(scene: &Scene, id: u64, field: &str) -> Value {
+        scene
+            .find_entity(id)
+            .unwrap()
+            .property("Weapon", Some(field))
+            .unwrap()
+    }
+
+    #[test]
+    fn edits_propagate_and_keep_overrides() {
+        let mut prefab = turret();
+        let mut scene = Scene::new("Base");
+        let a = scene.instantiate_prefab(&prefab, None);
+        let b = scene.instantiate_prefab(&prefab, None);
+        scene
+            .set_override(a, PropertyOverride::field("Weapon", "range", json!(50.0)))
+            .unwrap();
+        assert!(scene
+            .set_override(a, PropertyOverride::field("Weapon", "ammo", json!(1)))
+            .is_err());
+
+        prefab.root.components[1]
+            .set_field("damage", json!(25))
+            .unwrap();
+        prefab.root.components[1]
+            .set_field("range", json!(30.0))
+            .unwrap();
+        prefab.add_child(EntityData::new("Scope"));
+        assert_eq!(scene.sync_prefab(&prefab), 2);
+
+        assert_eq!(weapon(&scene, a, "damage"), json!(25));
+        assert_eq!(weapon(&scene, a, "range"), json!(50.0));
+        assert_eq!(weapon(&scene, b, "range"), json!(30.0));
+        assert_eq!(scene.children_of(a).len(), 2);
+        assert_eq!(scene.children_of(b).len(), 2);
+        assert_eq!(scene.entities.len(), 6);
+
+        prefab.children.remove(0);
+        scene.sync_prefab(&prefab);
+        assert_eq!(scene.children_of(a).len(), 1);
+        assert_eq!(scene.children_of(a)[0].name, "Scope");
+    }
+
+    #[test]
+    fn revert_and_apply() {
+        let prefab = turret();
+        let mut manager = SceneManager::new();
+        manager.add_prefab(prefab.clone());
+        let id = manager.create_scene("Base");
+        let scene = manager.get_scene_mut(id).unwrap();
+        let a = scene.instantiate_prefab(&prefab, None);
+        let b = scene.instantiate_prefab(&prefab, None);
+        let barrel = scene.children_of(a)[0].id;
+
+        scene
+            .set_override(
+                barrel,
+                PropertyOverride::field(ENTITY_PROPERTIES, "name", json!("Cannon")),
+            )
+            .unwrap();
+        scene
+            .set_override(
+                barrel,
+                PropertyOverride::field("Rigidbody", "mass", json!(8.0)),
+            )
+            .unwrap();
+        scene
+            .revert_to_prefab(barrel, &prefab, Some((ENTITY_PROPERTIES, Some("name"))))
+            .unwrap();
+        assert_eq!(scene.find_entity(barrel).unwrap().name, "Barrel");
+        assert_eq!(
+            scene
+                .find_entity(barrel)
+                .unwrap()
+                .prefab
+                .as_ref()
+                .unwrap()
+                .overrides
+                .len(),
+            1
+        );
+
+        scene
+            .set_override(
+                a,
+                PropertyOverride::field("Transform3D", "position", json!([4.0, 0.0, 0.0])),
+            )
+            .unwrap();
+        scene.add_entity(EntityData::new("Light").with_parent(barrel));
+
+        let mut edited = prefab.clone();
+        scene.apply_to_prefab(a, &mut edited).unwrap();
+        let root = scene.find_entity(a).unwrap().prefab.clone().unwrap();
+        assert_eq!(root.overrides.len(), 1);
+        assert_eq!(
+            edited.root.property("Transform3D", Some("position")),
+            Some(json!([0.0, 0.0, 0.0]))
+        );
+        assert_eq!(
+            edited.children[0].property("Rigidbody", Some("mass")),
+            Some(json!(8.0))
+        );
+        assert_eq!(edited.children[1].name, "Light");
+        assert_eq!(edited.children[1].parent, Some(edited.children[0].id));
+
+        assert_eq!(manager.update_prefab(edited), 2);
+        let scene = manager.get_scene(id).unwrap();
+        let other_barrel = scene.children_of(b)[0].id;
+        assert_eq!(scene.children_of(other_barrel)[0].name, "Light");
+        assert_eq!(scene.entities.len(), 6);
+        assert_eq!(
+            scene
+                .find_entity(other_barrel)
+                .unwrap()
+                .property("Rigidbody", Some("mass")),
+            Some(json!(8.0))
+        );
+    }
+
+    #[test]
+    fn nested_prefab_edits_reach_scenes() {
+        let inner = turret();
+        let mut outer = Prefab::new("Fort", EntityData::new("Fort"));
+        let (mut turret_root, turret_children) = inner.instantiate();
+        turret_root.parent = Some(outer.root.id);
+        outer.add_child(turret_root);
+        outer.children.extend(turret_children);
+        assert!(outer.contains_instances_of("Turret"));
+
+        let mut manager = SceneManager::new();
+        manager.add_prefab(inner.clone());
+        manager.add_prefab(outer.clone());
+        let id = manager.create_scene("Level");
+        let fort = manager
+            .get_scene_mut(id)
+            .unwrap()
+            .instantiate_prefab(&outer, None);
+
+        let mut edited = inner;
+        edited.root.components[1]
+            .set_field("damage", json!(99))
+            .unwrap();
+        assert_eq!(manager.update_prefab(edited), 1);
+
+        let nested = &manager.get_prefab("Fort").unwrap().children[0];
+        assert_eq!(nested.property("Weapon", Some("damage")), Some(json!(99)));
+        let scene = manager.get_scene(id).unwrap();
+        let turret = scene.children_of(fort)[0];
+        assert_eq!(turret.property("Weapon", Some("damage")), Some(json!(99)));
+        assert_eq!(turret.prefab.as_ref().unwrap().source, "Fort");
+    }
+
+    #[test]
+    fn record_overrides_after_world_round_trip() {
+        let prefab = turret();
+        let mut scene = Scene::new("Base");
+        let a = scene.instantiate_prefab(&prefab, None);
+
+        let mut world = World::new();
+        let spawned = scene.spawn_into(&mut world).unwrap();
+        world
+            .get_mut::<crate::Transform3D>(spawned[&a])
+            .unwrap()
+            .position
+            .x = 7.0;
+        let mut captured = Scene::from_world("Base", &world);
+        captured.record_overrides(&prefab);
+
+        let link = captured.find_entity(a).unwrap().prefab.clone().unwrap();
+        assert_eq!(link.overrides.len(), 1);
+        assert_eq!(link.overrides[0].value, json!([7.0, 0.0, 0.0]));
+        assert!(captured.children_of(a)[0]
+            .prefab
+            .as_ref()
+            .unwrap()
+            .overrides
+            .is_empty());
+    }
+}
@@ -0,0 +1,490 @@
+//! Component registry for saving and editing user components
+//!
+//! Components register a name, a field schema and serde-backed read and write
+//! functions. Scenes and prefabs store registered components as
+//! `ComponentData::Custom`, and tools edit them field by field through the
+//! schema without knowing the concrete type.
+
+use bevy_ecs::prelude::*;
+use bevy_ecs::world::{EntityRef, EntityWorldMut};
+use lunaris_core::{Error, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Kind of value stored in a component field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldKind {
+    /// Boolean
+    Bool,
+    /// Integer
+    Int,
+    /// Floating point number
+    Float,
+    /// Text
+    String,
+    /// Two floats, as an array or an `{x, y}` object
+    Vec2,
+    /// Three floats, as an array or an `{x, y, z}` object
+    Vec3,
+    /// Four floats, as an array or an `{x, y, z, w}` object
+    Vec4,
+    /// RGBA color, as an array or an `{r, g, b, a}` object
+    Color,
+    /// One of a fixed set of names
+    Enum(Vec<String>),
+    /// Asset path of the given asset type
+    Asset(String),
+    /// Any other value, edited as raw JSON
+    Any,
+}
+
+/// Schema of one component field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSchema {
+    /// Field name, as serialized
+    pub name: String,
+    /// Value kind
+    pub kind: FieldKind,
+    /// Allowed numeric range
+    pub range: Option<(f64, f64)>,
+    /// Help text
+    pub tooltip: String,
+}
+
+impl FieldSchema {
+    /// Create a field schema
+    #[must_use]
+    pub fn new(name: impl Into<String>, kind: FieldKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            range: None,
+            tooltip: String::new(),
+        }
+    }
+
+    /// Limit a numeric field to a range
+    #[must_use]
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    /// Set help text
+    #[must_use]
+    pub fn with_tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = tooltip.into();
+        self
+    }
+
+    /// Check that a value matches the field's kind and range
+    ///
+    /// The range applies to numbers and to each component of vectors and
+    /// colors.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the value has the wrong shape or is out of range
+    pub fn validate(&self, value: &Value) -> Result<()> {
+        let numbers = match &self.kind {
+            FieldKind::Bool => value.is_boolean().then(Vec::new),
+            FieldKind::Int => (value.is_i64() || value.is_u64()).then(|| vec![value]),
+            FieldKind::Float => value.is_number().then(|| vec![value]),
+            FieldKind::String | FieldKind::Asset(_) => value.is_string().then(Vec::new),
+            FieldKind::Enum(names) => {
+                let known = value.as_str().is_some_and(|v| names.iter().any(|n| n == v));
+                known.then(Vec::new)
+            },
+            FieldKind::Vec2 => vector(value, &["x", "y"]),
+            FieldKind::Vec3 => vector(value, &["x", "y", "z"]),
+            FieldKind::Vec4 => vector(value, &["x", "y", "z", "w"]),
+            FieldKind::Color => vector(value, &["r", "g", "b", "a"]),
+            FieldKind::Any => Some(Vec::new()),
+        };
+        let numbers = numbers.ok_or_else(|| {
+            Error::Asset(format!(
+                "{}: expected {:?}, got {}",
+                self.name, self.kind, value
+            ))
+        })?;
+        if let Some((min, max)) = self.range {
+            if numbers
+                .iter()
+                .filter_map(|n| n.as_f64())
+                .any(|n| n < min || n > max)
+            {
+                return Err(Error::Asset(format!(
+                    "{}: {} is outside {}..={}",
+                    self.name, value, min, max
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Components of a vector stored as an array or an object with named keys,
+/// `None` unless every component is a number. Objects may carry extra keys.
+fn vector<'a>(value: &'a Value, keys: &[&str]) -> Option<Vec<&'a Value>> {
+    let components: Vec<&Value> = match value {
+        Value::Array(items) if items.len() == keys.len() => items.iter().collect(),
+        Value::Object(fields) => keys.iter().map(|k| fields.get(*k)).collect::<Option<_>>()?,
+        _ => return None,
+    };
+    components
+        .iter()
+        .all(|c| c.is_number())
+        .then_some(components)
+}
+
+/// Schema of a registered component
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentSchema {
+    /// Unique component name, used in scene files
+    pub name: String,
+    /// Editable fields
+    pub fields: Vec<FieldSchema>,
+}
+
+impl ComponentSchema {
+    /// Create a schema without fields
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Add a field
+    #[must_use]
+    pub fn with_field(mut self, field: FieldSchema) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Find a field by name
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+}
+
+/// A component that can be saved in scenes and edited generically
+///
+/// The serde representation must be a JSON object whose keys are the schema
+/// field names.
+pub trait ReflectComponent: Component + Serialize + DeserializeOwned {
+    /// Describe the component
+    fn schema() -> ComponentSchema;
+}
+
+/// Type-erased access to a registered component
+pub struct ComponentRegistration {
+    /// Component schema
+    pub schema: ComponentSchema,
+    read: fn(&EntityRef<'_>) -> Option<Result<Value>>,
+    write: fn(&mut EntityWorldMut<'_>, Value) -> Result<()>,
+    remove: fn(&mut EntityWorldMut<'_>),
+}
+
+impl std::fmt::Debug for ComponentRegistration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ComponentRegistration")
+            .field("schema", &self.schema)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ComponentRegistration {
+    /// Serialize the component of an entity, if it has one
+    #[must_use]
+    pub fn read(&self, entity: &EntityRef<'_>) -> Option<Result<Value>> {
+        (self.read)(entity)
+    }
+
+    /// Insert or replace the component from its serialized form
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the value does not deserialize into the component
+    pub fn write(&self, entity: &mut EntityWorldMut<'_>, value: Value) -> Result<()> {
+        (self.write)(entity, value)
+    }
+
+    /// Remove the component from an entity
+    pub fn remove(&self, entity: &mut EntityWorldMut<'_>) {
+        (self.remove)(entity);
+    }
+}
+
+fn read_component<T: ReflectComponent>(entity: &EntityRef<'_>) -> Option<Result<Value>> {
+    entity
+        .get::<T>()
+        .map(|c| serde_json::to_value(c).map_err(|e| Error::Asset(e.to_string())))
+}
+
+fn write_component<T: ReflectComponent>(
+    entity: &mut EntityWorldMut<'_>,
+    value: Value,
+) -> Result<()> {
+    let component: T = serde_json::from_value(value).map_err(|e| Error::Asset(e.to_string()))?;
+    entity.insert(component);
+    Ok(())
+}
+
+fn remove_component<T: ReflectComponent>(entity: &mut EntityWorldMut<'_>) {
+    entity.remove::<T>();
+}
+
+/// Registry of reflected components
+///
+/// Insert it as a world resource so `Scene::spawn_into` and
+/// `Scene::from_world` pick up registered components.
+#[derive(Resource, Debug, Default)]
+pub struct ComponentRegistry {
+    registrations: Vec<ComponentRegistration>,
+    by_name: HashMap<String, usize>,
+}
+
+impl ComponentRegistry {
+    /// Create an empty registry
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a component type, replacing any registration with its name
+    pub fn register<T: ReflectComponent>(&mut self) {
+        let registration = ComponentRegistration {
+            schema: T::schema(),
+            read: read_component::<T>,
+            write: write_component::<T>,
+            remove: remove_component::<T>,
+        };
+        match self.by_name.get(&registration.schema.name) {
+            Some(&index) => self.registrations[index] = registration,
+            None => {
+                self.by_name
+                    .insert(registration.schema.name.clone(), self.registrations.len());
+                self.registrations.push(registration);
+            },
+        }
+    }
+
+    /// Get a registration by component name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&ComponentRegistration> {
+        self.by_name.get(name).map(|&i| &self.registrations[i])
+    }
+
+    /// Check if a component name is registered
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    /// Iterate registrations in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentRegistration> {
+        self.registrations.iter()
+    }
+
+    /// Number of registered components
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    /// Check if empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+
+    /// Insert a component by name from its serialized form
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the name is not registered or the data does not match
+    pub fn insert(&self, entity: &mut EntityWorldMut<'_>, name: &str, data: Value) -> Result<()> {
+        let registration = self
+            .get(name)
+            .ok_or_else(|| Error::Asset(format!("Component not registered: {}", name)))?;
+        registration
+            .write(entity, data)
+            .map_err(|e| Error::Asset(format!("{}: {}", name, e)))
+    }
+
+    /// Read one field of a component on an entity
+    #[must_use]
+    pub fn get_field(
+        &self,
+        world: &World,
+        entity: Entity,
+        component: &str,
+        field: &str,
+    ) -> Option<Value> {
+        let value = self
+            .get(component)?
+            .read(&world.get_entity(entity)?)?
+            .ok()?;
+        value.get(field).cloned()
+    }
+
+    /// Replace one field of a component on an entity
+    ///
+    /// The value is checked against the field's [`FieldSchema`], then the
+    /// component is serialized, patched and written back, so the new value
+    /// must also deserialize into the field's type.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the entity, component or field does not exist, or the
+    /// value has the wrong type or is out of range
+    pub fn set_field(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        component: &str,
+        field: &str,
+        value: Value,
+    ) -> Result<()> {
+        let registration = self
+            .get(component)
+            .ok_or_else(|| Error::Asset(format!("Component not registered: {}", component)))?;
+        let schema = registration
+            .schema
+            .field(field)
+            .ok_or_else(|| Error::Asset(format!("{} has no field {}", component, field)))?;
+        schema.validate(&value)?;
+        let mut entity = world
+            .get_entity_mut(entity)
+            .ok_or_else(|| Error::Asset(format!("Entity not found: {:?}", entity)))?;
+        let mut data = registration
+            .read(&EntityRef::from(&entity))
+            .ok_or_else(|| Error::Asset(format!("Entity has no {}", component)))??;
+        let object = data.as_object_mut().ok_or_else(|| {
+            Error::Asset(format!("{} does not serialize to an object", component))
+        })?;
+        object.insert(field.to_string(), value);
+        registration.write(&mut entity, data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Component, Serialize, Deserialize, Debug, PartialEq)]
+    struct Turret {
+        range: f32,
+        target: String,
+    }
+
+    impl ReflectComponent for Turret {
+        fn schema() -> ComponentSchema {
+            ComponentSchema::new("Turret")
+                .with_field(FieldSchema::new("range", FieldKind::Float).with_range(0.0, 100.0))
+                .with_field(FieldSchema::new("target", FieldKind::String))
+        }
+    }
+
+    #[test]
+    fn edit_fields_through_registry() {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Turret>();
+        registry.register::<Turret>();
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.get("Turret").unwrap().schema.fields.len(), 2);
+
+        let mut world = World::new();
+        let entity = world
+            .spawn(Turret {
+                range: 10.0,
+                target: "player".to_string(),
+            })
+            .id();
+
+        registry
+            .set_field(
+                &mut world,
+                entity,
+                "Turret",
+                "range",
+                serde_json::json!(25.0),
+            )
+            .unwrap();
+        assert_eq!(
+            registry.get_field(&world, entity, "Turret", "range"),
+            Some(serde_json::json!(25.0))
+        );
+        assert_eq!(world.get::<Turret>(entity).unwrap().range, 25.0);
+
+        let wrong_type = registry.set_field(
+            &mut world,
+            entity,
+            "Turret",
+            "range",
+            serde_json::json!("far"),
+        );
+        assert!(wrong_type.is_err());
+        assert!(registry
+            .set_field(
+                &mut world,
+                entity,
+                "Turret",
+                "speed",
+                serde_json::json!(1.0)
+            )
+            .is_err());
+        assert_eq!(world.get::<Turret>(entity).unwrap().range, 25.0);
+        let out_of_range = registry.set_field(
+            &mut world,
+            entity,
+            "Turret",
+            "range",
+            serde_json::json!(250.0),
+        );
+        assert!(out_of_range.is_err());
+        assert_eq!(world.get::<Turret>(entity).unwrap().range, 25.0);
+
+        registry
+            .get("Turret")
+            .unwrap()
+            .remove(&mut world.entity_mut(entity));
+        assert!(world.get::<Turret>(entity).is_none());
+    }
+
+    #[test]
+    fn validates_values_against_the_field_schema() {
+        use serde_json::json;
+
+        let int = FieldSchema::new("ammo", FieldKind::Int).with_range(0.0, 10.0);
+        assert!(int.validate(&json!(4)).is_ok());
+        assert!(int.validate(&json!(4.5)).is_err());
+        assert!(int.validate(&json!(11)).is_err());
+
+        let offset = FieldSchema::new("offset", FieldKind::Vec3).with_range(-1.0, 1.0);
+        assert!(offset.validate(&json!([0.0, 1.0, -1.0])).is_ok());
+        assert!(offset
+            .validate(&json!({ "x": 0.0, "y": 0.5, "z": 0.0, "space": "local" }))
+            .is_ok());
+        assert!(offset.validate(&json!([0.0, 2.0, 0.0])).is_err());
+        assert!(offset.validate(&json!([0.0, 1.0])).is_err());
+        assert!(offset
+            .validate(&json!({ "x": 0.0, "y": "up", "z": 0.0 }))
+            .is_err());
+
+        let state = FieldSchema::new("state", FieldKind::Enum(vec!["idle".to_string()]));
+        assert!(state.validate(&json!("idle")).is_ok());
+        assert!(state.validate(&json!("attack")).is_err());
+        assert!(FieldSchema::new("flag", FieldKind::Bool)
+            .validate(&json!(1))
+            .is_err());
+        assert!(FieldSchema::new("data", FieldKind::Any)
+            .validate(&json!([1, "a"]))
+            .is_ok());
+    }
+}
@@ -2,12 +2,23 @@
 //!
 //! Provides serialization and management of game worlds.
 
+use crate::components::{
+    AudioSource, Camera, Camera2DSettings, Camera3DSettings, Collider, GlobalTransform3D, Name,
+    RigidBody, RigidBodyType, SceneEntityId, SceneExtras, Script, Tags, Transform2D, Transform3D,
+};
+use crate::hierarchy::{Children, Parent};
+use crate::migration::{SceneMigrator, SCENE_FORMAT_VERSION};
+use crate::prefab::PrefabLink;
+use crate::reflect::ComponentRegistry;
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::{EntityRef, EntityWorldMut, World};
 use lunaris_core::{
     id::Id,
+    math::{Color, Vec2, Vec3},
     Result,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 /// Scene identifier
@@ -54,6 +65,9 @@ pub struct SceneMetadata {
     pub description: Option<String>,
     /// Tags
     pub tags: Vec<String>,
+    /// Format version the scene was saved with, 0 before versioning
+    #[serde(default)]
+    pub format_version: u32,
 }
 
 /// Serialized entity data
@@ -69,6 +83,9 @@ pub struct EntityData {
     pub tags: Vec<String>,
     /// Components
     pub components: Vec<ComponentData>,
+    /// Source prefab, if the entity is part of a prefab instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefab: Option<PrefabLink>,
 }
 
 /// Serialized component data
@@ -162,7 +179,10 @@ impl Scene {
             id: SceneId::new(),
             name: name.into(),
             entities: Vec::new(),
-            metadata: SceneMetadata::default(),
+            metadata: SceneMetadata {
+                format_version: SCENE_FORMAT_VERSION,
+                ..SceneMetadata::default()
+            },
         }
     }
 
@@ -198,6 +218,51 @@ impl Scene {
             .collect()
     }
 
+    /// Spawn the scene's entities into a world
+    ///
+    /// Every entity gets a `Name`, a `SceneEntityId` and the live version of
+    /// its components, and is linked to its parent through `Parent` and
+    /// `Children`. A parent that is not part of the scene leaves the entity at
+    /// the root. Custom components are inserted through the world's
+    /// `ComponentRegistry` when they are registered. Returns the spawned entity
+    /// for each scene entity ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a component holds an invalid value, such as an
+    /// unknown rigidbody type. No entities are spawned in that case.
+    pub fn spawn_into(&self, world: &mut World) -> Result<HashMap<u64, Entity>> {
+        with_registry(world, |world, registry| {
+            spawn_entities(&self.entities, world, registry)
+        })
+    }
+
+    /// Capture the entities of a world into a scene
+    ///
+    /// Entities with a `Name` or `SceneEntityId` are captured, along with any
+    /// component registered in the world's `ComponentRegistry`. Entities that
+    /// were spawned from a scene keep their ID, others get a new one. Parents
+    /// are written before their children, and roots in entity index order.
+    #[must_use]
+    pub fn from_world(name: impl Into<String>, world: &World) -> Self {
+        let mut scene = Self::new(name);
+        let captured: Vec<EntityRef<'_>> = world
+            .iter_entities()
+            .filter(|e| e.contains::<Name>() || e.contains::<SceneEntityId>())
+            .collect();
+        let ids = scene_ids(&captured);
+
+        let mut roots: Vec<Entity> = captured
+            .iter()
+            .filter(|e| e.get::<Parent>().map_or(true, |p| !ids.contains_key(&p.get())))
+            .map(EntityRef::id)
+            .collect();
+        roots.sort_by_key(|e| e.index());
+
+        scene.entities = capture_tree(world, roots, &ids);
+        scene
+    }
+
     /// Save scene to JSON file
     ///
     /// # Errors
@@ -212,21 +277,37 @@ impl Scene {
 
     /// Load scene from JSON file
     ///
+    /// Older scenes are upgraded with the engine's migrations only; use
+    /// [`Scene::load_json_with`] when the game registers its own steps.
+    ///
     /// # Errors
     ///
-    /// Returns error if file reading or deserialization fails
+    /// Returns error if file reading, migration or deserialization fails
     pub fn load_json(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_json_with(path, &SceneMigrator::new())
+    }
+
+    /// Load scene from JSON file, upgrading older scenes with a migrator
+    ///
+    /// # Errors
+    ///
+    /// Returns error if file reading, migration or deserialization fails
+    pub fn load_json_with(path: impl AsRef<Path>, migrator: &SceneMigrator) -> Result<Self> {
         let json = std::fs::read_to_string(path)?;
-        serde_json::from_str(&json).map_err(|e| lunaris_core::Error::Asset(e.to_string()))
+        let value = serde_json::from_str(&json)
+            .map_err(|e| lunaris_core::Error::Asset(e.to_string()))?;
+        migrator.from_value(value)
     }
 
     /// Save scene to binary format (MessagePack)
     ///
+    /// Fields are stored by name so migrations can read older files.
+    ///
     /// # Errors
     ///
     /// Returns error if serialization fails
     pub fn save_binary(&self, path: impl AsRef<Path>) -> Result<()> {
-        let data = rmp_serde::to_vec(self)
+        let data = rmp_serde::to_vec_named(self)
             .map_err(|e| lunaris_core::Error::Asset(e.to_string()))?;
         std::fs::write(path, data)?;
         Ok(())
@@ -234,13 +315,321 @@ impl Scene {
 
     /// Load scene from binary format
     ///
+    /// Older scenes are upgraded with the engine's migrations only; use
+    /// [`Scene::load_binary_with`] when the game registers its own steps.
+    ///
     /// # Errors
     ///
-    /// Returns error if file reading or deserialization fails
+    /// Returns error if file reading, migration or deserialization fails
     pub fn load_binary(path: impl AsRef<Path>) -> Result<Self> {
+        Self::load_binary_with(path, &SceneMigrator::new())
+    }
+
+    /// Load scene from binary format, upgrading older scenes with a migrator
+    ///
+    /// # Errors
+    ///
+    /// Returns error if file reading, migration or deserialization fails
+    pub fn load_binary_with(path: impl AsRef<Path>, migrator: &SceneMigrator) -> Result<Self> {
         let data = std::fs::read(path)?;
-        rmp_serde::from_slice(&data).map_err(|e| lunaris_core::Error::Asset(e.to_string()))
+        migrator.read(&data).map(|(scene, _)| scene)
+    }
+}
+
+/// Run a closure with the world's component registry taken out of the world
+fn with_registry<R>(
+    world: &mut World,
+    f: impl FnOnce(&mut World, Option<&ComponentRegistry>) -> R,
+) -> R {
+    let registry = world.remove_resource::<ComponentRegistry>();
+    let result = f(world, registry.as_ref());
+    if let Some(registry) = registry {
+        world.insert_resource(registry);
+    }
+    result
+}
+
+/// Spawn entity data and link parents that are part of the same set
+///
+/// If a component cannot be inserted, every entity spawned so far is
+/// despawned again.
+fn spawn_entities(
+    entities: &[EntityData],
+    world: &mut World,
+    registry: Option<&ComponentRegistry>,
+) -> Result<HashMap<u64, Entity>> {
+    let mut spawned = HashMap::with_capacity(entities.len());
+    for data in entities {
+        let mut entity = world.spawn((Name::new(data.name.clone()), SceneEntityId(data.id)));
+        if !data.tags.is_empty() {
+            entity.insert(Tags(data.tags.clone()));
+        }
+        if let Some(link) = &data.prefab {
+            entity.insert(link.clone());
+        }
+        let mut extras = Vec::new();
+        let inserted = data.components.iter().try_for_each(|component| {
+            insert_component(&mut entity, component, registry, &mut extras)
+        });
+        if let Err(e) = inserted {
+            entity.despawn();
+            for entity in spawned.into_values() {
+                world.despawn(entity);
+            }
+            return Err(lunaris_core::Error::Asset(format!("Entity {}: {e}", data.name)));
+        }
+        if !extras.is_empty() {
+            entity.insert(SceneExtras(extras));
+        }
+        spawned.insert(data.id, entity.id());
+    }
+
+    for data in entities {
+        let child = spawned[&data.id];
+        let Some(&parent) = data.parent.and_then(|id| spawned.get(&id)) else {
+            continue;
+        };
+        if parent == child {
+            continue;
+        }
+        world.entity_mut(child).insert(Parent(parent));
+        let mut parent = world.entity_mut(parent);
+        if let Some(mut children) = parent.get_mut::<Children>() {
+            children.add(child);
+        } else {
+            parent.insert(Children(vec![child]));
+        }
+    }
+    Ok(spawned)
+}
+
+/// Scene IDs for captured entities, keeping the ones they were spawned with
+fn scene_ids(entities: &[EntityRef<'_>]) -> HashMap<Entity, u64> {
+    entities
+        .iter()
+        .map(|e| {
+            let id = e.get::<SceneEntityId>().map_or_else(|| Id::new().raw(), |id| id.0);
+            (e.id(), id)
+        })
+        .collect()
+}
+
+/// Capture entities depth first from the roots, visiting only entities in `ids`
+///
+/// Children are found through both `Children` and `Parent`, so a child that
+/// is missing from its parent's `Children` is still captured. Entities in
+/// `ids` that cannot be reached from the roots, e.g. because of a parent
+/// cycle, are captured after them.
+fn capture_tree(world: &World, roots: Vec<Entity>, ids: &HashMap<Entity, u64>) -> Vec<EntityData> {
+    let registry = world.get_resource::<ComponentRegistry>();
+
+    let mut by_parent: HashMap<Entity, Vec<Entity>> = HashMap::new();
+    for &entity in ids.keys() {
+        if let Some(parent) = world.get::<Parent>(entity) {
+            by_parent.entry(parent.get()).or_default().push(entity);
+        }
+    }
+    for children in by_parent.values_mut() {
+        children.sort_by_key(|e| e.index());
+    }
+    let mut unreached: Vec<Entity> = ids.keys().copied().collect();
+    unreached.sort_by_key(|e| e.index());
+
+    let mut captured = Vec::with_capacity(ids.len());
+    let mut visited = HashSet::with_capacity(ids.len());
+    // Unreached entities sit below the roots, so they are only visited last
+    let mut stack: Vec<Entity> =
+        unreached.into_iter().rev().chain(roots.into_iter().rev()).collect();
+    while let Some(entity) = stack.pop() {
+        if !ids.contains_key(&entity) || !visited.insert(entity) {
+            continue;
+        }
+        let entity = world.entity(entity);
+        captured.push(capture_entity(entity, ids, registry));
+
+        let listed = entity.get::<Children>().map_or(&[][..], Children::get);
+        let unlisted = by_parent.get(&entity.id()).into_iter().flatten();
+        let children: Vec<Entity> = listed
+            .iter()
+            .chain(unlisted.filter(|c| !listed.contains(c)))
+            .copied()
+            .collect();
+        stack.extend(children.into_iter().rev().filter(|c| !visited.contains(c)));
+    }
+    captured
+}
+
+/// Insert the live version of a scene component, or keep it as an extra
+///
+/// Custom components that are not registered, or whose data does not match the
+/// registered type, are kept as extras so they survive a save. Values no live
+/// component can hold, such as an unknown rigidbody type, are an error.
+fn insert_component(
+    entity: &mut EntityWorldMut<'_>,
+    component: &ComponentData,
+    registry: Option<&ComponentRegistry>,
+    extras: &mut Vec<ComponentData>,
+) -> std::result::Result<(), String> {
+    match component {
+        ComponentData::Transform2D { position, rotation, scale } => {
+            entity.insert(Transform2D::new(
+                Vec2::new(position[0], position[1]),
+                *rotation,
+                Vec2::new(scale[0], scale[1]),
+            ));
+        }
+        ComponentData::Transform3D { position, rotation, scale } => {
+            entity.insert((
+                Transform3D::new(vec3(*position), vec3(*rotation), vec3(*scale)),
+                GlobalTransform3D::default(),
+            ));
+        }
+        ComponentData::Camera { is_active, priority, clear_color } => {
+            entity.insert(Camera {
+                is_active: *is_active,
+                priority: *priority,
+                clear_color: clear_color.map(|[r, g, b, a]| Color::new(r, g, b, a)),
+            });
+        }
+        ComponentData::Camera2D { zoom } => {
+            entity.insert(Camera2DSettings { zoom: *zoom });
+        }
+        ComponentData::Camera3D { fov, near, far } => {
+            entity.insert(Camera3DSettings { fov: *fov, near: *near, far: *far });
+        }
+        ComponentData::Rigidbody { body_type, mass, gravity_scale } => {
+            let Some(parsed) = RigidBodyType::parse(body_type) else {
+                return Err(format!("unknown rigidbody type {body_type:?}"));
+            };
+            entity.insert(RigidBody {
+                body_type: parsed,
+                mass: *mass,
+                gravity_scale: *gravity_scale,
+            });
+        }
+        ComponentData::Collider { shape, is_trigger } => {
+            entity.insert(Collider { shape: shape.clone(), is_trigger: *is_trigger });
+        }
+        ComponentData::AudioSource { clip, volume, looping, play_on_start } => {
+            entity.insert(AudioSource {
+                clip: clip.clone(),
+                volume: *volume,
+                looping: *looping,
+                play_on_start: *play_on_start,
+            });
+        }
+        ComponentData::Script { path, properties } => {
+            entity.insert(Script { path: path.clone(), properties: properties.clone() });
+        }
+        ComponentData::Custom { name, data } => {
+            let inserted =
+                registry.is_some_and(|r| r.insert(entity, name, data.clone()).is_ok());
+            if !inserted {
+                extras.push(component.clone());
+            }
+        }
+        ComponentData::Sprite { .. } => {
+            extras.push(component.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Serialize a live entity, mapping its parent through the captured IDs
+///
+/// Registered components that fail to serialize are skipped.
+fn capture_entity(
+    entity: EntityRef<'_>,
+    ids: &HashMap<Entity, u64>,
+    registry: Option<&ComponentRegistry>,
+) -> EntityData {
+    let mut data = EntityData {
+        id: ids[&entity.id()],
+        name: entity
+            .get::<Name>()
+            .map_or_else(|| format!("Entity {}", entity.id().index()), |n| n.0.clone()),
+        parent: entity.get::<Parent>().and_then(|p| ids.get(&p.get()).copied()),
+        tags: entity.get::<Tags>().map(|t| t.0.clone()).unwrap_or_default(),
+        components: Vec::new(),
+        prefab: entity.get::<PrefabLink>().cloned(),
+    };
+
+    if let Some(t) = entity.get::<Transform2D>() {
+        data.add_component(ComponentData::Transform2D {
+            position: [t.position.x, t.position.y],
+            rotation: t.rotation,
+            scale: [t.scale.x, t.scale.y],
+        });
+    }
+    if let Some(t) = entity.get::<Transform3D>() {
+        data.add_component(ComponentData::Transform3D {
+            position: array3(t.position),
+            rotation: array3(t.rotation),
+            scale: array3(t.scale),
+        });
+    }
+    if let Some(c) = entity.get::<Camera>() {
+        data.add_component(ComponentData::Camera {
+            is_active: c.is_active,
+            priority: c.priority,
+            clear_color: c.clear_color.map(|c| [c.r, c.g, c.b, c.a]),
+        });
+    }
+    if let Some(c) = entity.get::<Camera2DSettings>() {
+        data.add_component(ComponentData::Camera2D { zoom: c.zoom });
+    }
+    if let Some(c) = entity.get::<Camera3DSettings>() {
+        data.add_component(ComponentData::Camera3D { fov: c.fov, near: c.near, far: c.far });
+    }
+    if let Some(b) = entity.get::<RigidBody>() {
+        data.add_component(ComponentData::Rigidbody {
+            body_type: b.body_type.as_str().to_string(),
+            mass: b.mass,
+            gravity_scale: b.gravity_scale,
+        });
+    }
+    if let Some(c) = entity.get::<Collider>() {
+        data.add_component(ComponentData::Collider {
+            shape: c.shape.clone(),
+            is_trigger: c.is_trigger,
+        });
+    }
+    if let Some(a) = entity.get::<AudioSource>() {
+        data.add_component(ComponentData::AudioSource {
+            clip: a.clip.clone(),
+            volume: a.volume,
+            looping: a.looping,
+            play_on_start: a.play_on_start,
+        });
+    }
+    if let Some(s) = entity.get::<Script>() {
+        data.add_component(ComponentData::Script {
+            path: s.path.clone(),
+            properties: s.properties.clone(),
+        });
+    }
+    if let Some(registry) = registry {
+        for registration in registry.iter() {
+            if let Some(Ok(value)) = registration.read(&entity) {
+                data.add_component(ComponentData::Custom {
+                    name: registration.schema.name.clone(),
+                    data: value,
+                });
+            }
+        }
     }
+    if let Some(extras) = entity.get::<SceneExtras>() {
+        data.components.extend(extras.0.iter().cloned());
+    }
+    data
+}
+
+fn vec3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+fn array3(v: Vec3) -> [f32; 3] {
+    [v.x, v.y, v.z]
 }
 
 impl EntityData {
@@ -253,6 +642,7 @@ impl EntityData {
             parent: None,
             tags: Vec::new(),
             components: Vec::new(),
+            prefab: None,
         }
     }
 
@@ -297,6 +687,10 @@ pub struct SceneManager {
     scenes: HashMap<SceneId, Scene>,
     /// Current active scene
     active_scene: Option<SceneId>,
+    /// Prefab assets by name
+    prefabs: HashMap<String, Prefab>,
+    /// Migrations applied to loaded scenes
+    migrator: SceneMigrator,
 }
 
 impl Default for SceneManager {
@@ -312,6 +706,8 @@ impl SceneManager {
         Self {
             scenes: HashMap::new(),
             active_scene: None,
+            prefabs: HashMap::new(),
+            migrator: SceneMigrator::new(),
         }
     }
 
@@ -323,17 +719,18 @@ impl SceneManager {
         id
     }
 
-    /// Load a scene from file
+    /// Migrations applied to loaded scenes, for registering game steps
+    pub fn migrator_mut(&mut self) -> &mut SceneMigrator {
+        &mut self.migrator
+    }
+
+    /// Load a scene from file, upgrading older formats
     ///
     /// # Errors
     ///
     /// Returns error if loading fails
     pub fn load_scene(&mut self, path: impl AsRef<Path>) -> Result<SceneId> {
-        let scene = if path.as_ref().extension().map_or(false, |e| e == "json") {
-            Scene::load_json(path)?
-        } else {
-            Scene::load_binary(path)?
-        };
+        let scene = self.migrator.load(path)?;
         let id = scene.id;
         self.scenes.insert(id, scene);
         Ok(id)
@@ -394,6 +791,76 @@ impl SceneManager {
     pub fn list_scenes(&self) -> Vec<(SceneId, &str)> {
         self.scenes.iter().map(|(id, s)| (*id, s.name.as_str())).collect()
     }
+
+    /// Get a scene by ID mutably
+    pub fn get_scene_mut(&mut self, id: SceneId) -> Option<&mut Scene> {
+        self.scenes.get_mut(&id)
+    }
+
+    /// Add a prefab asset without propagating it
+    pub fn add_prefab(&mut self, prefab: Prefab) {
+        self.prefabs.insert(prefab.name.clone(), prefab);
+    }
+
+    /// Get a prefab asset by name
+    #[must_use]
+    pub fn get_prefab(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    /// Replace a prefab asset and propagate the edit
+    ///
+    /// Prefabs that nest instances of it, directly or through other prefabs,
+    /// are synced first, inner prefabs before outer ones. Every instance in
+    /// the loaded scenes is then rebuilt with its overrides kept. Returns the
+    /// number of scene instances updated.
+    pub fn update_prefab(&mut self, prefab: Prefab) -> usize {
+        let mut affected = vec![prefab.name.clone()];
+        self.prefabs.insert(prefab.name.clone(), prefab);
+        let mut i = 0;
+        while i < affected.len() {
+            for (name, other) in &self.prefabs {
+                if !affected.contains(name) && other.contains_instances_of(&affected[i]) {
+                    affected.push(name.clone());
+                }
+            }
+            i += 1;
+        }
+
+        let mut ordered: Vec<String> = Vec::with_capacity(affected.len());
+        while ordered.len() < affected.len() {
+            let pending = |name: &&String| !ordered.contains(*name);
+            let ready = affected.iter().filter(pending).find(|name| {
+                affected.iter().all(|dep| {
+                    dep == *name
+                        || ordered.contains(dep)
+                        || !self.prefabs[*name].contains_instances_of(dep)
+                })
+            });
+            // Nesting cycles have no ready prefab; take any to make progress
+            let Some(name) = ready.or_else(|| affected.iter().find(pending)).cloned() else {
+                break;
+            };
+            for dep in &ordered {
+                if self.prefabs[&name].contains_instances_of(dep) {
+                    let nested = self.prefabs[dep].clone();
+                    if let Some(outer) = self.prefabs.get_mut(&name) {
+                        outer.sync_nested(&nested);
+                    }
+                }
+            }
+            ordered.push(name);
+        }
+
+        let mut updated = 0;
+        for name in &ordered {
+            let prefab = &self.prefabs[name];
+            for scene in self.scenes.values_mut() {
+                updated += scene.sync_prefab(prefab);
+            }
+        }
+        updated
+    }
 }
 
 /// Prefab - reusable entity template
@@ -423,19 +890,68 @@ impl Prefab {
         self.children.push(child);
     }
 
+    /// Capture an entity and its descendants from a world as a prefab
+    ///
+    /// Components registered in the world's `ComponentRegistry` are included.
+    /// Returns `None` if the root entity does not exist.
+    #[must_use]
+    pub fn from_world(name: impl Into<String>, world: &World, root: Entity) -> Option<Self> {
+        let mut subtree = Vec::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![root];
+        while let Some(entity) = stack.pop() {
+            let Some(entity) = world.get_entity(entity) else {
+                continue;
+            };
+            if !seen.insert(entity.id()) {
+                continue;
+            }
+            if let Some(children) = entity.get::<Children>() {
+                stack.extend(children.get());
+            }
+            subtree.push(entity);
+        }
+        let ids = scene_ids(&subtree);
+
+        let mut entities = capture_tree(world, vec![root], &ids).into_iter();
+        let mut root = entities.next()?;
+        root.parent = None;
+        Some(Self {
+            name: name.into(),
+            root,
+            children: entities.collect(),
+        })
+    }
+
     /// Instantiate the prefab (creates new IDs)
+    ///
+    /// Children keep their place in the hierarchy; children without a parent
+    /// in the prefab are attached to the root. Every entity links back to its
+    /// prefab entity.
     #[must_use]
     pub fn instantiate(&self) -> (EntityData, Vec<EntityData>) {
+        let mut ids = HashMap::with_capacity(self.children.len() + 1);
         let mut root = self.root.clone();
         root.id = Id::new().raw();
+        root.prefab = Some(self.link(self.root.id, root.id));
+        ids.insert(self.root.id, root.id);
+        for child in &self.children {
+            ids.insert(child.id, Id::new().raw());
+        }
 
         let children: Vec<EntityData> = self
             .children
             .iter()
             .map(|c| {
                 let mut child = c.clone();
-                child.id = Id::new().raw();
-                child.parent = Some(root.id);
+                child.id = ids[&c.id];
+                child.prefab = Some(self.link(c.id, root.id));
+                child.parent = Some(
+                    c.parent
+                        .and_then(|p| ids.get(&p).copied())
+                        .filter(|&p| p != child.id)
+                        .unwrap_or(root.id),
+                );
                 child
             })
             .collect();
@@ -443,6 +959,32 @@ impl Prefab {
         (root, children)
     }
 
+    /// Link to one of this prefab's entities
+    fn link(&self, entity: u64, instance: u64) -> PrefabLink {
+        PrefabLink {
+            source: self.name.clone(),
+            entity,
+            instance,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Instantiate the prefab into a world, returning the root entity
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a component holds an invalid value. No entities are
+    /// spawned in that case.
+    pub fn spawn_into(&self, world: &mut World) -> Result<Entity> {
+        let (root, mut entities) = self.instantiate();
+        let root_id = root.id;
+        entities.insert(0, root);
+        let spawned = with_registry(world, |world, registry| {
+            spawn_entities(&entities, world, registry)
+        })?;
+        Ok(spawned[&root_id])
+    }
+
     /// Save prefab to file
     ///
     /// # Errors
@@ -505,4 +1047,208 @@ mod tests {
         assert_eq!(scene.root_entities().len(), 1);
         assert_eq!(scene.children_of(parent_id).len(), 1);
     }
+
+    fn json(scene: &Scene) -> serde_json::Value {
+        let mut value = serde_json::to_value(&scene.entities).unwrap();
+        for entity in value.as_array_mut().unwrap() {
+            entity.as_object_mut().unwrap().remove("id");
+            entity.as_object_mut().unwrap().remove("parent");
+        }
+        value
+    }
+
+    #[test]
+    fn spawn_and_capture_round_trip() {
+        let mut scene = Scene::new("Level");
+        let mut root = EntityData::new("Ship").with_tag("vehicle");
+        root.add_component(ComponentData::Transform3D {
+            position: [1.0, 2.0, 3.0],
+            rotation: [0.0, 0.5, 0.0],
+            scale: [1.0, 1.0, 1.0],
+        });
+        root.add_component(ComponentData::Rigidbody {
+            body_type: "kinematic".to_string(),
+            mass: 40.0,
+            gravity_scale: 0.0,
+        });
+        root.add_component(ComponentData::Collider {
+            shape: ColliderShapeData::Capsule { height: 2.0, radius: 0.5 },
+            is_trigger: false,
+        });
+        let root_id = root.id;
+        let mut engine = EntityData::new("Engine").with_parent(root_id);
+        engine.add_component(ComponentData::AudioSource {
+            clip: "audio/engine.ogg".to_string(),
+            volume: 0.8,
+            looping: true,
+            play_on_start: true,
+        });
+        engine.add_component(ComponentData::Custom {
+            name: "Thruster".to_string(),
+            data: serde_json::json!({ "power": 3 }),
+        });
+        let engine_id = engine.id;
+        scene.add_entity(root);
+        scene.add_entity(engine);
+
+        let mut world = World::new();
+        let spawned = scene.spawn_into(&mut world).unwrap();
+        let (ship, child) = (spawned[&root_id], spawned[&engine_id]);
+
+        assert_eq!(world.get::<Name>(ship).unwrap().as_str(), "Ship");
+        assert_eq!(world.get::<Parent>(child), Some(&Parent(ship)));
+        assert_eq!(world.get::<Children>(ship).unwrap().get(), &[child]);
+        assert_eq!(world.get::<RigidBody>(ship).unwrap().body_type, RigidBodyType::Kinematic);
+        assert!(world.get::<GlobalTransform3D>(ship).is_some());
+        assert!(world.get::<AudioSource>(child).unwrap().looping);
+
+        let captured = Scene::from_world("Level", &world);
+        assert_eq!(captured.entities.len(), 2);
+        assert_eq!(captured.entities[0].id, root_id);
+        assert_eq!(captured.entities[1].id, engine_id);
+        assert_eq!(captured.entities[1].parent, Some(root_id));
+        assert_eq!(json(&captured), json(&scene));
+    }
+
+    #[test]
+    fn capture_assigns_ids_to_new_entities() {
+        let mut world = World::new();
+        let parent = world.spawn((Name::new("Door"), Transform2D::from_position(4.0, 0.0))).id();
+        let child = world.spawn((Name::new("Handle"), Parent(parent))).id();
+        world.entity_mut(parent).insert(Children(vec![child]));
+        world.spawn(Transform2D::IDENTITY);
+
+        let scene = Scene::from_world("Captured", &world);
+        assert_eq!(scene.entities.len(), 2);
+        assert_eq!(scene.entities[0].name, "Door");
+        assert_eq!(scene.entities[1].parent, Some(scene.entities[0].id));
+
+        let mut copy = World::new();
+        let spawned = scene.spawn_into(&mut copy).unwrap();
+        let door = spawned[&scene.entities[0].id];
+        let transform = copy.get::<Transform2D>(door).unwrap();
+        assert!((transform.position.x - 4.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn capture_keeps_children_missing_from_their_parents_list() {
+        let mut world = World::new();
+        let parent = world.spawn(Name::new("Crane")).id();
+        let listed = world.spawn((Name::new("Arm"), Parent(parent))).id();
+        world.spawn((Name::new("Hook"), Parent(listed)));
+        world.entity_mut(parent).insert(Children(vec![listed]));
+        // Two entities that are each other's parent have no root
+        let a = world.spawn(Name::new("A")).id();
+        let b = world.spawn((Name::new("B"), Parent(a))).id();
+        world.entity_mut(a).insert(Parent(b));
+
+        let scene = Scene::from_world("Captured", &world);
+        let names: Vec<_> = scene.entities.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Crane", "Arm", "Hook", "A", "B"]);
+        assert_eq!(scene.entities[2].parent, Some(scene.entities[1].id));
+    }
+
+    #[test]
+    fn unknown_rigidbody_types_fail_to_spawn() {
+        let mut scene = Scene::new("Broken");
+        let parent = EntityData::new("Crate");
+        let parent_id = parent.id;
+        let mut child = EntityData::new("Lid").with_parent(parent_id);
+        child.add_component(ComponentData::Rigidbody {
+            body_type: "floating".to_string(),
+            mass: 1.0,
+            gravity_scale: 1.0,
+        });
+        scene.add_entity(parent);
+        scene.add_entity(child);
+
+        let mut world = World::new();
+        let error = scene.spawn_into(&mut world).unwrap_err();
+        assert!(error.to_string().contains("floating"), "{error}");
+        assert_eq!(world.entities().len(), 0);
+    }
+
+    #[derive(bevy_ecs::component::Component, Serialize, Deserialize)]
+    struct Health {
+        current: i32,
+        max: i32,
+    }
+
+    impl crate::reflect::ReflectComponent for Health {
+        fn schema() -> crate::reflect::ComponentSchema {
+            use crate::reflect::{ComponentSchema, FieldKind, FieldSchema};
+            ComponentSchema::new("Health")
+                .with_field(FieldSchema::new("current", FieldKind::Int))
+                .with_field(FieldSchema::new("max", FieldKind::Int))
+        }
+    }
+
+    fn registry_world() -> World {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Health>();
+        let mut world = World::new();
+        world.insert_resource(registry);
+        world
+    }
+
+    #[test]
+    fn registered_components_round_trip() {
+        let mut scene = Scene::new("Arena");
+        let mut enemy = EntityData::new("Enemy");
+        enemy.add_component(ComponentData::Custom {
+            name: "Health".to_string(),
+            data: serde_json::json!({ "current": 30, "max": 50 }),
+        });
+        enemy.add_component(ComponentData::Custom {
+            name: "Unknown".to_string(),
+            data: serde_json::json!(1),
+        });
+        scene.add_entity(enemy);
+
+        let mut world = registry_world();
+        let spawned = scene.spawn_into(&mut world).unwrap();
+        let entity = spawned[&scene.entities[0].id];
+        assert_eq!(world.get::<Health>(entity).unwrap().current, 30);
+        assert!(world.contains_resource::<ComponentRegistry>());
+
+        world.get_mut::<Health>(entity).unwrap().current = 12;
+        let saved = Scene::from_world("Arena", &world);
+        let names: Vec<_> = saved.entities[0]
+            .components
+            .iter()
+            .filter_map(|c| match c {
+                ComponentData::Custom { name, data } => Some((name.as_str(), data.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Health", serde_json::json!({ "current": 12, "max": 50 })),
+                ("Unknown", serde_json::json!(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn prefab_captures_nested_hierarchy() {
+        let mut world = registry_world();
+        let tower = world.spawn((Name::new("Tower"), Health { current: 80, max: 80 })).id();
+        let floor = world.spawn((Name::new("Floor"), Parent(tower))).id();
+        let lamp = world.spawn((Name::new("Lamp"), Parent(floor))).id();
+        world.entity_mut(tower).insert(Children(vec![floor]));
+        world.entity_mut(floor).insert(Children(vec![lamp]));
+
+        let prefab = Prefab::from_world("Tower", &world, tower).unwrap();
+        assert_eq!(prefab.root.name, "Tower");
+        assert_eq!(prefab.children.len(), 2);
+        assert_eq!(prefab.children[1].parent, Some(prefab.children[0].id));
+
+        let copy = prefab.spawn_into(&mut world).unwrap();
+        assert_ne!(copy, tower);
+        assert_eq!(world.get::<Health>(copy).unwrap().max, 80);
+        let floor_copy = world.get::<Children>(copy).unwrap().get()[0];
+        let lamp_copy = world.get::<Children>(floor_copy).unwrap().get()[0];
+        assert_eq!(world.get::<Name>(lamp_copy).unwrap().as_str(), "Lamp");
+    }
 }
@@ -3,6 +3,7 @@
 //! Polished, production-ready editor UI components.
 
 use glam::Vec2;
+use lunaris_ecs::{ComponentRegistry, Entity, FieldKind, FieldSchema, World};
 use super::design_system::*;
 
 // ==================== TOOLBAR ====================
@@ -389,6 +390,8 @@ pub struct InspectorSection {
     pub removable: bool,
     pub enabled: bool,
     pub properties: Vec<InspectorProperty>,
+    /// Registered component edited by this section
+    pub component: Option<String>,
 }
 
 /// Inspector property
@@ -402,7 +405,7 @@ pub struct InspectorProperty {
 /// Property type
 pub enum PropertyType {
     Bool(bool),
-    Int { value: i32, min: Option<i32>, max: Option<i32>, step: i32 },
+    Int { value: i64, min: Option<i64>, max: Option<i64>, step: i64 },
     Float { value: f32, min: Option<f32>, max: Option<f32>, step: f32, precision: u32 },
     String { value: String, multiline: bool, max_length: Option<usize> },
     Vec2 { value: [f32; 2] },
@@ -479,8 +482,227 @@ impl InspectorPanel {
                     read_only: false,
                 },
             ],
+            component: None,
         });
     }
+
+    /// Show the registered components of an entity
+    ///
+    /// Replaces previous component sections with one section per registered
+    /// component on the entity, holding a property for each schema field.
+    pub fn show_components(&mut self, registry: &ComponentRegistry, world: &World, entity: Entity) {
+        self.sections.retain(|s| s.component.is_none());
+        let Some(entity) = world.get_entity(entity) else {
+            return;
+        };
+
+        for registration in registry.iter() {
+            let Some(Ok(value)) = registration.read(&entity) else {
+                continue;
+            };
+            let schema = &registration.schema;
+            self.sections.push(InspectorSection {
+                title: schema.name.clone(),
+                icon: "component".to_string(),
+                expanded: true,
+                removable: true,
+                enabled: true,
+                properties: schema
+                    .fields
+                    .iter()
+                    .map(|field| InspectorProperty {
+                        name: field.name.clone(),
+                        property_type: property_from_json(field, value.get(&field.name)),
+                        tooltip: field.tooltip.clone(),
+                        read_only: false,
+                    })
+                    .collect(),
+                component: Some(schema.name.clone()),
+            });
+        }
+    }
+
+    /// Write an edited property back to its component
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the section is not a registered component, or the
+    /// property value does not fit the field
+    pub fn apply_property(
+        &self,
+        registry: &ComponentRegistry,
+        world: &mut World,
+        entity: Entity,
+        section: usize,
+        property: usize,
+    ) -> lunaris_core::Result<()> {
+        let invalid = |msg: &str| lunaris_core::Error::Asset(msg.to_string());
+        let section = self.sections.get(section).ok_or_else(|| invalid("No such section"))?;
+        let component = section
+            .component
+            .as_deref()
+            .ok_or_else(|| invalid("Section is not a registered component"))?;
+        let property = section
+            .properties
+            .get(property)
+            .ok_or_else(|| invalid("No such property"))?;
+        let field = registry
+            .get(component)
+            .and_then(|r| r.schema.field(&property.name))
+            .ok_or_else(|| invalid("Field is not in the component schema"))?;
+
+        let current = registry.get_field(world, entity, component, &field.name);
+        let value = property_to_json(&property.property_type, field, current.as_ref())?;
+        registry.set_field(world, entity, component, &field.name, value)
+    }
+}
+
+/// Key names for vector and color fields stored as objects
+fn vector_keys(kind: &FieldKind) -> &'static [&'static str] {
+    match kind {
+        FieldKind::Vec2 => &["x", "y"],
+        FieldKind::Vec3 => &["x", "y", "z"],
+        FieldKind::Color => &["r", "g", "b", "a"],
+        _ => &["x", "y", "z", "w"],
+    }
+}
+
+/// Read up to N floats from an array or an object with vector keys
+fn json_floats<const N: usize>(value: Option<&serde_json::Value>, kind: &FieldKind) -> [f32; N] {
+    let mut out = [0.0; N];
+    if matches!(kind, FieldKind::Color) {
+        out[N - 1] = 1.0;
+    }
+    let keys = vector_keys(kind);
+    for (i, slot) in out.iter_mut().enumerate() {
+        let component = match value {
+            Some(serde_json::Value::Array(items)) => items.get(i),
+            Some(serde_json::Value::Object(map)) => keys.get(i).and_then(|k| map.get(*k)),
+            _ => None,
+        };
+        if let Some(v) = component.and_then(serde_json::Value::as_f64) {
+            *slot = v as f32;
+        }
+    }
+    out
+}
+
+/// Write floats in the same shape as the current value
+fn floats_json(
+    values: &[f32],
+    kind: &FieldKind,
+    current: Option<&serde_json::Value>,
+) -> serde_json::Value {
+    if let Some(serde_json::Value::Object(map)) = current {
+        let mut map = map.clone();
+        for (key, v) in vector_keys(kind).iter().zip(values) {
+            map.insert((*key).to_string(), serde_json::json!(v));
+        }
+        serde_json::Value::Object(map)
+    } else {
+        serde_json::json!(values)
+    }
+}
+
+/// Inspector property for a component field value
+fn property_from_json(field: &FieldSchema, value: Option<&serde_json::Value>) -> PropertyType {
+    let range = field.range;
+    match &field.kind {
+        FieldKind::Bool => {
+            PropertyType::Bool(value.and_then(serde_json::Value::as_bool).unwrap_or(false))
+        },
+        FieldKind::Int => PropertyType::Int {
+            value: value.and_then(serde_json::Value::as_i64).unwrap_or(0),
+            min: range.map(|(min, _)| min.ceil() as i64),
+            max: range.map(|(_, max)| max.floor() as i64),
+            step: 1,
+        },
+        FieldKind::Float => PropertyType::Float {
+            value: value.and_then(serde_json::Value::as_f64).unwrap_or(0.0) as f32,
+            min: range.map(|(min, _)| min as f32),
+            max: range.map(|(_, max)| max as f32),
+            step: 0.1,
+            precision: 3,
+        },
+        FieldKind::String | FieldKind::Asset(_) => PropertyType::String {
+            value: value
+                .and_then(serde_json::Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            multiline: false,
+            max_length: None,
+        },
+        FieldKind::Vec2 => PropertyType::Vec2 {
+            value: json_floats(value, &field.kind),
+        },
+        FieldKind::Vec3 => PropertyType::Vec3 {
+            value: json_floats(value, &field.kind),
+        },
+        FieldKind::Vec4 => PropertyType::Vec4 {
+            value: json_floats(value, &field.kind),
+        },
+        FieldKind::Color => PropertyType::Color {
+            value: json_floats(value, &field.kind),
+            has_alpha: true,
+        },
+        FieldKind::Enum(options) => PropertyType::Enum {
+            value: value
+                .and_then(serde_json::Value::as_str)
+                .and_then(|v| options.iter().position(|o| o == v))
+                .unwrap_or(0),
+            options: options.clone(),
+        },
+        FieldKind::Any => PropertyType::String {
+            value: value.map(ToString::to_string).unwrap_or_default(),
+            multiline: true,
+            max_length: None,
+        },
+    }
+}
+
+/// Component field value for an edited inspector property
+fn property_to_json(
+    property: &PropertyType,
+    field: &FieldSchema,
+    current: Option<&serde_json::Value>,
+) -> lunaris_core::Result<serde_json::Value> {
+    let clamp = |v: f64| field.range.map_or(v, |(min, max)| v.clamp(min, max));
+    let value = match (property, &field.kind) {
+        (PropertyType::Bool(v), FieldKind::Bool) => serde_json::json!(v),
+        (PropertyType::Int { value, .. }, FieldKind::Int) => {
+            let (min, max) = field.range.map_or((i64::MIN, i64::MAX), |(min, max)| {
+                (min.ceil() as i64, max.floor() as i64)
+            });
+            serde_json::json!((*value).max(min).min(max))
+        },
+        (PropertyType::Float { value, .. }, FieldKind::Float) => {
+            serde_json::json!(clamp(f64::from(*value)))
+        },
+        (PropertyType::String { value, .. }, FieldKind::Any) => serde_json::from_str(value)
+            .map_err(|e| lunaris_core::Error::Asset(format!("{}: {}", field.name, e)))?,
+        (PropertyType::String { value, .. }, FieldKind::String | FieldKind::Asset(_)) => {
+            serde_json::json!(value)
+        },
+        (PropertyType::Vec2 { value }, FieldKind::Vec2) => floats_json(value, &field.kind, current),
+        (PropertyType::Vec3 { value }, FieldKind::Vec3) => floats_json(value, &field.kind, current),
+        (PropertyType::Vec4 { value }, FieldKind::Vec4) => floats_json(value, &field.kind, current),
+        (PropertyType::Color { value, .. }, FieldKind::Color) => {
+            floats_json(value, &field.kind, current)
+        },
+        (PropertyType::Enum { value, options }, FieldKind::Enum(_)) => serde_json::json!(options
+            .get(*value)
+            .ok_or_else(|| lunaris_core::Error::Asset(format!(
+                "{}: option out of range",
+                field.name
+            )))?),
+        _ => {
+            return Err(lunaris_core::Error::Asset(format!(
+                "{}: property type does not match the field",
+                field.name
+            )))
+        },
+    };
+    Ok(value)
 }
 
 /// Console panel
@@ -751,3 +973,165 @@ impl AssetBrowserPanel {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lunaris_ecs::{ComponentSchema, ReflectComponent};
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize)]
+    struct Turret {
+        range: f32,
+        aim: [f32; 3],
+        mode: String,
+    }
+
+    impl lunaris_ecs::Component for Turret {
+        type Storage = lunaris_ecs::ecs::component::TableStorage;
+    }
+
+    impl ReflectComponent for Turret {
+        fn schema() -> ComponentSchema {
+            ComponentSchema::new("Turret")
+                .with_field(FieldSchema::new("range", FieldKind::Float).with_range(0.0, 50.0))
+                .with_field(FieldSchema::new("aim", FieldKind::Vec3))
+                .with_field(FieldSchema::new(
+                    "mode",
+                    FieldKind::Enum(vec!["idle".to_string(), "attack".to_string()]),
+                ))
+        }
+    }
+
+    fn round_trip(field: &FieldSchema, value: serde_json::Value) -> serde_json::Value {
+        let property = property_from_json(field, Some(&value));
+        property_to_json(&property, field, Some(&value)).unwrap()
+    }
+
+    #[test]
+    fn properties_round_trip_through_json() {
+        let cases = [
+            (FieldKind::Bool, json!(true)),
+            (FieldKind::Int, json!(-7)),
+            (FieldKind::Int, json!(5_000_000_000_i64)),
+            (FieldKind::Float, json!(2.5)),
+            (FieldKind::String, json!("hello")),
+            (FieldKind::Asset("texture".to_string()), json!("textures/rock.png")),
+            (FieldKind::Vec2, json!([1.0, 2.0])),
+            (FieldKind::Vec3, json!({ "x": 1.0, "y": 2.0, "z": 3.0 })),
+            (FieldKind::Vec4, json!([1.0, 2.0, 3.0, 4.0])),
+            (FieldKind::Color, json!({ "r": 1.0, "g": 0.5, "b": 0.0, "a": 0.25 })),
+            (FieldKind::Enum(vec!["a".to_string(), "b".to_string()]), json!("b")),
+            (FieldKind::Any, json!({ "nested": [1, 2] })),
+        ];
+        for (kind, value) in cases {
+            let field = FieldSchema::new("value", kind);
+            assert_eq!(round_trip(&field, value.clone()), value, "{:?}", field.kind);
+        }
+
+        // Object vectors keep their extra keys
+        let field = FieldSchema::new("offset", FieldKind::Vec2);
+        let value = json!({ "x": 1.0, "y": 2.0, "space": "local" });
+        assert_eq!(round_trip(&field, value.clone()), value);
+    }
+
+    #[test]
+    fn properties_apply_ranges_and_defaults() {
+        let field = FieldSchema::new("speed", FieldKind::Int).with_range(0.0, 10.0);
+        assert!(matches!(
+            property_from_json(&field, Some(&json!(4))),
+            PropertyType::Int { value: 4, min: Some(0), max: Some(10), .. }
+        ));
+        assert_eq!(round_trip(&field, json!(25)), json!(10));
+
+        let field = FieldSchema::new("tint", FieldKind::Color);
+        let PropertyType::Color { value, .. } = property_from_json(&field, None) else {
+            panic!("expected a color property");
+        };
+        assert_eq!(value, [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(
+            property_to_json(&PropertyType::Color { value, has_alpha: true }, &field, None)
+                .unwrap(),
+            json!([0.0, 0.0, 0.0, 1.0])
+        );
+
+        let field = FieldSchema::new("mode", FieldKind::Enum(vec!["a".to_string()]));
+        assert!(matches!(
+            property_from_json(&field, Some(&json!("missing"))),
+            PropertyType::Enum { value: 0, .. }
+        ));
+        let out_of_range = PropertyType::Enum {
+            value: 3,
+            options: vec!["a".to_string()],
+        };
+        assert!(property_to_json(&out_of_range, &field, None).is_err());
+        assert!(property_to_json(&PropertyType::Bool(true), &field, None).is_err());
+
+        let field = FieldSchema::new("data", FieldKind::Any);
+        let broken = PropertyType::String {
+            value: "{broken".to_string(),
+            multiline: true,
+            max_length: None,
+        };
+        assert!(property_to_json(&broken, &field, None).is_err());
+    }
+
+    #[test]
+    fn inspector_edits_registered_components() {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Turret>();
+        let mut world = World::new();
+        let entity = world
+            .spawn(Turret {
+                range: 10.0,
+                aim: [0.0, 0.0, 1.0],
+                mode: "idle".to_string(),
+            })
+            .id();
+
+        let mut inspector = InspectorPanel::new();
+        inspector.show_entity("Turret");
+        inspector.show_components(&registry, &world, entity);
+        inspector.show_components(&registry, &world, entity);
+        assert_eq!(inspector.sections.len(), 2);
+        let section = &inspector.sections[1];
+        assert_eq!(section.component.as_deref(), Some("Turret"));
+        assert_eq!(section.properties.len(), 3);
+        assert!(matches!(
+            section.properties[0].property_type,
+            PropertyType::Float { min: Some(_), max: Some(_), .. }
+        ));
+
+        let properties = &mut inspector.sections[1].properties;
+        properties[0].property_type = PropertyType::Float {
+            value: 80.0,
+            min: Some(0.0),
+            max: Some(50.0),
+            step: 0.1,
+            precision: 3,
+        };
+        properties[2].property_type = PropertyType::Enum {
+            value: 1,
+            options: vec!["idle".to_string(), "attack".to_string()],
+        };
+        inspector
+            .apply_property(&registry, &mut world, entity, 1, 0)
+            .unwrap();
+        inspector
+            .apply_property(&registry, &mut world, entity, 1, 2)
+            .unwrap();
+        let turret = world.get::<Turret>(entity).unwrap();
+        assert_eq!(turret.range, 50.0);
+        assert_eq!(turret.mode, "attack");
+        assert_eq!(turret.aim, [0.0, 0.0, 1.0]);
+
+        // Built-in sections and missing properties are not written back
+        assert!(inspector
+            .apply_property(&registry, &mut world, entity, 0, 0)
+            .is_err());
+        assert!(inspector
+            .apply_property(&registry, &mut world, entity, 1, 9)
+            .is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Physics backends
+//!
+//! [`PhysicsWorld`](crate::PhysicsWorld) forwards all simulation work to a
+//! [`PhysicsBackend`], so gameplay code stays the same whether the built-in
+//! solver or Rapier runs underneath.
+
+use crate::{
+    collision::{
+        ColliderShape, CollisionEvent, CollisionEventType, ContactPoint, OverlapQuery, RaycastHit,
+        RaycastQuery, ShapeCastQuery,
+    },
+    constraints::Constraint,
+    rigidbody::{
+        ColliderProperties, ForceMode, RigidbodyHandle, RigidbodyProperties, RigidbodyState,
+    },
+    snapshot::{SnapshotCodec, SnapshotReader, SnapshotWriter},
+};
+use lunaris_core::{id::Id, math::Vec3, Error, Result};
+use std::collections::BTreeMap;
+
+/// Which backend a [`PhysicsWorld`](crate::PhysicsWorld) is created with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhysicsBackendKind {
+    /// Built-in 3D solver
+    #[default]
+    Builtin,
+    /// Rapier 2D (XY plane, rotation around Z)
+    #[cfg(feature = "2d")]
+    Rapier2D,
+    /// Rapier 3D
+    #[cfg(feature = "3d")]
+    Rapier3D,
+}
+
+impl PhysicsBackendKind {
+    /// Instantiate the backend
+    #[must_use]
+    pub fn create(self) -> Box<dyn PhysicsBackend> {
+        match self {
+            Self::Builtin => Box::new(crate::builtin::BuiltinBackend::new()),
+            #[cfg(feature = "2d")]
+            Self::Rapier2D => Box::new(crate::physics2d::Rapier2DBackend::new()),
+            #[cfg(feature = "3d")]
+            Self::Rapier3D => Box::new(crate::physics3d::Rapier3DBackend::new()),
+        }
+    }
+}
+
+/// Output of a single fixed step
+#[derive(Debug, Clone, Default)]
+pub struct StepEvents {
+    /// Collision events raised during the step
+    pub collisions: Vec<CollisionEvent>,
+    /// Constraints that exceeded their break thresholds
+    pub broken_constraints: Vec<u64>,
+}
+
+/// Simulation backend used by [`PhysicsWorld`](crate::PhysicsWorld)
+///
+/// Bodies are identified by the [`RigidbodyHandle`] the world allocates.
+/// Constraint body ids are the raw ids of those handles.
+pub trait PhysicsBackend: Send {
+    /// Backend name for diagnostics
+    fn name(&self) -> &'static str;
+
+    /// Add a rigidbody
+    fn insert_body(
+        &mut self,
+        handle: RigidbodyHandle,
+        entity_id: Id,
+        properties: &RigidbodyProperties,
+        position: Vec3,
+    );
+
+    /// Remove a rigidbody and its collider
+    fn remove_body(&mut self, handle: RigidbodyHandle);
+
+    /// Attach or replace the collider of a rigidbody
+    fn set_collider(
+        &mut self,
+        handle: RigidbodyHandle,
+        shape: &ColliderShape,
+        properties: &ColliderProperties,
+        offset: Vec3,
+    );
+
+    /// Read the state of a rigidbody
+    fn state(&self, handle: RigidbodyHandle) -> Option<RigidbodyState>;
+
+    /// Teleport a rigidbody
+    fn set_position(&mut self, handle: RigidbodyHandle, position: Vec3);
+
+    /// Set rotation (euler angles in radians)
+    fn set_rotation(&mut self, handle: RigidbodyHandle, rotation: Vec3);
+
+    /// Set linear velocity
+    fn set_linear_velocity(&mut self, handle: RigidbodyHandle, velocity: Vec3);
+
+    /// Queue a force for the next step
+    fn apply_force(&mut self, handle: RigidbodyHandle, force: Vec3, mode: ForceMode);
+
+    /// Queue a torque for the next step
+    fn apply_torque(&mut self, handle: RigidbodyHandle, torque: Vec3, mode: ForceMode);
+
+    /// Check if a rigidbody is asleep
+    fn is_sleeping(&self, handle: RigidbodyHandle) -> bool;
+
+    /// Wake a sleeping rigidbody
+    fn wake_up(&mut self, handle: RigidbodyHandle);
+
+    /// Add a constraint (its id is already assigned)
+    fn insert_constraint(&mut self, constraint: &Constraint);
+
+    /// Remove a constraint
+    fn remove_constraint(&mut self, id: u64);
+
+    /// Advance the simulation by one fixed step
+    fn step(&mut self, dt: f32, gravity: Vec3) -> StepEvents;
+
+    /// Closest hit along a ray
+    ///
+    /// Ray and shape casts ignore sensors; overlaps report them.
+    fn raycast(&self, query: &RaycastQuery) -> Option<RaycastHit> {
+        self.raycast_all(query).into_iter().next()
+    }
+
+    /// Every body hit along a ray, nearest first
+    fn raycast_all(&self, query: &RaycastQuery) -> Vec<RaycastHit>;
+
+    /// First hit of a shape swept along a direction
+    fn shape_cast(&self, query: &ShapeCastQuery) -> Option<RaycastHit>;
+
+    /// Entities whose colliders overlap a shape
+    fn overlap(&self, query: &OverlapQuery) -> Vec<Id>;
+
+    /// Append the complete simulation state to a snapshot
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be snapshotted
+    fn save_state(&self, _writer: &mut SnapshotWriter) -> Result<()> {
+        Err(Error::Internal(format!(
+            "The {} physics backend does not support snapshots",
+            self.name()
+        )))
+    }
+
+    /// Replace the simulation state with the rest of a snapshot
+    ///
+    /// The backend state is the last part of a snapshot. Implementations
+    /// decode all of it and call [`SnapshotReader::finish`] before replacing
+    /// anything, so a malformed snapshot leaves the backend unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is malformed or the backend cannot be
+    /// snapshotted
+    fn load_state(&mut self, _reader: SnapshotReader<'_>) -> Result<()> {
+        Err(Error::Internal(format!(
+            "The {} physics backend does not support snapshots",
+            self.name()
+        )))
+    }
+}
+
+/// Turns the set of touching body pairs of each step into Started,
+/// Ongoing and Ended collision events
+#[derive(Debug, Default)]
+pub struct ContactTracker {
+    /// Pairs touching after the last step, keyed by raw handle ids
+    active: BTreeMap<(u64, u64), (Id, Id)>,
+}
+
+impl ContactTracker {
+    /// Create an empty tracker
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of pairs currently touching
+    #[must_use]
+    pub fn active_pairs(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Bodies touching `handle` after the last step
+    pub fn touching(&self, handle: RigidbodyHandle) -> impl Iterator<Item = RigidbodyHandle> + '_ {
+        let raw = handle.0.raw();
+        self.active
+            .keys()
+            .filter_map(move |&(a, b)| match (a == raw, b == raw) {
+                (true, _) => Some(b),
+                (_, true) => Some(a),
+                _ => None,
+            })
+            .map(|other| RigidbodyHandle(Id::from_raw(other)))
+    }
+
+    /// Diff this step's touching pairs against the previous step
+    ///
+    /// Each entry is `(handle_a, handle_b, entity_a, entity_b, contacts)`.
+    pub fn update(
+        &mut self,
+        touching: impl IntoIterator<
+            Item = (RigidbodyHandle, RigidbodyHandle, Id, Id, Vec<ContactPoint>),
+        >,
+        events: &mut Vec<CollisionEvent>,
+    ) {
+        let mut current = BTreeMap::new();
+
+        for (handle_a, handle_b, entity_a, entity_b, contacts) in touching {
+            let (raw_a, raw_b) = (handle_a.0.raw(), handle_b.0.raw());
+            let key = (raw_a.min(raw_b), raw_a.max(raw_b));
+            let event_type = if self.active.contains_key(&key) {
+                CollisionEventType::Ongoing
+            } else {
+                CollisionEventType::Started
+            };
+            events.push(CollisionEvent {
+                event_type,
+                entity_a,
+                entity_b,
+                contacts,
+            });
+            current.insert(key, (entity_a, entity_b));
+        }
+
+        for (key, (entity_a, entity_b)) in &self.active {
+            if !current.contains_key(key) {
+                events.push(CollisionEvent {
+                    event_type: CollisionEventType::Ended,
+                    entity_a: *entity_a,
+                    entity_b: *entity_b,
+                    contacts: Vec::new(),
+                });
+            }
+        }
+
+        self.active = current;
+    }
+}
+
+impl SnapshotCodec for ContactTracker {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        let active: Vec<((u64, u64), (Id, Id))> = self
+            .active
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect();
+        writer.write(&active);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let active: Vec<((u64, u64), (Id, Id))> = reader.read()?;
+        Ok(Self {
+            active: active.into_iter().collect(),
+        })
+    }
+}
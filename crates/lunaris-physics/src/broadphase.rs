@@ -0,0 +1,311 @@
+//! Broadphase collision detection
+//!
+//! Axis-aligned bounding boxes and an incremental sweep-and-prune that
+//! finds candidate pairs for the narrowphase.
+
+use glam::Vec3;
+
+/// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    /// Minimum corner
+    pub min: Vec3,
+    /// Maximum corner
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// An empty box that any union will replace
+    pub const EMPTY: Self = Self {
+        min: Vec3::splat(f32::MAX),
+        max: Vec3::splat(f32::MIN),
+    };
+
+    /// Create a box from corners
+    #[must_use]
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Create a box from a center and half extents
+    #[must_use]
+    pub fn from_center_half_extents(center: Vec3, half_extents: Vec3) -> Self {
+        Self {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    /// Smallest box containing a set of points
+    #[must_use]
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |aabb, p| Self {
+            min: aabb.min.min(p),
+            max: aabb.max.max(p),
+        })
+    }
+
+    /// Check if the box contains nothing
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.min.cmpgt(self.max).any()
+    }
+
+    /// Box center
+    #[must_use]
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Box half extents
+    #[must_use]
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// Smallest box containing both boxes
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grow the box by a margin on every side
+    #[must_use]
+    pub fn expanded(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - Vec3::splat(margin),
+            max: self.max + Vec3::splat(margin),
+        }
+    }
+
+    /// Check if two boxes overlap
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Check if a point is inside the box
+    #[must_use]
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.min.cmple(point).all() && point.cmple(self.max).all()
+    }
+
+    /// Slab test against a ray, returning the entry distance
+    #[must_use]
+    pub fn ray_intersection(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let mut t_min = 0.0_f32;
+        let mut t_max = max_distance;
+
+        for axis in 0..3 {
+            let o = origin[axis];
+            let d = direction[axis];
+            if d.abs() < f32::EPSILON {
+                if o < self.min[axis] || o > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let inv = 1.0 / d;
+            let mut t0 = (self.min[axis] - o) * inv;
+            let mut t1 = (self.max[axis] - o) * inv;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_min > t_max {
+                return None;
+            }
+        }
+
+        Some(t_min)
+    }
+}
+
+/// Incremental sweep-and-prune along the X axis
+///
+/// Proxies keep their order between updates, so the insertion sort that
+/// restores the ordering is close to linear when objects move coherently.
+#[derive(Debug, Default)]
+pub struct SweepAndPrune {
+    proxies: Vec<(u64, Aabb)>,
+}
+
+impl SweepAndPrune {
+    /// Create an empty broadphase
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of proxies
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.proxies.len()
+    }
+
+    /// Check if there are no proxies
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.proxies.is_empty()
+    }
+
+    /// Replace the proxy set, reusing the previous ordering
+    ///
+    /// Keys missing from `proxies` are removed and new keys are appended
+    /// before the list is re-sorted.
+    pub fn update(&mut self, proxies: &[(u64, Aabb)]) {
+        let mut incoming: std::collections::BTreeMap<u64, Aabb> = proxies.iter().copied().collect();
+
+        self.proxies
+            .retain_mut(|(key, aabb)| match incoming.remove(key) {
+                Some(new_aabb) => {
+                    *aabb = new_aabb;
+                    true
+                },
+                None => false,
+            });
+        self.proxies.extend(incoming);
+
+        // Insertion sort: cheap for nearly sorted input
+        for i in 1..self.proxies.len() {
+            let mut j = i;
+            while j > 0 && Self::less(&self.proxies[j], &self.proxies[j - 1]) {
+                self.proxies.swap(j, j - 1);
+                j -= 1;
+            }
+        }
+    }
+
+    fn less(a: &(u64, Aabb), b: &(u64, Aabb)) -> bool {
+        match a.1.min.x.total_cmp(&b.1.min.x) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Equal => a.0 < b.0,
+            std::cmp::Ordering::Greater => false,
+        }
+    }
+
+    /// Find all overlapping pairs
+    ///
+    /// Each pair is returned once as `(smaller_key, larger_key)`, sorted so
+    /// the output does not depend on insertion history.
+    #[must_use]
+    pub fn pairs(&self) -> Vec<(u64, u64)> {
+        let mut pairs = Vec::new();
+        let mut active: Vec<usize> = Vec::new();
+
+        for (i, (key, aabb)) in self.proxies.iter().enumerate() {
+            active.retain(|&j| self.proxies[j].1.max.x >= aabb.min.x);
+            for &j in &active {
+                let (other_key, other) = &self.proxies[j];
+                if aabb.intersects(other) {
+                    pairs.push(((*key).min(*other_key), (*key).max(*other_key)));
+                }
+            }
+            active.push(i);
+        }
+
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Find all proxies overlapping a box
+    #[must_use]
+    pub fn query(&self, aabb: &Aabb) -> Vec<u64> {
+        let mut keys: Vec<u64> = self
+            .proxies
+            .iter()
+            .take_while(|(_, proxy)| proxy.min.x <= aabb.max.x)
+            .filter(|(_, proxy)| proxy.intersects(aabb))
+            .map(|(key, _)| *key)
+            .collect();
+        keys.sort_unstable();
+        keys
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box(center: Vec3) -> Aabb {
+        Aabb::from_center_half_extents(center, Vec3::splat(0.5))
+    }
+
+    fn brute_force_pairs(proxies: &[(u64, Aabb)]) -> Vec<(u64, u64)> {
+        let mut pairs = Vec::new();
+        for (i, (a, aabb_a)) in proxies.iter().enumerate() {
+            for (b, aabb_b) in &proxies[i + 1..] {
+                if aabb_a.intersects(aabb_b) {
+                    pairs.push(((*a).min(*b), (*a).max(*b)));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    #[test]
+    fn sweep_and_prune_matches_brute_force_while_proxies_move() {
+        let mut proxies: Vec<(u64, Aabb)> = (0..40u64)
+            .map(|key| {
+                let center = Vec3::new(7.3, 3.1, 1.7) * key as f32 % Vec3::new(10.0, 4.0, 3.0);
+                (key, unit_box(center))
+            })
+            .collect();
+        let mut broadphase = SweepAndPrune::new();
+
+        for step in 0..5u64 {
+            broadphase.update(&proxies);
+            assert_eq!(broadphase.len(), proxies.len());
+            assert_eq!(broadphase.pairs(), brute_force_pairs(&proxies));
+
+            for (key, aabb) in &mut proxies {
+                let shift = Vec3::X * (((*key + step) % 3) as f32 - 1.0) * 0.4;
+                *aabb = Aabb::new(aabb.min + shift, aabb.max + shift);
+            }
+            proxies.remove(step as usize * 3);
+        }
+    }
+
+    #[test]
+    fn query_returns_sorted_overlapping_keys() {
+        let mut broadphase = SweepAndPrune::new();
+        broadphase.update(&[
+            (3, unit_box(Vec3::ZERO)),
+            (1, unit_box(Vec3::new(0.8, 0.0, 0.0))),
+            (2, unit_box(Vec3::new(5.0, 0.0, 0.0))),
+        ]);
+        assert_eq!(
+            broadphase.query(&unit_box(Vec3::new(0.4, 0.0, 0.0))),
+            vec![1, 3]
+        );
+        assert!(broadphase
+            .query(&unit_box(Vec3::new(0.0, 3.0, 0.0)))
+            .is_empty());
+
+        broadphase.update(&[(2, unit_box(Vec3::ZERO))]);
+        assert_eq!(broadphase.len(), 1);
+        assert_eq!(broadphase.query(&unit_box(Vec3::ZERO)), vec![2]);
+    }
+
+    #[test]
+    fn rays_enter_boxes_at_the_near_face() {
+        let aabb = unit_box(Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(aabb.ray_intersection(Vec3::ZERO, Vec3::X, 10.0), Some(4.5));
+        assert_eq!(aabb.ray_intersection(Vec3::ZERO, Vec3::X, 4.0), None);
+        assert_eq!(aabb.ray_intersection(Vec3::ZERO, Vec3::Y, 10.0), None);
+        assert_eq!(
+            aabb.ray_intersection(Vec3::new(5.0, 0.0, 0.0), Vec3::X, 1.0),
+            Some(0.0)
+        );
+    }
+}
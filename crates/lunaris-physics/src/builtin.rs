@@ -0,0 +1,1119 @@
+//! Built-in physics backend
+//!
+//! Sweep-and-prune broadphase, GJK/EPA narrowphase and a sequential
+//! impulse solver operating directly on engine types.
+
+use crate::{
+    backend::{ContactTracker, PhysicsBackend, StepEvents},
+    broadphase::{Aabb, SweepAndPrune},
+    collision::{
+        ColliderShape, ColliderShape3D, CollisionEvent, CollisionLayers, ContactPoint,
+        OverlapQuery, RaycastHit, RaycastQuery, ShapeCastQuery,
+    },
+    constraints::Constraint,
+    midphase::{transform_aabb, TriMeshBvh},
+    narrowphase::{self, from_glam, to_glam, Contact, Isometry, ShapePart},
+    rigidbody::{
+        ColliderProperties, ForceMode, RigidbodyHandle, RigidbodyProperties, RigidbodyState,
+        RigidbodyType,
+    },
+    snapshot::{SnapshotCodec, SnapshotReader, SnapshotWriter},
+    solver::{ContactInput, ContactSolver, JointInput, JointSolver, SolverBody, SolverConfig},
+};
+use glam::{Mat3, Quat};
+use lunaris_core::{id::Id, math::Vec3, Result};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+/// Speed under which a body counts as resting
+const SLEEP_VELOCITY_THRESHOLD: f32 = 0.05;
+/// Seconds a body must rest before it falls asleep
+const TIME_TO_SLEEP: f32 = 0.5;
+
+/// The built-in simulation backend
+pub struct BuiltinBackend {
+    solver_config: SolverConfig,
+    bodies: HashMap<RigidbodyHandle, RigidbodyData>,
+    constraints: BTreeMap<u64, Constraint>,
+    /// Joint impulses of the last step, used to warm start the next
+    joint_impulses: BTreeMap<u64, Vec<f32>>,
+    broadphase: SweepAndPrune,
+    contact_tracker: ContactTracker,
+}
+
+struct RigidbodyData {
+    entity_id: Id,
+    properties: RigidbodyProperties,
+    state: RigidbodyState,
+    orientation: Quat,
+    collider: Option<ColliderData>,
+    pending_forces: Vec<(Vec3, ForceMode)>,
+    pending_torques: Vec<(Vec3, ForceMode)>,
+    /// Asleep bodies are neither integrated nor pushed by the solver
+    sleeping: bool,
+    /// Time spent below the sleep velocity threshold
+    sleep_timer: f32,
+}
+
+/// Contacts found between two bodies during a step
+struct ContactManifold {
+    body_a: RigidbodyHandle,
+    body_b: RigidbodyHandle,
+    contacts: Vec<Contact>,
+    sensor: bool,
+}
+
+struct ColliderData {
+    shape: ColliderShape,
+    properties: ColliderProperties,
+    offset: Vec3,
+    /// Triangle hierarchy of a mesh collider, built once
+    bvh: Option<TriMeshBvh>,
+}
+
+impl ColliderData {
+    fn new(shape: ColliderShape, properties: ColliderProperties, offset: Vec3) -> Self {
+        let bvh = match &shape {
+            ColliderShape::Shape3D(ColliderShape3D::TriMesh { vertices, indices }) => {
+                Some(TriMeshBvh::new(vertices, indices))
+            },
+            _ => None,
+        };
+        Self {
+            shape,
+            properties,
+            offset,
+            bvh,
+        }
+    }
+}
+
+/// Geometry a body brings to the narrowphase
+enum BodyGeometry<'a> {
+    /// Convex parts in world space
+    Parts(Vec<ShapePart<'a>>),
+    /// Triangle mesh placed at an isometry
+    Mesh(&'a TriMeshBvh, Isometry),
+}
+
+impl BodyGeometry<'_> {
+    /// Parts that can touch a world-space box
+    fn parts_near(&self, aabb: &Aabb) -> Cow<'_, [ShapePart<'_>]> {
+        match self {
+            Self::Parts(parts) => Cow::Borrowed(parts),
+            Self::Mesh(bvh, isometry) => Cow::Owned(bvh.parts_near(*isometry, aabb)),
+        }
+    }
+}
+
+impl RigidbodyData {
+    fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.sleep_timer = 0.0;
+    }
+
+    /// Whether the body is still or about to sleep, so touching it should
+    /// not wake its neighbours
+    fn is_resting(&self) -> bool {
+        match self.properties.body_type {
+            RigidbodyType::Static => true,
+            RigidbodyType::Kinematic => {
+                self.state.linear_velocity.length_squared() == 0.0
+                    && self.state.angular_velocity.length_squared() == 0.0
+            },
+            RigidbodyType::Dynamic => self.sleeping || self.sleep_timer > 0.0,
+        }
+    }
+
+    fn isometry(&self) -> Isometry {
+        Isometry::new(to_glam(self.state.position), self.orientation)
+    }
+
+    /// World transform of the collider, including its offset
+    fn collider_isometry(&self, collider: &ColliderData) -> Isometry {
+        let offset = Isometry::new(to_glam(collider.offset), Quat::IDENTITY);
+        self.isometry().mul(&offset)
+    }
+
+    /// Convex parts of the 3D collider in world space
+    ///
+    /// 2D shapes are not handled by the built-in 3D pipeline.
+    fn shape_parts(&self) -> Option<Vec<ShapePart<'_>>> {
+        let collider = self.collider.as_ref()?;
+        let ColliderShape::Shape3D(shape) = &collider.shape else {
+            return None;
+        };
+        Some(narrowphase::shape_parts(
+            shape,
+            self.collider_isometry(collider),
+        ))
+    }
+
+    /// Collider geometry and its world bounds for collision detection
+    ///
+    /// Meshes are returned whole with their hierarchy instead of one part
+    /// per triangle.
+    fn geometry(&self) -> Option<(BodyGeometry<'_>, Aabb)> {
+        let collider = self.collider.as_ref()?;
+        if let Some(bvh) = &collider.bvh {
+            let isometry = self.collider_isometry(collider);
+            let aabb = transform_aabb(&bvh.bounds(), &isometry);
+            return Some((BodyGeometry::Mesh(bvh, isometry), aabb));
+        }
+        let parts = self.shape_parts()?;
+        let aabb = parts
+            .iter()
+            .map(ShapePart::aabb)
+            .fold(Aabb::EMPTY, |acc, aabb| acc.union(&aabb));
+        Some((BodyGeometry::Parts(parts), aabb))
+    }
+
+    /// Inverse inertia tensor in world space, honoring rotation locks
+    fn world_inverse_inertia(&self) -> Mat3 {
+        if self.properties.body_type != RigidbodyType::Dynamic {
+            return Mat3::ZERO;
+        }
+        let local = self
+            .collider
+            .as_ref()
+            .and_then(|c| match &c.shape {
+                ColliderShape::Shape3D(shape) => Some(shape_inertia(shape, self.properties.mass)),
+                ColliderShape::Shape2D(_) => None,
+            })
+            .unwrap_or_else(|| glam::Vec3::splat(0.1 * self.properties.mass));
+        let inverse = glam::Vec3::new(
+            if local.x > 0.0 { 1.0 / local.x } else { 0.0 },
+            if local.y > 0.0 { 1.0 / local.y } else { 0.0 },
+            if local.z > 0.0 { 1.0 / local.z } else { 0.0 },
+        );
+        let rotation = Mat3::from_quat(self.orientation);
+        let world = rotation * Mat3::from_diagonal(inverse) * rotation.transpose();
+
+        let lock = glam::Vec3::new(
+            if self.properties.lock_rotation_x {
+                0.0
+            } else {
+                1.0
+            },
+            if self.properties.lock_rotation_y {
+                0.0
+            } else {
+                1.0
+            },
+            if self.properties.lock_rotation_z {
+                0.0
+            } else {
+                1.0
+            },
+        );
+        let lock = Mat3::from_diagonal(lock);
+        lock * world * lock
+    }
+
+    fn lock_angular_velocity(&mut self) {
+        if self.properties.lock_rotation_x {
+            self.state.angular_velocity.x = 0.0;
+        }
+        if self.properties.lock_rotation_y {
+            self.state.angular_velocity.y = 0.0;
+        }
+        if self.properties.lock_rotation_z {
+            self.state.angular_velocity.z = 0.0;
+        }
+    }
+
+    fn solver_body(&self) -> SolverBody {
+        let position = to_glam(self.state.position);
+        let linear_velocity = to_glam(self.state.linear_velocity);
+        let angular_velocity = to_glam(self.state.angular_velocity);
+        if self.properties.body_type != RigidbodyType::Dynamic
+            || self.properties.mass <= 0.0
+            || self.sleeping
+        {
+            return SolverBody::fixed(position, linear_velocity, angular_velocity);
+        }
+        SolverBody {
+            inv_mass: 1.0 / self.properties.mass,
+            inv_inertia: self.world_inverse_inertia(),
+            position,
+            linear_velocity,
+            angular_velocity,
+        }
+    }
+}
+
+/// Principal moments of inertia of a shape with uniform density
+fn shape_inertia(shape: &ColliderShape3D, mass: f32) -> glam::Vec3 {
+    let box_inertia = |h: glam::Vec3| {
+        let size = h * 2.0;
+        glam::Vec3::new(
+            size.y * size.y + size.z * size.z,
+            size.x * size.x + size.z * size.z,
+            size.x * size.x + size.y * size.y,
+        ) * (mass / 12.0)
+    };
+    let cylinder_inertia = |half_height: f32, radius: f32| {
+        let height = half_height * 2.0;
+        let side = mass * (3.0 * radius * radius + height * height) / 12.0;
+        glam::Vec3::new(side, 0.5 * mass * radius * radius, side)
+    };
+
+    match shape {
+        ColliderShape3D::Sphere { radius } => glam::Vec3::splat(0.4 * mass * radius * radius),
+        ColliderShape3D::Box { half_extents } => box_inertia(to_glam(*half_extents)),
+        ColliderShape3D::Capsule {
+            half_height,
+            radius,
+        } => cylinder_inertia(half_height + radius, *radius),
+        ColliderShape3D::Cylinder {
+            half_height,
+            radius,
+        } => cylinder_inertia(*half_height, *radius),
+        _ => {
+            let aabb = narrowphase::shape_parts(shape, Isometry::IDENTITY)
+                .iter()
+                .map(ShapePart::aabb)
+                .fold(Aabb::EMPTY, |acc, aabb| acc.union(&aabb));
+            if aabb.is_empty() {
+                glam::Vec3::splat(0.1 * mass)
+            } else {
+                box_inertia(aabb.half_extents())
+            }
+        },
+    }
+}
+
+impl Default for BuiltinBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BuiltinBackend {
+    /// Create a backend with the default solver settings
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_solver_config(SolverConfig::default())
+    }
+
+    /// Create a backend with custom solver settings
+    #[must_use]
+    pub fn with_solver_config(solver_config: SolverConfig) -> Self {
+        Self {
+            solver_config,
+            bodies: HashMap::new(),
+            constraints: BTreeMap::new(),
+            joint_impulses: BTreeMap::new(),
+            broadphase: SweepAndPrune::new(),
+            contact_tracker: ContactTracker::new(),
+        }
+    }
+
+    /// Bodies a scene query may report, with their convex parts
+    ///
+    /// Scans every body rather than the broadphase, which is only refreshed
+    /// during a step and would miss bodies moved since.
+    fn query_candidates(
+        &self,
+        layers: CollisionLayers,
+        exclude: Option<Id>,
+        include_sensors: bool,
+    ) -> impl Iterator<Item = (&RigidbodyData, Vec<ShapePart<'_>>)> {
+        self.sorted_handles().into_iter().filter_map(move |handle| {
+            let body = &self.bodies[&handle];
+            let collider = body.collider.as_ref()?;
+            if Some(body.entity_id) == exclude
+                || (body.properties.is_sensor && !include_sensors)
+                || !layers.can_interact(collider.properties.layers)
+            {
+                return None;
+            }
+            Some((body, body.shape_parts()?))
+        })
+    }
+
+    /// Handles in a stable order so stepping does not depend on hash order
+    fn sorted_handles(&self) -> Vec<RigidbodyHandle> {
+        let mut handles: Vec<RigidbodyHandle> = self.bodies.keys().copied().collect();
+        handles.sort_unstable_by_key(|h| h.0.raw());
+        handles
+    }
+
+    fn integrate_forces(&mut self, dt: f32, gravity: Vec3) {
+        for body in self.bodies.values_mut() {
+            if body.properties.body_type != RigidbodyType::Dynamic {
+                body.pending_forces.clear();
+                body.pending_torques.clear();
+                continue;
+            }
+            if body.sleeping {
+                continue;
+            }
+
+            let gravity = gravity * body.properties.gravity_scale;
+            let mass = body.properties.mass;
+            let inv_inertia = body.world_inverse_inertia();
+
+            // Apply pending forces
+            for (force, mode) in body.pending_forces.drain(..) {
+                match mode {
+                    ForceMode::Force => {
+                        body.state.linear_velocity += force * dt / mass;
+                    },
+                    ForceMode::Impulse => {
+                        body.state.linear_velocity += force / mass;
+                    },
+                    ForceMode::Acceleration => {
+                        body.state.linear_velocity += force * dt;
+                    },
+                    ForceMode::VelocityChange => {
+                        body.state.linear_velocity += force;
+                    },
+                }
+            }
+
+            // Apply pending torques
+            for (torque, mode) in body.pending_torques.drain(..) {
+                let torque = to_glam(torque);
+                let delta = match mode {
+                    ForceMode::Force => inv_inertia * torque * dt,
+                    ForceMode::Impulse => inv_inertia * torque,
+                    ForceMode::Acceleration => torque * dt,
+                    ForceMode::VelocityChange => torque,
+                };
+                body.state.angular_velocity += from_glam(delta);
+            }
+
+            // Apply gravity
+            body.state.linear_velocity += gravity * dt;
+
+            // Apply damping
+            body.state.linear_velocity *= 1.0 - body.properties.linear_damping * dt;
+            body.state.angular_velocity *= 1.0 - body.properties.angular_damping * dt;
+            body.lock_angular_velocity();
+        }
+    }
+
+    /// Broadphase and narrowphase over all 3D colliders
+    fn detect_collisions(&mut self, handles: &[RigidbodyHandle]) -> Vec<ContactManifold> {
+        let bodies = &self.bodies;
+        let mut geometry: HashMap<u64, (BodyGeometry<'_>, Aabb)> = HashMap::new();
+        let mut proxies: Vec<(u64, Aabb)> = Vec::new();
+
+        for handle in handles {
+            let body = &bodies[handle];
+            let Some((body_geometry, aabb)) = body.geometry() else {
+                continue;
+            };
+            if aabb.is_empty() {
+                continue;
+            }
+            proxies.push((handle.0.raw(), aabb));
+            geometry.insert(handle.0.raw(), (body_geometry, aabb));
+        }
+
+        self.broadphase.update(&proxies);
+
+        let mut manifolds = Vec::new();
+        for (key_a, key_b) in self.broadphase.pairs() {
+            let handle_a = RigidbodyHandle(Id::from_raw(key_a));
+            let handle_b = RigidbodyHandle(Id::from_raw(key_b));
+            let (a, b) = (&bodies[&handle_a], &bodies[&handle_b]);
+
+            if a.properties.body_type != RigidbodyType::Dynamic
+                && b.properties.body_type != RigidbodyType::Dynamic
+            {
+                continue;
+            }
+            let (Some(collider_a), Some(collider_b)) = (&a.collider, &b.collider) else {
+                continue;
+            };
+            if !collider_a
+                .properties
+                .layers
+                .can_interact(collider_b.properties.layers)
+            {
+                continue;
+            }
+
+            // Meshes only contribute the triangles near the other body
+            let (geometry_a, aabb_a) = &geometry[&key_a];
+            let (geometry_b, aabb_b) = &geometry[&key_b];
+            let contacts = narrowphase::collide_shapes(
+                &geometry_a.parts_near(aabb_b),
+                &geometry_b.parts_near(aabb_a),
+            );
+            if !contacts.is_empty() {
+                manifolds.push(ContactManifold {
+                    body_a: handle_a,
+                    body_b: handle_b,
+                    contacts,
+                    sensor: a.properties.is_sensor || b.properties.is_sensor,
+                });
+            }
+        }
+
+        manifolds
+    }
+
+    /// Wake sleeping bodies touched by moving ones
+    fn wake_touched(&mut self, manifolds: &[ContactManifold]) {
+        for manifold in manifolds.iter().filter(|m| !m.sensor) {
+            let pair = [
+                (manifold.body_a, manifold.body_b),
+                (manifold.body_b, manifold.body_a),
+            ];
+            for (sleeper, other) in pair {
+                if self.bodies[&sleeper].sleeping && !self.bodies[&other].is_resting() {
+                    if let Some(body) = self.bodies.get_mut(&sleeper) {
+                        body.wake_up();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Put bodies to sleep once they have rested long enough
+    fn update_sleep(&mut self, dt: f32) {
+        let threshold = SLEEP_VELOCITY_THRESHOLD * SLEEP_VELOCITY_THRESHOLD;
+        for body in self.bodies.values_mut() {
+            if body.properties.body_type != RigidbodyType::Dynamic || body.sleeping {
+                continue;
+            }
+            if body.state.linear_velocity.length_squared() < threshold
+                && body.state.angular_velocity.length_squared() < threshold
+            {
+                body.sleep_timer += dt;
+                if body.sleep_timer >= TIME_TO_SLEEP {
+                    body.sleeping = true;
+                    body.state.linear_velocity = Vec3::ZERO;
+                    body.state.angular_velocity = Vec3::ZERO;
+                }
+            } else {
+                body.sleep_timer = 0.0;
+            }
+        }
+    }
+
+    /// Wake sleeping bodies jointed to moving ones or driven by a motor
+    fn wake_jointed(&mut self) {
+        for constraint in self.constraints.values().filter(|c| c.enabled) {
+            let a = RigidbodyHandle(Id::from_raw(constraint.body_a));
+            let b = constraint
+                .body_b
+                .map(|raw| RigidbodyHandle(Id::from_raw(raw)));
+            let driven = constraint.motor.is_some_and(|m| m.enabled);
+            let moving = |handle: &RigidbodyHandle| {
+                self.bodies
+                    .get(handle)
+                    .is_some_and(|body| !body.sleeping && !body.is_resting())
+            };
+            let wake = driven || moving(&a) || b.as_ref().is_some_and(moving);
+            if wake {
+                for handle in std::iter::once(a).chain(b) {
+                    if let Some(body) = self.bodies.get_mut(&handle) {
+                        if body.sleeping {
+                            body.wake_up();
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resolve contacts and joints with sequential impulses
+    ///
+    /// Returns the ids of joints that exceeded their break thresholds.
+    fn solve_constraints(
+        &mut self,
+        handles: &[RigidbodyHandle],
+        manifolds: &[ContactManifold],
+        dt: f32,
+    ) -> Vec<u64> {
+        if manifolds.iter().all(|m| m.sensor) && self.constraints.is_empty() {
+            return Vec::new();
+        }
+
+        let indices: HashMap<RigidbodyHandle, usize> =
+            handles.iter().enumerate().map(|(i, h)| (*h, i)).collect();
+        let mut solver_bodies: Vec<SolverBody> = handles
+            .iter()
+            .map(|h| self.bodies[h].solver_body())
+            .collect();
+        // World attachments are solved against a fixed body at the origin
+        let world = solver_bodies.len();
+        solver_bodies.push(SolverBody::fixed(
+            glam::Vec3::ZERO,
+            glam::Vec3::ZERO,
+            glam::Vec3::ZERO,
+        ));
+
+        let mut joints = JointSolver::new(self.solver_config);
+        for constraint in self.constraints.values() {
+            if !constraint.enabled || constraint.is_broken {
+                continue;
+            }
+            let handle_a = RigidbodyHandle(Id::from_raw(constraint.body_a));
+            let Some(&body_a) = indices.get(&handle_a) else {
+                continue;
+            };
+            let (body_b, orientation_b) = match constraint.body_b {
+                Some(raw) => {
+                    let handle_b = RigidbodyHandle(Id::from_raw(raw));
+                    let Some(&body_b) = indices.get(&handle_b) else {
+                        continue;
+                    };
+                    (body_b, self.bodies[&handle_b].orientation)
+                },
+                None => (world, Quat::IDENTITY),
+            };
+            joints.add_joint(
+                &solver_bodies,
+                JointInput {
+                    constraint,
+                    body_a,
+                    body_b,
+                    orientation_a: self.bodies[&handle_a].orientation,
+                    orientation_b,
+                },
+                dt,
+            );
+        }
+
+        let mut solver = ContactSolver::new(self.solver_config);
+        for manifold in manifolds.iter().filter(|m| !m.sensor) {
+            let a = &self.bodies[&manifold.body_a];
+            let b = &self.bodies[&manifold.body_b];
+            let (Some(collider_a), Some(collider_b)) = (&a.collider, &b.collider) else {
+                continue;
+            };
+            let friction = (collider_a.properties.friction * collider_b.properties.friction).sqrt();
+            let restitution = collider_a
+                .properties
+                .restitution
+                .max(collider_b.properties.restitution);
+
+            for contact in &manifold.contacts {
+                solver.add_contact(
+                    &solver_bodies,
+                    ContactInput {
+                        body_a: indices[&manifold.body_a],
+                        body_b: indices[&manifold.body_b],
+                        position: contact.position,
+                        normal: contact.normal,
+                        depth: contact.depth,
+                        friction,
+                        restitution,
+                    },
+                    dt,
+                );
+            }
+        }
+
+        joints.warm_start(&mut solver_bodies, &self.joint_impulses);
+        for _ in 0..self.solver_config.iterations {
+            joints.iterate(&mut solver_bodies);
+            solver.iterate(&mut solver_bodies);
+        }
+
+        for (handle, solved) in handles.iter().zip(&solver_bodies) {
+            let body = self
+                .bodies
+                .get_mut(handle)
+                .expect("handle from sorted_handles");
+            if body.properties.body_type == RigidbodyType::Dynamic && !body.sleeping {
+                body.state.linear_velocity = from_glam(solved.linear_velocity);
+                body.state.angular_velocity = from_glam(solved.angular_velocity);
+                body.lock_angular_velocity();
+            }
+        }
+
+        self.joint_impulses = joints.impulses();
+        joints.broken(dt)
+    }
+
+    /// Earliest impact of a CCD body's translation this step against static
+    /// and kinematic colliders, as `(distance, normal, restitution)`
+    ///
+    /// Kinematic bodies are swept against at their start-of-step pose.
+    fn time_of_impact(
+        &self,
+        body: &RigidbodyData,
+        direction: glam::Vec3,
+        max_distance: f32,
+    ) -> Option<(f32, glam::Vec3, f32)> {
+        let collider = body.collider.as_ref()?;
+        let moving = body.shape_parts()?;
+        let start = moving
+            .iter()
+            .map(ShapePart::aabb)
+            .fold(Aabb::EMPTY, |acc, aabb| acc.union(&aabb));
+        let offset = direction * max_distance;
+        let swept = start.union(&Aabb::new(start.min + offset, start.max + offset));
+
+        self.query_candidates(collider.properties.layers, Some(body.entity_id), false)
+            .filter(|(target, _)| target.properties.body_type != RigidbodyType::Dynamic)
+            .filter_map(|(target, parts)| {
+                let restitution = target
+                    .collider
+                    .as_ref()
+                    .map_or(0.0, |c| c.properties.restitution)
+                    .max(collider.properties.restitution);
+                parts
+                    .iter()
+                    .filter(|part| part.aabb().intersects(&swept))
+                    .flat_map(|part| {
+                        moving.iter().filter_map(move |m| {
+                            narrowphase::cast_part(m, direction, part, max_distance)
+                        })
+                    })
+                    // Already touching: the discrete contacts handle it
+                    .filter(|(hit, _)| hit.normal != glam::Vec3::ZERO && hit.distance > 0.0)
+                    .map(|(hit, _)| (hit.distance, hit.normal, restitution))
+                    .min_by(|a, b| a.0.total_cmp(&b.0))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    /// Stop fast CCD bodies at their time of impact instead of letting them
+    /// tunnel through thin colliders
+    ///
+    /// Only bodies moving further than half their thinnest extent in a step
+    /// are swept. A clamped body is moved to the impact point, its velocity
+    /// into the surface is reflected with the pair's restitution and the
+    /// rest of its motion this step is dropped. Returns the translation each
+    /// clamped body makes this step.
+    fn continuous_collisions(
+        &mut self,
+        handles: &[RigidbodyHandle],
+        dt: f32,
+    ) -> HashMap<RigidbodyHandle, Vec3> {
+        let mut impacts = Vec::new();
+        for handle in handles {
+            let body = &self.bodies[handle];
+            if !body.properties.ccd_enabled
+                || body.properties.body_type != RigidbodyType::Dynamic
+                || body.properties.is_sensor
+                || body.sleeping
+            {
+                continue;
+            }
+            let Some(parts) = body.shape_parts() else {
+                continue;
+            };
+            let thinnest = parts
+                .iter()
+                .map(ShapePart::aabb)
+                .fold(Aabb::EMPTY, |acc, aabb| acc.union(&aabb))
+                .half_extents()
+                .min_element();
+
+            let translation = to_glam(body.state.linear_velocity) * dt;
+            let length = translation.length();
+            if length <= thinnest {
+                continue;
+            }
+            let direction = translation / length;
+            if let Some((distance, normal, restitution)) =
+                self.time_of_impact(body, direction, length)
+            {
+                impacts.push((*handle, direction * distance, normal, restitution));
+            }
+        }
+
+        let mut travel = HashMap::new();
+        for (handle, translation, normal, restitution) in impacts {
+            let Some(body) = self.bodies.get_mut(&handle) else {
+                continue;
+            };
+            let velocity = to_glam(body.state.linear_velocity);
+            let approach = velocity.dot(normal);
+            if approach < 0.0 {
+                body.state.linear_velocity =
+                    from_glam(velocity - normal * approach * (1.0 + restitution));
+            }
+            travel.insert(handle, from_glam(translation));
+        }
+        travel
+    }
+
+    /// Move bodies by their velocities, or by their clamped CCD translation
+    fn integrate_positions(&mut self, dt: f32, travel: &HashMap<RigidbodyHandle, Vec3>) {
+        for (handle, body) in &mut self.bodies {
+            if body.properties.body_type == RigidbodyType::Static || body.sleeping {
+                continue;
+            }
+
+            body.state.position += travel
+                .get(handle)
+                .copied()
+                .unwrap_or(body.state.linear_velocity * dt);
+
+            let omega = to_glam(body.state.angular_velocity);
+            if omega.length_squared() > 0.0 {
+                let spin = Quat::from_xyzw(omega.x, omega.y, omega.z, 0.0) * body.orientation;
+                body.orientation = (body.orientation + spin * (0.5 * dt)).normalize();
+                body.state.rotation = from_glam(narrowphase::quat_to_euler(body.orientation));
+            }
+        }
+    }
+
+    /// Emit Started/Ongoing/Ended events by diffing touching pairs
+    fn update_collision_events(
+        &mut self,
+        manifolds: &[ContactManifold],
+        events: &mut Vec<CollisionEvent>,
+    ) {
+        let bodies = &self.bodies;
+        let touching = manifolds.iter().map(|manifold| {
+            let contacts = manifold
+                .contacts
+                .iter()
+                .map(|c| ContactPoint {
+                    position: from_glam(c.position),
+                    normal: from_glam(c.normal),
+                    depth: c.depth,
+                })
+                .collect();
+            (
+                manifold.body_a,
+                manifold.body_b,
+                bodies[&manifold.body_a].entity_id,
+                bodies[&manifold.body_b].entity_id,
+                contacts,
+            )
+        });
+        self.contact_tracker.update(touching, events);
+    }
+}
+
+impl PhysicsBackend for BuiltinBackend {
+    fn name(&self) -> &'static str {
+        "builtin"
+    }
+
+    fn insert_body(
+        &mut self,
+        handle: RigidbodyHandle,
+        entity_id: Id,
+        properties: &RigidbodyProperties,
+        position: Vec3,
+    ) {
+        self.bodies.insert(
+            handle,
+            RigidbodyData {
+                entity_id,
+                properties: properties.clone(),
+                state: RigidbodyState {
+                    position,
+                    ..Default::default()
+                },
+                orientation: Quat::IDENTITY,
+                collider: None,
+                pending_forces: Vec::new(),
+                pending_torques: Vec::new(),
+                sleeping: false,
+                sleep_timer: 0.0,
+            },
+        );
+    }
+
+    fn remove_body(&mut self, handle: RigidbodyHandle) {
+        if self.bodies.remove(&handle).is_none() {
+            return;
+        }
+        // Bodies resting on or jointed to the removed body have to move again
+        let raw = handle.0.raw();
+        let jointed = self.constraints.values().filter_map(|c| match c.body_b {
+            Some(b) if c.body_a == raw => Some(b),
+            Some(b) if b == raw => Some(c.body_a),
+            _ => None,
+        });
+        let neighbours: Vec<RigidbodyHandle> = jointed
+            .map(|raw| RigidbodyHandle(Id::from_raw(raw)))
+            .chain(self.contact_tracker.touching(handle))
+            .collect();
+        for neighbour in neighbours {
+            if let Some(body) = self.bodies.get_mut(&neighbour) {
+                body.wake_up();
+            }
+        }
+    }
+
+    fn set_collider(
+        &mut self,
+        handle: RigidbodyHandle,
+        shape: &ColliderShape,
+        properties: &ColliderProperties,
+        offset: Vec3,
+    ) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.collider = Some(ColliderData::new(shape.clone(), properties.clone(), offset));
+            body.wake_up();
+        }
+    }
+
+    fn state(&self, handle: RigidbodyHandle) -> Option<RigidbodyState> {
+        self.bodies.get(&handle).map(|b| b.state)
+    }
+
+    fn set_position(&mut self, handle: RigidbodyHandle, position: Vec3) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.state.position = position;
+            body.wake_up();
+        }
+    }
+
+    fn set_rotation(&mut self, handle: RigidbodyHandle, rotation: Vec3) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.state.rotation = rotation;
+            body.orientation = narrowphase::euler_to_quat(to_glam(rotation));
+            body.wake_up();
+        }
+    }
+
+    fn set_linear_velocity(&mut self, handle: RigidbodyHandle, velocity: Vec3) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.state.linear_velocity = velocity;
+            body.wake_up();
+        }
+    }
+
+    fn apply_force(&mut self, handle: RigidbodyHandle, force: Vec3, mode: ForceMode) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.pending_forces.push((force, mode));
+            body.wake_up();
+        }
+    }
+
+    fn apply_torque(&mut self, handle: RigidbodyHandle, torque: Vec3, mode: ForceMode) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.pending_torques.push((torque, mode));
+            body.wake_up();
+        }
+    }
+
+    fn is_sleeping(&self, handle: RigidbodyHandle) -> bool {
+        self.bodies.get(&handle).is_some_and(|b| b.sleeping)
+    }
+
+    fn wake_up(&mut self, handle: RigidbodyHandle) {
+        if let Some(body) = self.bodies.get_mut(&handle) {
+            body.wake_up();
+        }
+    }
+
+    fn insert_constraint(&mut self, constraint: &Constraint) {
+        let bodies = [Some(constraint.body_a), constraint.body_b];
+        for raw in bodies.into_iter().flatten() {
+            self.wake_up(RigidbodyHandle(Id::from_raw(raw)));
+        }
+        self.constraints.insert(constraint.id, constraint.clone());
+    }
+
+    fn remove_constraint(&mut self, id: u64) {
+        if let Some(constraint) = self.constraints.remove(&id) {
+            let bodies = [Some(constraint.body_a), constraint.body_b];
+            for raw in bodies.into_iter().flatten() {
+                self.wake_up(RigidbodyHandle(Id::from_raw(raw)));
+            }
+        }
+        self.joint_impulses.remove(&id);
+    }
+
+    fn step(&mut self, dt: f32, gravity: Vec3) -> StepEvents {
+        let handles = self.sorted_handles();
+        let mut events = StepEvents::default();
+
+        self.integrate_forces(dt, gravity);
+        let manifolds = self.detect_collisions(&handles);
+        self.wake_touched(&manifolds);
+        self.wake_jointed();
+        events.broken_constraints = self.solve_constraints(&handles, &manifolds, dt);
+        let travel = self.continuous_collisions(&handles, dt);
+        self.integrate_positions(dt, &travel);
+        self.update_sleep(dt);
+        self.update_collision_events(&manifolds, &mut events.collisions);
+
+        events
+    }
+
+    fn raycast_all(&self, query: &RaycastQuery) -> Vec<RaycastHit> {
+        let origin = to_glam(query.origin);
+        let direction = to_glam(query.direction);
+        let mut hits: Vec<RaycastHit> = self
+            .query_candidates(query.layers, query.exclude, false)
+            .filter_map(|(body, parts)| {
+                let hit = parts
+                    .iter()
+                    .filter(|part| {
+                        part.aabb()
+                            .ray_intersection(origin, direction, query.max_distance)
+                            .is_some()
+                    })
+                    .filter_map(|part| {
+                        narrowphase::cast_ray(part, origin, direction, query.max_distance)
+                    })
+                    .min_by(|a, b| a.distance.total_cmp(&b.distance))?;
+                let normal = if hit.normal == glam::Vec3::ZERO {
+                    -direction
+                } else {
+                    hit.normal
+                };
+                Some(RaycastHit {
+                    entity: body.entity_id,
+                    point: from_glam(origin + direction * hit.distance),
+                    normal: from_glam(normal),
+                    distance: hit.distance,
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        hits
+    }
+
+    fn shape_cast(&self, query: &ShapeCastQuery) -> Option<RaycastHit> {
+        let direction = to_glam(query.direction);
+        let isometry = Isometry::from_euler(to_glam(query.origin), to_glam(query.rotation));
+        let moving = narrowphase::shape_parts(&query.shape, isometry);
+        let start = moving
+            .iter()
+            .map(ShapePart::aabb)
+            .fold(Aabb::EMPTY, |acc, aabb| acc.union(&aabb));
+        let offset = direction * query.max_distance;
+        let swept = start.union(&Aabb::new(start.min + offset, start.max + offset));
+
+        self.query_candidates(query.layers, query.exclude, false)
+            .filter_map(|(body, parts)| {
+                let (hit, point) = parts
+                    .iter()
+                    .filter(|target| target.aabb().intersects(&swept))
+                    .flat_map(|target| {
+                        moving.iter().filter_map(move |part| {
+                            narrowphase::cast_part(part, direction, target, query.max_distance)
+                        })
+                    })
+                    .min_by(|a, b| a.0.distance.total_cmp(&b.0.distance))?;
+                let normal = if hit.normal == glam::Vec3::ZERO {
+                    -direction
+                } else {
+                    hit.normal
+                };
+                Some(RaycastHit {
+                    entity: body.entity_id,
+                    point: from_glam(point),
+                    normal: from_glam(normal),
+                    distance: hit.distance,
+                })
+            })
+            .min_by(|a, b| a.distance.total_cmp(&b.distance))
+    }
+
+    fn overlap(&self, query: &OverlapQuery) -> Vec<Id> {
+        let isometry = Isometry::from_euler(to_glam(query.position), to_glam(query.rotation));
+        let shape = narrowphase::shape_parts(&query.shape, isometry);
+        let bounds = shape
+            .iter()
+            .map(ShapePart::aabb)
+            .fold(Aabb::EMPTY, |acc, aabb| acc.union(&aabb));
+
+        self.query_candidates(query.layers, query.exclude, true)
+            .filter(|(_, parts)| {
+                parts
+                    .iter()
+                    .filter(|part| part.aabb().intersects(&bounds))
+                    .any(|part| {
+                        shape.iter().any(|q| {
+                            q.aabb().intersects(&part.aabb())
+                                && narrowphase::parts_intersect(q, part)
+                        })
+                    })
+            })
+            .map(|(body, _)| body.entity_id)
+            .collect()
+    }
+
+    fn save_state(&self, writer: &mut SnapshotWriter) -> Result<()> {
+        let handles = self.sorted_handles();
+        writer.write(&(handles.len() as u32));
+        for handle in handles {
+            writer.write(&handle);
+            writer.write(&self.bodies[&handle]);
+        }
+        let constraints: Vec<Constraint> = self.constraints.values().cloned().collect();
+        writer.write(&constraints);
+        let impulses: Vec<(u64, Vec<f32>)> = self
+            .joint_impulses
+            .iter()
+            .map(|(id, i)| (*id, i.clone()))
+            .collect();
+        writer.write(&impulses);
+        writer.write(&self.contact_tracker);
+        Ok(())
+    }
+
+    fn load_state(&mut self, mut reader: SnapshotReader<'_>) -> Result<()> {
+        let count: u32 = reader.read()?;
+        let bodies = (0..count)
+            .map(|_| reader.read::<(RigidbodyHandle, RigidbodyData)>())
+            .collect::<Result<HashMap<_, _>>>()?;
+        let constraints: Vec<Constraint> = reader.read()?;
+        let impulses: Vec<(u64, Vec<f32>)> = reader.read()?;
+        let contact_tracker = reader.read()?;
+        reader.finish()?;
+
+        self.bodies = bodies;
+        self.constraints = constraints.into_iter().map(|c| (c.id, c)).collect();
+        self.joint_impulses = impulses.into_iter().collect();
+        self.contact_tracker = contact_tracker;
+        self.broadphase = SweepAndPrune::new();
+        Ok(())
+    }
+}
+
+impl SnapshotCodec for ColliderData {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.shape);
+        writer.write(&self.properties);
+        writer.write(&self.offset);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(Self::new(reader.read()?, reader.read()?, reader.read()?))
+    }
+}
+
+impl SnapshotCodec for RigidbodyData {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.entity_id);
+        writer.write(&self.properties);
+        writer.write(&self.state);
+        writer.write(&self.orientation);
+        writer.write(&self.collider);
+        writer.write(&self.pending_forces);
+        writer.write(&self.pending_torques);
+        writer.write(&(self.sleeping, self.sleep_timer));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let entity_id = reader.read()?;
+        let properties = reader.read()?;
+        let state = reader.read()?;
+        let orientation = reader.read()?;
+        let collider = reader.read()?;
+        let pending_forces = reader.read()?;
+        let pending_torques = reader.read()?;
+        let (sleeping, sleep_timer) = reader.read()?;
+        Ok(Self {
+            entity_id,
+            properties,
+            state,
+            orientation,
+            collider,
+            pending_forces,
+            pending_torques,
+            sleeping,
+            sleep_timer,
+        })
+    }
+}
@@ -2,7 +2,14 @@
 //!
 //! Physics-based character movement for games.
 
-use lunaris_core::math::{Vec2, Vec3};
+use crate::{
+    collision::{ColliderShape3D, CollisionLayers, ShapeCastQuery},
+    world::PhysicsWorld,
+};
+use lunaris_core::{
+    id::Id,
+    math::{Vec2, Vec3},
+};
 
 /// 2D Character controller configuration
 #[derive(Debug, Clone)]
@@ -207,6 +214,8 @@ pub struct CharacterController3DConfig {
     pub step_height: f32,
     /// Slope limit (degrees)
     pub slope_limit: f32,
+    /// Layers the ground probe collides with
+    pub ground_layers: CollisionLayers,
 }
 
 impl Default for CharacterController3DConfig {
@@ -223,6 +232,7 @@ impl Default for CharacterController3DConfig {
             radius: 0.3,
             step_height: 0.3,
             slope_limit: 45.0,
+            ground_layers: CollisionLayers::default(),
         }
     }
 }
@@ -303,4 +313,33 @@ impl CharacterController3D {
             self.velocity.y = 0.0;
         }
     }
+
+    /// Probe for ground under a capsule centered at `position`
+    ///
+    /// Sweeps the capsule's bottom sphere down by the step height and calls
+    /// [`Self::set_grounded`] with the result. Ground steeper than the slope
+    /// limit does not count. `owner` is the character's own entity, which
+    /// the probe ignores.
+    pub fn update_grounded(&mut self, world: &PhysicsWorld, position: Vec3, owner: Option<Id>) {
+        let radius = self.config.radius;
+        let foot_offset = (self.config.height * 0.5 - radius).max(0.0);
+        let origin = Vec3::new(position.x, position.y - foot_offset, position.z);
+
+        let mut query = ShapeCastQuery::new(
+            ColliderShape3D::sphere(radius),
+            origin,
+            Vec3::new(0.0, -1.0, 0.0),
+            self.config.step_height,
+        )
+        .with_layers(self.config.ground_layers);
+        if let Some(owner) = owner {
+            query = query.excluding(owner);
+        }
+
+        let min_normal_y = self.config.slope_limit.to_radians().cos();
+        match world.shape_cast(&query) {
+            Some(hit) if hit.normal.y >= min_normal_y => self.set_grounded(true, hit.normal),
+            _ => self.set_grounded(false, Vec3::new(0.0, 1.0, 0.0)),
+        }
+    }
 }
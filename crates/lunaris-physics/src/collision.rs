@@ -184,7 +184,7 @@ pub struct ContactPoint {
     pub depth: f32,
 }
 
-/// Raycast hit result
+/// Raycast or shape cast hit result
 #[derive(Debug, Clone)]
 pub struct RaycastHit {
     /// Entity that was hit
@@ -193,7 +193,7 @@ pub struct RaycastHit {
     pub point: Vec3,
     /// Surface normal at hit point
     pub normal: Vec3,
-    /// Distance from ray origin
+    /// Distance travelled along the ray or sweep
     pub distance: f32,
 }
 
@@ -208,6 +208,8 @@ pub struct RaycastQuery {
     pub max_distance: f32,
     /// Collision layers to query
     pub layers: CollisionLayers,
+    /// Entity ignored by the query
+    pub exclude: Option<lunaris_core::id::Id>,
 }
 
 impl RaycastQuery {
@@ -219,6 +221,127 @@ impl RaycastQuery {
             direction: direction.normalize(),
             max_distance,
             layers: CollisionLayers::default(),
+            exclude: None,
         }
     }
+
+    /// Only hit colliders these layers can interact with
+    #[must_use]
+    pub fn with_layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Ignore an entity (typically the caster itself)
+    #[must_use]
+    pub fn excluding(mut self, entity: lunaris_core::id::Id) -> Self {
+        self.exclude = Some(entity);
+        self
+    }
+}
+
+/// Shape cast (sweep) query
+#[derive(Debug, Clone)]
+pub struct ShapeCastQuery {
+    /// Shape to sweep
+    pub shape: ColliderShape3D,
+    /// Start position of the shape
+    pub origin: Vec3,
+    /// Shape rotation (euler angles in radians)
+    pub rotation: Vec3,
+    /// Sweep direction (normalized)
+    pub direction: Vec3,
+    /// Maximum sweep distance
+    pub max_distance: f32,
+    /// Collision layers to query
+    pub layers: CollisionLayers,
+    /// Entity ignored by the query
+    pub exclude: Option<lunaris_core::id::Id>,
+}
+
+impl ShapeCastQuery {
+    /// Create a new shape cast query
+    #[must_use]
+    pub fn new(shape: ColliderShape3D, origin: Vec3, direction: Vec3, max_distance: f32) -> Self {
+        Self {
+            shape,
+            origin,
+            rotation: Vec3::ZERO,
+            direction: direction.normalize(),
+            max_distance,
+            layers: CollisionLayers::default(),
+            exclude: None,
+        }
+    }
+
+    /// Set the shape rotation
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Vec3) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Only hit colliders these layers can interact with
+    #[must_use]
+    pub fn with_layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Ignore an entity (typically the caster itself)
+    #[must_use]
+    pub fn excluding(mut self, entity: lunaris_core::id::Id) -> Self {
+        self.exclude = Some(entity);
+        self
+    }
+}
+
+/// Overlap query
+#[derive(Debug, Clone)]
+pub struct OverlapQuery {
+    /// Shape to test
+    pub shape: ColliderShape3D,
+    /// Shape position
+    pub position: Vec3,
+    /// Shape rotation (euler angles in radians)
+    pub rotation: Vec3,
+    /// Collision layers to query
+    pub layers: CollisionLayers,
+    /// Entity ignored by the query
+    pub exclude: Option<lunaris_core::id::Id>,
+}
+
+impl OverlapQuery {
+    /// Create a new overlap query
+    #[must_use]
+    pub fn new(shape: ColliderShape3D, position: Vec3) -> Self {
+        Self {
+            shape,
+            position,
+            rotation: Vec3::ZERO,
+            layers: CollisionLayers::default(),
+            exclude: None,
+        }
+    }
+
+    /// Set the shape rotation
+    #[must_use]
+    pub fn with_rotation(mut self, rotation: Vec3) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Only report colliders these layers can interact with
+    #[must_use]
+    pub fn with_layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Ignore an entity (typically the caller itself)
+    #[must_use]
+    pub fn excluding(mut self, entity: lunaris_core::id::Id) -> Self {
+        self.exclude = Some(entity);
+        self
+    }
 }
@@ -2,6 +2,7 @@
 //!
 //! Joint and constraint systems for physics simulation.
 
+use crate::snapshot::{SnapshotCodec, SnapshotReader, SnapshotWriter};
 use glam::{Quat, Vec3};
 
 /// Constraint type
@@ -50,6 +51,12 @@ pub struct Constraint {
     pub break_torque: Option<f32>,
     /// Is broken
     pub is_broken: bool,
+    /// Limits on the free axis (radians for hinges, meters for sliders)
+    pub limits: Option<(f32, f32)>,
+    /// Motor driving the free axis
+    pub motor: Option<Motor>,
+    /// Spring pulling the free axis towards its rest position
+    pub spring: Option<Spring>,
 }
 
 impl Constraint {
@@ -69,6 +76,9 @@ impl Constraint {
             break_force: None,
             break_torque: None,
             is_broken: false,
+            limits: None,
+            motor: None,
+            spring: None,
         }
     }
 
@@ -87,6 +97,27 @@ impl Constraint {
         self.break_torque = Some(torque);
         self
     }
+
+    /// Limit the free axis of a hinge or slider
+    #[must_use]
+    pub fn with_limits(mut self, min: f32, max: f32) -> Self {
+        self.limits = Some((min, max));
+        self
+    }
+
+    /// Drive the free axis of a hinge or slider
+    #[must_use]
+    pub fn with_motor(mut self, motor: Motor) -> Self {
+        self.motor = Some(motor);
+        self
+    }
+
+    /// Spring the free axis of a hinge or slider towards `rest_length`
+    #[must_use]
+    pub fn with_spring(mut self, spring: Spring) -> Self {
+        self.spring = Some(spring);
+        self
+    }
 }
 
 /// Motor for powered constraints
@@ -100,6 +131,18 @@ pub struct Motor {
     pub enabled: bool,
 }
 
+impl Motor {
+    /// Create an enabled motor
+    #[must_use]
+    pub fn new(target_velocity: f32, max_force: f32) -> Self {
+        Self {
+            target_velocity,
+            max_force,
+            enabled: true,
+        }
+    }
+}
+
 impl Default for Motor {
     fn default() -> Self {
         Self {
@@ -111,6 +154,8 @@ impl Default for Motor {
 }
 
 /// Spring constraint
+///
+/// On a hinge `rest_length` is the rest angle in radians.
 #[derive(Debug, Clone, Copy)]
 pub struct Spring {
     /// Spring stiffness
@@ -271,6 +316,11 @@ impl ConstraintManager {
         self.constraints.iter_mut().find(|c| c.id == id)
     }
 
+    /// Iterate over all constraints
+    pub fn iter(&self) -> impl Iterator<Item = &Constraint> {
+        self.constraints.iter()
+    }
+
     /// Update all constraints
     pub fn update(&mut self, delta_time: f32) {
         // Update rope simulations
@@ -294,3 +344,19 @@ impl ConstraintManager {
         self.constraints.retain(|c| !c.is_broken);
     }
 }
+
+impl SnapshotCodec for ConstraintManager {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.next_id);
+        writer.write(&self.constraints);
+        writer.write(&self.ropes);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> lunaris_core::Result<Self> {
+        Ok(Self {
+            next_id: reader.read()?,
+            constraints: reader.read()?,
+            ropes: reader.read()?,
+        })
+    }
+}
@@ -16,14 +16,21 @@
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+pub mod backend;
+pub mod broadphase;
+pub mod builtin;
 pub mod chaos;
 pub mod character;
 pub mod cloth;
 pub mod collision;
 pub mod constraints;
 pub mod destruction;
+pub mod midphase;
+pub mod narrowphase;
 pub mod ragdoll;
 pub mod rigidbody;
+pub mod snapshot;
+pub mod solver;
 pub mod vehicle;
 pub mod world;
 
@@ -33,11 +40,18 @@ pub mod physics2d;
 #[cfg(feature = "3d")]
 pub mod physics3d;
 
+#[cfg(any(feature = "2d", feature = "3d"))]
+mod rapier;
+
+pub use backend::{PhysicsBackend, PhysicsBackendKind};
 pub use character::{CharacterController2D, CharacterController3D};
-pub use collision::{ColliderShape, CollisionEvent, CollisionLayers};
+pub use collision::{
+    ColliderShape, CollisionEvent, CollisionLayers, OverlapQuery, RaycastHit, RaycastQuery, ShapeCastQuery,
+};
 pub use constraints::{Constraint, ConstraintManager, ConstraintType, RopeConstraint};
-pub use ragdoll::{Joint, JointConfig, JointType, RagdollConfig, RagdollController};
+pub use ragdoll::{Joint, JointConfig, JointType, RagdollBuilder, RagdollConfig, RagdollController};
 pub use rigidbody::{RigidbodyHandle, RigidbodyType};
+pub use snapshot::PhysicsSnapshot;
 pub use world::PhysicsWorld;
 
 use lunaris_core::Result;
@@ -51,6 +65,8 @@ pub struct PhysicsConfig {
     pub timestep: f32,
     /// Maximum substeps per frame
     pub max_substeps: u32,
+    /// Simulation backend
+    pub backend: PhysicsBackendKind,
 }
 
 impl Default for PhysicsConfig {
@@ -59,6 +75,7 @@ impl Default for PhysicsConfig {
             gravity: lunaris_core::math::Vec3::new(0.0, -9.81, 0.0),
             timestep: 1.0 / 60.0,
             max_substeps: 4,
+            backend: PhysicsBackendKind::default(),
         }
     }
 }
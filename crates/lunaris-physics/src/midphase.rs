@@ -0,0 +1,165 @@
+//! Midphase collision detection
+//!
+//! Bounding volume hierarchy over the triangles of a mesh collider, built
+//! once when the collider is created so the narrowphase only visits the
+//! triangles near the other shape instead of the whole mesh.
+
+use crate::broadphase::Aabb;
+use crate::narrowphase::{to_glam, ConvexPrimitive, Isometry, ShapePart};
+use glam::Vec3;
+use lunaris_core::math::Vec3 as EngineVec3;
+
+/// Triangles kept together in one leaf
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+/// BVH node covering `triangles[start..start + count]`
+///
+/// Leaves have no children; inner nodes store their second child index,
+/// the first one directly follows the node.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    aabb: Aabb,
+    start: usize,
+    count: usize,
+    right: Option<usize>,
+}
+
+/// Bounding volume hierarchy over mesh triangles in the mesh's local frame
+#[derive(Debug, Clone, Default)]
+pub struct TriMeshBvh {
+    triangles: Vec<[Vec3; 3]>,
+    nodes: Vec<Node>,
+}
+
+impl TriMeshBvh {
+    /// Build the hierarchy for a triangle mesh
+    ///
+    /// Triangles with out-of-range indices are skipped.
+    #[must_use]
+    pub fn new(vertices: &[EngineVec3], indices: &[[u32; 3]]) -> Self {
+        let fetch = |i: u32| vertices.get(i as usize).map(|v| to_glam(*v));
+        let mut triangles: Vec<[Vec3; 3]> = indices
+            .iter()
+            .filter_map(|tri| Some([fetch(tri[0])?, fetch(tri[1])?, fetch(tri[2])?]))
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * triangles.len() / MAX_LEAF_TRIANGLES + 1);
+        if !triangles.is_empty() {
+            let count = triangles.len();
+            build(&mut triangles, 0, count, &mut nodes);
+        }
+        Self { triangles, nodes }
+    }
+
+    /// Number of triangles
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Check if the mesh has no triangles
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Bounds of the whole mesh in its local frame
+    #[must_use]
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.aabb)
+    }
+
+    /// Triangles whose bounds overlap a box in the mesh's local frame
+    #[must_use]
+    pub fn query(&self, aabb: &Aabb) -> Vec<[Vec3; 3]> {
+        let mut found = Vec::new();
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.aabb.intersects(aabb) {
+                continue;
+            }
+            match node.right {
+                Some(right) => {
+                    stack.push(right);
+                    stack.push(index + 1);
+                },
+                None => found.extend(
+                    self.triangles[node.start..node.start + node.count]
+                        .iter()
+                        .filter(|tri| Aabb::from_points(tri.iter().copied()).intersects(aabb)),
+                ),
+            }
+        }
+        found
+    }
+
+    /// Triangles near a world-space box, as parts placed at `isometry`
+    #[must_use]
+    pub fn parts_near(&self, isometry: Isometry, aabb: &Aabb) -> Vec<ShapePart<'static>> {
+        let local = transform_aabb(aabb, &isometry.inverse());
+        self.query(&local)
+            .into_iter()
+            .map(|vertices| ShapePart {
+                primitive: ConvexPrimitive::Triangle { vertices },
+                isometry,
+            })
+            .collect()
+    }
+}
+
+/// Build the subtree for `triangles[start..start + count]`, returning its index
+fn build(triangles: &mut [[Vec3; 3]], start: usize, count: usize, nodes: &mut Vec<Node>) -> usize {
+    let slice = &mut triangles[start..start + count];
+    let aabb = Aabb::from_points(slice.iter().flatten().copied());
+    let index = nodes.len();
+    nodes.push(Node {
+        aabb,
+        start,
+        count,
+        right: None,
+    });
+    if count <= MAX_LEAF_TRIANGLES {
+        return index;
+    }
+
+    // Median split along the longest axis of the centroids
+    let centroid = |tri: &[Vec3; 3]| (tri[0] + tri[1] + tri[2]) / 3.0;
+    let centers = Aabb::from_points(slice.iter().map(centroid));
+    let extent = centers.max - centers.min;
+    let axis = if extent.x >= extent.y && extent.x >= extent.z {
+        0
+    } else if extent.y >= extent.z {
+        1
+    } else {
+        2
+    };
+    let half = count / 2;
+    slice.select_nth_unstable_by(half, |a, b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+
+    build(triangles, start, half, nodes);
+    let right = build(triangles, start + half, count - half, nodes);
+    nodes[index].right = Some(right);
+    index
+}
+
+/// Bounds of a box after a rigid transform
+#[must_use]
+pub fn transform_aabb(aabb: &Aabb, isometry: &Isometry) -> Aabb {
+    if aabb.is_empty() {
+        return *aabb;
+    }
+    Aabb::from_points((0..8).map(|corner| {
+        let pick = |bit: usize, axis: usize| {
+            if corner & bit == 0 {
+                aabb.min[axis]
+            } else {
+                aabb.max[axis]
+            }
+        };
+        isometry.transform_point(Vec3::new(pick(1, 0), pick(2, 1), pick(4, 2)))
+    }))
+}
@@ -0,0 +1,1386 @@
+//! Narrowphase collision detection
+//!
+//! Exact contact generation between collider shapes. Shapes are decomposed
+//! into convex parts; common rounded pairs are solved analytically and
+//! everything else goes through GJK/EPA followed by feature clipping to
+//! build multi-point manifolds.
+
+use crate::broadphase::Aabb;
+use crate::collision::ColliderShape3D;
+use glam::{EulerRot, Quat, Vec3};
+use lunaris_core::math::Vec3 as EngineVec3;
+
+/// Relative tolerance used to pick faces and edges as support features
+const FEATURE_TOLERANCE: f32 = 0.05;
+/// Distance under which clipped points still count as touching
+const CONTACT_TOLERANCE: f32 = 1.0e-3;
+/// Maximum number of contacts kept per part pair
+const MAX_MANIFOLD_POINTS: usize = 4;
+const GJK_MAX_ITERATIONS: usize = 64;
+const EPA_MAX_ITERATIONS: usize = 64;
+const EPA_TOLERANCE: f32 = 1.0e-4;
+/// Distance at which a cast counts as touching
+const CAST_TOLERANCE: f32 = 1.0e-4;
+
+/// Rigid transform (translation + rotation)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Isometry {
+    /// Translation
+    pub translation: Vec3,
+    /// Rotation
+    pub rotation: Quat,
+}
+
+impl Default for Isometry {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Isometry {
+    /// Identity transform
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+    };
+
+    /// Create a transform
+    #[must_use]
+    pub const fn new(translation: Vec3, rotation: Quat) -> Self {
+        Self {
+            translation,
+            rotation,
+        }
+    }
+
+    /// Create a transform from a translation and euler angles (pitch, yaw, roll)
+    #[must_use]
+    pub fn from_euler(translation: Vec3, euler: Vec3) -> Self {
+        Self::new(translation, euler_to_quat(euler))
+    }
+
+    /// Transform a point into world space
+    #[must_use]
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation * point + self.translation
+    }
+
+    /// Rotate a vector into world space
+    #[must_use]
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation * vector
+    }
+
+    /// Transform a world point into local space
+    #[must_use]
+    pub fn inverse_transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation.inverse() * (point - self.translation)
+    }
+
+    /// Rotate a world vector into local space
+    #[must_use]
+    pub fn inverse_transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation.inverse() * vector
+    }
+
+    /// Compose with a child transform expressed in this frame
+    #[must_use]
+    pub fn mul(&self, child: &Self) -> Self {
+        Self {
+            translation: self.transform_point(child.translation),
+            rotation: (self.rotation * child.rotation).normalize(),
+        }
+    }
+
+    /// Inverse transform
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        Self {
+            translation: rotation * -self.translation,
+            rotation,
+        }
+    }
+
+    /// Interpolate towards `other`, slerping the rotation
+    #[must_use]
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+        }
+    }
+}
+
+/// Convert engine euler angles (pitch, yaw, roll) to a quaternion
+#[must_use]
+pub fn euler_to_quat(euler: Vec3) -> Quat {
+    Quat::from_euler(EulerRot::YXZ, euler.y, euler.x, euler.z)
+}
+
+/// Convert a quaternion to engine euler angles (pitch, yaw, roll)
+#[must_use]
+pub fn quat_to_euler(rotation: Quat) -> Vec3 {
+    let (yaw, pitch, roll) = rotation.to_euler(EulerRot::YXZ);
+    Vec3::new(pitch, yaw, roll)
+}
+
+/// Convex primitive in its local frame
+///
+/// Capsules and cylinders are aligned with the local Y axis.
+#[derive(Debug, Clone, Copy)]
+pub enum ConvexPrimitive<'a> {
+    /// Sphere
+    Sphere {
+        /// Radius
+        radius: f32,
+    },
+    /// Box with half extents
+    Box {
+        /// Half extents
+        half_extents: Vec3,
+    },
+    /// Capsule
+    Capsule {
+        /// Half height of the core segment
+        half_height: f32,
+        /// Radius
+        radius: f32,
+    },
+    /// Cylinder
+    Cylinder {
+        /// Half height
+        half_height: f32,
+        /// Radius
+        radius: f32,
+    },
+    /// Convex hull
+    Hull {
+        /// Hull vertices
+        vertices: &'a [EngineVec3],
+    },
+    /// Single triangle (from a triangle mesh)
+    Triangle {
+        /// Triangle corners
+        vertices: [Vec3; 3],
+    },
+}
+
+impl ConvexPrimitive<'_> {
+    /// Furthest point along a local direction
+    #[must_use]
+    pub fn local_support(&self, dir: Vec3) -> Vec3 {
+        match *self {
+            Self::Sphere { radius } => dir.normalize_or_zero() * radius,
+            Self::Box { half_extents } => Vec3::new(
+                sign(dir.x) * half_extents.x,
+                sign(dir.y) * half_extents.y,
+                sign(dir.z) * half_extents.z,
+            ),
+            Self::Capsule {
+                half_height,
+                radius,
+            } => Vec3::new(0.0, sign(dir.y) * half_height, 0.0) + dir.normalize_or_zero() * radius,
+            Self::Cylinder {
+                half_height,
+                radius,
+            } => {
+                let radial = Vec3::new(dir.x, 0.0, dir.z).normalize_or_zero() * radius;
+                radial + Vec3::new(0.0, sign(dir.y) * half_height, 0.0)
+            },
+            Self::Hull { vertices } => max_dot(vertices.iter().map(|v| to_glam(*v)), dir),
+            Self::Triangle { vertices } => max_dot(vertices.iter().copied(), dir),
+        }
+    }
+
+    /// Points of the support feature (vertex, edge or face) along a local direction
+    ///
+    /// Faces are returned in winding order so they can be used for clipping.
+    #[must_use]
+    pub fn local_feature(&self, dir: Vec3) -> Vec<Vec3> {
+        let dir = dir.normalize_or_zero();
+        match *self {
+            Self::Sphere { .. } => vec![self.local_support(dir)],
+            Self::Box { half_extents } => {
+                let free: Vec<usize> = (0..3)
+                    .filter(|&i| dir[i].abs() < FEATURE_TOLERANCE)
+                    .collect();
+                let fixed = Vec3::new(
+                    sign(dir.x) * half_extents.x,
+                    sign(dir.y) * half_extents.y,
+                    sign(dir.z) * half_extents.z,
+                );
+                let with = |p: Vec3, axis: usize, s: f32| {
+                    let mut p = p;
+                    p[axis] = s * half_extents[axis];
+                    p
+                };
+                match free.as_slice() {
+                    [a] => vec![with(fixed, *a, 1.0), with(fixed, *a, -1.0)],
+                    [a, b] => vec![
+                        with(with(fixed, *a, 1.0), *b, 1.0),
+                        with(with(fixed, *a, -1.0), *b, 1.0),
+                        with(with(fixed, *a, -1.0), *b, -1.0),
+                        with(with(fixed, *a, 1.0), *b, -1.0),
+                    ],
+                    _ => vec![fixed],
+                }
+            },
+            Self::Capsule {
+                half_height,
+                radius,
+            } => {
+                if dir.y.abs() < FEATURE_TOLERANCE {
+                    let side = Vec3::new(dir.x, 0.0, dir.z).normalize_or_zero() * radius;
+                    vec![
+                        side + Vec3::new(0.0, half_height, 0.0),
+                        side - Vec3::new(0.0, half_height, 0.0),
+                    ]
+                } else {
+                    vec![self.local_support(dir)]
+                }
+            },
+            Self::Cylinder {
+                half_height,
+                radius,
+            } => {
+                if dir.y.abs() > 1.0 - FEATURE_TOLERANCE {
+                    let y = sign(dir.y) * half_height;
+                    let mut cap: Vec<Vec3> = (0..8)
+                        .map(|i| {
+                            let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+                            Vec3::new(angle.cos() * radius, y, angle.sin() * radius)
+                        })
+                        .collect();
+                    if dir.y < 0.0 {
+                        cap.reverse();
+                    }
+                    cap
+                } else if dir.y.abs() < FEATURE_TOLERANCE {
+                    let side = Vec3::new(dir.x, 0.0, dir.z).normalize_or_zero() * radius;
+                    vec![
+                        side + Vec3::new(0.0, half_height, 0.0),
+                        side - Vec3::new(0.0, half_height, 0.0),
+                    ]
+                } else {
+                    vec![self.local_support(dir)]
+                }
+            },
+            Self::Hull { vertices } => {
+                let vertices: Vec<Vec3> = vertices.iter().map(|v| to_glam(*v)).collect();
+                polytope_feature(&vertices, dir)
+            },
+            Self::Triangle { vertices } => polytope_feature(&vertices, dir),
+        }
+    }
+
+    /// Bounding box in the local frame
+    #[must_use]
+    pub fn local_aabb(&self) -> Aabb {
+        match *self {
+            Self::Sphere { radius } => {
+                Aabb::from_center_half_extents(Vec3::ZERO, Vec3::splat(radius))
+            },
+            Self::Box { half_extents } => Aabb::from_center_half_extents(Vec3::ZERO, half_extents),
+            Self::Capsule {
+                half_height,
+                radius,
+            } => Aabb::from_center_half_extents(
+                Vec3::ZERO,
+                Vec3::new(radius, half_height + radius, radius),
+            ),
+            Self::Cylinder {
+                half_height,
+                radius,
+            } => Aabb::from_center_half_extents(Vec3::ZERO, Vec3::new(radius, half_height, radius)),
+            Self::Hull { vertices } => Aabb::from_points(vertices.iter().map(|v| to_glam(*v))),
+            Self::Triangle { vertices } => Aabb::from_points(vertices),
+        }
+    }
+}
+
+/// A convex primitive placed in world space
+#[derive(Debug, Clone, Copy)]
+pub struct ShapePart<'a> {
+    /// Primitive geometry
+    pub primitive: ConvexPrimitive<'a>,
+    /// World transform of the primitive
+    pub isometry: Isometry,
+}
+
+impl ShapePart<'_> {
+    /// Furthest point along a world direction
+    #[must_use]
+    pub fn support(&self, dir: Vec3) -> Vec3 {
+        let local = self
+            .primitive
+            .local_support(self.isometry.inverse_transform_vector(dir));
+        self.isometry.transform_point(local)
+    }
+
+    /// Support feature along a world direction, in world space
+    #[must_use]
+    pub fn feature(&self, dir: Vec3) -> Vec<Vec3> {
+        self.primitive
+            .local_feature(self.isometry.inverse_transform_vector(dir))
+            .into_iter()
+            .map(|p| self.isometry.transform_point(p))
+            .collect()
+    }
+
+    /// World-space bounding box
+    #[must_use]
+    pub fn aabb(&self) -> Aabb {
+        match self.primitive {
+            ConvexPrimitive::Sphere { radius } => {
+                Aabb::from_center_half_extents(self.isometry.translation, Vec3::splat(radius))
+            },
+            ConvexPrimitive::Triangle { vertices } => {
+                Aabb::from_points(vertices.iter().map(|v| self.isometry.transform_point(*v)))
+            },
+            _ => {
+                let axes = [Vec3::X, Vec3::Y, Vec3::Z];
+                let min = Vec3::new(
+                    self.support(-axes[0]).x,
+                    self.support(-axes[1]).y,
+                    self.support(-axes[2]).z,
+                );
+                let max = Vec3::new(
+                    self.support(axes[0]).x,
+                    self.support(axes[1]).y,
+                    self.support(axes[2]).z,
+                );
+                Aabb::new(min, max)
+            },
+        }
+    }
+}
+
+/// Decompose a collider shape into convex parts placed at `isometry`
+///
+/// Triangle meshes yield one part per triangle and compounds are flattened
+/// recursively.
+#[must_use]
+pub fn shape_parts(shape: &ColliderShape3D, isometry: Isometry) -> Vec<ShapePart<'_>> {
+    let mut parts = Vec::new();
+    collect_parts(shape, isometry, &mut parts);
+    parts
+}
+
+fn collect_parts<'a>(
+    shape: &'a ColliderShape3D,
+    isometry: Isometry,
+    parts: &mut Vec<ShapePart<'a>>,
+) {
+    let primitive = match shape {
+        ColliderShape3D::Sphere { radius } => ConvexPrimitive::Sphere { radius: *radius },
+        ColliderShape3D::Box { half_extents } => ConvexPrimitive::Box {
+            half_extents: to_glam(*half_extents),
+        },
+        ColliderShape3D::Capsule {
+            half_height,
+            radius,
+        } => ConvexPrimitive::Capsule {
+            half_height: *half_height,
+            radius: *radius,
+        },
+        ColliderShape3D::Cylinder {
+            half_height,
+            radius,
+        } => ConvexPrimitive::Cylinder {
+            half_height: *half_height,
+            radius: *radius,
+        },
+        ColliderShape3D::ConvexHull { vertices } => {
+            if vertices.is_empty() {
+                return;
+            }
+            ConvexPrimitive::Hull { vertices }
+        },
+        ColliderShape3D::TriMesh { vertices, indices } => {
+            for tri in indices {
+                let fetch = |i: u32| vertices.get(i as usize).map(|v| to_glam(*v));
+                if let (Some(a), Some(b), Some(c)) = (fetch(tri[0]), fetch(tri[1]), fetch(tri[2])) {
+                    parts.push(ShapePart {
+                        primitive: ConvexPrimitive::Triangle {
+                            vertices: [a, b, c],
+                        },
+                        isometry,
+                    });
+                }
+            }
+            return;
+        },
+        ColliderShape3D::Compound { shapes } => {
+            for (position, rotation, child) in shapes {
+                let local = Isometry::from_euler(to_glam(*position), to_glam(*rotation));
+                collect_parts(child, isometry.mul(&local), parts);
+            }
+            return;
+        },
+    };
+    parts.push(ShapePart {
+        primitive,
+        isometry,
+    });
+}
+
+/// Convert an engine vector to glam
+#[must_use]
+pub fn to_glam(v: EngineVec3) -> Vec3 {
+    Vec3::new(v.x, v.y, v.z)
+}
+
+/// Convert a glam vector to the engine type
+#[must_use]
+pub fn from_glam(v: Vec3) -> EngineVec3 {
+    EngineVec3::new(v.x, v.y, v.z)
+}
+
+/// Contact between two parts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// World position (midway between the surfaces)
+    pub position: Vec3,
+    /// Contact normal pointing from A to B
+    pub normal: Vec3,
+    /// Penetration depth
+    pub depth: f32,
+}
+
+/// Compute contacts between two sets of convex parts
+#[must_use]
+pub fn collide_shapes(parts_a: &[ShapePart<'_>], parts_b: &[ShapePart<'_>]) -> Vec<Contact> {
+    let aabbs_b: Vec<Aabb> = parts_b.iter().map(ShapePart::aabb).collect();
+    let mut contacts = Vec::new();
+
+    for a in parts_a {
+        let aabb_a = a.aabb();
+        for (b, aabb_b) in parts_b.iter().zip(&aabbs_b) {
+            if aabb_a.intersects(aabb_b) {
+                contacts.extend(collide_parts(a, b));
+            }
+        }
+    }
+
+    contacts
+}
+
+/// Compute contacts between two convex parts
+#[must_use]
+pub fn collide_parts(a: &ShapePart<'_>, b: &ShapePart<'_>) -> Vec<Contact> {
+    use ConvexPrimitive as P;
+
+    match (a.primitive, b.primitive) {
+        (P::Sphere { .. } | P::Capsule { .. }, P::Sphere { .. } | P::Capsule { .. }) => {
+            collide_rounded(a, b).into_iter().collect()
+        },
+        (P::Box { .. }, P::Sphere { .. }) => collide_box_sphere(a, b).into_iter().collect(),
+        (P::Triangle { .. }, P::Sphere { .. }) => {
+            collide_triangle_sphere(a, b).into_iter().collect()
+        },
+        (P::Sphere { .. }, P::Box { .. } | P::Triangle { .. }) => flip(collide_parts(b, a)),
+        _ => collide_convex(a, b),
+    }
+}
+
+fn flip(contacts: Vec<Contact>) -> Vec<Contact> {
+    contacts
+        .into_iter()
+        .map(|c| Contact {
+            normal: -c.normal,
+            ..c
+        })
+        .collect()
+}
+
+/// Core segment and radius of a sphere or capsule
+fn rounded_core(part: &ShapePart<'_>) -> (Vec3, Vec3, f32) {
+    match part.primitive {
+        ConvexPrimitive::Capsule {
+            half_height,
+            radius,
+        } => {
+            let axis = part
+                .isometry
+                .transform_vector(Vec3::new(0.0, half_height, 0.0));
+            let center = part.isometry.translation;
+            (center - axis, center + axis, radius)
+        },
+        ConvexPrimitive::Sphere { radius } => {
+            (part.isometry.translation, part.isometry.translation, radius)
+        },
+        _ => unreachable!("rounded_core called on a non-rounded primitive"),
+    }
+}
+
+fn collide_rounded(a: &ShapePart<'_>, b: &ShapePart<'_>) -> Option<Contact> {
+    let (a0, a1, ra) = rounded_core(a);
+    let (b0, b1, rb) = rounded_core(b);
+    let (pa, pb) = closest_points_segments(a0, a1, b0, b1);
+    sphere_contact(
+        pa,
+        ra,
+        pb,
+        rb,
+        b.isometry.translation - a.isometry.translation,
+    )
+}
+
+/// Contact between two spheres, with a fallback axis for concentric centers
+fn sphere_contact(ca: Vec3, ra: f32, cb: Vec3, rb: f32, fallback: Vec3) -> Option<Contact> {
+    let delta = cb - ca;
+    let distance = delta.length();
+    let depth = ra + rb - distance;
+    if depth < 0.0 {
+        return None;
+    }
+    let normal = if distance > f32::EPSILON {
+        delta / distance
+    } else {
+        fallback.try_normalize().unwrap_or(Vec3::Y)
+    };
+    let surface_a = ca + normal * ra;
+    let surface_b = cb - normal * rb;
+    Some(Contact {
+        position: (surface_a + surface_b) * 0.5,
+        normal,
+        depth,
+    })
+}
+
+fn collide_box_sphere(a: &ShapePart<'_>, b: &ShapePart<'_>) -> Option<Contact> {
+    let (ConvexPrimitive::Box { half_extents }, ConvexPrimitive::Sphere { radius }) =
+        (a.primitive, b.primitive)
+    else {
+        return None;
+    };
+    let center = a.isometry.inverse_transform_point(b.isometry.translation);
+    let clamped = center.clamp(-half_extents, half_extents);
+
+    let (local_normal, depth, surface) = if clamped != center {
+        let delta = center - clamped;
+        let distance = delta.length();
+        if distance > radius {
+            return None;
+        }
+        (delta / distance, radius - distance, clamped)
+    } else {
+        // Center inside the box: push out through the nearest face
+        let gaps = half_extents - center.abs();
+        let axis = if gaps.x <= gaps.y && gaps.x <= gaps.z {
+            0
+        } else if gaps.y <= gaps.z {
+            1
+        } else {
+            2
+        };
+        let mut normal = Vec3::ZERO;
+        normal[axis] = sign(center[axis]);
+        let mut surface = center;
+        surface[axis] = sign(center[axis]) * half_extents[axis];
+        (normal, radius + gaps[axis], surface)
+    };
+
+    let normal = a.isometry.transform_vector(local_normal);
+    let surface_a = a.isometry.transform_point(surface);
+    let surface_b = b.isometry.translation - normal * radius;
+    Some(Contact {
+        position: (surface_a + surface_b) * 0.5,
+        normal,
+        depth,
+    })
+}
+
+fn collide_triangle_sphere(a: &ShapePart<'_>, b: &ShapePart<'_>) -> Option<Contact> {
+    let (ConvexPrimitive::Triangle { vertices }, ConvexPrimitive::Sphere { radius }) =
+        (a.primitive, b.primitive)
+    else {
+        return None;
+    };
+    let [v0, v1, v2] = vertices.map(|v| a.isometry.transform_point(v));
+    let center = b.isometry.translation;
+    let closest = closest_point_on_triangle(center, v0, v1, v2);
+    let face_normal = (v1 - v0).cross(v2 - v0).normalize_or_zero();
+    let fallback = if face_normal.dot(center - v0) >= 0.0 {
+        face_normal
+    } else {
+        -face_normal
+    };
+    sphere_contact(closest, 0.0, center, radius, fallback)
+}
+
+/// General convex-convex contact using GJK, EPA and feature clipping
+fn collide_convex(a: &ShapePart<'_>, b: &ShapePart<'_>) -> Vec<Contact> {
+    let Some(simplex) = gjk(a, b) else {
+        return Vec::new();
+    };
+    let Some(penetration) = epa(a, b, simplex) else {
+        return Vec::new();
+    };
+
+    let feature_a = a.feature(penetration.normal);
+    let feature_b = b.feature(-penetration.normal);
+    let clipped = clip_features(&feature_a, &feature_b, penetration.normal);
+
+    if clipped.is_empty() {
+        vec![Contact {
+            position: (penetration.point_a + penetration.point_b) * 0.5,
+            normal: penetration.normal,
+            depth: penetration.depth,
+        }]
+    } else {
+        reduce_manifold(clipped)
+    }
+}
+
+/// Point on the Minkowski difference with the contributing support points
+#[derive(Debug, Clone, Copy)]
+struct SupportPoint {
+    point: Vec3,
+    a: Vec3,
+    b: Vec3,
+}
+
+fn minkowski_support(a: &ShapePart<'_>, b: &ShapePart<'_>, dir: Vec3) -> SupportPoint {
+    let pa = a.support(dir);
+    let pb = b.support(-dir);
+    SupportPoint {
+        point: pa - pb,
+        a: pa,
+        b: pb,
+    }
+}
+
+/// GJK intersection test, returning a tetrahedron enclosing the origin
+fn gjk(a: &ShapePart<'_>, b: &ShapePart<'_>) -> Option<[SupportPoint; 4]> {
+    let mut dir = b.isometry.translation - a.isometry.translation;
+    if dir.length_squared() < f32::EPSILON {
+        dir = Vec3::X;
+    }
+
+    let mut c = minkowski_support(a, b, dir);
+    dir = -c.point;
+    if dir.length_squared() < f32::EPSILON {
+        dir = Vec3::X;
+        c = minkowski_support(a, b, dir);
+        dir = -c.point;
+    }
+    let mut b_pt = minkowski_support(a, b, dir);
+    if b_pt.point.dot(dir) < 0.0 {
+        return None;
+    }
+
+    let cb = c.point - b_pt.point;
+    dir = cb.cross(-b_pt.point).cross(cb);
+    if dir.length_squared() < f32::EPSILON {
+        dir = any_perpendicular(cb);
+    }
+
+    let mut d = c;
+    let mut simplex_dim = 2;
+
+    for _ in 0..GJK_MAX_ITERATIONS {
+        let a_pt = minkowski_support(a, b, dir);
+        if a_pt.point.dot(dir) < 0.0 {
+            return None;
+        }
+
+        simplex_dim += 1;
+        if simplex_dim == 3 {
+            update_simplex3(a_pt, &mut b_pt, &mut c, &mut d, &mut simplex_dim, &mut dir);
+        } else {
+            // Tetrahedron with apex `a_pt` over base (b, c, d)
+            let ao = -a_pt.point;
+            let ab = b_pt.point - a_pt.point;
+            let ac = c.point - a_pt.point;
+            let ad = d.point - a_pt.point;
+            let abc = ab.cross(ac);
+            let acd = ac.cross(ad);
+            let adb = ad.cross(ab);
+            simplex_dim = 3;
+
+            if abc.dot(ao) > 0.0 {
+                d = c;
+                c = b_pt;
+                b_pt = a_pt;
+                dir = abc;
+            } else if acd.dot(ao) > 0.0 {
+                b_pt = a_pt;
+                dir = acd;
+            } else if adb.dot(ao) > 0.0 {
+                c = d;
+                d = b_pt;
+                b_pt = a_pt;
+                dir = adb;
+            } else {
+                return Some([a_pt, b_pt, c, d]);
+            }
+        }
+
+        if dir.length_squared() < f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+    }
+
+    None
+}
+
+/// Triangle case of GJK; the triangle is (a, b, c) with `a` newest
+fn update_simplex3(
+    a: SupportPoint,
+    b: &mut SupportPoint,
+    c: &mut SupportPoint,
+    d: &mut SupportPoint,
+    simplex_dim: &mut usize,
+    dir: &mut Vec3,
+) {
+    let ab = b.point - a.point;
+    let ac = c.point - a.point;
+    let n = ab.cross(ac);
+    let ao = -a.point;
+
+    *simplex_dim = 2;
+    if ab.cross(n).dot(ao) > 0.0 {
+        *c = a;
+        *dir = ab.cross(ao).cross(ab);
+        return;
+    }
+    if n.cross(ac).dot(ao) > 0.0 {
+        *b = a;
+        *dir = ac.cross(ao).cross(ac);
+        return;
+    }
+
+    *simplex_dim = 3;
+    if n.dot(ao) > 0.0 {
+        *d = *c;
+        *c = *b;
+        *b = a;
+        *dir = n;
+    } else {
+        *d = *b;
+        *b = a;
+        *dir = -n;
+    }
+}
+
+/// Penetration found by EPA
+#[derive(Debug, Clone, Copy)]
+struct Penetration {
+    normal: Vec3,
+    depth: f32,
+    point_a: Vec3,
+    point_b: Vec3,
+}
+
+/// Expanding polytope algorithm on a GJK tetrahedron
+fn epa(a: &ShapePart<'_>, b: &ShapePart<'_>, simplex: [SupportPoint; 4]) -> Option<Penetration> {
+    let mut vertices: Vec<SupportPoint> = simplex.to_vec();
+    let centroid = vertices.iter().map(|v| v.point).sum::<Vec3>() / 4.0;
+    let mut faces: Vec<[usize; 3]> = Vec::new();
+    for face in [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]] {
+        let [i, j, k] = face;
+        let n =
+            (vertices[j].point - vertices[i].point).cross(vertices[k].point - vertices[i].point);
+        if n.dot(vertices[i].point - centroid) < 0.0 {
+            faces.push([i, k, j]);
+        } else {
+            faces.push(face);
+        }
+    }
+
+    let face_plane = |vertices: &[SupportPoint], face: &[usize; 3]| -> Option<(Vec3, f32)> {
+        let p0 = vertices[face[0]].point;
+        let n = (vertices[face[1]].point - p0)
+            .cross(vertices[face[2]].point - p0)
+            .try_normalize()?;
+        Some((n, n.dot(p0)))
+    };
+
+    let mut best: Option<([usize; 3], Vec3, f32)> = None;
+    for _ in 0..EPA_MAX_ITERATIONS {
+        best = faces
+            .iter()
+            .filter_map(|f| face_plane(&vertices, f).map(|(n, d)| (*f, n, d)))
+            .min_by(|x, y| x.2.total_cmp(&y.2));
+        let (_, normal, distance) = best?;
+
+        let support = minkowski_support(a, b, normal);
+        if support.point.dot(normal) - distance < EPA_TOLERANCE {
+            break;
+        }
+
+        // Remove faces visible from the new point and stitch the horizon
+        let new_index = vertices.len();
+        vertices.push(support);
+        let mut horizon: Vec<(usize, usize)> = Vec::new();
+        faces.retain(|face| {
+            let visible = face_plane(&vertices, face).map_or(true, |(n, _)| {
+                n.dot(support.point - vertices[face[0]].point) > 0.0
+            });
+            if visible {
+                for edge in [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])] {
+                    if let Some(pos) = horizon
+                        .iter()
+                        .position(|&(s, e)| s == edge.1 && e == edge.0)
+                    {
+                        horizon.swap_remove(pos);
+                    } else {
+                        horizon.push(edge);
+                    }
+                }
+            }
+            !visible
+        });
+        if horizon.is_empty() {
+            break;
+        }
+        faces.extend(horizon.into_iter().map(|(s, e)| [s, e, new_index]));
+    }
+
+    let (face, normal, depth) = best?;
+    let projected = normal * depth;
+    let (u, v, w) = barycentric(
+        projected,
+        vertices[face[0]].point,
+        vertices[face[1]].point,
+        vertices[face[2]].point,
+    );
+    let point_a = vertices[face[0]].a * u + vertices[face[1]].a * v + vertices[face[2]].a * w;
+    let point_b = vertices[face[0]].b * u + vertices[face[1]].b * v + vertices[face[2]].b * w;
+
+    // The polytope normal separates B from A, so it already points from A to B
+    Some(Penetration {
+        normal,
+        depth: depth.max(0.0),
+        point_a,
+        point_b,
+    })
+}
+
+/// Build contacts by clipping the incident feature against the reference feature
+fn clip_features(feature_a: &[Vec3], feature_b: &[Vec3], normal: Vec3) -> Vec<Contact> {
+    if feature_a.len() < 2 || feature_b.len() < 2 {
+        return Vec::new();
+    }
+
+    // The feature with more vertices is the reference
+    let (reference, incident, ref_normal) = if feature_a.len() >= feature_b.len() {
+        (feature_a, feature_b, normal)
+    } else {
+        (feature_b, feature_a, -normal)
+    };
+
+    let clipped: Vec<Vec3> = if reference.len() >= 3 {
+        let centroid = reference.iter().copied().sum::<Vec3>() / reference.len() as f32;
+        let mut points = incident.to_vec();
+        for i in 0..reference.len() {
+            let v0 = reference[i];
+            let v1 = reference[(i + 1) % reference.len()];
+            let mut side = (v1 - v0).cross(ref_normal);
+            if side.dot(centroid - v0) > 0.0 {
+                side = -side;
+            }
+            points = clip_polygon(&points, v0, side);
+            if points.is_empty() {
+                break;
+            }
+        }
+        points
+    } else {
+        // Segment against segment: clamp the incident segment to the reference span
+        let axis = reference[1] - reference[0];
+        let length_sq = axis.length_squared();
+        if length_sq < f32::EPSILON {
+            return Vec::new();
+        }
+        let mut points = incident.to_vec();
+        points = clip_polygon(&points, reference[0], -axis);
+        points = clip_polygon(&points, reference[1], axis);
+        points
+    };
+
+    let plane = reference[0].dot(ref_normal);
+    clipped
+        .into_iter()
+        .filter_map(|p| {
+            let depth = plane - p.dot(ref_normal);
+            (depth >= -CONTACT_TOLERANCE).then(|| Contact {
+                position: p + ref_normal * (depth * 0.5),
+                normal,
+                depth: depth.max(0.0),
+            })
+        })
+        .collect()
+}
+
+/// Sutherland-Hodgman clip of a polygon (or segment) against a half-space
+///
+/// Keeps the side where `(p - plane_point) . plane_normal <= 0`.
+fn clip_polygon(points: &[Vec3], plane_point: Vec3, plane_normal: Vec3) -> Vec<Vec3> {
+    let distance = |p: Vec3| (p - plane_point).dot(plane_normal);
+
+    if points.len() == 2 {
+        let (d0, d1) = (distance(points[0]), distance(points[1]));
+        return match (d0 <= 0.0, d1 <= 0.0) {
+            (true, true) => points.to_vec(),
+            (false, false) => Vec::new(),
+            (true, false) => vec![points[0], points[0].lerp(points[1], d0 / (d0 - d1))],
+            (false, true) => vec![points[0].lerp(points[1], d0 / (d0 - d1)), points[1]],
+        };
+    }
+
+    let mut output = Vec::with_capacity(points.len() + 2);
+    for i in 0..points.len() {
+        let current = points[i];
+        let next = points[(i + 1) % points.len()];
+        let (dc, dn) = (distance(current), distance(next));
+        if dc <= 0.0 {
+            output.push(current);
+        }
+        if (dc <= 0.0) != (dn <= 0.0) {
+            output.push(current.lerp(next, dc / (dc - dn)));
+        }
+    }
+    output
+}
+
+/// Keep at most four well-spread contacts, starting with the deepest
+fn reduce_manifold(mut contacts: Vec<Contact>) -> Vec<Contact> {
+    if contacts.len() <= MAX_MANIFOLD_POINTS {
+        return contacts;
+    }
+
+    let mut kept = Vec::with_capacity(MAX_MANIFOLD_POINTS);
+    let take_best = |contacts: &mut Vec<Contact>, score: &dyn Fn(&Contact) -> f32| {
+        let index = (0..contacts.len())
+            .max_by(|&i, &j| score(&contacts[i]).total_cmp(&score(&contacts[j])))
+            .unwrap_or(0);
+        contacts.swap_remove(index)
+    };
+
+    let first = take_best(&mut contacts, &|c| c.depth);
+    kept.push(first);
+    let second = take_best(&mut contacts, &|c| {
+        c.position.distance_squared(first.position)
+    });
+    kept.push(second);
+    let edge = second.position - first.position;
+    let third = take_best(&mut contacts, &|c| {
+        (c.position - first.position).cross(edge).length_squared()
+    });
+    kept.push(third);
+    let centroid = (first.position + second.position + third.position) / 3.0;
+    let fourth = take_best(&mut contacts, &|c| c.position.distance_squared(centroid));
+    kept.push(fourth);
+    kept
+}
+
+fn polytope_feature(vertices: &[Vec3], dir: Vec3) -> Vec<Vec3> {
+    if vertices.is_empty() {
+        return Vec::new();
+    }
+    let max = vertices.iter().map(|v| v.dot(dir)).fold(f32::MIN, f32::max);
+    let scale = vertices
+        .iter()
+        .map(|v| v.length())
+        .fold(0.0, f32::max)
+        .max(f32::EPSILON);
+    let mut feature: Vec<Vec3> = vertices
+        .iter()
+        .copied()
+        .filter(|v| v.dot(dir) >= max - FEATURE_TOLERANCE * scale)
+        .collect();
+    feature.dedup_by(|a, b| a.distance_squared(*b) < f32::EPSILON);
+
+    if feature.len() >= 3 {
+        // Order the face around its centroid
+        let centroid = feature.iter().copied().sum::<Vec3>() / feature.len() as f32;
+        let u = any_perpendicular(dir).normalize();
+        let v = dir.cross(u);
+        feature.sort_by(|p, q| {
+            let angle = |x: &Vec3| {
+                let d = *x - centroid;
+                d.dot(v).atan2(d.dot(u))
+            };
+            angle(p).total_cmp(&angle(q))
+        });
+    }
+    feature
+}
+
+/// Result of a ray or shape cast against a convex set
+#[derive(Debug, Clone, Copy)]
+pub struct CastHit {
+    /// Distance travelled along the cast direction
+    pub distance: f32,
+    /// Unit normal pointing out of the hit set (zero when starting inside)
+    pub normal: Vec3,
+}
+
+/// Cast a ray against a convex set described by its support function
+///
+/// GJK ray cast after van den Bergen: the ray origin advances to the
+/// support plane of each separating direction until it touches the set.
+#[must_use]
+pub fn cast_ray_support(
+    support: impl Fn(Vec3) -> Vec3,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<CastHit> {
+    let mut distance = 0.0;
+    let mut x = origin;
+    let mut normal = Vec3::ZERO;
+    let mut points: Vec<Vec3> = Vec::with_capacity(4);
+    let mut v = x - support(direction);
+
+    for _ in 0..GJK_MAX_ITERATIONS {
+        if v.length_squared() <= CAST_TOLERANCE * CAST_TOLERANCE {
+            break;
+        }
+
+        let p = support(v);
+        let w = x - p;
+        let vw = v.dot(w);
+        if vw > 0.0 {
+            let vr = v.dot(direction);
+            if vr >= 0.0 {
+                return None;
+            }
+            distance -= vw / vr;
+            if distance > max_distance {
+                return None;
+            }
+            x = origin + direction * distance;
+            normal = v;
+        }
+
+        if points
+            .iter()
+            .any(|q| q.distance_squared(p) <= CAST_TOLERANCE * CAST_TOLERANCE)
+        {
+            // No further progress possible
+            break;
+        }
+        points.push(p);
+        v = closest_on_simplex(x, &mut points);
+    }
+
+    Some(CastHit {
+        distance,
+        normal: normal.normalize_or_zero(),
+    })
+}
+
+/// Cast a ray against a convex part
+#[must_use]
+pub fn cast_ray(
+    part: &ShapePart<'_>,
+    origin: Vec3,
+    direction: Vec3,
+    max_distance: f32,
+) -> Option<CastHit> {
+    cast_ray_support(|dir| part.support(dir), origin, direction, max_distance)
+}
+
+/// Sweep `moving` along `direction` until it touches `target`
+///
+/// Returns the hit together with the contact point at the time of impact.
+#[must_use]
+pub fn cast_part(
+    moving: &ShapePart<'_>,
+    direction: Vec3,
+    target: &ShapePart<'_>,
+    max_distance: f32,
+) -> Option<(CastHit, Vec3)> {
+    // Ray from the origin against the Minkowski difference target - moving
+    let hit = cast_ray_support(
+        |dir| target.support(dir) - moving.support(-dir),
+        Vec3::ZERO,
+        direction,
+        max_distance,
+    )?;
+    let point = if hit.normal == Vec3::ZERO {
+        moving.isometry.translation
+    } else {
+        moving.support(-hit.normal) + direction * hit.distance
+    };
+    Some((hit, point))
+}
+
+/// Check if two convex parts overlap
+#[must_use]
+pub fn parts_intersect(a: &ShapePart<'_>, b: &ShapePart<'_>) -> bool {
+    gjk(a, b).is_some()
+}
+
+/// Closest point to `x - p` over the hull of `points`
+///
+/// `points` is reduced to the smallest subset whose hull contains the
+/// closest point. Returns the closest point of the shifted hull, i.e. the
+/// vector from the hull to `x`.
+fn closest_on_simplex(x: Vec3, points: &mut Vec<Vec3>) -> Vec3 {
+    let shifted: Vec<Vec3> = points.iter().map(|p| x - *p).collect();
+    let n = shifted.len();
+    let mut best: Option<(f32, Vec3, u32)> = None;
+
+    // Subsets in order of size so ties keep the smallest simplex
+    let mut masks: Vec<u32> = (1..(1u32 << n)).collect();
+    masks.sort_by_key(|mask| mask.count_ones());
+
+    for mask in masks {
+        let subset: Vec<Vec3> = (0..n)
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| shifted[i])
+            .collect();
+        let closest = match subset.as_slice() {
+            [a] => Some(*a),
+            [a, b] => Some(closest_points_segments(Vec3::ZERO, Vec3::ZERO, *a, *b).1),
+            [a, b, c] => {
+                if (*b - *a).cross(*c - *a).length_squared() <= f32::EPSILON * f32::EPSILON {
+                    None
+                } else {
+                    Some(closest_point_on_triangle(Vec3::ZERO, *a, *b, *c))
+                }
+            },
+            [a, b, c, d] => tetrahedron_contains_origin(*a, *b, *c, *d).then_some(Vec3::ZERO),
+            _ => None,
+        };
+        if let Some(closest) = closest {
+            let distance = closest.length_squared();
+            if best.map_or(true, |(d, _, _)| distance < d) {
+                best = Some((distance, closest, mask));
+            }
+        }
+    }
+
+    let Some((_, closest, mask)) = best else {
+        return x - points[0];
+    };
+    let mut i = 0;
+    points.retain(|_| {
+        let keep = mask & (1 << i) != 0;
+        i += 1;
+        keep
+    });
+    closest
+}
+
+fn tetrahedron_contains_origin(a: Vec3, b: Vec3, c: Vec3, d: Vec3) -> bool {
+    let same_side = |p: Vec3, q: Vec3, r: Vec3, opposite: Vec3| {
+        let n = (q - p).cross(r - p);
+        let side_opposite = n.dot(opposite - p);
+        let side_origin = n.dot(-p);
+        side_opposite * side_origin >= 0.0 && side_opposite.abs() > f32::EPSILON
+    };
+    same_side(a, b, c, d) && same_side(a, c, d, b) && same_side(a, d, b, c) && same_side(b, d, c, a)
+}
+
+/// Closest points between segments `p0-p1` and `q0-q1`
+#[must_use]
+pub fn closest_points_segments(p0: Vec3, p1: Vec3, q0: Vec3, q1: Vec3) -> (Vec3, Vec3) {
+    let d1 = p1 - p0;
+    let d2 = q1 - q0;
+    let r = p0 - q0;
+    let a = d1.length_squared();
+    let e = d2.length_squared();
+    let f = d2.dot(r);
+
+    let (s, t) = if a <= f32::EPSILON && e <= f32::EPSILON {
+        (0.0, 0.0)
+    } else if a <= f32::EPSILON {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e <= f32::EPSILON {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+            let mut s = if denom > f32::EPSILON {
+                ((b * f - c * e) / denom).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let mut t = (b * s + f) / e;
+            if t < 0.0 {
+                t = 0.0;
+                s = (-c / a).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                t = 1.0;
+                s = ((b - c) / a).clamp(0.0, 1.0);
+            }
+            (s, t)
+        }
+    };
+
+    (p0 + d1 * s, q0 + d2 * t)
+}
+
+/// Closest point on triangle `abc` to `p`
+#[must_use]
+pub fn closest_point_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+fn barycentric(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> (f32, f32, f32) {
+    let v0 = b - a;
+    let v1 = c - a;
+    let v2 = p - a;
+    let d00 = v0.dot(v0);
+    let d01 = v0.dot(v1);
+    let d11 = v1.dot(v1);
+    let d20 = v2.dot(v0);
+    let d21 = v2.dot(v1);
+    let denom = d00 * d11 - d01 * d01;
+    if denom.abs() < f32::EPSILON {
+        return (1.0, 0.0, 0.0);
+    }
+    let v = (d11 * d20 - d01 * d21) / denom;
+    let w = (d00 * d21 - d01 * d20) / denom;
+    (1.0 - v - w, v, w)
+}
+
+fn max_dot(points: impl Iterator<Item = Vec3>, dir: Vec3) -> Vec3 {
+    points
+        .max_by(|a, b| a.dot(dir).total_cmp(&b.dot(dir)))
+        .unwrap_or(Vec3::ZERO)
+}
+
+fn any_perpendicular(v: Vec3) -> Vec3 {
+    let candidate = v.cross(Vec3::X);
+    if candidate.length_squared() > f32::EPSILON {
+        candidate
+    } else {
+        v.cross(Vec3::Z)
+    }
+}
+
+fn sign(value: f32) -> f32 {
+    if value < 0.0 {
+        -1.0
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed(shape: &ColliderShape3D, position: Vec3) -> Vec<ShapePart<'_>> {
+        shape_parts(shape, Isometry::new(position, Quat::IDENTITY))
+    }
+
+    fn part(shape: &ColliderShape3D, position: Vec3) -> ShapePart<'_> {
+        placed(shape, position)[0]
+    }
+
+    #[test]
+    fn overlapping_spheres_touch_along_the_center_line() {
+        let sphere = ColliderShape3D::sphere(1.0);
+        let a = part(&sphere, Vec3::ZERO);
+        let b = part(&sphere, Vec3::new(1.5, 0.0, 0.0));
+
+        let contacts = collide_parts(&a, &b);
+        assert_eq!(contacts.len(), 1);
+        assert!(contacts[0].normal.abs_diff_eq(Vec3::X, 1.0e-5));
+        assert!((contacts[0].depth - 0.5).abs() < 1.0e-5);
+        assert!((contacts[0].position.x - 0.75).abs() < 1.0e-5);
+        assert!(parts_intersect(&a, &b));
+
+        let far = part(&sphere, Vec3::new(2.5, 0.0, 0.0));
+        assert!(collide_parts(&a, &far).is_empty());
+        assert!(!parts_intersect(&a, &far));
+    }
+
+    #[test]
+    fn box_resting_on_box_has_a_four_point_manifold() {
+        let ground = ColliderShape3D::box_shape(10.0, 1.0, 10.0);
+        let cube = ColliderShape3D::cube(1.0);
+        let contacts = collide_shapes(
+            &placed(&ground, Vec3::new(0.0, -0.5, 0.0)),
+            &placed(&cube, Vec3::new(0.0, 0.49, 0.0)),
+        );
+
+        assert_eq!(contacts.len(), 4);
+        for contact in &contacts {
+            assert!(contact.normal.abs_diff_eq(Vec3::Y, 1.0e-4), "{contact:?}");
+            assert!((contact.depth - 0.01).abs() < 1.0e-3, "{contact:?}");
+            assert!(contact.position.x.abs() <= 0.5 + 1.0e-4);
+            assert!(contact.position.z.abs() <= 0.5 + 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn rays_hit_rotated_boxes_on_their_surface() {
+        let cube = ColliderShape3D::cube(1.0);
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        let target = shape_parts(&cube, Isometry::new(Vec3::ZERO, rotation))[0];
+
+        let hit = cast_ray(&target, Vec3::new(-5.0, 0.0, 0.0), Vec3::X, 10.0).unwrap();
+        assert!((hit.distance - (5.0 - 0.5 * std::f32::consts::SQRT_2)).abs() < 1.0e-3);
+        assert!(hit.normal.x < 0.0);
+        assert!(cast_ray(&target, Vec3::new(-5.0, 2.0, 0.0), Vec3::X, 10.0).is_none());
+        assert!(cast_ray(&target, Vec3::new(-5.0, 0.0, 0.0), Vec3::X, 3.0).is_none());
+    }
+
+    #[test]
+    fn closest_points_clamp_to_triangles_and_segments() {
+        let (a, b, c) = (Vec3::ZERO, Vec3::X, Vec3::Y);
+        let above = closest_point_on_triangle(Vec3::new(0.25, 0.25, 3.0), a, b, c);
+        assert!(above.abs_diff_eq(Vec3::new(0.25, 0.25, 0.0), 1.0e-6));
+        let beyond = closest_point_on_triangle(Vec3::new(2.0, -1.0, 0.0), a, b, c);
+        assert!(beyond.abs_diff_eq(b, 1.0e-6));
+
+        let (p, q) = closest_points_segments(
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.5, 1.0, -1.0),
+            Vec3::new(0.5, 1.0, 1.0),
+        );
+        assert!(p.abs_diff_eq(Vec3::new(0.5, 0.0, 0.0), 1.0e-6));
+        assert!(q.abs_diff_eq(Vec3::new(0.5, 1.0, 0.0), 1.0e-6));
+    }
+}
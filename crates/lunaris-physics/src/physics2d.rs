@@ -3,6 +3,194 @@
 #[cfg(feature = "2d")]
 pub use rapier2d::prelude::*;
 
+#[cfg(feature = "2d")]
+pub use rapier_backend::Rapier2DBackend;
+
 /// 2D Physics world wrapper (placeholder for Rapier integration)
 #[cfg(not(feature = "2d"))]
 pub struct Physics2D;
+
+#[cfg(feature = "2d")]
+mod rapier_backend {
+    use crate::{
+        collision::{ColliderShape2D, ColliderShape3D},
+        constraints::{Constraint, ConstraintType},
+        rapier::impl_rapier_backend,
+        rigidbody::RigidbodyProperties,
+    };
+    use lunaris_core::math::Vec3;
+    use rapier2d::prelude as rp;
+
+    impl_rapier_backend! {
+        /// Physics backend running on Rapier 2D
+        ///
+        /// Bodies live in the XY plane: Z positions are dropped and only the Z
+        /// components of rotations, torques and angular velocities are used.
+        Rapier2DBackend {
+            name: "rapier2d",
+            shape: Shape2D,
+            dimension: "2D",
+            other_dimension: "3D",
+        }
+    }
+
+    /// Cross-section of a 3D query shape in the XY plane
+    fn query_shape(shape: &ColliderShape3D) -> Option<rp::SharedShape> {
+        match shape {
+            ColliderShape3D::Sphere { radius } => Some(rp::SharedShape::ball(*radius)),
+            ColliderShape3D::Box { half_extents } => {
+                Some(rp::SharedShape::cuboid(half_extents.x, half_extents.y))
+            },
+            ColliderShape3D::Capsule {
+                half_height,
+                radius,
+            } => Some(rp::SharedShape::capsule_y(*half_height, *radius)),
+            ColliderShape3D::Cylinder {
+                half_height,
+                radius,
+            } => Some(rp::SharedShape::cuboid(*radius, *half_height)),
+            ColliderShape3D::ConvexHull { vertices } => {
+                let points: Vec<_> = vertices.iter().map(|v| rp::Point::new(v.x, v.y)).collect();
+                rp::SharedShape::convex_hull(&points)
+            },
+            ColliderShape3D::TriMesh { .. } | ColliderShape3D::Compound { .. } => {
+                tracing::warn!("Rapier 2D backend cannot query with mesh or compound shapes");
+                None
+            },
+        }
+    }
+
+    fn vector(v: Vec3) -> rp::Vector<rp::Real> {
+        rp::Vector::new(v.x, v.y)
+    }
+
+    fn point(v: Vec3) -> rp::Point<rp::Real> {
+        rp::Point::new(v.x, v.y)
+    }
+
+    fn from_vector(v: &rp::Vector<rp::Real>) -> Vec3 {
+        Vec3::new(v.x, v.y, 0.0)
+    }
+
+    fn from_point(p: &rp::Point<rp::Real>) -> Vec3 {
+        Vec3::new(p.x, p.y, 0.0)
+    }
+
+    fn angular(v: Vec3) -> rp::AngVector<rp::Real> {
+        v.z
+    }
+
+    fn from_angular(angvel: rp::AngVector<rp::Real>) -> Vec3 {
+        Vec3::new(0.0, 0.0, angvel)
+    }
+
+    fn euler_rotation(euler: Vec3) -> rp::Rotation<rp::Real> {
+        rp::Rotation::new(euler.z)
+    }
+
+    fn euler(rotation: &rp::Rotation<rp::Real>) -> Vec3 {
+        Vec3::new(0.0, 0.0, rotation.angle())
+    }
+
+    fn unit_vector(v: glam::Vec3) -> rp::UnitVector<rp::Real> {
+        rp::UnitVector::new_normalize(rp::Vector::new(v.x, v.y))
+    }
+
+    fn lock_rotations(
+        builder: rp::RigidBodyBuilder,
+        properties: &RigidbodyProperties,
+    ) -> rp::RigidBodyBuilder {
+        if properties.lock_rotation_z {
+            builder.lock_rotations()
+        } else {
+            builder
+        }
+    }
+
+    /// Linear and angular impulse a joint applied during the last step
+    fn joint_load(joint: &rp::ImpulseJoint) -> (f32, f32) {
+        (
+            joint.impulses.fixed_rows::<2>(0).norm(),
+            joint.impulses[2].abs(),
+        )
+    }
+
+    fn shared_shape(shape: &ColliderShape2D) -> Option<rp::SharedShape> {
+        Some(match shape {
+            ColliderShape2D::Circle { radius } => rp::SharedShape::ball(*radius),
+            ColliderShape2D::Rectangle {
+                half_width,
+                half_height,
+            } => rp::SharedShape::cuboid(*half_width, *half_height),
+            ColliderShape2D::Capsule {
+                half_height,
+                radius,
+            } => rp::SharedShape::capsule_y(*half_height, *radius),
+            ColliderShape2D::ConvexPolygon { vertices } => {
+                let points: Vec<_> = vertices.iter().map(|v| rp::Point::new(v.x, v.y)).collect();
+                rp::SharedShape::convex_hull(&points)?
+            },
+            ColliderShape2D::Compound { shapes } => {
+                let parts: Vec<_> = shapes
+                    .iter()
+                    .filter_map(|(position, angle, child)| {
+                        let isometry =
+                            rp::Isometry::new(rp::Vector::new(position.x, position.y), *angle);
+                        shared_shape(child).map(|shape| (isometry, shape))
+                    })
+                    .collect();
+                if parts.is_empty() {
+                    return None;
+                }
+                rp::SharedShape::compound(parts)
+            },
+        })
+    }
+
+    /// Map an engine constraint onto a Rapier generic joint
+    ///
+    /// Every rotational joint collapses to a revolute joint in the plane.
+    fn generic_joint(constraint: &Constraint) -> rp::GenericJoint {
+        use rp::JointAxesMask as Mask;
+        use rp::JointAxis as Axis;
+
+        let builder = match constraint.constraint_type {
+            ConstraintType::Fixed => rp::GenericJointBuilder::new(Mask::LOCKED_FIXED_AXES),
+            ConstraintType::BallSocket
+            | ConstraintType::Hinge { .. }
+            | ConstraintType::Universal { .. } => {
+                rp::GenericJointBuilder::new(Mask::LOCKED_REVOLUTE_AXES)
+            },
+            ConstraintType::Slider { axis } => {
+                rp::GenericJointBuilder::new(Mask::LOCKED_PRISMATIC_AXES)
+                    .local_axis1(unit_vector(axis))
+                    .local_axis2(unit_vector(axis))
+            },
+            ConstraintType::Distance { min, max } => rp::GenericJointBuilder::new(Mask::empty())
+                .coupled_axes(Mask::LIN_AXES)
+                .limits(Axis::X, [min, max]),
+            ConstraintType::ConeTwist { swing_span, .. } => {
+                rp::GenericJointBuilder::new(Mask::LOCKED_REVOLUTE_AXES)
+                    .limits(Axis::AngX, [-swing_span, swing_span])
+            },
+        };
+
+        let builder = match constraint.constraint_type {
+            ConstraintType::Hinge { .. } => drive_free_axis(builder, Axis::AngX, constraint),
+            ConstraintType::Slider { .. } => drive_free_axis(builder, Axis::X, constraint),
+            _ => builder,
+        };
+
+        let mut joint = builder
+            .local_anchor1(rp::Point::new(constraint.anchor_a.x, constraint.anchor_a.y))
+            .local_anchor2(rp::Point::new(constraint.anchor_b.x, constraint.anchor_b.y))
+            .build();
+        // Reference rotations (around Z) turn the body frames into the joint frames
+        let angle = |q: glam::Quat| 2.0 * q.z.atan2(q.w);
+        joint.local_frame1.rotation =
+            rp::Rotation::new(angle(constraint.rotation_a)) * joint.local_frame1.rotation;
+        joint.local_frame2.rotation =
+            rp::Rotation::new(angle(constraint.rotation_b)) * joint.local_frame2.rotation;
+        joint
+    }
+}
@@ -3,6 +3,206 @@
 #[cfg(feature = "3d")]
 pub use rapier3d::prelude::*;
 
+#[cfg(feature = "3d")]
+pub use rapier_backend::Rapier3DBackend;
+
 /// 3D Physics world wrapper (placeholder for Rapier integration)
 #[cfg(not(feature = "3d"))]
 pub struct Physics3D;
+
+#[cfg(feature = "3d")]
+mod rapier_backend {
+    use crate::{
+        collision::ColliderShape3D,
+        constraints::{Constraint, ConstraintType},
+        narrowphase::{euler_to_quat, from_glam, quat_to_euler, to_glam},
+        rapier::impl_rapier_backend,
+        rigidbody::RigidbodyProperties,
+    };
+    use lunaris_core::math::Vec3;
+    use rapier3d::na::{Quaternion, UnitQuaternion};
+    use rapier3d::prelude as rp;
+
+    impl_rapier_backend! {
+        /// Physics backend running on Rapier 3D
+        Rapier3DBackend {
+            name: "rapier3d",
+            shape: Shape3D,
+            dimension: "3D",
+            other_dimension: "2D",
+        }
+    }
+
+    /// Query shapes are simulated shapes in 3D
+    fn query_shape(shape: &ColliderShape3D) -> Option<rp::SharedShape> {
+        shared_shape(shape)
+    }
+
+    fn vector(v: Vec3) -> rp::Vector<rp::Real> {
+        rp::Vector::new(v.x, v.y, v.z)
+    }
+
+    fn point(v: Vec3) -> rp::Point<rp::Real> {
+        rp::Point::new(v.x, v.y, v.z)
+    }
+
+    fn from_vector(v: &rp::Vector<rp::Real>) -> Vec3 {
+        Vec3::new(v.x, v.y, v.z)
+    }
+
+    fn from_point(p: &rp::Point<rp::Real>) -> Vec3 {
+        Vec3::new(p.x, p.y, p.z)
+    }
+
+    fn angular(v: Vec3) -> rp::AngVector<rp::Real> {
+        vector(v)
+    }
+
+    fn from_angular(angvel: &rp::AngVector<rp::Real>) -> Vec3 {
+        from_vector(angvel)
+    }
+
+    fn unit_vector(v: glam::Vec3) -> rp::UnitVector<rp::Real> {
+        rp::UnitVector::new_normalize(rp::Vector::new(v.x, v.y, v.z))
+    }
+
+    fn euler_rotation(euler: Vec3) -> UnitQuaternion<f32> {
+        rotation(euler_to_quat(to_glam(euler)))
+    }
+
+    fn rotation(q: glam::Quat) -> UnitQuaternion<f32> {
+        UnitQuaternion::from_quaternion(Quaternion::new(q.w, q.x, q.y, q.z))
+    }
+
+    fn euler(rotation: &UnitQuaternion<f32>) -> Vec3 {
+        from_glam(quat_to_euler(glam::Quat::from_xyzw(
+            rotation.i, rotation.j, rotation.k, rotation.w,
+        )))
+    }
+
+    fn lock_rotations(
+        builder: rp::RigidBodyBuilder,
+        properties: &RigidbodyProperties,
+    ) -> rp::RigidBodyBuilder {
+        builder.enabled_rotations(
+            !properties.lock_rotation_x,
+            !properties.lock_rotation_y,
+            !properties.lock_rotation_z,
+        )
+    }
+
+    /// Linear and angular impulse a joint applied during the last step
+    fn joint_load(joint: &rp::ImpulseJoint) -> (f32, f32) {
+        (
+            joint.impulses.fixed_rows::<3>(0).norm(),
+            joint.impulses.fixed_rows::<3>(3).norm(),
+        )
+    }
+
+    fn shared_shape(shape: &ColliderShape3D) -> Option<rp::SharedShape> {
+        Some(match shape {
+            ColliderShape3D::Sphere { radius } => rp::SharedShape::ball(*radius),
+            ColliderShape3D::Box { half_extents } => {
+                rp::SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z)
+            },
+            ColliderShape3D::Capsule {
+                half_height,
+                radius,
+            } => rp::SharedShape::capsule_y(*half_height, *radius),
+            ColliderShape3D::Cylinder {
+                half_height,
+                radius,
+            } => rp::SharedShape::cylinder(*half_height, *radius),
+            ColliderShape3D::ConvexHull { vertices } => {
+                let points: Vec<_> = vertices.iter().map(|v| point(*v)).collect();
+                rp::SharedShape::convex_hull(&points)?
+            },
+            ColliderShape3D::TriMesh { vertices, indices } => {
+                if indices.is_empty() {
+                    return None;
+                }
+                rp::SharedShape::trimesh(
+                    vertices.iter().map(|v| point(*v)).collect(),
+                    indices.clone(),
+                )
+            },
+            ColliderShape3D::Compound { shapes } => {
+                let parts: Vec<_> = shapes
+                    .iter()
+                    .filter_map(|(position, rotation, child)| {
+                        let isometry = rp::Isometry::from_parts(
+                            vector(*position).into(),
+                            euler_rotation(*rotation),
+                        );
+                        shared_shape(child).map(|shape| (isometry, shape))
+                    })
+                    .collect();
+                if parts.is_empty() {
+                    return None;
+                }
+                rp::SharedShape::compound(parts)
+            },
+        })
+    }
+
+    /// Map an engine constraint onto a Rapier generic joint
+    fn generic_joint(constraint: &Constraint) -> rp::GenericJoint {
+        use rp::JointAxesMask as Mask;
+        use rp::JointAxis as Axis;
+
+        let builder = match constraint.constraint_type {
+            ConstraintType::Fixed => rp::GenericJointBuilder::new(Mask::LOCKED_FIXED_AXES),
+            ConstraintType::BallSocket => rp::GenericJointBuilder::new(Mask::LOCKED_SPHERICAL_AXES),
+            ConstraintType::Hinge { axis } => {
+                rp::GenericJointBuilder::new(Mask::LOCKED_REVOLUTE_AXES)
+                    .local_axis1(unit_vector(axis))
+                    .local_axis2(unit_vector(axis))
+            },
+            ConstraintType::Slider { axis } => {
+                rp::GenericJointBuilder::new(Mask::LOCKED_PRISMATIC_AXES)
+                    .local_axis1(unit_vector(axis))
+                    .local_axis2(unit_vector(axis))
+            },
+            ConstraintType::Distance { min, max } => rp::GenericJointBuilder::new(Mask::empty())
+                .coupled_axes(Mask::LIN_AXES)
+                .limits(Axis::X, [min, max]),
+            ConstraintType::ConeTwist {
+                swing_span,
+                twist_span,
+            } => rp::GenericJointBuilder::new(Mask::LOCKED_SPHERICAL_AXES)
+                .limits(Axis::AngX, [-twist_span, twist_span])
+                .limits(Axis::AngY, [-swing_span, swing_span])
+                .limits(Axis::AngZ, [-swing_span, swing_span]),
+            ConstraintType::Universal { axis1, axis2 } => {
+                // Lock the twist around the axis perpendicular to both hinge axes
+                let twist = axis1.cross(axis2);
+                rp::GenericJointBuilder::new(Mask::LOCKED_SPHERICAL_AXES | Mask::ANG_X)
+                    .local_axis1(unit_vector(twist))
+                    .local_axis2(unit_vector(twist))
+            },
+        };
+
+        let builder = match constraint.constraint_type {
+            ConstraintType::Hinge { .. } => drive_free_axis(builder, Axis::AngX, constraint),
+            ConstraintType::Slider { .. } => drive_free_axis(builder, Axis::X, constraint),
+            _ => builder,
+        };
+
+        let mut joint = builder
+            .local_anchor1(rp::Point::new(
+                constraint.anchor_a.x,
+                constraint.anchor_a.y,
+                constraint.anchor_a.z,
+            ))
+            .local_anchor2(rp::Point::new(
+                constraint.anchor_b.x,
+                constraint.anchor_b.y,
+                constraint.anchor_b.z,
+            ))
+            .build();
+        // Reference rotations turn the body frames into the joint frames
+        joint.local_frame1.rotation = rotation(constraint.rotation_a) * joint.local_frame1.rotation;
+        joint.local_frame2.rotation = rotation(constraint.rotation_b) * joint.local_frame2.rotation;
+        joint
+    }
+}
@@ -1,9 +1,25 @@
 //! Ragdoll Physics
 //!
 //! Physics-based ragdoll simulation for characters.
+//!
+//! [`RagdollBuilder`] turns a [`Skeleton`] into capsule bodies and limited
+//! joints. While the character is animated, [`RagdollController::match_pose`]
+//! keeps the bodies on the animated pose; once blending in,
+//! [`RagdollController::apply_pose`] mixes the simulated bones back into the
+//! pose that gets skinned.
 
-use lunaris_core::{id::Id, math::Vec3};
 use super::rigidbody::RigidbodyHandle;
+use crate::{
+    collision::{ColliderShape, ColliderShape3D, CollisionLayers},
+    constraints::{Constraint, ConstraintType, Motor},
+    narrowphase::{from_glam, quat_to_euler, to_glam, Isometry},
+    rigidbody::{ColliderProperties, ForceMode, RigidbodyProperties},
+    world::PhysicsWorld,
+};
+use glam::{EulerRot, Quat};
+use lunaris_core::{id::Id, math::Vec3, Error, Result};
+use lunaris_renderer::animation::{BoneTransform, Skeleton};
+use std::collections::HashMap;
 
 /// Joint type
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -286,6 +302,7 @@ impl RagdollConfig {
                 child: "LowerArmL".to_string(),
                 config: JointConfig {
                     joint_type: JointType::Hinge,
+                    axis: Vec3::new(1.0, 0.0, 0.0),
                     limits: JointLimits {
                         lower_angle: Vec3::new(0.0, 0.0, 0.0),
                         upper_angle: Vec3::new(2.5, 0.0, 0.0),
@@ -299,6 +316,7 @@ impl RagdollConfig {
                 child: "LowerArmR".to_string(),
                 config: JointConfig {
                     joint_type: JointType::Hinge,
+                    axis: Vec3::new(1.0, 0.0, 0.0),
                     limits: JointLimits {
                         lower_angle: Vec3::new(0.0, 0.0, 0.0),
                         upper_angle: Vec3::new(2.5, 0.0, 0.0),
@@ -339,6 +357,7 @@ impl RagdollConfig {
                 child: "LowerLegL".to_string(),
                 config: JointConfig {
                     joint_type: JointType::Hinge,
+                    axis: Vec3::new(1.0, 0.0, 0.0),
                     limits: JointLimits {
                         lower_angle: Vec3::new(-2.5, 0.0, 0.0),
                         upper_angle: Vec3::new(0.0, 0.0, 0.0),
@@ -352,6 +371,7 @@ impl RagdollConfig {
                 child: "LowerLegR".to_string(),
                 config: JointConfig {
                     joint_type: JointType::Hinge,
+                    axis: Vec3::new(1.0, 0.0, 0.0),
                     limits: JointLimits {
                         lower_angle: Vec3::new(-2.5, 0.0, 0.0),
                         upper_angle: Vec3::new(0.0, 0.0, 0.0),
@@ -385,6 +405,304 @@ pub enum RagdollState {
     BlendingOut,
 }
 
+/// A ragdoll body driven by a skeleton bone
+#[derive(Debug, Clone)]
+struct RagdollBody {
+    /// Index into [`RagdollConfig::bones`]
+    config_bone: usize,
+    /// Bone index in the skeleton
+    bone: usize,
+    handle: RigidbodyHandle,
+    /// Pose of the bone relative to the body
+    bone_offset: Isometry,
+}
+
+/// Creates ragdoll bodies and joints for a skeleton
+///
+/// Bones of the [`RagdollConfig`] are matched to skeleton bones by name.
+/// Each becomes a capsule reaching from the bone to its child, and each
+/// config joint a limited constraint at the child bone. Bone rotations use
+/// the renderer's euler convention and bone scale is ignored.
+pub struct RagdollBuilder<'a> {
+    skeleton: &'a Skeleton,
+    config: RagdollConfig,
+    bone_names: HashMap<String, String>,
+    root: Isometry,
+    entity: Id,
+}
+
+impl<'a> RagdollBuilder<'a> {
+    /// Start a humanoid ragdoll for a skeleton
+    #[must_use]
+    pub fn new(skeleton: &'a Skeleton) -> Self {
+        Self {
+            skeleton,
+            config: RagdollConfig::humanoid(),
+            bone_names: HashMap::new(),
+            root: Isometry::IDENTITY,
+            entity: Id::new(),
+        }
+    }
+
+    /// Use a custom bone and joint layout
+    #[must_use]
+    pub fn with_config(mut self, config: RagdollConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Map a ragdoll bone onto a differently named skeleton bone
+    #[must_use]
+    pub fn with_bone_name(
+        mut self,
+        ragdoll_bone: impl Into<String>,
+        skeleton_bone: impl Into<String>,
+    ) -> Self {
+        self.bone_names
+            .insert(ragdoll_bone.into(), skeleton_bone.into());
+        self
+    }
+
+    /// Place the skeleton in the world (euler angles in radians)
+    #[must_use]
+    pub fn with_transform(mut self, position: Vec3, rotation: Vec3) -> Self {
+        self.root = Isometry::from_euler(to_glam(position), to_glam(rotation));
+        self
+    }
+
+    /// Entity the ragdoll bodies belong to
+    #[must_use]
+    pub fn with_entity(mut self, entity: Id) -> Self {
+        self.entity = entity;
+        self
+    }
+
+    /// Create the bodies and joints in `world` from the bind pose
+    ///
+    /// Bone masses are scaled so they add up to the config's total mass.
+    /// Ragdoll bodies do not collide with each other.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no ragdoll bone matches a skeleton bone
+    pub fn build(mut self, world: &mut PhysicsWorld) -> Result<RagdollController> {
+        let skeleton = self.skeleton;
+        let bind = model_space(skeleton, |i| {
+            let bone = &skeleton.bones[i];
+            Isometry::new(to_glam(bone.local_position), bone_rotation(bone.local_rotation))
+        });
+
+        let indices: Vec<Option<usize>> = self
+            .config
+            .bones
+            .iter()
+            .map(|bone| {
+                let name = self.bone_names.get(&bone.name).unwrap_or(&bone.name);
+                skeleton.bone_index(name)
+            })
+            .collect();
+        if indices.iter().all(Option::is_none) {
+            return Err(Error::Config(format!(
+                "No ragdoll bone matches a bone of skeleton '{}'",
+                skeleton.name
+            )));
+        }
+
+        let group = 1u32 << self.config.collision_group.min(31);
+        let layers = CollisionLayers::new(group, !group);
+        let bone_mass: f32 = self.config.bones.iter().map(|b| b.mass).sum();
+        let mass_scale = if bone_mass > 0.0 {
+            self.config.total_mass / bone_mass
+        } else {
+            1.0
+        };
+
+        let mut bodies = Vec::new();
+        let mut body_poses: HashMap<String, (RigidbodyHandle, Isometry)> = HashMap::new();
+        for (config_bone, (bone, index)) in
+            self.config.bones.iter_mut().zip(&indices).enumerate()
+        {
+            let Some(index) = *index else {
+                tracing::warn!("Ragdoll bone '{}' not found in skeleton", bone.name);
+                continue;
+            };
+            let pose = self.root.mul(&bind[index]);
+
+            // Reach towards the child bone, preferring one that is simulated too
+            let children: Vec<usize> = (0..skeleton.bones.len())
+                .filter(|&c| usize::try_from(skeleton.bones[c].parent).ok() == Some(index))
+                .collect();
+            let child = children
+                .iter()
+                .find(|&&c| indices.contains(&Some(c)))
+                .or_else(|| children.first());
+            let radius = bone.dimensions.x.max(bone.dimensions.z);
+            let (direction, length) = match child {
+                Some(&c) => {
+                    let offset = self.root.mul(&bind[c]).translation - pose.translation;
+                    (offset.normalize_or_zero(), offset.length())
+                },
+                None => (pose.rotation * glam::Vec3::Y, bone.dimensions.y * 2.0),
+            };
+            let direction = if direction == glam::Vec3::ZERO {
+                pose.rotation * glam::Vec3::Y
+            } else {
+                direction
+            };
+
+            // Capsules are Y aligned: turn the bone's Y onto the segment
+            let rotation =
+                Quat::from_rotation_arc(pose.rotation * glam::Vec3::Y, direction) * pose.rotation;
+            let body_pose = Isometry::new(pose.translation + direction * (length * 0.5), rotation);
+
+            let handle = world.create_rigidbody(
+                self.entity,
+                RigidbodyProperties::dynamic().with_mass(bone.mass * mass_scale),
+                from_glam(body_pose.translation),
+            );
+            world.set_rotation(handle, from_glam(quat_to_euler(rotation)));
+            world.attach_collider(
+                handle,
+                ColliderShape::Shape3D(ColliderShape3D::capsule(
+                    (length - 2.0 * radius).max(0.0),
+                    radius,
+                )),
+                ColliderProperties {
+                    layers,
+                    ..Default::default()
+                },
+                Vec3::ZERO,
+            );
+
+            bone.rigidbody = handle;
+            body_poses.insert(bone.name.clone(), (handle, body_pose));
+            bodies.push(RagdollBody {
+                config_bone,
+                bone: index,
+                handle,
+                bone_offset: body_pose.inverse().mul(&pose),
+            });
+        }
+
+        let mut constraints = Vec::new();
+        for joint in &self.config.joints {
+            let (Some((parent, parent_pose)), Some((child, child_pose))) =
+                (body_poses.get(&joint.parent), body_poses.get(&joint.child))
+            else {
+                continue;
+            };
+            let child_index = bodies
+                .iter()
+                .find(|b| b.handle == *child)
+                .map(|b| b.bone)
+                .expect("child body was just created");
+            let constraint = ragdoll_constraint(
+                &joint.config,
+                parent.0.raw(),
+                child.0.raw(),
+                parent_pose,
+                child_pose,
+                &self.root.mul(&bind[child_index]),
+            );
+            constraints.push(world.add_constraint(constraint));
+        }
+
+        let mut controller = RagdollController::new(self.config);
+        controller.bodies = bodies;
+        controller.constraints = constraints;
+        controller.root = self.root;
+        Ok(controller)
+    }
+}
+
+/// Map a ragdoll joint onto a world constraint placed at the child bone
+fn ragdoll_constraint(
+    config: &JointConfig,
+    parent: u64,
+    child: u64,
+    parent_pose: &Isometry,
+    child_pose: &Isometry,
+    bone_pose: &Isometry,
+) -> Constraint {
+    let limits = &config.limits;
+    let axis = to_glam(config.axis);
+    let mut frame = bone_pose.rotation;
+    let constraint_type = match config.joint_type {
+        JointType::Fixed => ConstraintType::Fixed,
+        JointType::BallSocket => ConstraintType::BallSocket,
+        JointType::Hinge => ConstraintType::Hinge { axis },
+        JointType::Slider => ConstraintType::Slider { axis },
+        JointType::Universal => ConstraintType::Universal {
+            axis1: axis,
+            axis2: axis.any_orthonormal_vector(),
+        },
+        JointType::Ragdoll => {
+            // Cone twist joints twist around their frame's X, bones around Y
+            frame *= Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+            let span = |a: f32, b: f32| a.abs().max(b.abs());
+            ConstraintType::ConeTwist {
+                swing_span: span(limits.lower_angle.x, limits.upper_angle.x)
+                    .max(span(limits.lower_angle.z, limits.upper_angle.z)),
+                twist_span: span(limits.lower_angle.y, limits.upper_angle.y),
+            }
+        },
+    };
+
+    let mut constraint = Constraint::new(parent, Some(child), constraint_type).with_anchors(
+        parent_pose.inverse_transform_point(bone_pose.translation),
+        child_pose.inverse_transform_point(bone_pose.translation),
+    );
+    constraint.rotation_a = parent_pose.rotation.inverse() * frame;
+    constraint.rotation_b = child_pose.rotation.inverse() * frame;
+    match config.joint_type {
+        JointType::Hinge => {
+            constraint = constraint.with_limits(limits.lower_angle.x, limits.upper_angle.x);
+        },
+        JointType::Slider => {
+            constraint = constraint.with_limits(limits.lower_translation, limits.upper_translation);
+        },
+        _ => {},
+    }
+    if limits.motor_enabled {
+        constraint =
+            constraint.with_motor(Motor::new(limits.motor_velocity, limits.motor_max_force));
+    }
+    constraint.break_force = (config.break_force > 0.0).then_some(config.break_force);
+    constraint.break_torque = (config.break_torque > 0.0).then_some(config.break_torque);
+    constraint
+}
+
+/// Bone rotation from the renderer's euler angles (X, then Y, then Z)
+fn bone_rotation(euler: Vec3) -> Quat {
+    Quat::from_euler(EulerRot::ZYX, euler.z, euler.y, euler.x)
+}
+
+/// Renderer euler angles of a bone rotation
+fn bone_euler(rotation: Quat) -> Vec3 {
+    let (z, y, x) = rotation.to_euler(EulerRot::ZYX);
+    Vec3::new(x, y, z)
+}
+
+/// Model-space pose of every bone from its local pose
+///
+/// Parents must come before their children, as in the animator.
+fn model_space(skeleton: &Skeleton, local: impl Fn(usize) -> Isometry) -> Vec<Isometry> {
+    let mut poses: Vec<Isometry> = Vec::with_capacity(skeleton.bones.len());
+    for (i, bone) in skeleton.bones.iter().enumerate() {
+        let pose = match usize::try_from(bone.parent).ok().and_then(|p| poses.get(p)) {
+            Some(parent) => parent.mul(&local(i)),
+            None => local(i),
+        };
+        poses.push(pose);
+    }
+    poses
+}
+
+/// Local pose of an animated bone
+fn pose_local(transform: &BoneTransform) -> Isometry {
+    Isometry::new(to_glam(transform.position), bone_rotation(transform.rotation))
+}
+
 /// Ragdoll controller
 pub struct RagdollController {
     /// Configuration
@@ -397,6 +715,12 @@ pub struct RagdollController {
     pub blend_time: f32,
     /// Current blend duration
     blend_duration: f32,
+    /// Bodies created by [`RagdollBuilder`]
+    bodies: Vec<RagdollBody>,
+    /// Joints created by [`RagdollBuilder`]
+    constraints: Vec<u64>,
+    /// Model-to-world transform of the skeleton
+    root: Isometry,
 }
 
 impl RagdollController {
@@ -409,6 +733,99 @@ impl RagdollController {
             blend_weight: 0.0,
             blend_time: 0.0,
             blend_duration: 0.3,
+            bodies: Vec::new(),
+            constraints: Vec::new(),
+            root: Isometry::IDENTITY,
+        }
+    }
+
+    /// Rigidbody simulating a bone
+    #[must_use]
+    pub fn bone_body(&self, bone_name: &str) -> Option<RigidbodyHandle> {
+        let index = self.config.bones.iter().position(|b| b.name == bone_name)?;
+        self.bodies
+            .iter()
+            .find(|body| body.config_bone == index)
+            .map(|body| body.handle)
+    }
+
+    /// Joints created for the ragdoll
+    #[must_use]
+    pub fn constraints(&self) -> &[u64] {
+        &self.constraints
+    }
+
+    /// Move the skeleton in the world (euler angles in radians)
+    pub fn set_root_transform(&mut self, position: Vec3, rotation: Vec3) {
+        self.root = Isometry::from_euler(to_glam(position), to_glam(rotation));
+    }
+
+    /// Keep the bodies on the animated pose
+    ///
+    /// Call every frame while the ragdoll is inactive. Bodies get the
+    /// velocity of the animation so a blend in carries its momentum.
+    pub fn match_pose(
+        &self,
+        world: &mut PhysicsWorld,
+        skeleton: &Skeleton,
+        pose: &[BoneTransform],
+        delta_time: f32,
+    ) {
+        let animated =
+            model_space(skeleton, |i| pose.get(i).map_or(Isometry::IDENTITY, pose_local));
+        for body in &self.bodies {
+            let target = self
+                .root
+                .mul(&animated[body.bone])
+                .mul(&body.bone_offset.inverse());
+            if let Some(state) = world.get_state(body.handle) {
+                if delta_time > 0.0 {
+                    let velocity = (target.translation - to_glam(state.position)) / delta_time;
+                    world.set_linear_velocity(body.handle, from_glam(velocity));
+                }
+            }
+            world.set_position(body.handle, from_glam(target.translation));
+            world.set_rotation(body.handle, from_glam(quat_to_euler(target.rotation)));
+        }
+    }
+
+    /// Blend the simulated bones into an animated pose by [`Self::blend_weight`]
+    ///
+    /// `pose` holds the local bone transforms from the animator and receives
+    /// the blended result. Bones without a body follow their parent.
+    pub fn apply_pose(
+        &self,
+        world: &PhysicsWorld,
+        skeleton: &Skeleton,
+        pose: &mut [BoneTransform],
+    ) {
+        if self.blend_weight <= 0.0 || pose.len() < skeleton.bones.len() {
+            return;
+        }
+        let animated = model_space(skeleton, |i| pose_local(&pose[i]));
+        let root_inverse = self.root.inverse();
+        let simulated: HashMap<usize, Isometry> = self
+            .bodies
+            .iter()
+            .filter_map(|body| {
+                let state = world.get_state(body.handle)?;
+                let body_pose =
+                    Isometry::from_euler(to_glam(state.position), to_glam(state.rotation));
+                Some((body.bone, root_inverse.mul(&body_pose).mul(&body.bone_offset)))
+            })
+            .collect();
+
+        let mut blended: Vec<Isometry> = Vec::with_capacity(skeleton.bones.len());
+        for (i, bone) in skeleton.bones.iter().enumerate() {
+            let parent = usize::try_from(bone.parent).ok().and_then(|p| blended.get(p));
+            let model = match simulated.get(&i) {
+                Some(simulated) => animated[i].lerp(simulated, self.blend_weight),
+                None => parent.map_or(animated[i], |parent| parent.mul(&pose_local(&pose[i]))),
+            };
+            let local = parent.map_or(model, |parent| parent.inverse().mul(&model));
+            pose[i].position = from_glam(local.translation);
+            pose[i].rotation = bone_euler(local.rotation);
+            blended.push(model);
         }
     }
 
@@ -465,8 +882,94 @@ impl RagdollController {
         matches!(self.state, RagdollState::Active | RagdollState::BlendingIn | RagdollState::BlendingOut)
     }
 
-    /// Apply an impulse to a bone
-    pub fn apply_impulse(&mut self, _bone_name: &str, _impulse: Vec3) {
-        // Would apply to rigidbody
+    /// Apply an impulse to a bone, e.g. for a hit reaction
+    pub fn apply_impulse(&self, world: &mut PhysicsWorld, bone_name: &str, impulse: Vec3) {
+        if let Some(handle) = self.bone_body(bone_name) {
+            world.apply_force(handle, impulse, ForceMode::Impulse);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PhysicsConfig;
+    use lunaris_renderer::animation::Bone;
+
+    fn spine_skeleton() -> Skeleton {
+        let mut skeleton = Skeleton::new("spine");
+        for (name, parent, height) in [
+            ("Pelvis", -1, 1.0),
+            ("Spine", 0, 0.2),
+            ("Chest", 1, 0.2),
+            ("Head", 2, 0.25),
+        ] {
+            skeleton.add_bone(Bone {
+                name: name.to_string(),
+                parent,
+                local_position: Vec3::new(0.0, height, 0.0),
+                local_rotation: Vec3::ZERO,
+                local_scale: Vec3::ONE,
+                inverse_bind: [0.0; 16],
+            });
+        }
+        skeleton
+    }
+
+    #[test]
+    fn ragdoll_builds_from_skeleton_and_blends_pose() {
+        let skeleton = spine_skeleton();
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let mut ragdoll = RagdollBuilder::new(&skeleton)
+            .with_transform(Vec3::new(0.0, 5.0, 0.0), Vec3::ZERO)
+            .build(&mut world)
+            .unwrap();
+        for bone in ["Pelvis", "Spine", "Chest", "Head"] {
+            assert!(world.get_state(ragdoll.bone_body(bone).unwrap()).is_some());
+        }
+        assert_eq!(ragdoll.bone_body("UpperArmL"), None);
+        assert_eq!(ragdoll.constraints().len(), 3);
+
+        let pose: Vec<BoneTransform> = skeleton
+            .bones
+            .iter()
+            .map(|bone| BoneTransform {
+                position: bone.local_position,
+                rotation: bone.local_rotation,
+                scale: bone.local_scale,
+            })
+            .collect();
+
+        // Bodies start on the bind pose, so a full blend leaves it untouched
+        ragdoll.activate();
+        let mut blended = pose.clone();
+        ragdoll.apply_pose(&world, &skeleton, &mut blended);
+        for (a, b) in pose.iter().zip(&blended) {
+            assert!((a.position - b.position).length() < 1e-4);
+            assert!((a.rotation - b.rotation).length() < 1e-4);
+        }
+
+        ragdoll.apply_impulse(&mut world, "Head", Vec3::new(20.0, 0.0, 0.0));
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        let head = world.get_state(ragdoll.bone_body("Head").unwrap()).unwrap();
+        assert!(head.position.x > 0.05, "head at {:?}", head.position);
+
+        ragdoll.apply_pose(&world, &skeleton, &mut blended);
+        assert!(blended[0].position.y < pose[0].position.y - 0.5);
+        assert!((blended[1].position - pose[1].position).length() < 0.05);
+
+        ragdoll.deactivate();
+        let mut animated = pose.clone();
+        ragdoll.apply_pose(&world, &skeleton, &mut animated);
+        assert_eq!(animated[0].position, pose[0].position);
+    }
+
+    #[test]
+    fn ragdoll_requires_matching_bones() {
+        let skeleton = Skeleton::new("empty");
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        assert!(RagdollBuilder::new(&skeleton).build(&mut world).is_err());
     }
 }
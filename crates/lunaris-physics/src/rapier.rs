@@ -0,0 +1,673 @@
+//! Rapier backend shared by the 2D and 3D physics modules
+//!
+//! Rapier 2D and 3D expose the same API over different math types, so the
+//! backend is written once as a macro and expanded in each module. The
+//! expanding module provides `rp` (the Rapier prelude) and the conversions
+//! that depend on the dimension:
+//!
+//! - `vector`, `point`, `from_vector` and `from_point` for positions and
+//!   directions
+//! - `angular` and `from_angular` for torques and angular velocities
+//! - `euler_rotation` and `euler` for rotations given as Euler angles
+//! - `lock_rotations` to apply a body's rotation locks
+//! - `joint_load` for the force and torque a joint applied during a step
+//! - `shared_shape` for the module's collider shapes and `query_shape` for
+//!   the 3D shapes used by queries
+//! - `generic_joint` to map an engine constraint onto a Rapier joint
+
+/// Define a Rapier physics backend in the current module
+///
+/// `shape` is the [`ColliderShape`](crate::collision::ColliderShape) variant
+/// the backend simulates; colliders of the other variant are ignored.
+macro_rules! impl_rapier_backend {
+    (
+        $(#[$meta:meta])*
+        $backend:ident {
+            name: $name:literal,
+            shape: $variant:ident,
+            dimension: $dimension:literal,
+            other_dimension: $other_dimension:literal $(,)?
+        }
+    ) => {
+        pub use self::shared::$backend;
+        use self::shared::drive_free_axis;
+
+        mod shared {
+            // Dimension specific conversions and the Rapier prelude
+            use super::*;
+
+            use $crate::{
+                backend::{ContactTracker, PhysicsBackend, StepEvents},
+                collision::{
+                    ColliderShape, CollisionLayers, ContactPoint, OverlapQuery, RaycastHit,
+                    RaycastQuery, ShapeCastQuery,
+                },
+                constraints::Constraint,
+                rigidbody::{
+                    ColliderProperties, ForceMode, RigidbodyHandle, RigidbodyProperties,
+                    RigidbodyState, RigidbodyType,
+                },
+            };
+            use lunaris_core::{id::Id, math::Vec3};
+            use std::collections::{BTreeMap, HashMap};
+
+            struct BodyEntry {
+                body: rp::RigidBodyHandle,
+                collider: Option<rp::ColliderHandle>,
+                entity_id: Id,
+                properties: RigidbodyProperties,
+                pending_forces: Vec<(Vec3, ForceMode)>,
+                pending_torques: Vec<(Vec3, ForceMode)>,
+            }
+
+            struct JointEntry {
+                joint: rp::ImpulseJointHandle,
+                break_force: Option<f32>,
+                break_torque: Option<f32>,
+            }
+
+            $(#[$meta])*
+            pub struct $backend {
+                pipeline: rp::PhysicsPipeline,
+                integration_parameters: rp::IntegrationParameters,
+                islands: rp::IslandManager,
+                broad_phase: rp::BroadPhase,
+                narrow_phase: rp::NarrowPhase,
+                bodies: rp::RigidBodySet,
+                colliders: rp::ColliderSet,
+                impulse_joints: rp::ImpulseJointSet,
+                multibody_joints: rp::MultibodyJointSet,
+                ccd_solver: rp::CCDSolver,
+                query_pipeline: rp::QueryPipeline,
+                entries: HashMap<RigidbodyHandle, BodyEntry>,
+                joints: BTreeMap<u64, JointEntry>,
+                /// Fixed body used as the second end of world-attached constraints
+                world_anchor: Option<rp::RigidBodyHandle>,
+                contact_tracker: ContactTracker,
+            }
+
+            impl Default for $backend {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl $backend {
+                /// Create an empty Rapier world
+                #[must_use]
+                pub fn new() -> Self {
+                    Self {
+                        pipeline: rp::PhysicsPipeline::new(),
+                        integration_parameters: rp::IntegrationParameters::default(),
+                        islands: rp::IslandManager::new(),
+                        broad_phase: rp::BroadPhase::new(),
+                        narrow_phase: rp::NarrowPhase::new(),
+                        bodies: rp::RigidBodySet::new(),
+                        colliders: rp::ColliderSet::new(),
+                        impulse_joints: rp::ImpulseJointSet::new(),
+                        multibody_joints: rp::MultibodyJointSet::new(),
+                        ccd_solver: rp::CCDSolver::new(),
+                        query_pipeline: rp::QueryPipeline::new(),
+                        entries: HashMap::new(),
+                        joints: BTreeMap::new(),
+                        world_anchor: None,
+                        contact_tracker: ContactTracker::new(),
+                    }
+                }
+
+                /// Direct access to the Rapier body set
+                #[must_use]
+                pub fn bodies(&self) -> &rp::RigidBodySet {
+                    &self.bodies
+                }
+
+                /// Direct access to the Rapier collider set
+                #[must_use]
+                pub fn colliders(&self) -> &rp::ColliderSet {
+                    &self.colliders
+                }
+
+                fn body_mut(&mut self, handle: RigidbodyHandle) -> Option<&mut rp::RigidBody> {
+                    let body = self.entries.get(&handle)?.body;
+                    self.bodies.get_mut(body)
+                }
+
+                fn world_anchor(&mut self) -> rp::RigidBodyHandle {
+                    *self.world_anchor.get_or_insert_with(|| {
+                        self.bodies.insert(rp::RigidBodyBuilder::fixed().build())
+                    })
+                }
+
+                /// Owning engine handle of a Rapier collider
+                fn collider_owner(
+                    &self,
+                    collider: rp::ColliderHandle,
+                ) -> Option<(RigidbodyHandle, Id)> {
+                    let raw = self.colliders.get(collider)?.user_data as u64;
+                    let handle = RigidbodyHandle(Id::from_raw(raw));
+                    self.entries.get(&handle).map(|e| (handle, e.entity_id))
+                }
+
+                fn apply_pending(&mut self, dt: f32) {
+                    for entry in self.entries.values_mut() {
+                        let Some(body) = self.bodies.get_mut(entry.body) else {
+                            continue;
+                        };
+                        body.reset_forces(false);
+                        body.reset_torques(false);
+                        if !body.is_dynamic() {
+                            entry.pending_forces.clear();
+                            entry.pending_torques.clear();
+                            continue;
+                        }
+
+                        for (force, mode) in entry.pending_forces.drain(..) {
+                            let force = vector(force);
+                            match mode {
+                                ForceMode::Force => body.add_force(force, true),
+                                ForceMode::Impulse => body.apply_impulse(force, true),
+                                ForceMode::Acceleration => {
+                                    body.set_linvel(body.linvel() + force * dt, true)
+                                },
+                                ForceMode::VelocityChange => {
+                                    body.set_linvel(body.linvel() + force, true)
+                                },
+                            }
+                        }
+                        for (torque, mode) in entry.pending_torques.drain(..) {
+                            let torque = angular(torque);
+                            match mode {
+                                ForceMode::Force => body.add_torque(torque, true),
+                                ForceMode::Impulse => body.apply_torque_impulse(torque, true),
+                                ForceMode::Acceleration => {
+                                    body.set_angvel(body.angvel() + torque * dt, true)
+                                },
+                                ForceMode::VelocityChange => {
+                                    body.set_angvel(body.angvel() + torque, true)
+                                },
+                            }
+                        }
+                    }
+                }
+
+                fn touching_pairs(
+                    &self,
+                ) -> Vec<(RigidbodyHandle, RigidbodyHandle, Id, Id, Vec<ContactPoint>)> {
+                    let mut touching = Vec::new();
+
+                    for pair in self.narrow_phase.contact_pairs() {
+                        if !pair.has_any_active_contact {
+                            continue;
+                        }
+                        let (Some(a), Some(b)) = (
+                            self.collider_owner(pair.collider1),
+                            self.collider_owner(pair.collider2),
+                        ) else {
+                            continue;
+                        };
+                        let contacts = pair
+                            .manifolds
+                            .iter()
+                            .flat_map(|manifold| {
+                                let normal = from_vector(&manifold.data.normal);
+                                manifold.data.solver_contacts.iter().map(move |contact| {
+                                    ContactPoint {
+                                        position: from_point(&contact.point),
+                                        normal,
+                                        depth: (-contact.dist).max(0.0),
+                                    }
+                                })
+                            })
+                            .collect();
+                        touching.push((a.0, b.0, a.1, b.1, contacts));
+                    }
+
+                    for (collider1, collider2, intersecting) in
+                        self.narrow_phase.intersection_pairs()
+                    {
+                        if !intersecting {
+                            continue;
+                        }
+                        if let (Some(a), Some(b)) = (
+                            self.collider_owner(collider1),
+                            self.collider_owner(collider2),
+                        ) {
+                            touching.push((a.0, b.0, a.1, b.1, Vec::new()));
+                        }
+                    }
+
+                    touching.sort_by_key(|(a, b, ..)| {
+                        (a.0.raw().min(b.0.raw()), a.0.raw().max(b.0.raw()))
+                    });
+                    touching
+                }
+
+                /// Query filter for the given layers, optionally skipping one entity's body
+                fn query_filter(
+                    &self,
+                    layers: CollisionLayers,
+                    exclude: Option<Id>,
+                ) -> rp::QueryFilter<'_> {
+                    let filter = rp::QueryFilter::new().groups(interaction_groups(layers));
+                    let excluded = exclude
+                        .and_then(|entity| self.entries.values().find(|e| e.entity_id == entity));
+                    match excluded {
+                        Some(entry) => filter.exclude_rigid_body(entry.body),
+                        None => filter,
+                    }
+                }
+
+                fn broken_joints(&self, dt: f32) -> Vec<u64> {
+                    self.joints
+                        .iter()
+                        .filter(|(_, entry)| {
+                            let Some(joint) = self.impulse_joints.get(entry.joint) else {
+                                return false;
+                            };
+                            let (force, torque) = joint_load(joint);
+                            entry.break_force.is_some_and(|limit| force / dt > limit)
+                                || entry.break_torque.is_some_and(|limit| torque / dt > limit)
+                        })
+                        .map(|(id, _)| *id)
+                        .collect()
+                }
+
+                fn ray_hit(
+                    &self,
+                    ray: &rp::Ray,
+                    collider: rp::ColliderHandle,
+                    hit: rp::RayIntersection,
+                ) -> Option<RaycastHit> {
+                    let (_, entity) = self.collider_owner(collider)?;
+                    Some(RaycastHit {
+                        entity,
+                        point: from_point(&ray.point_at(hit.toi)),
+                        normal: from_vector(&hit.normal),
+                        distance: hit.toi,
+                    })
+                }
+            }
+
+            impl PhysicsBackend for $backend {
+                fn name(&self) -> &'static str {
+                    $name
+                }
+
+                fn insert_body(
+                    &mut self,
+                    handle: RigidbodyHandle,
+                    entity_id: Id,
+                    properties: &RigidbodyProperties,
+                    position: Vec3,
+                ) {
+                    let builder = match properties.body_type {
+                        RigidbodyType::Dynamic => {
+                            rp::RigidBodyBuilder::dynamic().additional_mass(properties.mass)
+                        },
+                        RigidbodyType::Kinematic => {
+                            rp::RigidBodyBuilder::kinematic_velocity_based()
+                        },
+                        RigidbodyType::Static => rp::RigidBodyBuilder::fixed(),
+                    };
+                    let builder = builder
+                        .translation(vector(position))
+                        .linear_damping(properties.linear_damping)
+                        .angular_damping(properties.angular_damping)
+                        .gravity_scale(properties.gravity_scale)
+                        .ccd_enabled(properties.ccd_enabled)
+                        .user_data(u128::from(handle.0.raw()));
+
+                    let body = self.bodies.insert(lock_rotations(builder, properties).build());
+                    self.entries.insert(
+                        handle,
+                        BodyEntry {
+                            body,
+                            collider: None,
+                            entity_id,
+                            properties: properties.clone(),
+                            pending_forces: Vec::new(),
+                            pending_torques: Vec::new(),
+                        },
+                    );
+                }
+
+                fn remove_body(&mut self, handle: RigidbodyHandle) {
+                    if let Some(entry) = self.entries.remove(&handle) {
+                        self.bodies.remove(
+                            entry.body,
+                            &mut self.islands,
+                            &mut self.colliders,
+                            &mut self.impulse_joints,
+                            &mut self.multibody_joints,
+                            true,
+                        );
+                        self.query_pipeline.update(&self.bodies, &self.colliders);
+                    }
+                }
+
+                fn set_collider(
+                    &mut self,
+                    handle: RigidbodyHandle,
+                    shape: &ColliderShape,
+                    properties: &ColliderProperties,
+                    offset: Vec3,
+                ) {
+                    let Some(entry) = self.entries.get_mut(&handle) else {
+                        return;
+                    };
+                    let ColliderShape::$variant(shape) = shape else {
+                        tracing::warn!(
+                            "Rapier {} backend ignores {} collider on {:?}",
+                            $dimension,
+                            $other_dimension,
+                            handle
+                        );
+                        return;
+                    };
+                    let Some(shared) = shared_shape(shape) else {
+                        tracing::warn!("Degenerate collider shape on {:?}", handle);
+                        return;
+                    };
+
+                    if let Some(old) = entry.collider.take() {
+                        self.colliders
+                            .remove(old, &mut self.islands, &mut self.bodies, true);
+                    }
+
+                    let mut builder = rp::ColliderBuilder::new(shared)
+                        .translation(vector(offset))
+                        .friction(properties.friction)
+                        .restitution(properties.restitution)
+                        .restitution_combine_rule(rp::CoefficientCombineRule::Max)
+                        .collision_groups(interaction_groups(properties.layers))
+                        .sensor(entry.properties.is_sensor)
+                        .user_data(u128::from(handle.0.raw()));
+                    if entry.properties.body_type == RigidbodyType::Dynamic {
+                        // The body's mass now comes from its collider
+                        builder = builder.mass(entry.properties.mass);
+                        if let Some(body) = self.bodies.get_mut(entry.body) {
+                            body.set_additional_mass(0.0, true);
+                        }
+                    }
+
+                    entry.collider = Some(self.colliders.insert_with_parent(
+                        builder.build(),
+                        entry.body,
+                        &mut self.bodies,
+                    ));
+                    self.query_pipeline.update(&self.bodies, &self.colliders);
+                }
+
+                fn state(&self, handle: RigidbodyHandle) -> Option<RigidbodyState> {
+                    let body = self.bodies.get(self.entries.get(&handle)?.body)?;
+                    Some(RigidbodyState {
+                        position: from_vector(body.translation()),
+                        rotation: euler(body.rotation()),
+                        linear_velocity: from_vector(body.linvel()),
+                        angular_velocity: from_angular(body.angvel()),
+                    })
+                }
+
+                fn set_position(&mut self, handle: RigidbodyHandle, position: Vec3) {
+                    if let Some(body) = self.body_mut(handle) {
+                        body.set_translation(vector(position), true);
+                    }
+                }
+
+                fn set_rotation(&mut self, handle: RigidbodyHandle, rotation: Vec3) {
+                    if let Some(body) = self.body_mut(handle) {
+                        body.set_rotation(euler_rotation(rotation), true);
+                    }
+                }
+
+                fn set_linear_velocity(&mut self, handle: RigidbodyHandle, velocity: Vec3) {
+                    if let Some(body) = self.body_mut(handle) {
+                        body.set_linvel(vector(velocity), true);
+                    }
+                }
+
+                fn apply_force(&mut self, handle: RigidbodyHandle, force: Vec3, mode: ForceMode) {
+                    if let Some(entry) = self.entries.get_mut(&handle) {
+                        entry.pending_forces.push((force, mode));
+                    }
+                }
+
+                fn apply_torque(
+                    &mut self,
+                    handle: RigidbodyHandle,
+                    torque: Vec3,
+                    mode: ForceMode,
+                ) {
+                    if let Some(entry) = self.entries.get_mut(&handle) {
+                        entry.pending_torques.push((torque, mode));
+                    }
+                }
+
+                fn is_sleeping(&self, handle: RigidbodyHandle) -> bool {
+                    self.entries
+                        .get(&handle)
+                        .and_then(|entry| self.bodies.get(entry.body))
+                        .is_some_and(rp::RigidBody::is_sleeping)
+                }
+
+                fn wake_up(&mut self, handle: RigidbodyHandle) {
+                    if let Some(body) = self.body_mut(handle) {
+                        body.wake_up(true);
+                    }
+                }
+
+                fn insert_constraint(&mut self, constraint: &Constraint) {
+                    let lookup = |raw: u64| {
+                        self.entries
+                            .get(&RigidbodyHandle(Id::from_raw(raw)))
+                            .map(|e| e.body)
+                    };
+                    let Some(body1) = lookup(constraint.body_a) else {
+                        tracing::warn!(
+                            "Constraint {} references unknown body {}",
+                            constraint.id,
+                            constraint.body_a
+                        );
+                        return;
+                    };
+                    let body2 = match constraint.body_b {
+                        Some(raw) => match lookup(raw) {
+                            Some(body) => body,
+                            None => {
+                                tracing::warn!(
+                                    "Constraint {} references unknown body {}",
+                                    constraint.id,
+                                    raw
+                                );
+                                return;
+                            },
+                        },
+                        None => self.world_anchor(),
+                    };
+
+                    let mut joint = generic_joint(constraint);
+                    joint.set_enabled(constraint.enabled);
+                    let joint = self.impulse_joints.insert(body1, body2, joint, true);
+                    self.joints.insert(
+                        constraint.id,
+                        JointEntry {
+                            joint,
+                            break_force: constraint.break_force,
+                            break_torque: constraint.break_torque,
+                        },
+                    );
+                }
+
+                fn remove_constraint(&mut self, id: u64) {
+                    if let Some(entry) = self.joints.remove(&id) {
+                        self.impulse_joints.remove(entry.joint, true);
+                    }
+                }
+
+                fn step(&mut self, dt: f32, gravity: Vec3) -> StepEvents {
+                    self.apply_pending(dt);
+                    self.integration_parameters.dt = dt;
+
+                    self.pipeline.step(
+                        &vector(gravity),
+                        &self.integration_parameters,
+                        &mut self.islands,
+                        &mut self.broad_phase,
+                        &mut self.narrow_phase,
+                        &mut self.bodies,
+                        &mut self.colliders,
+                        &mut self.impulse_joints,
+                        &mut self.multibody_joints,
+                        &mut self.ccd_solver,
+                        Some(&mut self.query_pipeline),
+                        &(),
+                        &(),
+                    );
+
+                    let mut events = StepEvents::default();
+                    let touching = self.touching_pairs();
+                    self.contact_tracker
+                        .update(touching, &mut events.collisions);
+                    events.broken_constraints = self.broken_joints(dt);
+                    events
+                }
+
+                fn raycast(&self, query: &RaycastQuery) -> Option<RaycastHit> {
+                    let ray = rp::Ray::new(point(query.origin), vector(query.direction));
+                    let filter = self
+                        .query_filter(query.layers, query.exclude)
+                        .exclude_sensors();
+                    let (collider, hit) = self.query_pipeline.cast_ray_and_get_normal(
+                        &self.bodies,
+                        &self.colliders,
+                        &ray,
+                        query.max_distance,
+                        true,
+                        filter,
+                    )?;
+                    self.ray_hit(&ray, collider, hit)
+                }
+
+                fn raycast_all(&self, query: &RaycastQuery) -> Vec<RaycastHit> {
+                    let ray = rp::Ray::new(point(query.origin), vector(query.direction));
+                    let filter = self
+                        .query_filter(query.layers, query.exclude)
+                        .exclude_sensors();
+                    let mut hits = Vec::new();
+                    self.query_pipeline.intersections_with_ray(
+                        &self.bodies,
+                        &self.colliders,
+                        &ray,
+                        query.max_distance,
+                        true,
+                        filter,
+                        |collider, hit| {
+                            hits.extend(self.ray_hit(&ray, collider, hit));
+                            true
+                        },
+                    );
+                    hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+                    hits
+                }
+
+                fn shape_cast(&self, query: &ShapeCastQuery) -> Option<RaycastHit> {
+                    let shape = query_shape(&query.shape)?;
+                    let position = rp::Isometry::from_parts(
+                        vector(query.origin).into(),
+                        euler_rotation(query.rotation),
+                    );
+                    let filter = self
+                        .query_filter(query.layers, query.exclude)
+                        .exclude_sensors();
+                    let (collider, toi) = self.query_pipeline.cast_shape(
+                        &self.bodies,
+                        &self.colliders,
+                        &position,
+                        &vector(query.direction),
+                        &*shape,
+                        query.max_distance,
+                        true,
+                        filter,
+                    )?;
+                    let (_, entity) = self.collider_owner(collider)?;
+                    let collider_position = self.colliders.get(collider)?.position();
+                    Some(RaycastHit {
+                        entity,
+                        point: from_point(&(collider_position * toi.witness1)),
+                        normal: from_vector(&(collider_position * toi.normal1)),
+                        distance: toi.toi,
+                    })
+                }
+
+                fn overlap(&self, query: &OverlapQuery) -> Vec<Id> {
+                    let Some(shape) = query_shape(&query.shape) else {
+                        return Vec::new();
+                    };
+                    let position = rp::Isometry::from_parts(
+                        vector(query.position).into(),
+                        euler_rotation(query.rotation),
+                    );
+                    let filter = self.query_filter(query.layers, query.exclude);
+                    let mut owners = Vec::new();
+                    self.query_pipeline.intersections_with_shape(
+                        &self.bodies,
+                        &self.colliders,
+                        &position,
+                        &*shape,
+                        filter,
+                        |collider| {
+                            owners.extend(self.collider_owner(collider));
+                            true
+                        },
+                    );
+                    owners.sort_by_key(|(handle, _)| handle.0.raw());
+                    owners.into_iter().map(|(_, entity)| entity).collect()
+                }
+            }
+
+            fn interaction_groups(layers: CollisionLayers) -> rp::InteractionGroups {
+                rp::InteractionGroups::new(
+                    rp::Group::from_bits_truncate(layers.membership),
+                    rp::Group::from_bits_truncate(layers.filter),
+                )
+            }
+
+            /// Apply a constraint's limits, motor and spring to a joint axis
+            ///
+            /// The motor is force based like the built-in solver's springs, and its
+            /// damping saturates at `max_force` one unit of velocity off target.
+            pub(super) fn drive_free_axis(
+                mut builder: rp::GenericJointBuilder,
+                axis: rp::JointAxis,
+                constraint: &Constraint,
+            ) -> rp::GenericJointBuilder {
+                if let Some((min, max)) = constraint.limits {
+                    builder = builder.limits(axis, [min, max]);
+                }
+                let motor = constraint.motor.filter(|m| m.enabled);
+                if motor.is_none() && constraint.spring.is_none() {
+                    return builder;
+                }
+                let (rest, stiffness, damping) = constraint
+                    .spring
+                    .map_or((0.0, 0.0, 0.0), |s| (s.rest_length, s.stiffness, s.damping));
+                let (target_velocity, motor_damping, max_force) = motor
+                    .map_or((0.0, 0.0, f32::MAX), |m| {
+                        (m.target_velocity, m.max_force, m.max_force)
+                    });
+                builder
+                    .motor_model(axis, rp::MotorModel::ForceBased)
+                    .set_motor(
+                        axis,
+                        rest,
+                        target_velocity,
+                        stiffness,
+                        damping + motor_damping,
+                    )
+                    .motor_max_force(axis, max_force)
+            }
+        }
+    };
+}
+
+pub(crate) use impl_rapier_backend;
@@ -0,0 +1,824 @@
+//! Physics state snapshots
+//!
+//! A compact little-endian binary encoding of the simulation state. Floats
+//! are stored as raw bits so a restored world continues bit-exactly, which
+//! is what rollback netcode and replays rely on.
+
+use crate::{
+    collision::{ColliderShape, ColliderShape2D, ColliderShape3D, CollisionLayers},
+    constraints::{Constraint, ConstraintType, Motor, RopeConstraint, Spring},
+    rigidbody::{
+        ColliderProperties, ForceMode, RigidbodyHandle, RigidbodyProperties, RigidbodyState,
+        RigidbodyType,
+    },
+};
+use lunaris_core::{
+    id::Id,
+    math::{Vec2, Vec3},
+    Error, Result,
+};
+
+/// Magic bytes at the start of every snapshot
+const MAGIC: &[u8; 4] = b"LPHS";
+/// Current encoding version
+const VERSION: u32 = 1;
+
+/// Serialized physics world state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhysicsSnapshot {
+    bytes: Vec<u8>,
+}
+
+impl PhysicsSnapshot {
+    /// Wrap previously saved bytes
+    #[must_use]
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    /// Encoded bytes
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Consume the snapshot, returning its bytes
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Size in bytes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Check if the snapshot holds no data
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// Appends encoded values to a byte buffer
+#[derive(Debug, Default)]
+pub struct SnapshotWriter {
+    bytes: Vec<u8>,
+}
+
+impl SnapshotWriter {
+    /// Create a writer with the snapshot header for `backend`
+    #[must_use]
+    pub fn new(backend: &str) -> Self {
+        let mut writer = Self::default();
+        writer.bytes.extend_from_slice(MAGIC);
+        writer.write(&VERSION);
+        writer.write_str(backend);
+        writer
+    }
+
+    /// Encode a value
+    pub fn write<T: SnapshotCodec>(&mut self, value: &T) {
+        value.encode(self);
+    }
+
+    /// Encode a string
+    pub fn write_str(&mut self, value: &str) {
+        self.write(&(value.len() as u32));
+        self.bytes.extend_from_slice(value.as_bytes());
+    }
+
+    /// Append raw bytes
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Finish writing
+    #[must_use]
+    pub fn finish(self) -> PhysicsSnapshot {
+        PhysicsSnapshot { bytes: self.bytes }
+    }
+}
+
+/// Decodes values from a snapshot
+#[derive(Debug)]
+pub struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SnapshotReader<'a> {
+    /// Open a snapshot, checking its header against `backend`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid or was written by a
+    /// different backend
+    pub fn new(snapshot: &'a PhysicsSnapshot, backend: &str) -> Result<Self> {
+        let mut reader = Self {
+            bytes: &snapshot.bytes,
+            position: 0,
+        };
+        if reader.read_bytes(MAGIC.len())? != MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        let version: u32 = reader.read()?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported version {version}")));
+        }
+        let saved_backend = reader.read_string()?;
+        if saved_backend != backend {
+            return Err(invalid(&format!(
+                "saved by the {saved_backend} backend, not {backend}"
+            )));
+        }
+        Ok(reader)
+    }
+
+    /// Decode a value
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated or malformed
+    pub fn read<T: SnapshotCodec>(&mut self) -> Result<T> {
+        T::decode(self)
+    }
+
+    /// Decode a string
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated or not UTF-8
+    pub fn read_string(&mut self) -> Result<String> {
+        let len: u32 = self.read()?;
+        let bytes = self.read_bytes(len as usize)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    /// Take raw bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if fewer than `len` bytes remain
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of data"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Check that every byte was consumed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if trailing data remains
+    pub fn finish(self) -> Result<()> {
+        if self.position == self.bytes.len() {
+            Ok(())
+        } else {
+            Err(invalid("trailing data"))
+        }
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::Internal(format!("Invalid physics snapshot: {reason}"))
+}
+
+/// Types that can be written to and read from a snapshot
+pub trait SnapshotCodec: Sized {
+    /// Append the encoded value
+    fn encode(&self, writer: &mut SnapshotWriter);
+
+    /// Decode a value
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated or malformed
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self>;
+}
+
+macro_rules! impl_codec_for_int {
+    ($($ty:ty),*) => {
+        $(
+            impl SnapshotCodec for $ty {
+                fn encode(&self, writer: &mut SnapshotWriter) {
+                    writer.write_bytes(&self.to_le_bytes());
+                }
+
+                fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+                    let bytes = reader.read_bytes(std::mem::size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(bytes.try_into().expect("length checked")))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_int!(u8, u32, u64);
+
+impl SnapshotCodec for f32 {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.to_bits());
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(Self::from_bits(reader.read()?))
+    }
+}
+
+impl SnapshotCodec for bool {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&u8::from(*self));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        match reader.read::<u8>()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            other => Err(invalid(&format!("bad bool {other}"))),
+        }
+    }
+}
+
+impl<T: SnapshotCodec> SnapshotCodec for Option<T> {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.is_some());
+        if let Some(value) = self {
+            writer.write(value);
+        }
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        if reader.read::<bool>()? {
+            Ok(Some(reader.read()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl<T: SnapshotCodec> SnapshotCodec for Vec<T> {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.len() as u32));
+        for item in self {
+            writer.write(item);
+        }
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let len: u32 = reader.read()?;
+        (0..len).map(|_| reader.read()).collect()
+    }
+}
+
+impl<T: SnapshotCodec> SnapshotCodec for Box<T> {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&**self);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(Self::new(reader.read()?))
+    }
+}
+
+impl<A: SnapshotCodec, B: SnapshotCodec> SnapshotCodec for (A, B) {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok((reader.read()?, reader.read()?))
+    }
+}
+
+impl<A: SnapshotCodec, B: SnapshotCodec, C: SnapshotCodec> SnapshotCodec for (A, B, C) {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+        writer.write(&self.2);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok((reader.read()?, reader.read()?, reader.read()?))
+    }
+}
+
+impl SnapshotCodec for Vec2 {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.x, self.y));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (x, y) = reader.read()?;
+        Ok(Self::new(x, y))
+    }
+}
+
+impl SnapshotCodec for Vec3 {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.x, self.y, self.z));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (x, y, z) = reader.read()?;
+        Ok(Self::new(x, y, z))
+    }
+}
+
+impl SnapshotCodec for glam::Vec3 {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.x, self.y, self.z));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (x, y, z) = reader.read()?;
+        Ok(Self::new(x, y, z))
+    }
+}
+
+impl SnapshotCodec for glam::Quat {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.x, self.y));
+        writer.write(&(self.z, self.w));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (x, y) = reader.read()?;
+        let (z, w) = reader.read()?;
+        Ok(Self::from_xyzw(x, y, z, w))
+    }
+}
+
+impl SnapshotCodec for Id {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.raw());
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(Self::from_raw(reader.read()?))
+    }
+}
+
+impl SnapshotCodec for RigidbodyHandle {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.0);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(Self(reader.read()?))
+    }
+}
+
+impl SnapshotCodec for CollisionLayers {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.membership, self.filter));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (membership, filter) = reader.read()?;
+        Ok(Self::new(membership, filter))
+    }
+}
+
+impl SnapshotCodec for ColliderShape2D {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Self::Circle { radius } => {
+                writer.write(&0u8);
+                writer.write(radius);
+            },
+            Self::Rectangle {
+                half_width,
+                half_height,
+            } => {
+                writer.write(&1u8);
+                writer.write(&(*half_width, *half_height));
+            },
+            Self::Capsule {
+                half_height,
+                radius,
+            } => {
+                writer.write(&2u8);
+                writer.write(&(*half_height, *radius));
+            },
+            Self::ConvexPolygon { vertices } => {
+                writer.write(&3u8);
+                writer.write(vertices);
+            },
+            Self::Compound { shapes } => {
+                writer.write(&4u8);
+                writer.write(shapes);
+            },
+        }
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(match reader.read::<u8>()? {
+            0 => Self::Circle {
+                radius: reader.read()?,
+            },
+            1 => {
+                let (half_width, half_height) = reader.read()?;
+                Self::Rectangle {
+                    half_width,
+                    half_height,
+                }
+            },
+            2 => {
+                let (half_height, radius) = reader.read()?;
+                Self::Capsule {
+                    half_height,
+                    radius,
+                }
+            },
+            3 => Self::ConvexPolygon {
+                vertices: reader.read()?,
+            },
+            4 => Self::Compound {
+                shapes: reader.read()?,
+            },
+            tag => return Err(invalid(&format!("bad 2D shape tag {tag}"))),
+        })
+    }
+}
+
+impl SnapshotCodec for ColliderShape3D {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Self::Sphere { radius } => {
+                writer.write(&0u8);
+                writer.write(radius);
+            },
+            Self::Box { half_extents } => {
+                writer.write(&1u8);
+                writer.write(half_extents);
+            },
+            Self::Capsule {
+                half_height,
+                radius,
+            } => {
+                writer.write(&2u8);
+                writer.write(&(*half_height, *radius));
+            },
+            Self::Cylinder {
+                half_height,
+                radius,
+            } => {
+                writer.write(&3u8);
+                writer.write(&(*half_height, *radius));
+            },
+            Self::ConvexHull { vertices } => {
+                writer.write(&4u8);
+                writer.write(vertices);
+            },
+            Self::TriMesh { vertices, indices } => {
+                writer.write(&5u8);
+                writer.write(vertices);
+                writer.write(&(indices.len() as u32));
+                for [a, b, c] in indices {
+                    writer.write(&(*a, *b, *c));
+                }
+            },
+            Self::Compound { shapes } => {
+                writer.write(&6u8);
+                writer.write(shapes);
+            },
+        }
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(match reader.read::<u8>()? {
+            0 => Self::Sphere {
+                radius: reader.read()?,
+            },
+            1 => Self::Box {
+                half_extents: reader.read()?,
+            },
+            2 => {
+                let (half_height, radius) = reader.read()?;
+                Self::Capsule {
+                    half_height,
+                    radius,
+                }
+            },
+            3 => {
+                let (half_height, radius) = reader.read()?;
+                Self::Cylinder {
+                    half_height,
+                    radius,
+                }
+            },
+            4 => Self::ConvexHull {
+                vertices: reader.read()?,
+            },
+            5 => {
+                let vertices = reader.read()?;
+                let count: u32 = reader.read()?;
+                let indices = (0..count)
+                    .map(|_| reader.read().map(|(a, b, c)| [a, b, c]))
+                    .collect::<Result<_>>()?;
+                Self::TriMesh { vertices, indices }
+            },
+            6 => Self::Compound {
+                shapes: reader.read()?,
+            },
+            tag => return Err(invalid(&format!("bad 3D shape tag {tag}"))),
+        })
+    }
+}
+
+impl SnapshotCodec for ColliderShape {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Self::Shape2D(shape) => {
+                writer.write(&0u8);
+                writer.write(shape);
+            },
+            Self::Shape3D(shape) => {
+                writer.write(&1u8);
+                writer.write(shape);
+            },
+        }
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        match reader.read::<u8>()? {
+            0 => Ok(Self::Shape2D(reader.read()?)),
+            1 => Ok(Self::Shape3D(reader.read()?)),
+            tag => Err(invalid(&format!("bad shape tag {tag}"))),
+        }
+    }
+}
+
+impl SnapshotCodec for RigidbodyType {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        let tag: u8 = match self {
+            Self::Dynamic => 0,
+            Self::Kinematic => 1,
+            Self::Static => 2,
+        };
+        writer.write(&tag);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        match reader.read::<u8>()? {
+            0 => Ok(Self::Dynamic),
+            1 => Ok(Self::Kinematic),
+            2 => Ok(Self::Static),
+            tag => Err(invalid(&format!("bad body type {tag}"))),
+        }
+    }
+}
+
+impl SnapshotCodec for RigidbodyProperties {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.body_type);
+        writer.write(&(self.mass, self.linear_damping, self.angular_damping));
+        writer.write(&self.gravity_scale);
+        writer.write(&(
+            self.lock_rotation_x,
+            self.lock_rotation_y,
+            self.lock_rotation_z,
+        ));
+        writer.write(&(self.is_sensor, self.ccd_enabled));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let body_type = reader.read()?;
+        let (mass, linear_damping, angular_damping) = reader.read()?;
+        let gravity_scale = reader.read()?;
+        let (lock_rotation_x, lock_rotation_y, lock_rotation_z) = reader.read()?;
+        let (is_sensor, ccd_enabled) = reader.read()?;
+        Ok(Self {
+            body_type,
+            mass,
+            linear_damping,
+            angular_damping,
+            gravity_scale,
+            lock_rotation_x,
+            lock_rotation_y,
+            lock_rotation_z,
+            is_sensor,
+            ccd_enabled,
+        })
+    }
+}
+
+impl SnapshotCodec for RigidbodyState {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.position, self.rotation));
+        writer.write(&(self.linear_velocity, self.angular_velocity));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (position, rotation) = reader.read()?;
+        let (linear_velocity, angular_velocity) = reader.read()?;
+        Ok(Self {
+            position,
+            rotation,
+            linear_velocity,
+            angular_velocity,
+        })
+    }
+}
+
+impl SnapshotCodec for ColliderProperties {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.friction, self.restitution, self.density));
+        writer.write(&self.layers);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (friction, restitution, density) = reader.read()?;
+        Ok(Self {
+            friction,
+            restitution,
+            density,
+            layers: reader.read()?,
+        })
+    }
+}
+
+impl SnapshotCodec for ForceMode {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        let tag: u8 = match self {
+            Self::Force => 0,
+            Self::Impulse => 1,
+            Self::Acceleration => 2,
+            Self::VelocityChange => 3,
+        };
+        writer.write(&tag);
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        match reader.read::<u8>()? {
+            0 => Ok(Self::Force),
+            1 => Ok(Self::Impulse),
+            2 => Ok(Self::Acceleration),
+            3 => Ok(Self::VelocityChange),
+            tag => Err(invalid(&format!("bad force mode {tag}"))),
+        }
+    }
+}
+
+impl SnapshotCodec for ConstraintType {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        match *self {
+            Self::Fixed => writer.write(&0u8),
+            Self::BallSocket => writer.write(&1u8),
+            Self::Hinge { axis } => {
+                writer.write(&2u8);
+                writer.write(&axis);
+            },
+            Self::Slider { axis } => {
+                writer.write(&3u8);
+                writer.write(&axis);
+            },
+            Self::Distance { min, max } => {
+                writer.write(&4u8);
+                writer.write(&(min, max));
+            },
+            Self::ConeTwist {
+                swing_span,
+                twist_span,
+            } => {
+                writer.write(&5u8);
+                writer.write(&(swing_span, twist_span));
+            },
+            Self::Universal { axis1, axis2 } => {
+                writer.write(&6u8);
+                writer.write(&(axis1, axis2));
+            },
+        }
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        Ok(match reader.read::<u8>()? {
+            0 => Self::Fixed,
+            1 => Self::BallSocket,
+            2 => Self::Hinge {
+                axis: reader.read()?,
+            },
+            3 => Self::Slider {
+                axis: reader.read()?,
+            },
+            4 => {
+                let (min, max) = reader.read()?;
+                Self::Distance { min, max }
+            },
+            5 => {
+                let (swing_span, twist_span) = reader.read()?;
+                Self::ConeTwist {
+                    swing_span,
+                    twist_span,
+                }
+            },
+            6 => {
+                let (axis1, axis2) = reader.read()?;
+                Self::Universal { axis1, axis2 }
+            },
+            tag => return Err(invalid(&format!("bad constraint tag {tag}"))),
+        })
+    }
+}
+
+impl SnapshotCodec for Constraint {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.id, self.body_a, self.body_b));
+        writer.write(&self.constraint_type);
+        writer.write(&(self.anchor_a, self.anchor_b));
+        writer.write(&(self.rotation_a, self.rotation_b));
+        writer.write(&(self.enabled, self.is_broken));
+        writer.write(&(self.break_force, self.break_torque));
+        writer.write(&(self.limits, self.motor, self.spring));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (id, body_a, body_b) = reader.read()?;
+        let constraint_type = reader.read()?;
+        let (anchor_a, anchor_b) = reader.read()?;
+        let (rotation_a, rotation_b) = reader.read()?;
+        let (enabled, is_broken) = reader.read()?;
+        let (break_force, break_torque) = reader.read()?;
+        let (limits, motor, spring) = reader.read()?;
+        Ok(Self {
+            id,
+            body_a,
+            body_b,
+            constraint_type,
+            anchor_a,
+            anchor_b,
+            rotation_a,
+            rotation_b,
+            enabled,
+            break_force,
+            break_torque,
+            is_broken,
+            limits,
+            motor,
+            spring,
+        })
+    }
+}
+
+impl SnapshotCodec for Motor {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.target_velocity, self.max_force, self.enabled));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (target_velocity, max_force, enabled) = reader.read()?;
+        Ok(Self {
+            target_velocity,
+            max_force,
+            enabled,
+        })
+    }
+}
+
+impl SnapshotCodec for Spring {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&(self.stiffness, self.damping, self.rest_length));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let (stiffness, damping, rest_length) = reader.read()?;
+        Ok(Self {
+            stiffness,
+            damping,
+            rest_length,
+        })
+    }
+}
+
+impl SnapshotCodec for RopeConstraint {
+    fn encode(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.id);
+        writer.write(&self.particles);
+        writer.write(&(self.segment_length, self.stiffness, self.iterations));
+        writer.write(&(self.fixed_start, self.fixed_end));
+    }
+
+    fn decode(reader: &mut SnapshotReader<'_>) -> Result<Self> {
+        let id = reader.read()?;
+        let particles = reader.read()?;
+        let (segment_length, stiffness, iterations) = reader.read()?;
+        let (fixed_start, fixed_end) = reader.read()?;
+        Ok(Self {
+            id,
+            particles,
+            segment_length,
+            stiffness,
+            iterations,
+            fixed_start,
+            fixed_end,
+        })
+    }
+}
@@ -0,0 +1,803 @@
+//! Contact and joint solvers
+//!
+//! Sequential-impulse resolution of contact constraints with Coulomb
+//! friction, restitution and Baumgarte position correction, and of
+//! [`Constraint`] joints with limits, motors and springs.
+
+use crate::constraints::{Constraint, ConstraintType};
+use glam::{Mat3, Quat, Vec3};
+use std::{collections::BTreeMap, ops::Range};
+
+/// Solver configuration
+#[derive(Debug, Clone, Copy)]
+pub struct SolverConfig {
+    /// Velocity iterations per step
+    pub iterations: u32,
+    /// Fraction of penetration corrected per step
+    pub baumgarte: f32,
+    /// Penetration allowed before correction kicks in
+    pub linear_slop: f32,
+    /// Approach speed under which restitution is ignored
+    pub restitution_threshold: f32,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            baumgarte: 0.2,
+            linear_slop: 0.005,
+            restitution_threshold: 1.0,
+        }
+    }
+}
+
+/// Body state seen by the solver
+#[derive(Debug, Clone, Copy)]
+pub struct SolverBody {
+    /// Inverse mass (0 for static and kinematic bodies)
+    pub inv_mass: f32,
+    /// World-space inverse inertia tensor
+    pub inv_inertia: Mat3,
+    /// Center of mass in world space
+    pub position: Vec3,
+    /// Linear velocity
+    pub linear_velocity: Vec3,
+    /// Angular velocity
+    pub angular_velocity: Vec3,
+}
+
+impl SolverBody {
+    /// Create a body that impulses cannot move
+    #[must_use]
+    pub fn fixed(position: Vec3, linear_velocity: Vec3, angular_velocity: Vec3) -> Self {
+        Self {
+            inv_mass: 0.0,
+            inv_inertia: Mat3::ZERO,
+            position,
+            linear_velocity,
+            angular_velocity,
+        }
+    }
+
+    /// Velocity of a point at offset `r` from the center of mass
+    #[must_use]
+    pub fn velocity_at(&self, r: Vec3) -> Vec3 {
+        self.linear_velocity + self.angular_velocity.cross(r)
+    }
+
+    /// Apply an impulse at offset `r` from the center of mass
+    pub fn apply_impulse(&mut self, impulse: Vec3, r: Vec3) {
+        self.linear_velocity += impulse * self.inv_mass;
+        self.angular_velocity += self.inv_inertia * r.cross(impulse);
+    }
+
+    /// Apply an angular impulse
+    pub fn apply_angular_impulse(&mut self, impulse: Vec3) {
+        self.angular_velocity += self.inv_inertia * impulse;
+    }
+
+    /// Effective mass denominator along `axis` at offset `r`
+    #[must_use]
+    pub fn effective_mass_term(&self, r: Vec3, axis: Vec3) -> f32 {
+        let rn = r.cross(axis);
+        self.inv_mass + rn.dot(self.inv_inertia * rn)
+    }
+}
+
+/// A single contact constraint between two solver bodies
+#[derive(Debug, Clone)]
+struct ContactConstraint {
+    body_a: usize,
+    body_b: usize,
+    normal: Vec3,
+    tangents: [Vec3; 2],
+    r_a: Vec3,
+    r_b: Vec3,
+    normal_mass: f32,
+    tangent_mass: [f32; 2],
+    friction: f32,
+    velocity_bias: f32,
+    normal_impulse: f32,
+    tangent_impulse: [f32; 2],
+}
+
+/// Contact description passed to [`ContactSolver::add_contact`]
+#[derive(Debug, Clone, Copy)]
+pub struct ContactInput {
+    /// Index of body A
+    pub body_a: usize,
+    /// Index of body B
+    pub body_b: usize,
+    /// World position
+    pub position: Vec3,
+    /// Normal pointing from A to B
+    pub normal: Vec3,
+    /// Penetration depth
+    pub depth: f32,
+    /// Combined friction coefficient
+    pub friction: f32,
+    /// Combined restitution
+    pub restitution: f32,
+}
+
+/// Sequential impulse contact solver
+#[derive(Debug, Default)]
+pub struct ContactSolver {
+    config: SolverConfig,
+    constraints: Vec<ContactConstraint>,
+}
+
+impl ContactSolver {
+    /// Create a solver
+    #[must_use]
+    pub fn new(config: SolverConfig) -> Self {
+        Self {
+            config,
+            constraints: Vec::new(),
+        }
+    }
+
+    /// Number of contact constraints
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.constraints.len()
+    }
+
+    /// Check if there are no constraints
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty()
+    }
+
+    /// Add a contact, precomputing its effective masses and bias
+    pub fn add_contact(&mut self, bodies: &[SolverBody], contact: ContactInput, dt: f32) {
+        let a = &bodies[contact.body_a];
+        let b = &bodies[contact.body_b];
+        if a.inv_mass == 0.0 && b.inv_mass == 0.0 {
+            return;
+        }
+
+        let normal = contact.normal;
+        let r_a = contact.position - a.position;
+        let r_b = contact.position - b.position;
+        let tangent_1 = if normal.x.abs() >= 0.57735 {
+            Vec3::new(normal.y, -normal.x, 0.0).normalize()
+        } else {
+            Vec3::new(0.0, normal.z, -normal.y).normalize()
+        };
+        let tangent_2 = normal.cross(tangent_1);
+
+        let inverse = |k: f32| if k > 0.0 { 1.0 / k } else { 0.0 };
+        let mass_along = |axis: Vec3| {
+            inverse(a.effective_mass_term(r_a, axis) + b.effective_mass_term(r_b, axis))
+        };
+
+        let relative = b.velocity_at(r_b) - a.velocity_at(r_a);
+        let approach = relative.dot(normal);
+        let restitution_bias = if -approach > self.config.restitution_threshold {
+            -contact.restitution * approach
+        } else {
+            0.0
+        };
+        let position_bias =
+            self.config.baumgarte / dt * (contact.depth - self.config.linear_slop).max(0.0);
+
+        self.constraints.push(ContactConstraint {
+            body_a: contact.body_a,
+            body_b: contact.body_b,
+            normal,
+            tangents: [tangent_1, tangent_2],
+            r_a,
+            r_b,
+            normal_mass: mass_along(normal),
+            tangent_mass: [mass_along(tangent_1), mass_along(tangent_2)],
+            friction: contact.friction,
+            velocity_bias: restitution_bias.max(position_bias),
+            normal_impulse: 0.0,
+            tangent_impulse: [0.0; 2],
+        });
+    }
+
+    /// Run the velocity iterations
+    pub fn solve(&mut self, bodies: &mut [SolverBody]) {
+        for _ in 0..self.config.iterations {
+            self.iterate(bodies);
+        }
+    }
+
+    /// Run a single velocity iteration
+    ///
+    /// Used to interleave contacts with other solvers.
+    pub fn iterate(&mut self, bodies: &mut [SolverBody]) {
+        for c in &mut self.constraints {
+            let (mut a, mut b) = (bodies[c.body_a], bodies[c.body_b]);
+
+            // Friction, bounded by the current normal impulse
+            for i in 0..2 {
+                let relative = b.velocity_at(c.r_b) - a.velocity_at(c.r_a);
+                let lambda = -c.tangent_mass[i] * relative.dot(c.tangents[i]);
+                let max_friction = c.friction * c.normal_impulse;
+                let accumulated =
+                    (c.tangent_impulse[i] + lambda).clamp(-max_friction, max_friction);
+                let delta = accumulated - c.tangent_impulse[i];
+                c.tangent_impulse[i] = accumulated;
+
+                let impulse = c.tangents[i] * delta;
+                a.apply_impulse(-impulse, c.r_a);
+                b.apply_impulse(impulse, c.r_b);
+            }
+
+            // Non-penetration
+            let relative = b.velocity_at(c.r_b) - a.velocity_at(c.r_a);
+            let lambda = -c.normal_mass * (relative.dot(c.normal) - c.velocity_bias);
+            let accumulated = (c.normal_impulse + lambda).max(0.0);
+            let delta = accumulated - c.normal_impulse;
+            c.normal_impulse = accumulated;
+
+            let impulse = c.normal * delta;
+            a.apply_impulse(-impulse, c.r_a);
+            b.apply_impulse(impulse, c.r_b);
+
+            bodies[c.body_a] = a;
+            bodies[c.body_b] = b;
+        }
+    }
+}
+
+/// Whether a joint row carries force or torque, for break thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Force,
+    Torque,
+}
+
+/// Direction a joint row constrains
+#[derive(Debug, Clone, Copy)]
+enum RowAxis {
+    /// Relative velocity of two anchors along a direction
+    Linear {
+        r_a: Vec3,
+        r_b: Vec3,
+        direction: Vec3,
+    },
+    /// Relative angular velocity around a direction
+    Angular(Vec3),
+}
+
+impl RowAxis {
+    /// `(linear, angular_a, angular_b, kind)` of the row's Jacobian
+    fn jacobian(self) -> (Vec3, Vec3, Vec3, RowKind) {
+        match self {
+            Self::Linear {
+                r_a,
+                r_b,
+                direction,
+            } => (
+                direction,
+                r_a.cross(direction),
+                r_b.cross(direction),
+                RowKind::Force,
+            ),
+            Self::Angular(direction) => (Vec3::ZERO, direction, direction, RowKind::Torque),
+        }
+    }
+}
+
+/// A single scalar velocity constraint of a joint
+///
+/// The row's velocity is
+/// `linear · (v_b - v_a) + angular_b · w_b - angular_a · w_a`.
+#[derive(Debug, Clone)]
+struct JointRow {
+    body_a: usize,
+    body_b: usize,
+    kind: RowKind,
+    linear: Vec3,
+    angular_a: Vec3,
+    angular_b: Vec3,
+    mass: f32,
+    bias: f32,
+    bounds: (f32, f32),
+    impulse: f32,
+}
+
+impl JointRow {
+    /// Apply an impulse along the row to both bodies
+    fn apply(&self, bodies: &mut [SolverBody], impulse: f32) {
+        let a = &mut bodies[self.body_a];
+        a.linear_velocity -= self.linear * (impulse * a.inv_mass);
+        a.angular_velocity -= a.inv_inertia * self.angular_a * impulse;
+        let b = &mut bodies[self.body_b];
+        b.linear_velocity += self.linear * (impulse * b.inv_mass);
+        b.angular_velocity += b.inv_inertia * self.angular_b * impulse;
+    }
+}
+
+/// Builds the rows of one joint
+struct RowBuilder<'a> {
+    bodies: &'a [SolverBody],
+    body_a: usize,
+    body_b: usize,
+    /// Baumgarte factor divided by the timestep
+    bias_rate: f32,
+    dt: f32,
+    rows: Vec<JointRow>,
+}
+
+impl RowBuilder<'_> {
+    fn push(&mut self, axis: RowAxis, bias: f32, bounds: (f32, f32)) {
+        let (a, b) = (&self.bodies[self.body_a], &self.bodies[self.body_b]);
+        let (linear, angular_a, angular_b, kind) = axis.jacobian();
+        let k = (a.inv_mass + b.inv_mass) * linear.length_squared()
+            + angular_a.dot(a.inv_inertia * angular_a)
+            + angular_b.dot(b.inv_inertia * angular_b);
+        if k <= 0.0 {
+            return;
+        }
+        self.rows.push(JointRow {
+            body_a: self.body_a,
+            body_b: self.body_b,
+            kind,
+            linear,
+            angular_a,
+            angular_b,
+            mass: 1.0 / k,
+            bias,
+            bounds,
+            impulse: 0.0,
+        });
+    }
+
+    /// Current velocity along a row
+    fn velocity(&self, axis: RowAxis) -> f32 {
+        let (a, b) = (&self.bodies[self.body_a], &self.bodies[self.body_b]);
+        let (linear, angular_a, angular_b, _) = axis.jacobian();
+        linear.dot(b.linear_velocity - a.linear_velocity) + angular_b.dot(b.angular_velocity)
+            - angular_a.dot(a.angular_velocity)
+    }
+
+    /// Drive `error` to zero
+    fn equality(&mut self, axis: RowAxis, error: f32) {
+        self.push(
+            axis,
+            self.bias_rate * error,
+            (f32::NEG_INFINITY, f32::INFINITY),
+        );
+    }
+
+    /// Keep `error` non-negative (`lower`) or non-positive
+    ///
+    /// While the limit is not reached the row only stops the joint from
+    /// overshooting it within the step.
+    fn limit(&mut self, axis: RowAxis, error: f32, lower: bool) {
+        let violated = if lower { error < 0.0 } else { error > 0.0 };
+        let bias = if violated {
+            self.bias_rate * error
+        } else {
+            error / self.dt
+        };
+        let bounds = if lower {
+            (0.0, f32::INFINITY)
+        } else {
+            (f32::NEG_INFINITY, 0.0)
+        };
+        self.push(axis, bias, bounds);
+    }
+
+    /// Lock the relative position of the two anchors
+    fn point(&mut self, r_a: Vec3, r_b: Vec3, separation: Vec3) {
+        for direction in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let axis = RowAxis::Linear {
+                r_a,
+                r_b,
+                direction,
+            };
+            self.equality(axis, separation.dot(direction));
+        }
+    }
+
+    /// Lock the relative rotation of the two frames
+    fn orientation(&mut self, rotation_error: Vec3) {
+        for direction in [Vec3::X, Vec3::Y, Vec3::Z] {
+            self.equality(RowAxis::Angular(direction), rotation_error.dot(direction));
+        }
+    }
+
+    /// Limits, motor and spring of a hinge or slider's free axis
+    fn free_axis(&mut self, constraint: &Constraint, axis: RowAxis, value: f32) {
+        if let Some(motor) = constraint.motor.filter(|m| m.enabled) {
+            let max_impulse = motor.max_force * self.dt;
+            self.push(axis, -motor.target_velocity, (-max_impulse, max_impulse));
+        }
+        if let Some(spring) = constraint.spring {
+            // Equal bounds apply the spring impulse exactly once
+            let impulse = spring.calculate_force(value, self.velocity(axis)) * self.dt;
+            self.push(axis, 0.0, (impulse, impulse));
+        }
+        // Limits come last so they win over the motor and spring
+        if let Some((min, max)) = constraint.limits {
+            self.limit(axis, value - min, true);
+            self.limit(axis, value - max, false);
+        }
+    }
+}
+
+/// Two unit vectors perpendicular to `axis` and to each other
+fn perpendiculars(axis: Vec3) -> (Vec3, Vec3) {
+    let first = axis.any_orthonormal_vector();
+    (first, axis.cross(first))
+}
+
+/// Joint description passed to [`JointSolver::add_joint`]
+#[derive(Debug, Clone, Copy)]
+pub struct JointInput<'a> {
+    /// The constraint to enforce
+    pub constraint: &'a Constraint,
+    /// Index of body A
+    pub body_a: usize,
+    /// Index of body B (a fixed body at the origin for world attachments)
+    pub body_b: usize,
+    /// Orientation of body A
+    pub orientation_a: Quat,
+    /// Orientation of body B
+    pub orientation_b: Quat,
+}
+
+/// Sequential impulse joint solver
+///
+/// Anchors and axes are in the local space of their body and the reference
+/// rotations define the joint frames. Hinges and sliders honor the
+/// constraint's limits, motor and spring on their free axis.
+#[derive(Debug, Default)]
+pub struct JointSolver {
+    config: SolverConfig,
+    joints: Vec<JointEntry>,
+    rows: Vec<JointRow>,
+}
+
+/// Rows and break thresholds of one joint
+#[derive(Debug, Clone)]
+struct JointEntry {
+    id: u64,
+    break_force: Option<f32>,
+    break_torque: Option<f32>,
+    rows: Range<usize>,
+}
+
+impl JointSolver {
+    /// Create a solver
+    #[must_use]
+    pub fn new(config: SolverConfig) -> Self {
+        Self {
+            config,
+            joints: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Number of joints
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    /// Check if there are no joints
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.joints.is_empty()
+    }
+
+    /// Add a joint, building its rows from the current body poses
+    pub fn add_joint(&mut self, bodies: &[SolverBody], joint: JointInput<'_>, dt: f32) {
+        let constraint = joint.constraint;
+        let (a, b) = (&bodies[joint.body_a], &bodies[joint.body_b]);
+        if a.inv_mass == 0.0 && b.inv_mass == 0.0 {
+            return;
+        }
+
+        let frame_a = joint.orientation_a * constraint.rotation_a;
+        let frame_b = joint.orientation_b * constraint.rotation_b;
+        let r_a = joint.orientation_a * constraint.anchor_a;
+        let r_b = joint.orientation_b * constraint.anchor_b;
+        let separation = (b.position + r_b) - (a.position + r_a);
+
+        // Rotation taking frame A onto frame B, with w >= 0 so small errors
+        // map to small vectors
+        let mut relative = frame_b * frame_a.inverse();
+        if relative.w < 0.0 {
+            relative = -relative;
+        }
+        let rotation_error = 2.0 * relative.xyz();
+        let twist = |axis: Vec3| 2.0 * relative.xyz().dot(axis).atan2(relative.w);
+
+        let mut rows = RowBuilder {
+            bodies,
+            body_a: joint.body_a,
+            body_b: joint.body_b,
+            bias_rate: self.config.baumgarte / dt,
+            dt,
+            rows: Vec::new(),
+        };
+
+        match constraint.constraint_type {
+            ConstraintType::Fixed => {
+                rows.point(r_a, r_b, separation);
+                rows.orientation(rotation_error);
+            },
+            ConstraintType::BallSocket => rows.point(r_a, r_b, separation),
+            ConstraintType::Hinge { axis } => {
+                let axis_a = (frame_a * axis).normalize_or_zero();
+                let axis_b = (frame_b * axis).normalize_or_zero();
+                rows.point(r_a, r_b, separation);
+                // Misalignment of the hinge axes, perpendicular to axis A
+                let error = axis_a.cross(axis_b);
+                let (p1, p2) = perpendiculars(axis_a);
+                rows.equality(RowAxis::Angular(p1), error.dot(p1));
+                rows.equality(RowAxis::Angular(p2), error.dot(p2));
+                rows.free_axis(constraint, RowAxis::Angular(axis_a), twist(axis_a));
+            },
+            ConstraintType::Slider { axis } => {
+                let axis_a = (frame_a * axis).normalize_or_zero();
+                // Measured at B's anchor so A's rotation is accounted for
+                let r_a = separation + r_a;
+                let linear = |direction| RowAxis::Linear {
+                    r_a,
+                    r_b,
+                    direction,
+                };
+                let (p1, p2) = perpendiculars(axis_a);
+                rows.equality(linear(p1), separation.dot(p1));
+                rows.equality(linear(p2), separation.dot(p2));
+                rows.orientation(rotation_error);
+                rows.free_axis(constraint, linear(axis_a), separation.dot(axis_a));
+            },
+            ConstraintType::Distance { min, max } => {
+                let distance = separation.length();
+                if distance > f32::EPSILON {
+                    let axis = RowAxis::Linear {
+                        r_a,
+                        r_b,
+                        direction: separation / distance,
+                    };
+                    if min > 0.0 {
+                        rows.limit(axis, distance - min, true);
+                    }
+                    rows.limit(axis, distance - max, false);
+                }
+            },
+            ConstraintType::ConeTwist {
+                swing_span,
+                twist_span,
+            } => {
+                rows.point(r_a, r_b, separation);
+                let twist_a = frame_a * Vec3::X;
+                let twist_b = frame_b * Vec3::X;
+                let swing = twist_a.dot(twist_b).clamp(-1.0, 1.0).acos();
+                let swing_axis = twist_a.cross(twist_b).normalize_or_zero();
+                if swing > swing_span && swing_axis != Vec3::ZERO {
+                    rows.limit(RowAxis::Angular(swing_axis), swing - swing_span, false);
+                }
+                let angle = twist(twist_a);
+                if angle > twist_span {
+                    rows.limit(RowAxis::Angular(twist_a), angle - twist_span, false);
+                } else if angle < -twist_span {
+                    rows.limit(RowAxis::Angular(twist_a), angle + twist_span, true);
+                }
+            },
+            ConstraintType::Universal { axis1, axis2 } => {
+                rows.point(r_a, r_b, separation);
+                let axis_a = (frame_a * axis1).normalize_or_zero();
+                let axis_b = (frame_b * axis2).normalize_or_zero();
+                // Keep the two hinge axes perpendicular
+                let locked = axis_a.cross(axis_b);
+                let length = locked.length();
+                if length > f32::EPSILON {
+                    let error = -axis_a.dot(axis_b) / length;
+                    rows.equality(RowAxis::Angular(locked / length), error);
+                }
+            },
+        }
+
+        let start = self.rows.len();
+        self.rows.extend(rows.rows);
+        self.joints.push(JointEntry {
+            id: constraint.id,
+            break_force: constraint.break_force,
+            break_torque: constraint.break_torque,
+            rows: start..self.rows.len(),
+        });
+    }
+
+    /// Seed rows with the impulses of the previous step
+    ///
+    /// Joints whose row layout changed since, e.g. because a limit became
+    /// active, start from zero.
+    pub fn warm_start(&mut self, bodies: &mut [SolverBody], impulses: &BTreeMap<u64, Vec<f32>>) {
+        for joint in &self.joints {
+            let Some(previous) = impulses.get(&joint.id) else {
+                continue;
+            };
+            if previous.len() != joint.rows.len() {
+                continue;
+            }
+            for (row, impulse) in self.rows[joint.rows.clone()].iter_mut().zip(previous) {
+                // Springs are re-applied from scratch every step
+                if row.bounds.0 == row.bounds.1 {
+                    continue;
+                }
+                row.impulse = impulse.clamp(row.bounds.0, row.bounds.1);
+                row.apply(bodies, row.impulse);
+            }
+        }
+    }
+
+    /// Accumulated impulses of each joint, for warm starting the next step
+    #[must_use]
+    pub fn impulses(&self) -> BTreeMap<u64, Vec<f32>> {
+        self.joints
+            .iter()
+            .map(|joint| {
+                let impulses = self.rows[joint.rows.clone()]
+                    .iter()
+                    .map(|r| r.impulse)
+                    .collect();
+                (joint.id, impulses)
+            })
+            .collect()
+    }
+
+    /// Run the velocity iterations
+    pub fn solve(&mut self, bodies: &mut [SolverBody]) {
+        for _ in 0..self.config.iterations {
+            self.iterate(bodies);
+        }
+    }
+
+    /// Run a single velocity iteration
+    ///
+    /// Used to interleave joints with contacts.
+    pub fn iterate(&mut self, bodies: &mut [SolverBody]) {
+        for row in &mut self.rows {
+            let (a, b) = (&bodies[row.body_a], &bodies[row.body_b]);
+            let velocity = row.linear.dot(b.linear_velocity - a.linear_velocity)
+                + row.angular_b.dot(b.angular_velocity)
+                - row.angular_a.dot(a.angular_velocity);
+            let lambda = -row.mass * (velocity + row.bias);
+            let accumulated = (row.impulse + lambda).clamp(row.bounds.0, row.bounds.1);
+            let delta = accumulated - row.impulse;
+            row.impulse = accumulated;
+            row.apply(bodies, delta);
+        }
+    }
+
+    /// Ids of joints whose force or torque exceeded their break thresholds
+    #[must_use]
+    pub fn broken(&self, dt: f32) -> Vec<u64> {
+        self.joints
+            .iter()
+            .filter(|joint| {
+                let (mut force, mut torque) = (Vec3::ZERO, Vec3::ZERO);
+                for row in &self.rows[joint.rows.clone()] {
+                    match row.kind {
+                        RowKind::Force => force += row.linear * row.impulse,
+                        RowKind::Torque => torque += row.angular_b * row.impulse,
+                    }
+                }
+                joint
+                    .break_force
+                    .is_some_and(|limit| force.length() / dt > limit)
+                    || joint
+                        .break_torque
+                        .is_some_and(|limit| torque.length() / dt > limit)
+            })
+            .map(|joint| joint.id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 60.0;
+
+    fn unit_body(position: Vec3, linear_velocity: Vec3) -> SolverBody {
+        SolverBody {
+            inv_mass: 1.0,
+            inv_inertia: Mat3::from_diagonal(Vec3::splat(6.0)),
+            position,
+            linear_velocity,
+            angular_velocity: Vec3::ZERO,
+        }
+    }
+
+    fn ground_contact(position: Vec3, friction: f32, restitution: f32) -> ContactInput {
+        ContactInput {
+            body_a: 0,
+            body_b: 1,
+            position,
+            normal: Vec3::Y,
+            depth: 0.0,
+            friction,
+            restitution,
+        }
+    }
+
+    #[test]
+    fn contacts_stop_approach_and_apply_friction() {
+        let mut bodies = [
+            SolverBody::fixed(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO),
+            unit_body(Vec3::new(0.0, 0.5, 0.0), Vec3::new(2.0, -0.5, 0.0)),
+        ];
+        let mut solver = ContactSolver::new(SolverConfig::default());
+        for x in [-0.5, 0.5] {
+            for z in [-0.5, 0.5] {
+                solver.add_contact(&bodies, ground_contact(Vec3::new(x, 0.0, z), 0.5, 0.0), DT);
+            }
+        }
+        assert_eq!(solver.len(), 4);
+        solver.solve(&mut bodies);
+
+        let body = bodies[1];
+        assert!(body.linear_velocity.y.abs() < 1.0e-3);
+        // Friction is bounded by the normal impulse that stopped the body
+        assert!(body.linear_velocity.x < 2.0);
+        assert!(body.linear_velocity.x >= 2.0 - 0.5 * 0.5 - 1.0e-3);
+        assert!(body.angular_velocity.length() < 1.0e-3);
+        assert_eq!(bodies[0].linear_velocity, Vec3::ZERO);
+    }
+
+    #[test]
+    fn fast_contacts_bounce_with_restitution() {
+        let mut bodies = [
+            SolverBody::fixed(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO),
+            unit_body(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, -4.0, 0.0)),
+        ];
+        let mut solver = ContactSolver::new(SolverConfig::default());
+        solver.add_contact(&bodies, ground_contact(Vec3::ZERO, 0.0, 0.5), DT);
+        solver.solve(&mut bodies);
+        assert!((bodies[1].linear_velocity.y - 2.0).abs() < 1.0e-3);
+
+        // Two fixed bodies never produce a constraint
+        let mut solver = ContactSolver::new(SolverConfig::default());
+        let fixed = [SolverBody::fixed(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO); 2];
+        solver.add_contact(&fixed, ground_contact(Vec3::ZERO, 0.5, 0.0), DT);
+        assert!(solver.is_empty());
+    }
+
+    #[test]
+    fn ball_socket_joints_keep_anchors_together_and_report_breaks() {
+        let mut bodies = [
+            unit_body(Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, -3.0, 0.0)),
+            SolverBody::fixed(Vec3::ZERO, Vec3::ZERO, Vec3::ZERO),
+        ];
+        let mut constraint = Constraint::new(1, None, ConstraintType::BallSocket)
+            .with_anchors(Vec3::new(0.0, 1.0, 0.0), Vec3::ZERO)
+            .with_break_thresholds(50.0, f32::MAX);
+        constraint.id = 7;
+
+        let mut solver = JointSolver::new(SolverConfig::default());
+        solver.add_joint(
+            &bodies,
+            JointInput {
+                constraint: &constraint,
+                body_a: 0,
+                body_b: 1,
+                orientation_a: Quat::IDENTITY,
+                orientation_b: Quat::IDENTITY,
+            },
+            DT,
+        );
+        assert_eq!(solver.len(), 1);
+        solver.solve(&mut bodies);
+
+        // The anchor point no longer moves away from the world anchor
+        let anchor_velocity = bodies[0].velocity_at(Vec3::new(0.0, 1.0, 0.0));
+        assert!(anchor_velocity.length() < 1.0e-3, "{anchor_velocity}");
+        let impulses = solver.impulses();
+        assert_eq!(impulses[&7].len(), 3);
+        // Stopping 3 m/s within one step takes about 180 N
+        assert_eq!(solver.broken(DT), vec![7]);
+    }
+}
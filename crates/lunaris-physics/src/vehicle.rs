@@ -2,6 +2,7 @@
 //!
 //! Realistic vehicle simulation with wheels, suspension, and drivetrain.
 
+use crate::rigidbody::RigidbodyProperties;
 use glam::{Quat, Vec3};
 
 /// Wheel info
@@ -282,6 +283,16 @@ impl Default for VehicleConfig {
     }
 }
 
+impl VehicleConfig {
+    /// Rigidbody properties for the chassis body
+    ///
+    /// CCD is enabled so vehicles at speed cannot tunnel through walls.
+    #[must_use]
+    pub fn chassis_properties(&self) -> RigidbodyProperties {
+        RigidbodyProperties::dynamic().with_mass(self.mass).with_ccd()
+    }
+}
+
 /// Vehicle physics controller
 pub struct Vehicle {
     /// Configuration
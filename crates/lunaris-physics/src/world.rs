@@ -1,49 +1,63 @@
 //! Physics world simulation
 
 use crate::{
-    collision::{ColliderShape, CollisionEvent, RaycastHit, RaycastQuery},
-    rigidbody::{ColliderProperties, ForceMode, RigidbodyHandle, RigidbodyProperties, RigidbodyState},
+    backend::PhysicsBackend,
+    collision::{
+        ColliderShape, ColliderShape3D, CollisionEvent, CollisionLayers, OverlapQuery, RaycastHit,
+        RaycastQuery, ShapeCastQuery,
+    },
+    constraints::{Constraint, ConstraintManager},
+    rigidbody::{
+        ColliderProperties, ForceMode, RigidbodyHandle, RigidbodyProperties, RigidbodyState,
+    },
+    snapshot::{PhysicsSnapshot, SnapshotReader, SnapshotWriter},
     PhysicsConfig,
 };
-use lunaris_core::{id::Id, math::Vec3};
-use std::collections::HashMap;
+use lunaris_core::{id::Id, math::Vec3, Result};
 
 /// The physics world containing all simulation state
+///
+/// Simulation is delegated to a [`PhysicsBackend`] chosen through
+/// [`PhysicsConfig::backend`] or [`PhysicsWorld::with_backend`].
 pub struct PhysicsWorld {
     config: PhysicsConfig,
-    bodies: HashMap<RigidbodyHandle, RigidbodyData>,
+    backend: Box<dyn PhysicsBackend>,
+    constraints: ConstraintManager,
     collision_events: Vec<CollisionEvent>,
     accumulator: f32,
 }
 
-struct RigidbodyData {
-    entity_id: Id,
-    properties: RigidbodyProperties,
-    state: RigidbodyState,
-    collider: Option<ColliderData>,
-    pending_forces: Vec<(Vec3, ForceMode)>,
-    pending_torques: Vec<(Vec3, ForceMode)>,
-}
-
-struct ColliderData {
-    shape: ColliderShape,
-    properties: ColliderProperties,
-    offset: Vec3,
-}
-
 impl PhysicsWorld {
     /// Create a new physics world
     #[must_use]
     pub fn new(config: PhysicsConfig) -> Self {
-        tracing::info!("Physics world created with gravity: {:?}", config.gravity);
+        let backend = config.backend.create();
+        Self::with_backend(config, backend)
+    }
+
+    /// Create a physics world driven by a specific backend
+    #[must_use]
+    pub fn with_backend(config: PhysicsConfig, backend: Box<dyn PhysicsBackend>) -> Self {
+        tracing::info!(
+            "Physics world created with gravity: {:?} ({} backend)",
+            config.gravity,
+            backend.name()
+        );
         Self {
             config,
-            bodies: HashMap::new(),
+            backend,
+            constraints: ConstraintManager::new(),
             collision_events: Vec::new(),
             accumulator: 0.0,
         }
     }
 
+    /// Name of the active backend
+    #[must_use]
+    pub fn backend_name(&self) -> &'static str {
+        self.backend.name()
+    }
+
     /// Create a rigidbody
     pub fn create_rigidbody(
         &mut self,
@@ -52,21 +66,8 @@ impl PhysicsWorld {
         position: Vec3,
     ) -> RigidbodyHandle {
         let handle = RigidbodyHandle(Id::new());
-        
-        self.bodies.insert(
-            handle,
-            RigidbodyData {
-                entity_id,
-                properties,
-                state: RigidbodyState {
-                    position,
-                    ..Default::default()
-                },
-                collider: None,
-                pending_forces: Vec::new(),
-                pending_torques: Vec::new(),
-            },
-        );
+        self.backend
+            .insert_body(handle, entity_id, &properties, position);
 
         tracing::debug!("Created rigidbody {:?} for entity {:?}", handle, entity_id);
         handle
@@ -80,104 +81,247 @@ impl PhysicsWorld {
         properties: ColliderProperties,
         offset: Vec3,
     ) {
-        if let Some(body) = self.bodies.get_mut(&handle) {
-            body.collider = Some(ColliderData {
-                shape,
-                properties,
-                offset,
-            });
-        }
+        self.backend
+            .set_collider(handle, &shape, &properties, offset);
     }
 
     /// Remove a rigidbody
+    ///
+    /// Constraints attached to the body are removed as well.
     pub fn remove_rigidbody(&mut self, handle: RigidbodyHandle) {
-        self.bodies.remove(&handle);
+        let raw = handle.0.raw();
+        let attached: Vec<u64> = self
+            .constraints
+            .iter()
+            .filter(|c| c.body_a == raw || c.body_b == Some(raw))
+            .map(|c| c.id)
+            .collect();
+        for id in attached {
+            self.remove_constraint(id);
+        }
+        self.backend.remove_body(handle);
     }
 
     /// Get rigidbody state
     #[must_use]
     pub fn get_state(&self, handle: RigidbodyHandle) -> Option<RigidbodyState> {
-        self.bodies.get(&handle).map(|b| b.state)
+        self.backend.state(handle)
     }
 
     /// Set rigidbody position
     pub fn set_position(&mut self, handle: RigidbodyHandle, position: Vec3) {
-        if let Some(body) = self.bodies.get_mut(&handle) {
-            body.state.position = position;
-        }
+        self.backend.set_position(handle, position);
     }
 
     /// Set rigidbody rotation
     pub fn set_rotation(&mut self, handle: RigidbodyHandle, rotation: Vec3) {
-        if let Some(body) = self.bodies.get_mut(&handle) {
-            body.state.rotation = rotation;
-        }
+        self.backend.set_rotation(handle, rotation);
     }
 
     /// Set linear velocity
     pub fn set_linear_velocity(&mut self, handle: RigidbodyHandle, velocity: Vec3) {
-        if let Some(body) = self.bodies.get_mut(&handle) {
-            body.state.linear_velocity = velocity;
-        }
+        self.backend.set_linear_velocity(handle, velocity);
     }
 
     /// Apply a force to a rigidbody
     pub fn apply_force(&mut self, handle: RigidbodyHandle, force: Vec3, mode: ForceMode) {
-        if let Some(body) = self.bodies.get_mut(&handle) {
-            body.pending_forces.push((force, mode));
-        }
+        self.backend.apply_force(handle, force, mode);
     }
 
     /// Apply a torque to a rigidbody
     pub fn apply_torque(&mut self, handle: RigidbodyHandle, torque: Vec3, mode: ForceMode) {
-        if let Some(body) = self.bodies.get_mut(&handle) {
-            body.pending_torques.push((torque, mode));
+        self.backend.apply_torque(handle, torque, mode);
+    }
+
+    /// Add a constraint between rigidbodies
+    ///
+    /// `body_a`/`body_b` are the raw ids of the bodies' [`RigidbodyHandle`]s.
+    pub fn add_constraint(&mut self, constraint: Constraint) -> u64 {
+        let id = self.constraints.add_constraint(constraint);
+        if let Some(constraint) = self.constraints.get_constraint(id) {
+            self.backend.insert_constraint(constraint);
         }
+        id
+    }
+
+    /// Remove a constraint
+    pub fn remove_constraint(&mut self, id: u64) {
+        self.constraints.remove_constraint(id);
+        self.backend.remove_constraint(id);
+    }
+
+    /// Constraints registered with the world
+    #[must_use]
+    pub fn constraints(&self) -> &ConstraintManager {
+        &self.constraints
+    }
+
+    /// Constraints that broke during simulation
+    #[must_use]
+    pub fn broken_constraints(&self) -> Vec<u64> {
+        self.constraints.broken_constraints()
     }
 
     /// Perform a raycast
     #[must_use]
     pub fn raycast(&self, query: &RaycastQuery) -> Option<RaycastHit> {
-        // Simplified raycast - in real implementation would use spatial acceleration
-        let mut closest: Option<RaycastHit> = None;
-
-        for (_handle, body) in &self.bodies {
-            if let Some(collider) = &body.collider {
-                if !query.layers.can_interact(collider.properties.layers) {
-                    continue;
-                }
-
-                // Simple sphere collision for demonstration
-                if let ColliderShape::Shape3D(ref shape) = collider.shape {
-                    if let crate::collision::ColliderShape3D::Sphere { radius } = shape {
-                        let center = body.state.position + collider.offset;
-                        if let Some(hit) = ray_sphere_intersection(
-                            query.origin,
-                            query.direction,
-                            center,
-                            *radius,
-                        ) {
-                            if hit.distance <= query.max_distance {
-                                if closest.is_none() || hit.distance < closest.as_ref().unwrap().distance {
-                                    closest = Some(RaycastHit {
-                                        entity: body.entity_id,
-                                        point: hit.point,
-                                        normal: hit.normal,
-                                        distance: hit.distance,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        self.backend.raycast(query)
+    }
+
+    /// Every body hit by a ray, nearest first
+    #[must_use]
+    pub fn raycast_all(&self, query: &RaycastQuery) -> Vec<RaycastHit> {
+        self.backend.raycast_all(query)
+    }
+
+    /// Sweep a shape and return the first hit
+    #[must_use]
+    pub fn shape_cast(&self, query: &ShapeCastQuery) -> Option<RaycastHit> {
+        self.backend.shape_cast(query)
+    }
 
-        closest
+    /// Sweep a sphere
+    #[must_use]
+    pub fn sphere_cast(
+        &self,
+        origin: Vec3,
+        radius: f32,
+        direction: Vec3,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Option<RaycastHit> {
+        let query = ShapeCastQuery::new(
+            ColliderShape3D::sphere(radius),
+            origin,
+            direction,
+            max_distance,
+        )
+        .with_layers(layers);
+        self.shape_cast(&query)
+    }
+
+    /// Sweep an axis-aligned box
+    #[must_use]
+    pub fn box_cast(
+        &self,
+        origin: Vec3,
+        half_extents: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Option<RaycastHit> {
+        let query = ShapeCastQuery::new(
+            ColliderShape3D::Box { half_extents },
+            origin,
+            direction,
+            max_distance,
+        )
+        .with_layers(layers);
+        self.shape_cast(&query)
+    }
+
+    /// Sweep an upright capsule
+    #[must_use]
+    pub fn capsule_cast(
+        &self,
+        origin: Vec3,
+        half_height: f32,
+        radius: f32,
+        direction: Vec3,
+        max_distance: f32,
+        layers: CollisionLayers,
+    ) -> Option<RaycastHit> {
+        let shape = ColliderShape3D::Capsule {
+            half_height,
+            radius,
+        };
+        let query = ShapeCastQuery::new(shape, origin, direction, max_distance).with_layers(layers);
+        self.shape_cast(&query)
+    }
+
+    /// Entities whose colliders overlap a shape
+    #[must_use]
+    pub fn overlap(&self, query: &OverlapQuery) -> Vec<Id> {
+        self.backend.overlap(query)
+    }
+
+    /// Entities whose colliders overlap a sphere
+    #[must_use]
+    pub fn overlap_sphere(&self, center: Vec3, radius: f32, layers: CollisionLayers) -> Vec<Id> {
+        self.overlap(
+            &OverlapQuery::new(ColliderShape3D::sphere(radius), center).with_layers(layers),
+        )
+    }
+
+    /// Entities whose colliders overlap an axis-aligned box
+    #[must_use]
+    pub fn overlap_box(
+        &self,
+        center: Vec3,
+        half_extents: Vec3,
+        layers: CollisionLayers,
+    ) -> Vec<Id> {
+        self.overlap(
+            &OverlapQuery::new(ColliderShape3D::Box { half_extents }, center).with_layers(layers),
+        )
+    }
+
+    /// Check if a rigidbody is asleep
+    #[must_use]
+    pub fn is_sleeping(&self, handle: RigidbodyHandle) -> bool {
+        self.backend.is_sleeping(handle)
+    }
+
+    /// Wake a sleeping rigidbody
+    pub fn wake_up(&mut self, handle: RigidbodyHandle) {
+        self.backend.wake_up(handle);
+    }
+
+    /// Capture the complete simulation state
+    ///
+    /// Restoring the snapshot and replaying the same inputs reproduces the
+    /// same states bit for bit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend does not support snapshots
+    pub fn snapshot(&self) -> Result<PhysicsSnapshot> {
+        let mut writer = SnapshotWriter::new(self.backend.name());
+        writer.write(&self.config.gravity);
+        writer.write(&self.accumulator);
+        writer.write(&self.constraints);
+        self.backend.save_state(&mut writer)?;
+        Ok(writer.finish())
+    }
+
+    /// Restore a state captured by [`Self::snapshot`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot is malformed or was taken with a
+    /// different backend. The world is left unchanged in that case.
+    pub fn restore(&mut self, snapshot: &PhysicsSnapshot) -> Result<()> {
+        let mut reader = SnapshotReader::new(snapshot, self.backend.name())?;
+        let gravity = reader.read()?;
+        let accumulator = reader.read()?;
+        let constraints = reader.read()?;
+        // The backend state comes last; the backend only commits it once the
+        // whole snapshot has decoded, so nothing below can fail
+        self.backend.load_state(reader)?;
+
+        self.config.gravity = gravity;
+        self.accumulator = accumulator;
+        self.constraints = constraints;
+        self.collision_events.clear();
+        Ok(())
     }
 
     /// Step the simulation
+    ///
+    /// Collision events from every fixed step run by this call are
+    /// available through [`Self::collision_events`] until the next call.
     pub fn step(&mut self, delta_time: f32) {
+        self.collision_events.clear();
         self.accumulator += delta_time;
         let timestep = self.config.timestep;
         let max_steps = self.config.max_substeps;
@@ -191,54 +335,15 @@ impl PhysicsWorld {
     }
 
     fn fixed_step(&mut self, dt: f32) {
-        // Clear previous collision events
-        self.collision_events.clear();
-
-        // Apply forces and integrate
-        for body in self.bodies.values_mut() {
-            if body.properties.body_type != crate::rigidbody::RigidbodyType::Dynamic {
-                body.pending_forces.clear();
-                body.pending_torques.clear();
-                continue;
-            }
+        let events = self.backend.step(dt, self.config.gravity);
+        self.collision_events.extend(events.collisions);
 
-            // Apply gravity
-            let gravity = self.config.gravity * body.properties.gravity_scale;
-            let mass = body.properties.mass;
-
-            // Apply pending forces
-            for (force, mode) in body.pending_forces.drain(..) {
-                match mode {
-                    ForceMode::Force => {
-                        body.state.linear_velocity = body.state.linear_velocity + force * dt / mass;
-                    }
-                    ForceMode::Impulse => {
-                        body.state.linear_velocity = body.state.linear_velocity + force / mass;
-                    }
-                    ForceMode::Acceleration => {
-                        body.state.linear_velocity = body.state.linear_velocity + force * dt;
-                    }
-                    ForceMode::VelocityChange => {
-                        body.state.linear_velocity = body.state.linear_velocity + force;
-                    }
-                }
+        for id in events.broken_constraints {
+            if let Some(constraint) = self.constraints.get_constraint_mut(id) {
+                constraint.is_broken = true;
+                self.backend.remove_constraint(id);
             }
-
-            // Apply gravity
-            body.state.linear_velocity = body.state.linear_velocity + gravity * dt;
-
-            // Apply damping
-            body.state.linear_velocity = body.state.linear_velocity
-                * (1.0 - body.properties.linear_damping * dt);
-            body.state.angular_velocity = body.state.angular_velocity
-                * (1.0 - body.properties.angular_damping * dt);
-
-            // Integrate position
-            body.state.position = body.state.position + body.state.linear_velocity * dt;
-            body.state.rotation = body.state.rotation + body.state.angular_velocity * dt;
         }
-
-        // TODO: Collision detection and response
     }
 
     /// Get collision events from the last step
@@ -259,42 +364,12 @@ impl PhysicsWorld {
     }
 }
 
-/// Ray-sphere intersection helper
-fn ray_sphere_intersection(
-    origin: Vec3,
-    direction: Vec3,
-    center: Vec3,
-    radius: f32,
-) -> Option<RaycastHit> {
-    let oc = origin - center;
-    let a = direction.dot(direction);
-    let b = 2.0 * oc.dot(direction);
-    let c = oc.dot(oc) - radius * radius;
-    let discriminant = b * b - 4.0 * a * c;
-
-    if discriminant < 0.0 {
-        return None;
-    }
-
-    let t = (-b - discriminant.sqrt()) / (2.0 * a);
-    if t < 0.0 {
-        return None;
-    }
-
-    let point = origin + direction * t;
-    let normal = (point - center).normalize();
-
-    Some(RaycastHit {
-        entity: Id::NULL,
-        point,
-        normal,
-        distance: t,
-    })
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::backend::PhysicsBackendKind;
+    use crate::collision::CollisionEventType;
+    use crate::constraints::{ConstraintType, Motor};
 
     #[test]
     fn create_world() {
@@ -305,11 +380,7 @@ mod tests {
     #[test]
     fn create_rigidbody() {
         let mut world = PhysicsWorld::new(PhysicsConfig::default());
-        let handle = world.create_rigidbody(
-            Id::new(),
-            RigidbodyProperties::dynamic(),
-            Vec3::ZERO,
-        );
+        let handle = world.create_rigidbody(Id::new(), RigidbodyProperties::dynamic(), Vec3::ZERO);
 
         let state = world.get_state(handle).unwrap();
         assert_eq!(state.position, Vec3::ZERO);
@@ -334,4 +405,787 @@ mod tests {
         assert!(state.position.y < 10.0);
         assert!(state.linear_velocity.y < 0.0);
     }
+
+    fn ground(world: &mut PhysicsWorld) -> RigidbodyHandle {
+        let handle =
+            world.create_rigidbody(Id::new(), RigidbodyProperties::static_body(), Vec3::ZERO);
+        world.attach_collider(
+            handle,
+            ColliderShape::Shape3D(ColliderShape3D::box_shape(20.0, 1.0, 20.0)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+        handle
+    }
+
+    #[test]
+    fn sphere_rests_on_ground() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let ball = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 3.0, 0.0),
+        );
+        world.attach_collider(
+            ball,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+        }
+
+        let state = world.get_state(ball).unwrap();
+        assert!(
+            (state.position.y - 1.0).abs() < 0.05,
+            "ball at {}",
+            state.position.y
+        );
+        assert!(state.linear_velocity.y.abs() < 0.1);
+    }
+
+    /// Flat grid of `cells * cells` quads, two triangles each, centered on the origin
+    fn grid_mesh(cells: u32) -> ColliderShape3D {
+        let half = cells as f32 / 2.0;
+        let vertices = (0..=cells)
+            .flat_map(|z| {
+                (0..=cells).map(move |x| Vec3::new(x as f32 - half, 0.0, z as f32 - half))
+            })
+            .collect();
+        let indices = (0..cells)
+            .flat_map(|z| {
+                (0..cells).flat_map(move |x| {
+                    let i = z * (cells + 1) + x;
+                    [
+                        [i, i + cells + 1, i + 1],
+                        [i + 1, i + cells + 1, i + cells + 2],
+                    ]
+                })
+            })
+            .collect();
+        ColliderShape3D::TriMesh { vertices, indices }
+    }
+
+    #[test]
+    fn triangle_mesh_queries_only_nearby_triangles() {
+        use crate::broadphase::Aabb;
+        use crate::midphase::TriMeshBvh;
+
+        let ColliderShape3D::TriMesh { vertices, indices } = grid_mesh(32) else {
+            unreachable!();
+        };
+        let bvh = TriMeshBvh::new(&vertices, &indices);
+        assert_eq!(bvh.len(), 2048);
+        assert_eq!(bvh.bounds().max, glam::Vec3::new(16.0, 0.0, 16.0));
+
+        let probe = Aabb::new(
+            glam::Vec3::new(0.2, -0.5, 0.2),
+            glam::Vec3::new(0.8, 0.5, 0.8),
+        );
+        assert_eq!(bvh.query(&probe).len(), 2);
+        let above = Aabb::new(
+            glam::Vec3::new(0.2, 1.0, 0.2),
+            glam::Vec3::new(0.8, 2.0, 0.8),
+        );
+        assert!(bvh.query(&above).is_empty());
+    }
+
+    #[test]
+    fn spheres_rest_on_triangle_mesh() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let terrain =
+            world.create_rigidbody(Id::new(), RigidbodyProperties::static_body(), Vec3::ZERO);
+        world.attach_collider(
+            terrain,
+            ColliderShape::Shape3D(grid_mesh(32)),
+            ColliderProperties::default(),
+            Vec3::new(0.0, -1.0, 0.0),
+        );
+        let balls: Vec<RigidbodyHandle> = [(-10.3, -4.7), (0.0, 0.0), (12.5, 9.1)]
+            .into_iter()
+            .map(|(x, z)| {
+                let ball = world.create_rigidbody(
+                    Id::new(),
+                    RigidbodyProperties::dynamic(),
+                    Vec3::new(x, 2.0, z),
+                );
+                world.attach_collider(
+                    ball,
+                    ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+                    ColliderProperties::default(),
+                    Vec3::ZERO,
+                );
+                ball
+            })
+            .collect();
+
+        // Restoring a snapshot rebuilds the mesh hierarchy
+        let snapshot = world.snapshot().unwrap();
+        world.restore(&snapshot).unwrap();
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+        }
+
+        for ball in balls {
+            let state = world.get_state(ball).unwrap();
+            assert!(
+                (state.position.y + 0.5).abs() < 0.05,
+                "ball at {}",
+                state.position.y
+            );
+            assert!(state.linear_velocity.y.abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn box_settles_flat() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let crate_box = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 2.0, 0.0),
+        );
+        world.attach_collider(
+            crate_box,
+            ColliderShape::Shape3D(ColliderShape3D::cube(1.0)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+
+        for _ in 0..240 {
+            world.step(1.0 / 60.0);
+        }
+
+        let state = world.get_state(crate_box).unwrap();
+        assert!(
+            (state.position.y - 1.0).abs() < 0.05,
+            "box at {}",
+            state.position.y
+        );
+        assert!(state.rotation.length() < 0.05);
+    }
+
+    #[test]
+    fn collision_events_lifecycle() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let ball = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 0.9, 0.0),
+        );
+        world.attach_collider(
+            ball,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+
+        world.step(1.0 / 60.0);
+        assert_eq!(world.collision_events().len(), 1);
+        assert_eq!(
+            world.collision_events()[0].event_type,
+            CollisionEventType::Started
+        );
+        assert!(!world.collision_events()[0].contacts.is_empty());
+
+        world.step(1.0 / 60.0);
+        assert_eq!(
+            world.collision_events()[0].event_type,
+            CollisionEventType::Ongoing
+        );
+
+        world.set_position(ball, Vec3::new(0.0, 10.0, 0.0));
+        world.set_linear_velocity(ball, Vec3::ZERO);
+        world.step(1.0 / 60.0);
+        assert_eq!(world.collision_events().len(), 1);
+        assert_eq!(
+            world.collision_events()[0].event_type,
+            CollisionEventType::Ended
+        );
+    }
+
+    #[test]
+    fn layers_filter_collisions() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let ghost = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        world.attach_collider(
+            ghost,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties {
+                layers: crate::collision::CollisionLayers::TRIGGER,
+                ..Default::default()
+            },
+            Vec3::ZERO,
+        );
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+
+        assert!(world.get_state(ghost).unwrap().position.y < 0.0);
+        assert!(world.collision_events().is_empty());
+    }
+
+    #[test]
+    fn restitution_bounces() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let ball = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 5.0, 0.0),
+        );
+        world.attach_collider(
+            ball,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties::bouncy(),
+            Vec3::ZERO,
+        );
+
+        let mut bounced = false;
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            if world.get_state(ball).unwrap().linear_velocity.y > 3.0 {
+                bounced = true;
+            }
+        }
+        assert!(bounced);
+    }
+
+    fn static_sphere(
+        world: &mut PhysicsWorld,
+        entity: Id,
+        position: Vec3,
+        layers: CollisionLayers,
+    ) {
+        let handle = world.create_rigidbody(entity, RigidbodyProperties::static_body(), position);
+        world.attach_collider(
+            handle,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties {
+                layers,
+                ..ColliderProperties::default()
+            },
+            Vec3::ZERO,
+        );
+    }
+
+    #[test]
+    fn raycasts_report_entities() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let (near, far) = (Id::new(), Id::new());
+        static_sphere(
+            &mut world,
+            near,
+            Vec3::new(0.0, 0.0, 5.0),
+            CollisionLayers::DEFAULT,
+        );
+        static_sphere(
+            &mut world,
+            far,
+            Vec3::new(0.0, 0.0, 10.0),
+            CollisionLayers::ENEMY,
+        );
+
+        let query = RaycastQuery::new(Vec3::ZERO, Vec3::new(0.0, 0.0, 1.0), 100.0);
+        let hit = world.raycast(&query).unwrap();
+        assert_eq!(hit.entity, near);
+        assert!(
+            (hit.distance - 4.5).abs() < 0.01,
+            "distance {}",
+            hit.distance
+        );
+        assert!((hit.normal.z + 1.0).abs() < 0.01);
+
+        let hits = world.raycast_all(&query);
+        assert_eq!(
+            hits.iter().map(|h| h.entity).collect::<Vec<_>>(),
+            vec![near, far]
+        );
+
+        let enemies_only = query.clone().with_layers(CollisionLayers::new(
+            0xFFFF_FFFF,
+            CollisionLayers::ENEMY.membership,
+        ));
+        assert_eq!(world.raycast(&enemies_only).unwrap().entity, far);
+        assert_eq!(world.raycast(&query.excluding(near)).unwrap().entity, far);
+    }
+
+    #[test]
+    fn shape_casts_and_overlaps() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+
+        let hit = world
+            .sphere_cast(
+                Vec3::new(0.0, 5.0, 0.0),
+                0.5,
+                Vec3::new(0.0, -1.0, 0.0),
+                10.0,
+                CollisionLayers::default(),
+            )
+            .unwrap();
+        assert!(
+            (hit.distance - 4.0).abs() < 0.01,
+            "distance {}",
+            hit.distance
+        );
+        assert!((hit.normal.y - 1.0).abs() < 0.01);
+        assert!((hit.point.y - 0.5).abs() < 0.01);
+
+        let hit = world
+            .box_cast(
+                Vec3::new(0.0, 5.0, 0.0),
+                Vec3::new(1.0, 0.25, 1.0),
+                Vec3::new(0.0, -1.0, 0.0),
+                10.0,
+                CollisionLayers::default(),
+            )
+            .unwrap();
+        assert!(
+            (hit.distance - 4.25).abs() < 0.01,
+            "distance {}",
+            hit.distance
+        );
+        assert!(world
+            .capsule_cast(
+                Vec3::new(0.0, 5.0, 0.0),
+                0.5,
+                0.25,
+                Vec3::new(1.0, 0.0, 0.0),
+                10.0,
+                CollisionLayers::default()
+            )
+            .is_none());
+
+        let enemy = Id::new();
+        static_sphere(
+            &mut world,
+            enemy,
+            Vec3::new(3.0, 2.0, 0.0),
+            CollisionLayers::ENEMY,
+        );
+        let found = world.overlap_sphere(Vec3::new(3.0, 2.0, 0.0), 1.0, CollisionLayers::default());
+        assert_eq!(found, vec![enemy]);
+        assert!(world
+            .overlap_box(
+                Vec3::new(3.0, 2.0, 0.0),
+                Vec3::new(1.0, 1.0, 1.0),
+                CollisionLayers::new(0xFFFF_FFFF, CollisionLayers::PLAYER.membership)
+            )
+            .is_empty());
+        assert_eq!(
+            world
+                .overlap_box(
+                    Vec3::new(0.0, 0.4, 0.0),
+                    Vec3::new(0.2, 0.2, 0.2),
+                    CollisionLayers::default()
+                )
+                .len(),
+            1
+        );
+    }
+
+    fn stacking_scene() -> (PhysicsWorld, Vec<RigidbodyHandle>) {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let mut handles = Vec::new();
+        for i in 0..3 {
+            let position = Vec3::new(0.1 * i as f32, 1.5 + 1.1 * i as f32, 0.0);
+            let handle =
+                world.create_rigidbody(Id::new(), RigidbodyProperties::dynamic(), position);
+            world.attach_collider(
+                handle,
+                ColliderShape::Shape3D(ColliderShape3D::cube(1.0)),
+                ColliderProperties::default(),
+                Vec3::ZERO,
+            );
+            handles.push(handle);
+        }
+        (world, handles)
+    }
+
+    fn run(world: &mut PhysicsWorld, handles: &[RigidbodyHandle], steps: u32) -> Vec<[u32; 6]> {
+        let mut trace = Vec::new();
+        for step in 0..steps {
+            if step % 20 == 0 {
+                world.apply_force(handles[2], Vec3::new(3.0, 0.0, 1.0), ForceMode::Impulse);
+            }
+            world.step(1.0 / 60.0);
+            for handle in handles {
+                let state = world.get_state(*handle).unwrap();
+                trace.push([
+                    state.position.x.to_bits(),
+                    state.position.y.to_bits(),
+                    state.position.z.to_bits(),
+                    state.rotation.x.to_bits(),
+                    state.rotation.y.to_bits(),
+                    state.rotation.z.to_bits(),
+                ]);
+            }
+        }
+        trace
+    }
+
+    #[test]
+    fn identical_inputs_are_deterministic() {
+        let (mut first, first_handles) = stacking_scene();
+        let (mut second, second_handles) = stacking_scene();
+        assert_eq!(
+            run(&mut first, &first_handles, 120),
+            run(&mut second, &second_handles, 120)
+        );
+    }
+
+    #[test]
+    fn snapshot_restores_bit_exactly() {
+        let (mut world, handles) = stacking_scene();
+        run(&mut world, &handles, 30);
+        world.apply_force(handles[0], Vec3::new(0.0, 0.0, 2.0), ForceMode::Impulse);
+
+        let snapshot = world.snapshot().unwrap();
+        let expected = run(&mut world, &handles, 90);
+
+        let bytes = snapshot.into_bytes();
+        world
+            .restore(&PhysicsSnapshot::from_bytes(bytes.clone()))
+            .unwrap();
+        assert_eq!(run(&mut world, &handles, 90), expected);
+
+        let truncated = PhysicsSnapshot::from_bytes(bytes[..bytes.len() - 1].to_vec());
+        assert!(world.restore(&truncated).is_err());
+    }
+
+    #[test]
+    fn failed_restore_leaves_the_world_unchanged() {
+        let (mut world, handles) = stacking_scene();
+        let mut bytes = world.snapshot().unwrap().into_bytes();
+        bytes.push(0);
+        run(&mut world, &handles, 30);
+        let poses = |world: &PhysicsWorld| -> Vec<_> {
+            handles
+                .iter()
+                .map(|h| world.get_state(*h).map(|s| (s.position, s.rotation)))
+                .collect()
+        };
+        let before = poses(&world);
+        let current = world.snapshot().unwrap();
+        let expected = run(&mut world, &handles, 30);
+        world.restore(&current).unwrap();
+
+        assert!(world.restore(&PhysicsSnapshot::from_bytes(bytes)).is_err());
+        assert_eq!(poses(&world), before);
+        assert_eq!(run(&mut world, &handles, 30), expected);
+    }
+
+    #[test]
+    fn resting_bodies_sleep_until_disturbed() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let ball = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 1.5, 0.0),
+        );
+        world.attach_collider(
+            ball,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.is_sleeping(ball));
+        let resting = world.get_state(ball).unwrap().position;
+        world.step(1.0 / 60.0);
+        assert_eq!(world.get_state(ball).unwrap().position, resting);
+
+        world.apply_force(ball, Vec3::new(0.0, 5.0, 0.0), ForceMode::VelocityChange);
+        assert!(!world.is_sleeping(ball));
+        world.step(1.0 / 60.0);
+        assert!(world.get_state(ball).unwrap().position.y > resting.y);
+    }
+
+    #[test]
+    fn removing_a_body_wakes_only_its_neighbours() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        ground(&mut world);
+        let bottom = cube(&mut world, Vec3::new(0.0, 0.75, 0.0));
+        let top = cube(&mut world, Vec3::new(0.0, 1.25, 0.0));
+        let left = cube(&mut world, Vec3::new(-4.0, 0.75, 0.0));
+        let right = cube(&mut world, Vec3::new(-3.0, 0.75, 0.0));
+        let far = cube(&mut world, Vec3::new(5.0, 0.75, 0.0));
+        world.add_constraint(
+            Constraint::new(left.0.raw(), Some(right.0.raw()), ConstraintType::Fixed)
+                .with_anchors(glam::Vec3::new(0.5, 0.0, 0.0), glam::Vec3::new(-0.5, 0.0, 0.0)),
+        );
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+        }
+        let all = [bottom, top, left, right, far];
+        assert!(all.iter().all(|body| world.is_sleeping(*body)));
+
+        world.remove_rigidbody(bottom);
+        assert!(!world.is_sleeping(top));
+        assert!(world.is_sleeping(left) && world.is_sleeping(right) && world.is_sleeping(far));
+
+        world.remove_rigidbody(left);
+        assert!(!world.is_sleeping(right));
+        assert!(world.is_sleeping(far));
+    }
+
+    #[test]
+    fn ccd_stops_fast_bodies_at_thin_walls() {
+        let fire = |properties: RigidbodyProperties| {
+            let mut world = PhysicsWorld::new(PhysicsConfig::default());
+            let wall = world.create_rigidbody(
+                Id::new(),
+                RigidbodyProperties::static_body(),
+                Vec3::new(5.0, 0.0, 0.0),
+            );
+            world.attach_collider(
+                wall,
+                ColliderShape::Shape3D(ColliderShape3D::box_shape(0.1, 4.0, 4.0)),
+                ColliderProperties::default(),
+                Vec3::ZERO,
+            );
+            let bullet =
+                world.create_rigidbody(Id::new(), properties.with_gravity_scale(0.0), Vec3::ZERO);
+            world.attach_collider(
+                bullet,
+                ColliderShape::Shape3D(ColliderShape3D::sphere(0.05)),
+                ColliderProperties::default(),
+                Vec3::ZERO,
+            );
+            world.set_linear_velocity(bullet, Vec3::new(200.0, 0.0, 0.0));
+            for _ in 0..10 {
+                world.step(1.0 / 60.0);
+            }
+            world.get_state(bullet).unwrap().position.x
+        };
+
+        assert!(fire(RigidbodyProperties::dynamic()) > 5.0);
+        let stopped = fire(RigidbodyProperties::dynamic().with_ccd());
+        assert!(stopped < 4.95 && stopped > 4.8, "bullet at {stopped}");
+    }
+
+    fn cube(world: &mut PhysicsWorld, position: Vec3) -> RigidbodyHandle {
+        let handle = world.create_rigidbody(Id::new(), RigidbodyProperties::dynamic(), position);
+        world.attach_collider(
+            handle,
+            ColliderShape::Shape3D(ColliderShape3D::cube(0.5)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+        handle
+    }
+
+    #[test]
+    fn ball_socket_holds_pendulum_length() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let bob = cube(&mut world, Vec3::new(2.0, 5.0, 0.0));
+        let pivot = glam::Vec3::new(0.0, 5.0, 0.0);
+        world.add_constraint(
+            Constraint::new(bob.0.raw(), None, ConstraintType::BallSocket)
+                .with_anchors(glam::Vec3::new(-2.0, 0.0, 0.0), pivot),
+        );
+
+        let mut lowest: f32 = 5.0;
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            let position = world.get_state(bob).unwrap().position;
+            let length = (glam::Vec3::new(position.x, position.y, position.z) - pivot).length();
+            assert!((length - 2.0).abs() < 0.1, "pendulum length {length}");
+            lowest = lowest.min(position.y);
+        }
+        assert!(lowest < 3.5);
+    }
+
+    #[test]
+    fn hinge_motor_respects_limits() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let frame = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::static_body(),
+            Vec3::new(0.0, 5.0, 0.0),
+        );
+        let door = cube(&mut world, Vec3::new(0.0, 5.0, 0.0));
+        // The motor drives body B relative to body A
+        let hinge = ConstraintType::Hinge {
+            axis: glam::Vec3::Y,
+        };
+        world.add_constraint(
+            Constraint::new(frame.0.raw(), Some(door.0.raw()), hinge)
+                .with_motor(Motor::new(2.0, 100.0))
+                .with_limits(-1.0, 1.0),
+        );
+
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        let spinning = world.get_state(door).unwrap();
+        assert!((spinning.angular_velocity.y - 2.0).abs() < 0.05);
+        assert!(spinning.angular_velocity.x.abs() < 0.01);
+        assert!((spinning.position.y - 5.0).abs() < 0.05);
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let stopped = world.get_state(door).unwrap();
+        assert!(
+            (stopped.rotation.y - 1.0).abs() < 0.05,
+            "yaw {}",
+            stopped.rotation.y
+        );
+        assert!(stopped.angular_velocity.y.abs() < 0.05);
+    }
+
+    #[test]
+    fn overloaded_joints_break() {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let anchor = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::static_body(),
+            Vec3::new(0.0, 5.0, 0.0),
+        );
+        let light = cube(&mut world, Vec3::new(-2.0, 4.0, 0.0));
+        let heavy = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic().with_mass(50.0),
+            Vec3::new(2.0, 4.0, 0.0),
+        );
+        let joint = |body: RigidbodyHandle, x: f32| {
+            Constraint::new(body.0.raw(), Some(anchor.0.raw()), ConstraintType::Fixed)
+                .with_anchors(glam::Vec3::new(-x, 1.0, 0.0), glam::Vec3::ZERO)
+                .with_break_thresholds(100.0, 1000.0)
+        };
+        let holds = world.add_constraint(joint(light, -2.0));
+        let snaps = world.add_constraint(joint(heavy, 2.0));
+
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.broken_constraints(), vec![snaps]);
+        assert!(world.get_state(heavy).unwrap().position.y < 3.0);
+        assert!((world.get_state(light).unwrap().position.y - 4.0).abs() < 0.05);
+        assert!(!world.constraints().get_constraint(holds).unwrap().is_broken);
+    }
+
+    #[cfg(feature = "2d")]
+    #[test]
+    fn rapier2d_backend_lands_and_reports_contacts() {
+        use crate::collision::ColliderShape2D;
+
+        let config = PhysicsConfig {
+            backend: PhysicsBackendKind::Rapier2D,
+            ..PhysicsConfig::default()
+        };
+        let mut world = PhysicsWorld::new(config);
+        assert_eq!(world.backend_name(), "rapier2d");
+
+        let floor =
+            world.create_rigidbody(Id::new(), RigidbodyProperties::static_body(), Vec3::ZERO);
+        world.attach_collider(
+            floor,
+            ColliderShape::Shape2D(ColliderShape2D::Rectangle {
+                half_width: 20.0,
+                half_height: 1.0,
+            }),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+        let ball = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 3.0, 0.0),
+        );
+        world.attach_collider(
+            ball,
+            ColliderShape::Shape2D(ColliderShape2D::Circle { radius: 0.5 }),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+
+        let mut started = false;
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+            started |= world
+                .collision_events()
+                .iter()
+                .any(|e| e.event_type == CollisionEventType::Started);
+        }
+
+        let state = world.get_state(ball).unwrap();
+        assert!(
+            (state.position.y - 1.5).abs() < 0.05,
+            "ball at {}",
+            state.position.y
+        );
+        assert!(started);
+    }
+
+    #[cfg(feature = "3d")]
+    #[test]
+    fn rapier3d_backend_lands_and_reports_contacts() {
+        use crate::collision::ColliderShape3D;
+
+        let config = PhysicsConfig {
+            backend: PhysicsBackendKind::Rapier3D,
+            ..PhysicsConfig::default()
+        };
+        let mut world = PhysicsWorld::new(config);
+        assert_eq!(world.backend_name(), "rapier3d");
+
+        let floor =
+            world.create_rigidbody(Id::new(), RigidbodyProperties::static_body(), Vec3::ZERO);
+        world.attach_collider(
+            floor,
+            ColliderShape::Shape3D(ColliderShape3D::Box {
+                half_extents: Vec3::new(20.0, 1.0, 20.0),
+            }),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+        let ball = world.create_rigidbody(
+            Id::new(),
+            RigidbodyProperties::dynamic(),
+            Vec3::new(0.0, 3.0, 0.0),
+        );
+        world.attach_collider(
+            ball,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties::default(),
+            Vec3::ZERO,
+        );
+
+        let mut started = false;
+        for _ in 0..180 {
+            world.step(1.0 / 60.0);
+            started |= world
+                .collision_events()
+                .iter()
+                .any(|e| e.event_type == CollisionEventType::Started);
+        }
+
+        let state = world.get_state(ball).unwrap();
+        assert!(
+            (state.position.y - 1.5).abs() < 0.05,
+            "ball at {}",
+            state.position.y
+        );
+        assert!(state.position.x.abs() < 0.05 && state.position.z.abs() < 0.05);
+        assert!(started);
+    }
 }
@@ -0,0 +1,360 @@
+//! Cooked textures
+//!
+//! GPU-ready texture container written by the asset importer. Mip levels are
+//! stored in the exact block layout the GPU samples, so uploading is a copy.
+
+use lunaris_core::{Error, Result};
+use wgpu::{
+    AstcBlock, AstcChannel, Device, Extent3d, Features, ImageCopyTexture, ImageDataLayout,
+    Origin3d, Queue, Texture, TextureAspect, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureUsages,
+};
+
+const MAGIC: [u8; 4] = *b"LTEX";
+const VERSION: u16 = 1;
+const FLAG_SRGB: u8 = 1;
+const FLAG_NORMAL_MAP: u8 = 2;
+
+/// Storage format of a cooked texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GpuTextureFormat {
+    /// Uncompressed RGBA 8-bit
+    Rgba8,
+    /// BC1 RGB with 1-bit alpha, 8 bytes per 4x4 block
+    Bc1,
+    /// BC3 RGBA, 16 bytes per 4x4 block
+    Bc3,
+    /// BC4 single channel, 8 bytes per 4x4 block
+    Bc4,
+    /// BC5 two channels, 16 bytes per 4x4 block
+    Bc5,
+    /// BC7 RGBA, 16 bytes per 4x4 block
+    Bc7,
+    /// ETC2 RGB, 8 bytes per 4x4 block
+    Etc2Rgb8,
+    /// ETC2 RGB with EAC alpha, 16 bytes per 4x4 block
+    Etc2Rgba8,
+    /// ASTC 4x4 LDR
+    Astc4x4,
+    /// ASTC 6x6 LDR
+    Astc6x6,
+    /// ASTC 8x8 LDR
+    Astc8x8,
+}
+
+impl GpuTextureFormat {
+    /// Width and height of a compression block in pixels
+    #[must_use]
+    pub fn block_size(self) -> (u32, u32) {
+        match self {
+            Self::Rgba8 => (1, 1),
+            Self::Astc6x6 => (6, 6),
+            Self::Astc8x8 => (8, 8),
+            _ => (4, 4),
+        }
+    }
+
+    /// Size of a block in bytes
+    #[must_use]
+    pub fn block_bytes(self) -> usize {
+        match self {
+            Self::Rgba8 => 4,
+            Self::Bc1 | Self::Bc4 | Self::Etc2Rgb8 => 8,
+            _ => 16,
+        }
+    }
+
+    /// Whether the format is block compressed
+    #[must_use]
+    pub fn is_compressed(self) -> bool {
+        self != Self::Rgba8
+    }
+
+    /// Whether the format has an sRGB variant
+    #[must_use]
+    pub fn supports_srgb(self) -> bool {
+        !matches!(self, Self::Bc4 | Self::Bc5)
+    }
+
+    /// Bytes needed for a mip level of the given size
+    #[must_use]
+    pub fn level_bytes(self, width: u32, height: u32) -> usize {
+        let (bw, bh) = self.block_size();
+        width.div_ceil(bw) as usize * height.div_ceil(bh) as usize * self.block_bytes()
+    }
+
+    /// Device feature needed to sample the format
+    #[must_use]
+    pub fn required_features(self) -> Features {
+        match self {
+            Self::Rgba8 => Features::empty(),
+            Self::Bc1 | Self::Bc3 | Self::Bc4 | Self::Bc5 | Self::Bc7 => {
+                Features::TEXTURE_COMPRESSION_BC
+            },
+            Self::Etc2Rgb8 | Self::Etc2Rgba8 => Features::TEXTURE_COMPRESSION_ETC2,
+            Self::Astc4x4 | Self::Astc6x6 | Self::Astc8x8 => Features::TEXTURE_COMPRESSION_ASTC,
+        }
+    }
+
+    /// Matching wgpu format
+    #[must_use]
+    pub fn to_wgpu(self, srgb: bool) -> TextureFormat {
+        let astc = |block| TextureFormat::Astc {
+            block,
+            channel: if srgb {
+                AstcChannel::UnormSrgb
+            } else {
+                AstcChannel::Unorm
+            },
+        };
+        match (self, srgb) {
+            (Self::Rgba8, false) => TextureFormat::Rgba8Unorm,
+            (Self::Rgba8, true) => TextureFormat::Rgba8UnormSrgb,
+            (Self::Bc1, false) => TextureFormat::Bc1RgbaUnorm,
+            (Self::Bc1, true) => TextureFormat::Bc1RgbaUnormSrgb,
+            (Self::Bc3, false) => TextureFormat::Bc3RgbaUnorm,
+            (Self::Bc3, true) => TextureFormat::Bc3RgbaUnormSrgb,
+            (Self::Bc4, _) => TextureFormat::Bc4RUnorm,
+            (Self::Bc5, _) => TextureFormat::Bc5RgUnorm,
+            (Self::Bc7, false) => TextureFormat::Bc7RgbaUnorm,
+            (Self::Bc7, true) => TextureFormat::Bc7RgbaUnormSrgb,
+            (Self::Etc2Rgb8, false) => TextureFormat::Etc2Rgb8Unorm,
+            (Self::Etc2Rgb8, true) => TextureFormat::Etc2Rgb8UnormSrgb,
+            (Self::Etc2Rgba8, false) => TextureFormat::Etc2Rgba8Unorm,
+            (Self::Etc2Rgba8, true) => TextureFormat::Etc2Rgba8UnormSrgb,
+            (Self::Astc4x4, _) => astc(AstcBlock::B4x4),
+            (Self::Astc6x6, _) => astc(AstcBlock::B6x6),
+            (Self::Astc8x8, _) => astc(AstcBlock::B8x8),
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Self::Rgba8 => 0,
+            Self::Bc1 => 1,
+            Self::Bc3 => 2,
+            Self::Bc4 => 3,
+            Self::Bc5 => 4,
+            Self::Bc7 => 5,
+            Self::Etc2Rgb8 => 6,
+            Self::Etc2Rgba8 => 7,
+            Self::Astc4x4 => 8,
+            Self::Astc6x6 => 9,
+            Self::Astc8x8 => 10,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => Self::Rgba8,
+            1 => Self::Bc1,
+            2 => Self::Bc3,
+            3 => Self::Bc4,
+            4 => Self::Bc5,
+            5 => Self::Bc7,
+            6 => Self::Etc2Rgb8,
+            7 => Self::Etc2Rgba8,
+            8 => Self::Astc4x4,
+            9 => Self::Astc6x6,
+            10 => Self::Astc8x8,
+            _ => return None,
+        })
+    }
+}
+
+/// Texture with every mip level encoded in its GPU format
+///
+/// Written by the texture cooker as `.tex` files. The layout is a small header
+/// followed by each level's length and data, largest level first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CookedTexture {
+    /// Width of the top level in pixels
+    pub width: u32,
+    /// Height of the top level in pixels
+    pub height: u32,
+    /// Storage format
+    pub format: GpuTextureFormat,
+    /// Color channels are sRGB encoded
+    pub srgb: bool,
+    /// Tangent-space normal map
+    pub normal_map: bool,
+    /// Encoded data of each mip level, largest first
+    pub mips: Vec<Vec<u8>>,
+}
+
+impl CookedTexture {
+    /// Dimensions of a mip level
+    #[must_use]
+    pub fn mip_size(&self, level: u32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+
+    /// Number of mip levels
+    #[must_use]
+    pub fn mip_levels(&self) -> u32 {
+        self.mips.len() as u32
+    }
+
+    /// Total size of the encoded data in bytes
+    #[must_use]
+    pub fn data_size(&self) -> usize {
+        self.mips.iter().map(Vec::len).sum()
+    }
+
+    /// wgpu format to create the texture with
+    #[must_use]
+    pub fn wgpu_format(&self) -> TextureFormat {
+        self.format
+            .to_wgpu(self.srgb && self.format.supports_srgb())
+    }
+
+    /// Serialize to the `.tex` container
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.srgb {
+            flags |= FLAG_SRGB;
+        }
+        if self.normal_map {
+            flags |= FLAG_NORMAL_MAP;
+        }
+
+        let mut bytes = Vec::with_capacity(20 + self.mips.len() * 8 + self.data_size());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.push(self.format.code());
+        bytes.push(flags);
+        bytes.extend_from_slice(&self.width.to_le_bytes());
+        bytes.extend_from_slice(&self.height.to_le_bytes());
+        bytes.extend_from_slice(&self.mip_levels().to_le_bytes());
+        for mip in &self.mips {
+            bytes.extend_from_slice(&(mip.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(mip);
+        }
+        bytes
+    }
+
+    /// Parse a `.tex` container
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data is truncated, from another version or has
+    /// levels of the wrong size.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = |reason: &str| Error::Asset(format!("Invalid cooked texture: {}", reason));
+
+        let mut data = bytes;
+        let mut take = |n: usize| split(&mut data, n).ok_or_else(|| invalid("truncated"));
+        let u32_at = |b: &[u8]| u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+
+        if take(4)? != MAGIC {
+            return Err(invalid("bad magic"));
+        }
+        let version = take(2)?;
+        if u16::from_le_bytes([version[0], version[1]]) != VERSION {
+            return Err(invalid("unsupported version"));
+        }
+        let header = take(2)?;
+        let format =
+            GpuTextureFormat::from_code(header[0]).ok_or_else(|| invalid("unknown format"))?;
+        let flags = header[1];
+        let width = u32_at(take(4)?);
+        let height = u32_at(take(4)?);
+        let levels = u32_at(take(4)?);
+        if width == 0 || height == 0 || levels > 32 {
+            return Err(invalid("bad dimensions"));
+        }
+
+        let mut texture = Self {
+            width,
+            height,
+            format,
+            srgb: flags & FLAG_SRGB != 0,
+            normal_map: flags & FLAG_NORMAL_MAP != 0,
+            mips: Vec::with_capacity(levels as usize),
+        };
+        for level in 0..levels {
+            let len = take(8)?;
+            let len = u64::from_le_bytes([
+                len[0], len[1], len[2], len[3], len[4], len[5], len[6], len[7],
+            ]);
+            let (w, h) = texture.mip_size(level);
+            if len != format.level_bytes(w, h) as u64 {
+                return Err(invalid("level size mismatch"));
+            }
+            texture.mips.push(take(len as usize)?.to_vec());
+        }
+        Ok(texture)
+    }
+
+    /// Create a GPU texture and upload every mip level
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the device was created without the feature the
+    /// format needs.
+    pub fn upload(&self, device: &Device, queue: &Queue, label: Option<&str>) -> Result<Texture> {
+        let required = self.format.required_features();
+        if !device.features().contains(required) {
+            return Err(Error::Renderer(format!(
+                "{:?} textures need {:?}, which the device does not support",
+                self.format, required
+            )));
+        }
+
+        let format = self.wgpu_format();
+        let size = Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        };
+        let texture = device.create_texture(&TextureDescriptor {
+            label,
+            size,
+            mip_level_count: self.mip_levels().max(1),
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let (block_width, block_height) = self.format.block_size();
+        for (level, data) in self.mips.iter().enumerate() {
+            let level = level as u32;
+            let (width, height) = self.mip_size(level);
+            let blocks_wide = width.div_ceil(block_width);
+            let blocks_high = height.div_ceil(block_height);
+            queue.write_texture(
+                ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                data,
+                ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(blocks_wide * self.format.block_bytes() as u32),
+                    rows_per_image: Some(blocks_high),
+                },
+                Extent3d {
+                    width: blocks_wide * block_width,
+                    height: blocks_high * block_height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        Ok(texture)
+    }
+}
+
+/// Split `n` bytes off the front of `data`
+fn split<'a>(data: &mut &'a [u8], n: usize) -> Option<&'a [u8]> {
+    if data.len() < n {
+        return None;
+    }
+    let (head, rest) = data.split_at(n);
+    *data = rest;
+    Some(head)
+}
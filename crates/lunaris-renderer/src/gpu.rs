@@ -68,11 +68,16 @@ impl GraphicsContext {
             .await
             .ok_or_else(|| lunaris_core::Error::Renderer("No suitable GPU adapter found".into()))?;
 
+        // Enable whichever block compression families the adapter can sample
+        let compression = Features::TEXTURE_COMPRESSION_BC
+            | Features::TEXTURE_COMPRESSION_ETC2
+            | Features::TEXTURE_COMPRESSION_ASTC;
+
         let (device, queue) = adapter
             .request_device(
                 &DeviceDescriptor {
                     label: Some("Lunaris Device"),
-                    required_features: Features::empty(),
+                    required_features: adapter.features() & compression,
                     required_limits: Limits::default(),
                     memory_hints: MemoryHints::Performance,
                 },
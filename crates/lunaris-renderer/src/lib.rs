@@ -18,6 +18,7 @@
 
 pub mod animation;
 pub mod camera;
+pub mod cooked_texture;
 pub mod debug_draw;
 pub mod decal;
 pub mod facial;
@@ -52,6 +53,7 @@ pub mod water;
 
 pub use animation::{AnimationClip, AnimationStateMachine, Skeleton, SkeletalAnimator};
 pub use camera::{Camera2D, Camera3D, CameraUniform};
+pub use cooked_texture::{CookedTexture, GpuTextureFormat};
 pub use debug_draw::{DebugDraw, DebugDraw2D, DebugShape};
 pub use gpu::{GraphicsConfig, GraphicsContext, GpuInfo, Vertex2D, Vertex3D};
 pub use lod::{CullingSystem, Frustum, LodGroup};
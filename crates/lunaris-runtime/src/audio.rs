@@ -3,6 +3,7 @@
 //! Provides spatial audio, music, and sound effect management.
 
 use glam::Vec3;
+use lunaris_assets::{loader::AudioAsset, AudioStream};
 use std::collections::HashMap;
 
 /// Audio listener (usually the camera/player)
@@ -209,6 +210,8 @@ pub struct MusicTrack {
     /// Loop points
     pub loop_start: f32,
     pub loop_end: f32,
+    /// Jump back to the loop start, or stop at the loop end
+    pub looping: bool,
 }
 
 /// Audio system
@@ -227,6 +230,10 @@ pub struct AudioSystem {
     next_clip_id: u64,
     /// Current music
     current_music: Option<u64>,
+    /// Decoders of streamed clips
+    streams: HashMap<SoundClipId, AudioStream>,
+    /// Loop region of the current music (seconds)
+    music_loop: Option<(f32, f32)>,
     /// Music crossfade time
     pub crossfade_time: f32,
     /// Max simultaneous sounds
@@ -270,6 +277,8 @@ impl AudioSystem {
             next_source_id: 1,
             next_clip_id: 1,
             current_music: None,
+            streams: HashMap::new(),
+            music_loop: None,
             crossfade_time: 1.0,
             max_sounds: 32,
         }
@@ -294,6 +303,32 @@ impl AudioSystem {
         id
     }
 
+    /// Register a decoded audio asset
+    pub fn register_asset(&mut self, name: &str, asset: AudioAsset) -> SoundClipId {
+        let channels = u8::try_from(asset.channels).unwrap_or(u8::MAX);
+        self.register_clip(name, asset.samples, asset.sample_rate, channels)
+    }
+
+    /// Register a clip that is decoded while it plays
+    ///
+    /// Meant for music; pull its samples with [`Self::read_music`].
+    pub fn register_stream(&mut self, name: &str, stream: AudioStream) -> SoundClipId {
+        let id = SoundClipId(self.next_clip_id);
+        self.next_clip_id += 1;
+
+        self.clips.insert(id, SoundClip {
+            id,
+            name: name.to_string(),
+            duration: stream.duration().map_or(f32::INFINITY, |d| d.as_secs_f32()),
+            sample_rate: stream.sample_rate(),
+            channels: u8::try_from(stream.channels()).unwrap_or(u8::MAX),
+            samples: Vec::new(),
+        });
+        self.streams.insert(id, stream);
+
+        id
+    }
+
     /// Play a sound
     pub fn play(&mut self, clip_id: SoundClipId) -> Option<u64> {
         self.play_at(clip_id, None, 1.0, 1.0, false)
@@ -388,6 +423,76 @@ impl AudioSystem {
         Some(id)
     }
 
+    /// Play a music track, honoring its volume and loop region
+    pub fn play_track(&mut self, track: &MusicTrack, fade_in: bool) -> Option<u64> {
+        if let Some(stream) = self.streams.get_mut(&track.clip_id) {
+            if let Err(e) = stream.rewind() {
+                tracing::warn!("Cannot restart music '{}': {}", track.name, e);
+            }
+        }
+
+        let id = self.play_music(track.clip_id, fade_in)?;
+        if let Some(source) = self.sources.iter_mut().find(|s| s.id == id) {
+            source.loop_enabled = track.looping;
+            if !fade_in {
+                source.volume = track.volume;
+            }
+        }
+        self.music_loop =
+            (track.loop_end > track.loop_start).then_some((track.loop_start, track.loop_end));
+        Some(id)
+    }
+
+    /// Decode the next samples of streamed music
+    ///
+    /// Fills `out` with interleaved samples at the stream's rate and returns
+    /// how many were written. Looping music jumps back to its loop start, other
+    /// music stops at the loop end or the end of the stream.
+    pub fn read_music(&mut self, out: &mut [f32]) -> usize {
+        let Some(source) = self
+            .current_music
+            .and_then(|id| self.sources.iter_mut().find(|s| s.id == id))
+        else {
+            return 0;
+        };
+        if !source.is_playing || source.is_paused {
+            return 0;
+        }
+        let Some(stream) = self.streams.get_mut(&source.clip_id) else {
+            return 0;
+        };
+
+        let (loop_start, loop_end) = self.music_loop.unwrap_or((0.0, f32::INFINITY));
+        let frame = usize::from(stream.channels());
+        let mut written = 0;
+        let mut restarted = false;
+        while written < out.len() {
+            // Stop each read at the loop end
+            let remaining = ((loop_end - stream.position()) * stream.sample_rate() as f32).ceil();
+            let limit = if remaining.is_finite() {
+                out.len().min(written + remaining.max(0.0) as usize * frame)
+            } else {
+                out.len()
+            };
+
+            let count = stream.read(&mut out[written..limit]);
+            written += count;
+            if count > 0 {
+                restarted = false;
+                continue;
+            }
+            if !source.loop_enabled || restarted || stream.seek(loop_start).is_err() {
+                if stream.is_finished() || remaining <= 0.0 {
+                    source.is_playing = false;
+                }
+                break;
+            }
+            restarted = true;
+        }
+        source.playback_position = stream.position();
+        written
+    }
+
     /// Set channel volume
     pub fn set_channel_volume(&mut self, channel: &str, volume: f32) {
         if let Some(ch) = self.channels.get_mut(channel) {
@@ -492,3 +597,96 @@ impl AudioEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 8_000;
+
+    /// One second of 16-bit mono PCM WAV whose samples count up from zero
+    fn counting_wav() -> Vec<u8> {
+        let data_len = RATE * 2;
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&RATE.to_le_bytes());
+        bytes.extend_from_slice(&(RATE * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_len.to_le_bytes());
+        for i in 0..RATE {
+            bytes.extend_from_slice(&(i as i16).to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Frame index a decoded sample came from
+    fn frame(sample: f32) -> i32 {
+        (sample * 32_768.0).round() as i32
+    }
+
+    fn play(loop_start: f32, loop_end: f32, looping: bool) -> AudioSystem {
+        let mut audio = AudioSystem::new();
+        let stream = AudioStream::from_bytes(counting_wav(), Some("wav")).unwrap();
+        let clip_id = audio.register_stream("theme", stream);
+        let track = MusicTrack {
+            name: "theme".to_string(),
+            clip_id,
+            volume: 0.5,
+            bpm: None,
+            loop_start,
+            loop_end,
+            looping,
+        };
+        audio.play_track(&track, false).unwrap();
+        audio
+    }
+
+    #[test]
+    fn music_repeats_its_loop_region() {
+        let mut audio = play(0.25, 0.5, true);
+        let mut out = vec![0.0; 6_000];
+        assert_eq!(audio.read_music(&mut out), out.len());
+        assert_eq!(frame(out[3_999]), 3_999);
+        assert_eq!(frame(out[4_000]), 2_000);
+        assert_eq!(frame(out[5_999]), 3_999);
+        assert_eq!(audio.active_source_count(), 1);
+    }
+
+    #[test]
+    fn music_without_region_loops_the_whole_stream() {
+        let mut audio = play(0.0, 0.0, true);
+        let mut out = vec![0.0; 10_000];
+        assert_eq!(audio.read_music(&mut out), out.len());
+        assert_eq!(frame(out[7_999]), 7_999);
+        assert_eq!(frame(out[8_000]), 0);
+        assert_eq!(audio.active_source_count(), 1);
+    }
+
+    #[test]
+    fn music_stops_at_the_loop_end_without_looping() {
+        let mut audio = play(0.0, 0.25, false);
+        let mut out = vec![0.0; 4_000];
+        assert_eq!(audio.read_music(&mut out), 2_000);
+        assert_eq!(frame(out[1_999]), 1_999);
+        assert_eq!(audio.active_source_count(), 0);
+        assert_eq!(audio.read_music(&mut out), 0);
+    }
+
+    #[test]
+    fn music_stops_at_the_end_of_the_stream() {
+        let mut audio = play(0.0, 0.0, false);
+        let mut out = vec![0.0; 6_000];
+        assert_eq!(audio.read_music(&mut out), 6_000);
+        assert_eq!(audio.active_source_count(), 1);
+        assert_eq!(audio.read_music(&mut out), 2_000);
+        assert_eq!(audio.active_source_count(), 0);
+        assert_eq!(audio.read_music(&mut out), 0);
+    }
+}
@@ -32,6 +32,7 @@ pub use save::{SaveMetadata, SaveSystem};
 pub use window::{AppRunner, Application, Window, WindowConfig, WindowState};
 
 use lunaris_core::Result;
+use lunaris_ecs::{SceneMigrator, SCENE_FORMAT_VERSION};
 
 /// Runtime configuration
 #[derive(Debug, Clone)]
@@ -69,6 +70,43 @@ pub fn init() -> Result<()> {
     Ok(())
 }
 
+/// Run the `migrate-scenes <dir> [--dry-run]` command, returning the exit code
+///
+/// The `lunaris` executable runs it with the engine's migrations. Games that
+/// register their own steps call it from their executable with their migrator.
+pub fn migrate_scenes(args: &[String], migrator: &SceneMigrator) -> i32 {
+    let dry_run = args.iter().any(|a| a == "--dry-run");
+    let Some(dir) = args.iter().find(|a| !a.starts_with("--")) else {
+        eprintln!("Usage: lunaris migrate-scenes <dir> [--dry-run]");
+        return 2;
+    };
+
+    let report = match migrator.migrate_dir(dir, dry_run) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Failed to migrate scenes in {dir}: {e}");
+            return 1;
+        },
+    };
+
+    let verb = if dry_run { "Would migrate" } else { "Migrated" };
+    for (path, version) in &report.migrated {
+        println!("{verb} {} (v{version} -> v{SCENE_FORMAT_VERSION})", path.display());
+    }
+    for (path, error) in &report.failed {
+        eprintln!("Failed {}: {error}", path.display());
+    }
+    println!(
+        "{} migrated, {} current, {} skipped, {} failed",
+        report.migrated.len(),
+        report.current.len(),
+        report.skipped.len(),
+        report.failed.len()
+    );
+
+    i32::from(!report.failed.is_empty())
+}
+
 /// Convenience macro to run a game
 #[macro_export]
 macro_rules! run_game {
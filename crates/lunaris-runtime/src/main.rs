@@ -1,6 +1,16 @@
 //! Lunaris Engine Runtime Executable
+//!
+//! `lunaris migrate-scenes <dir> [--dry-run]` upgrades every scene under a
+//! directory to the current scene format.
+
+use lunaris_ecs::SceneMigrator;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate-scenes") {
+        std::process::exit(lunaris_runtime::migrate_scenes(&args[1..], &SceneMigrator::new()));
+    }
+
     if let Err(e) = lunaris_runtime::init() {
         eprintln!("Failed to initialize Lunaris: {e}");
         std::process::exit(1);
@@ -3,6 +3,8 @@
 //! Sight, hearing, and other senses for AI agents.
 
 use glam::Vec3;
+use lunaris_core::id::Id;
+use lunaris_physics::{CollisionLayers, PhysicsWorld, RaycastQuery};
 use std::collections::HashMap;
 
 /// Perception stimulus type
@@ -92,6 +94,8 @@ pub struct SightConfig {
     pub eye_height: f32,
     /// Lose sight time (seconds to forget)
     pub lose_sight_time: f32,
+    /// Layers that block line of sight
+    pub occlusion_layers: CollisionLayers,
 }
 
 impl Default for SightConfig {
@@ -103,6 +107,7 @@ impl Default for SightConfig {
             peripheral_fov: 180.0,
             eye_height: 1.7,
             lose_sight_time: 3.0,
+            occlusion_layers: CollisionLayers::default(),
         }
     }
 }
@@ -222,6 +227,33 @@ impl AIPerception {
         false
     }
 
+    /// Check if a point is in sight and not hidden behind colliders
+    ///
+    /// `target_entity` is ignored by the occlusion test, as is the owner.
+    #[must_use]
+    pub fn can_see_in(&self, world: &PhysicsWorld, target_pos: Vec3, target_entity: u64) -> bool {
+        self.can_see(target_pos) && self.has_line_of_sight(world, target_pos, target_entity)
+    }
+
+    /// Check that no collider blocks the segment from the eye to a point
+    #[must_use]
+    pub fn has_line_of_sight(&self, world: &PhysicsWorld, target_pos: Vec3, target_entity: u64) -> bool {
+        let to_target = target_pos - self.eye_position;
+        let distance = to_target.length();
+        if distance <= f32::EPSILON {
+            return true;
+        }
+
+        let to_engine = |v: Vec3| lunaris_core::math::Vec3::new(v.x, v.y, v.z);
+        let query = RaycastQuery::new(to_engine(self.eye_position), to_engine(to_target), distance)
+            .with_layers(self.sight.occlusion_layers);
+        let (owner, target) = (Id::from_raw(self.owner), Id::from_raw(target_entity));
+        world
+            .raycast_all(&query)
+            .iter()
+            .all(|hit| hit.entity == owner || hit.entity == target)
+    }
+
     /// Process a stimulus
     pub fn process_stimulus(&mut self, stimulus: &Stimulus) {
         if !self.enabled_senses.contains(&stimulus.stimulus_type) {
@@ -432,3 +464,71 @@ impl PerceptionSystem {
         self.stimuli.len()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lunaris_physics::collision::ColliderShape3D;
+    use lunaris_physics::rigidbody::{ColliderProperties, RigidbodyProperties};
+    use lunaris_physics::{ColliderShape, PhysicsConfig};
+
+    fn sphere(world: &mut PhysicsWorld, entity: Id, position: Vec3, layers: CollisionLayers) {
+        let position = lunaris_core::math::Vec3::new(position.x, position.y, position.z);
+        let handle = world.create_rigidbody(entity, RigidbodyProperties::static_body(), position);
+        world.attach_collider(
+            handle,
+            ColliderShape::Shape3D(ColliderShape3D::sphere(0.5)),
+            ColliderProperties {
+                layers,
+                ..ColliderProperties::default()
+            },
+            lunaris_core::math::Vec3::ZERO,
+        );
+    }
+
+    /// A guard at the origin looking down +Z, with its own collider, and a
+    /// target ten units ahead
+    fn setup() -> (PhysicsWorld, AIPerception, Id) {
+        let mut world = PhysicsWorld::new(PhysicsConfig::default());
+        let (guard, target) = (Id::new(), Id::new());
+        sphere(&mut world, guard, Vec3::ZERO, CollisionLayers::DEFAULT);
+        sphere(&mut world, target, Vec3::new(0.0, 0.0, 10.0), CollisionLayers::DEFAULT);
+        (world, AIPerception::new(guard.raw()), target)
+    }
+
+    #[test]
+    fn clear_line_of_sight_ignores_owner_and_target() {
+        let (world, perception, target) = setup();
+        let target_pos = Vec3::new(0.0, 0.0, 10.0);
+        assert!(perception.has_line_of_sight(&world, target_pos, target.raw()));
+        assert!(perception.can_see_in(&world, target_pos, target.raw()));
+        assert!(perception.has_line_of_sight(&world, Vec3::ZERO, target.raw()));
+
+        // The target's collider hides anything else behind it
+        assert!(!perception.has_line_of_sight(&world, target_pos, Id::new().raw()));
+
+        // Out of the view cone even with nothing in the way
+        let behind = Vec3::new(0.0, 0.0, -10.0);
+        assert!(perception.has_line_of_sight(&world, behind, target.raw()));
+        assert!(!perception.can_see_in(&world, behind, target.raw()));
+    }
+
+    #[test]
+    fn colliders_in_between_block_sight() {
+        let (mut world, mut perception, target) = setup();
+        let target_pos = Vec3::new(0.0, 0.0, 10.0);
+        sphere(&mut world, Id::new(), Vec3::new(0.0, 0.0, 5.0), CollisionLayers::ENVIRONMENT);
+        assert!(perception.can_see(target_pos));
+        assert!(!perception.has_line_of_sight(&world, target_pos, target.raw()));
+        assert!(!perception.can_see_in(&world, target_pos, target.raw()));
+
+        // Colliders beyond the target or off the line do not
+        assert!(perception.has_line_of_sight(&world, Vec3::new(0.0, 0.0, 3.0), target.raw()));
+        assert!(perception.has_line_of_sight(&world, Vec3::new(5.0, 0.0, 5.0), target.raw()));
+
+        // Only the occlusion layers block
+        perception.sight.occlusion_layers =
+            CollisionLayers::new(u32::MAX, CollisionLayers::DEFAULT.membership);
+        assert!(perception.can_see_in(&world, target_pos, target.raw()));
+    }
+}